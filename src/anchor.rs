@@ -0,0 +1,137 @@
+//! Comparison constrained by known corresponding positions.
+//!
+//! The caller often knows more about the structure of the data than the matching algorithms can
+//! infer: the sections of two executables start at known offsets, the symbols of two builds have
+//! known addresses and the records of two dumps have known boundaries. An
+//! [`Anchor`](struct.Anchor.html) declares that a position of the first piece of data corresponds
+//! to a position of the second one. The anchors split both pieces of data into corresponding
+//! regions and [`anchored_matches`](fn.anchored_matches.html) only matches each region of the
+//! second piece of data against the corresponding region of the first one, so no match ever
+//! crosses an anchor or relates unrelated sections.
+//!
+//! # Examples
+//!
+//! ```
+//! use bcmp::{AlgoSpec, Match};
+//! use bcmp::anchor::{Anchor, anchored_matches};
+//!
+//! // Two sections, the second one starting at offset 8 in both
+//! let a = b"abcdefgh01234567";
+//! let b = b"abcdXXXX0123efgh";
+//! let anchors = [Anchor::new(8, 8)];
+//! let matches: Vec<Match> = anchored_matches(a, b, &anchors, AlgoSpec::TreeMatch(4)).collect();
+//! // "efgh" is in different sections
+//! assert!(matches == [Match::new(0, 0, 4), Match::new(8, 8, 4)]);
+//! ```
+
+use std::vec::IntoIter;
+
+use {AlgoSpec, Match, MatchIterator, Region, patches_of};
+
+/// A pair of corresponding positions in the first and second pieces of data.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,PartialOrd,Ord,Hash)]
+pub struct Anchor {
+    /// The position in the first piece of data.
+    pub first_pos: usize,
+    /// The position in the second piece of data.
+    pub second_pos: usize,
+}
+
+impl Anchor {
+    /// Allocate a new `Anchor`.
+    pub fn new(first_pos: usize, second_pos: usize) -> Anchor {
+        Anchor {
+            first_pos,
+            second_pos,
+        }
+    }
+}
+
+/// Return the pairs of corresponding regions delimited by `anchors`, from the start to the end of
+/// both pieces of data of lengths `first_len` and `second_len`. Some regions can be empty.
+///
+/// It will panic if the anchors aren't in strictly ascending order in both pieces of data or point
+/// beyond their end.
+pub fn anchored_regions(first_len: usize, second_len: usize, anchors: &[Anchor]) -> Vec<(Region, Region)> {
+    let mut regions = Vec::with_capacity(anchors.len() + 1);
+    let mut start = Anchor::new(0, 0);
+    for (i, &anchor) in anchors.iter().enumerate() {
+        assert!(anchor.first_pos <= first_len && anchor.second_pos <= second_len, "Anchor {} is beyond the end of the data", i);
+        assert!(i == 0 || (anchor.first_pos > start.first_pos && anchor.second_pos > start.second_pos),
+            "Anchor {} is not after the previous one in both pieces of data", i);
+        regions.push((Region::new(start.first_pos, anchor.first_pos), Region::new(start.second_pos, anchor.second_pos)));
+        start = anchor;
+    }
+    regions.push((Region::new(start.first_pos, first_len), Region::new(start.second_pos, second_len)));
+    regions
+}
+
+/// An iterator over the matches between corresponding regions, see
+/// [`anchored_matches`](fn.anchored_matches.html).
+pub struct AnchoredMatches<'a> {
+    first: &'a [u8],
+    second: &'a [u8],
+    algo_spec: AlgoSpec,
+    regions: IntoIter<(Region, Region)>,
+    current: Option<MatchIterator<'a>>,
+}
+
+impl<'a> Iterator for AnchoredMatches<'a> {
+    type Item = Match;
+    fn next(&mut self) -> Option<Match> {
+        loop {
+            if let Some(m) = self.current.as_mut().and_then(Iterator::next) {
+                return Some(m);
+            }
+            let (first_region, second_region) = self.regions.next()?;
+            self.current = Some(MatchIterator::with_regions(self.first, first_region, self.second, second_region, self.algo_spec));
+        }
+    }
+}
+
+/// Return an iterator over the matches between each region of `second` delimited by `anchors` and
+/// the corresponding region of `first`.
+///
+/// The regions are compared lazily, one after the other, with
+/// [`MatchIterator::with_regions`](../struct.MatchIterator.html#method.with_regions): the matches
+/// are reported in the coordinates of the whole pieces of data, in ascending order of
+/// [`second_pos`](../struct.Match.html#structfield.second_pos), and are maximal within their
+/// region. Only one region is indexed at a time.
+///
+/// It will panic if the [`AlgoSpec`](../enum.AlgoSpec.html) is not supported or if the anchors
+/// aren't in strictly ascending order in both pieces of data or point beyond their end.
+pub fn anchored_matches<'a>(first: &'a [u8], second: &'a [u8], anchors: &[Anchor], algo_spec: AlgoSpec) -> AnchoredMatches<'a> {
+    assert!(algo_spec.is_supported(), "Unsupported AlgoSpec");
+    AnchoredMatches {
+        first,
+        second,
+        algo_spec,
+        regions: anchored_regions(first.len(), second.len(), anchors).into_iter(),
+        current: None,
+    }
+}
+
+/// Return the [`patch_set`](../fn.patch_set.html) building each region of `second` delimited by
+/// `anchors` from the corresponding region of `first`, in ascending order of position in `second`.
+///
+/// It will panic under the same conditions as [`anchored_matches`](fn.anchored_matches.html).
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::{AlgoSpec, Match, patch_set};
+/// use bcmp::anchor::{Anchor, anchored_patch_set};
+///
+/// let a = b"[text]0123456789[data]abcdefghij";
+/// let b = b"[text]abcdefghij[data]0123456789";
+/// // Without anchors the sections are copied across each other
+/// assert!(patch_set(a, b, AlgoSpec::TreeMatch(4)).len() == 4);
+/// let patches = anchored_patch_set(a, b, &[Anchor::new(16, 16)], AlgoSpec::TreeMatch(4));
+/// assert!(patches == [Match::new(0, 0, 6), Match::new(16, 16, 6)]);
+/// ```
+pub fn anchored_patch_set(first: &[u8], second: &[u8], anchors: &[Anchor], algo_spec: AlgoSpec) -> Vec<Match> {
+    assert!(algo_spec.is_supported(), "Unsupported AlgoSpec");
+    anchored_regions(first.len(), second.len(), anchors).into_iter()
+        .flat_map(|(first_region, second_region)| patches_of(MatchIterator::with_regions(first, first_region, second, second_region, algo_spec)))
+        .collect()
+}
@@ -0,0 +1,140 @@
+//! Asynchronous interfaces, available with the `async` feature.
+//!
+//! [`MatchStream`](struct.MatchStream.html) exposes the matches of a
+//! [`MatchIterator`](../struct.MatchIterator.html) as a `futures::Stream` which regularly yields
+//! back to the executor, so async services can run comparisons without blocking their executor
+//! threads for the whole enumeration. The inputs themselves can be read from any `AsyncRead` with
+//! the `*_async` functions.
+
+use std::future::Future;
+use std::io::Result;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::future::{TryFutureExt, try_join};
+use futures::io::AsyncRead;
+use futures::stream::Stream;
+
+use {AlgoSpec, Match, MatchIterator, patch_set};
+use hashindex::HashIndex;
+use suffixarray::SuffixArray;
+
+/// Number of matches a [`MatchStream`](struct.MatchStream.html) returns before yielding back to
+/// the executor by default.
+pub const DEFAULT_BUDGET: usize = 64;
+
+/// A `Stream` over all the [`Match`](../struct.Match.html) between two pieces of data.
+///
+/// After `budget` matches, the stream returns `Poll::Pending` once, waking itself immediately, to
+/// give the executor a chance to run other tasks. Building the index of the first piece of data
+/// happens in [`new`](#method.new) and is not interrupted.
+///
+/// # Examples
+///
+/// ```
+/// extern crate bcmp;
+/// extern crate futures;
+///
+/// use bcmp::AlgoSpec;
+/// use bcmp::asyncmatch::MatchStream;
+/// use futures::executor::block_on;
+/// use futures::stream::StreamExt;
+///
+/// fn main() {
+///     let a = b"abcdefg";
+///     let b = b"012abc34cdef56efg78abcdefg";
+///     let matches = block_on(MatchStream::new(a, b, AlgoSpec::HashMatch(2)).collect::<Vec<_>>());
+///     assert!(matches.len() == 4);
+/// }
+/// ```
+pub struct MatchStream<'a> {
+    iter: MatchIterator<'a>,
+    budget: usize,
+    served: usize,
+}
+
+impl<'a> MatchStream<'a> {
+    /// Build a new `MatchStream` from two pieces of data to compare and an
+    /// [`AlgoSpec`](../enum.AlgoSpec.html).
+    pub fn new(first: &'a [u8], second: &'a [u8], algo_spec: AlgoSpec) -> MatchStream<'a> {
+        MatchStream::from_iter(MatchIterator::new(first, second, algo_spec))
+    }
+
+    /// Wrap an existing [`MatchIterator`](../struct.MatchIterator.html).
+    pub fn from_iter(iter: MatchIterator<'a>) -> MatchStream<'a> {
+        MatchStream {
+            iter,
+            budget: DEFAULT_BUDGET,
+            served: 0,
+        }
+    }
+
+    /// Set the number of matches returned before yielding back to the executor.
+    pub fn budget(mut self, budget: usize) -> MatchStream<'a> {
+        self.budget = budget.max(1);
+        self
+    }
+}
+
+impl<'a> Stream for MatchStream<'a> {
+    type Item = Match;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Match>> {
+        let this = self.get_mut();
+        if this.served >= this.budget {
+            this.served = 0;
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        this.served += 1;
+        Poll::Ready(this.iter.next())
+    }
+}
+
+/// A future reading an `AsyncRead` until its end.
+pub struct ReadAll<R: AsyncRead + Unpin> {
+    reader: R,
+    buffer: Vec<u8>,
+}
+
+impl<R: AsyncRead + Unpin> Future for ReadAll<R> {
+    type Output = Result<Vec<u8>>;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<Vec<u8>>> {
+        let this = self.get_mut();
+        let mut chunk = [0u8; 8192];
+        loop {
+            match Pin::new(&mut this.reader).poll_read(cx, &mut chunk) {
+                Poll::Ready(Ok(0)) => return Poll::Ready(Ok(std::mem::take(&mut this.buffer))),
+                Poll::Ready(Ok(n)) => this.buffer.extend_from_slice(&chunk[..n]),
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// Read `reader` until its end.
+pub fn read_all<R: AsyncRead + Unpin>(reader: R) -> ReadAll<R> {
+    ReadAll {
+        reader,
+        buffer: Vec::new(),
+    }
+}
+
+/// Asynchronous variant of [`patch_set`](../fn.patch_set.html) reading both pieces of data from
+/// `AsyncRead` sources, concurrently.
+pub fn patch_set_async<A, B>(first: A, second: B, algo_spec: AlgoSpec) -> impl Future<Output=Result<Vec<Match>>>
+    where A: AsyncRead + Unpin, B: AsyncRead + Unpin {
+    try_join(read_all(first), read_all(second)).map_ok(move |(first, second)| patch_set(&first, &second, algo_spec))
+}
+
+/// Asynchronous variant of [`SuffixArray::open`](../suffixarray/struct.SuffixArray.html#method.open)
+/// reading the index from an `AsyncRead` source.
+pub fn read_suffix_array<R: AsyncRead + Unpin>(reader: R) -> impl Future<Output=Result<SuffixArray<Vec<u8>>>> {
+    read_all(reader).and_then(|bytes| futures::future::ready(SuffixArray::from_bytes(bytes)))
+}
+
+/// Asynchronous variant of [`HashIndex::open`](../hashindex/struct.HashIndex.html#method.open)
+/// reading the index from an `AsyncRead` source.
+pub fn read_hash_index<R: AsyncRead + Unpin>(reader: R) -> impl Future<Output=Result<HashIndex<Vec<u8>>>> {
+    read_all(reader).and_then(|bytes| futures::future::ready(HashIndex::from_bytes(bytes)))
+}
@@ -0,0 +1,320 @@
+//! Command line interface to the bcmp library, built with the `cli` feature.
+
+extern crate bcmp;
+
+use std::env;
+use std::fs::{File, read};
+use std::io::{BufWriter, Write};
+use std::process::exit;
+
+use bcmp::{AlgoSpec, Match, Region, coverage, longest_common_substring, patch_set_with_min_copy, unique_strings};
+use bcmp::patch::{Compression, Delta, Metadata, PatchInfo, dictionary};
+use bcmp::walker::{DiffWalker, Segment};
+
+const USAGE: &str = "Usage: bcmp [OPTIONS] <COMMAND> [ARGS]
+
+Commands:
+    diff FIRST SECOND                 Compare two files
+    lcs FIRST SECOND                  Print the longest common substring
+    unique FIRST SECOND               Print the regions of SECOND which can't be found in FIRST
+    patch create FIRST SECOND DELTA   Write the delta building SECOND from FIRST
+    patch apply FIRST DELTA OUTPUT    Rebuild a file from FIRST and a delta
+    patch info DELTA                  Print the header of a delta
+    patch check FIRST DELTA           Check that a delta applies to FIRST without writing anything
+    patch dict FIRST SECOND DICT      Write a zstd --patch-from dictionary for SECOND and print
+                                      the regions of FIRST it is made of
+
+Options:
+    -a, --algo <tree|hash|array>      Matching algorithm (default: tree)
+    -m, --mml <N>                     Minimal matching length (default: 8)
+    -c, --min-copy <N>                Minimal length of the copies of a patch (default: 0)
+    -f, --format <text|json>          Output format (default: text)
+    -v, --view <patches|hunks|stats>  What diff prints (default: patches)
+    -z, --compress                    Compress the deltas written by patch create
+    --codec <lz|zstd|lzma>            Compress the deltas with this codec (default: lz), zstd and
+                                      lzma need the features of the same name
+    -h, --help                        Print this help";
+
+const HASH_LENGTHS: &[usize] = &[1, 2, 3, 4, 5, 6, 7, 8, 10, 12, 14, 16, 20, 24, 28, 32, 40, 48, 56, 64];
+
+#[derive(Clone,Copy,PartialEq)]
+enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Clone,Copy,PartialEq)]
+enum View {
+    Patches,
+    Hunks,
+    Stats,
+}
+
+struct Options {
+    algo_spec: AlgoSpec,
+    min_copy: usize,
+    format: Format,
+    view: View,
+    compression: Compression,
+    args: Vec<String>,
+}
+
+fn parse_args(mut raw: env::Args) -> Result<Options, String> {
+    let mut algo: fn(usize) -> AlgoSpec = AlgoSpec::TreeMatch;
+    let mut mml = 8;
+    let mut min_copy = 0;
+    let mut format = Format::Text;
+    let mut view = View::Patches;
+    let mut compression = Compression::None;
+    let mut args = Vec::new();
+    raw.next();
+    while let Some(arg) = raw.next() {
+        let mut value = |name: &str| raw.next().ok_or(format!("Missing value for {}", name));
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                exit(0);
+            },
+            "-a" | "--algo" => match value(&arg)?.as_str() {
+                "tree" => algo = AlgoSpec::TreeMatch,
+                "hash" => algo = AlgoSpec::HashMatch,
+                "array" => algo = AlgoSpec::SuffixArrayMatch,
+                other => return Err(format!("Unknown algorithm {}", other))
+            },
+            "-m" | "--mml" => {
+                mml = value(&arg)?.parse().map_err(|e| format!("Invalid minimal matching length: {}", e))?;
+            },
+            "-c" | "--min-copy" => {
+                min_copy = value(&arg)?.parse().map_err(|e| format!("Invalid minimal copy length: {}", e))?;
+            },
+            "-f" | "--format" => match value(&arg)?.as_str() {
+                "text" => format = Format::Text,
+                "json" => format = Format::Json,
+                other => return Err(format!("Unknown format {}", other))
+            },
+            "-v" | "--view" => match value(&arg)?.as_str() {
+                "patches" => view = View::Patches,
+                "hunks" => view = View::Hunks,
+                "stats" => view = View::Stats,
+                other => return Err(format!("Unknown view {}", other))
+            },
+            "-z" | "--compress" => compression = Compression::Lz,
+            "--codec" => match value(&arg)?.as_str() {
+                "lz" => compression = Compression::Lz,
+                #[cfg(feature = "zstd")]
+                "zstd" => compression = Compression::Zstd,
+                #[cfg(feature = "lzma")]
+                "lzma" => compression = Compression::Lzma,
+                other => return Err(format!("Unsupported codec {}", other))
+            },
+            _ if arg.starts_with('-') && arg.len() > 1 => return Err(format!("Unknown option {}", arg)),
+            _ => args.push(arg)
+        }
+    }
+    if mml == 0 {
+        return Err(String::from("The minimal matching length must be at least 1"));
+    }
+    let algo_spec = algo(mml);
+    if let AlgoSpec::HashMatch(_) = algo_spec {
+        if !HASH_LENGTHS.contains(&mml) {
+            return Err(format!("Unsupported minimal matching length for hash, use one of {:?}", HASH_LENGTHS));
+        }
+    }
+    Ok(Options {
+        algo_spec,
+        min_copy,
+        format,
+        view,
+        compression,
+        args,
+    })
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>, String> {
+    read(path).map_err(|e| format!("Could not read {}: {}", path, e))
+}
+
+fn read_pair(first: &str, second: &str) -> Result<(Vec<u8>, Vec<u8>), String> {
+    Ok((read_file(first)?, read_file(second)?))
+}
+
+fn match_json(m: &Match) -> String {
+    format!("{{\"first_pos\":{},\"second_pos\":{},\"length\":{}}}", m.first_pos, m.second_pos, m.length)
+}
+
+fn region_json(r: &Region) -> String {
+    format!("{{\"start\":{},\"end\":{}}}", r.start, r.end)
+}
+
+fn print_match(m: &Match) {
+    println!("second[0x{:x}..0x{:x}] == first[0x{:x}..0x{:x}]", m.second_pos, m.second_end(), m.first_pos, m.first_end());
+}
+
+fn diff(opts: &Options, first: &[u8], second: &[u8]) {
+    match opts.view {
+        View::Patches => {
+            let patches = patch_set_with_min_copy(first, second, opts.algo_spec, opts.min_copy);
+            match opts.format {
+                Format::Text => patches.iter().for_each(print_match),
+                Format::Json => println!("[{}]", patches.iter().map(match_json).collect::<Vec<_>>().join(",")),
+            }
+        },
+        View::Hunks => {
+            let mut json = Vec::new();
+            for segment in DiffWalker::new(first, second, opts.algo_spec) {
+                match (opts.format, segment) {
+                    (Format::Text, Segment::Matched(m)) => print_match(&m),
+                    (Format::Text, Segment::OnlyFirst(r)) => println!("- first{}", r),
+                    (Format::Text, Segment::OnlySecond(r)) => println!("+ second{}", r),
+                    (Format::Json, Segment::Matched(m)) => json.push(format!("{{\"matched\":{}}}", match_json(&m))),
+                    (Format::Json, Segment::OnlyFirst(r)) => json.push(format!("{{\"only_first\":{}}}", region_json(&r))),
+                    (Format::Json, Segment::OnlySecond(r)) => json.push(format!("{{\"only_second\":{}}}", region_json(&r))),
+                }
+            }
+            if opts.format == Format::Json {
+                println!("[{}]", json.join(","));
+            }
+        },
+        View::Stats => {
+            let patches = patch_set_with_min_copy(first, second, opts.algo_spec, opts.min_copy).len();
+            let covered = coverage(first, second, opts.algo_spec).total_len();
+            let unique = second.len() - covered;
+            let similarity = if second.is_empty() { 1.0 } else { covered as f64 / second.len() as f64 };
+            match opts.format {
+                Format::Text => {
+                    println!("first:      {} bytes", first.len());
+                    println!("second:     {} bytes", second.len());
+                    println!("patches:    {}", patches);
+                    println!("covered:    {} bytes", covered);
+                    println!("unique:     {} bytes", unique);
+                    println!("similarity: {:.4}", similarity);
+                },
+                Format::Json => println!("{{\"first_len\":{},\"second_len\":{},\"patches\":{},\"covered\":{},\"unique\":{},\"similarity\":{}}}",
+                    first.len(), second.len(), patches, covered, unique, similarity),
+            }
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn print_info(opts: &Options, info: &PatchInfo) {
+    let compression = match info.compression {
+        Compression::None => "none",
+        Compression::Lz => "lz",
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => "zstd",
+        #[cfg(feature = "lzma")]
+        Compression::Lzma => "lzma",
+        _ => "unknown",
+    };
+    match (opts.format, &info.metadata) {
+        (Format::Text, metadata) => {
+            println!("source length: {}", info.source_len);
+            println!("target length: {}", info.target_len);
+            println!("operations:    {}", info.ops);
+            println!("compression:   {}", compression);
+            if let Some(m) = metadata {
+                println!("source hash:   {}", hex(&m.source_hash));
+                println!("target hash:   {}", hex(&m.target_hash));
+                println!("algorithm:     {:?}", m.algo_spec);
+                println!("version:       {}", m.version);
+            }
+        },
+        (Format::Json, None) => println!("{{\"source_len\":{},\"target_len\":{},\"ops\":{},\"compression\":\"{}\"}}",
+                                         info.source_len, info.target_len, info.ops, compression),
+        (Format::Json, Some(m)) => println!("{{\"source_len\":{},\"target_len\":{},\"ops\":{},\"compression\":\"{}\",\"source_hash\":\"{}\",\"target_hash\":\"{}\",\"algorithm\":\"{:?}\",\"version\":\"{}\"}}",
+                                            info.source_len, info.target_len, info.ops, compression, hex(&m.source_hash), hex(&m.target_hash), m.algo_spec, m.version),
+    }
+}
+
+fn run(opts: &Options) -> Result<(), String> {
+    let args: Vec<&str> = opts.args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["diff", first, second] => {
+            let (first, second) = read_pair(first, second)?;
+            diff(opts, &first, &second);
+        },
+        ["lcs", first, second] => {
+            let (first, second) = read_pair(first, second)?;
+            let m = longest_common_substring(&first, &second, opts.algo_spec);
+            match opts.format {
+                Format::Text => print_match(&m),
+                Format::Json => println!("{}", match_json(&m)),
+            }
+        },
+        ["unique", first, second] => {
+            let (first, second) = read_pair(first, second)?;
+            let regions = unique_strings(&first, &second, opts.algo_spec);
+            match opts.format {
+                Format::Text => regions.iter().for_each(|r| println!("second{}", r)),
+                Format::Json => println!("[{}]", regions.iter().map(region_json).collect::<Vec<_>>().join(",")),
+            }
+        },
+        ["patch", "create", first, second, output] => {
+            let (first, second) = (read_file(first)?, read_file(second)?);
+            let delta = Delta::with_min_copy(&first, &second, opts.algo_spec, opts.min_copy);
+            let metadata = Metadata::new(&first, &second, opts.algo_spec);
+            let file = File::create(output).map_err(|e| format!("Could not create {}: {}", output, e))?;
+            let mut w = BufWriter::new(file);
+            delta.write_with_metadata_to(&mut w, &metadata, opts.compression).and_then(|_| w.flush())
+                .map_err(|e| format!("Could not write {}: {}", output, e))?;
+        },
+        ["patch", "apply", first, delta, output] => {
+            let encoded = read_file(delta)?;
+            let info = PatchInfo::read(&mut &encoded[..]).map_err(|e| format!("Invalid delta {}: {}", delta, e))?;
+            let source = read_file(first)?;
+            info.check_source(&source).map_err(|e| format!("The delta does not apply to {}: {}", first, e))?;
+            let delta = Delta::read_from(&mut &encoded[..]).map_err(|e| format!("Invalid delta {}: {}", delta, e))?;
+            let target = delta.apply(&source)
+                .and_then(|target| info.check_target(&target).map(|_| target))
+                .map_err(|e| format!("Could not apply the delta: {}", e))?;
+            std::fs::write(output, target).map_err(|e| format!("Could not write {}: {}", output, e))?;
+        },
+        ["patch", "check", first, delta] => {
+            let encoded = read_file(delta)?;
+            let info = PatchInfo::read(&mut &encoded[..]).map_err(|e| format!("Invalid delta {}: {}", delta, e))?;
+            let source = read_file(first)?;
+            let delta = Delta::read_from(&mut &encoded[..]).map_err(|e| format!("Invalid delta {}: {}", delta, e))?;
+            info.check_source(&source)
+                .and_then(|_| delta.apply(&source))
+                .and_then(|target| info.check_target(&target))
+                .map_err(|e| format!("The delta does not apply to {}: {}", first, e))?;
+        },
+        ["patch", "info", delta] => {
+            let encoded = read_file(delta)?;
+            let info = PatchInfo::read(&mut &encoded[..]).map_err(|e| format!("Invalid delta {}: {}", delta, e))?;
+            print_info(opts, &info);
+        },
+        ["patch", "dict", first, second, output] => {
+            let (first, second) = read_pair(first, second)?;
+            let dict = dictionary(&first, &second, opts.algo_spec);
+            std::fs::write(output, &dict.data).map_err(|e| format!("Could not write {}: {}", output, e))?;
+            match opts.format {
+                Format::Text => dict.regions.iter().for_each(|r| println!("first{}", r)),
+                Format::Json => println!("[{}]", dict.regions.iter().map(region_json).collect::<Vec<_>>().join(",")),
+            }
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            exit(2);
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    let opts = match parse_args(env::args()) {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(2);
+        }
+    };
+    if let Err(e) = run(&opts) {
+        eprintln!("{}", e);
+        exit(1);
+    }
+}
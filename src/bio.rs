@@ -0,0 +1,141 @@
+//! FASTA and FASTQ input helpers, available with the `bio` feature.
+//!
+//! The readers load each sequence into a byte buffer while preserving the record names, and the
+//! pairwise helpers run the comparison functions over every pair of records.
+
+use std::io::{BufRead, Error, ErrorKind, Result};
+
+use {AlgoSpec, Match, MatchIterator, longest_common_substring};
+
+/// A sequence record read from a FASTA or FASTQ file.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Record {
+    /// The header line without its leading `>` or `@`.
+    pub name: String,
+    /// The sequence with line breaks removed.
+    pub seq: Vec<u8>,
+    /// The quality string for FASTQ records, `None` for FASTA records.
+    pub quality: Option<Vec<u8>>,
+}
+
+/// The result of comparing two records from two lists of [`Record`](struct.Record.html).
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct RecordMatches {
+    /// Index of the record in the first list.
+    pub first: usize,
+    /// Index of the record in the second list.
+    pub second: usize,
+    /// The matches between the two sequences.
+    pub matches: Vec<Match>,
+}
+
+fn trim_line(line: &str) -> &str {
+    line.trim_end_matches(['\n', '\r'])
+}
+
+/// Read all the records of a FASTA file. Sequences spanning multiple lines are concatenated and 
+/// blank lines are ignored.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::bio::read_fasta;
+///
+/// let fasta = ">seq1 first\nACGT\nACGT\n>seq2\nTTTT\n";
+/// let records = read_fasta(fasta.as_bytes()).unwrap();
+/// assert!(records[0].name == "seq1 first");
+/// assert!(records[0].seq == b"ACGTACGT");
+/// assert!(records[1].seq == b"TTTT");
+/// ```
+pub fn read_fasta<R: BufRead>(reader: R) -> Result<Vec<Record>> {
+    let mut records = Vec::<Record>::new();
+    for line in reader.lines() {
+        let line = line?;
+        let line = trim_line(&line);
+        if let Some(name) = line.strip_prefix('>') {
+            records.push(Record {
+                name: name.to_string(),
+                seq: Vec::new(),
+                quality: None,
+            });
+        }
+        else if !line.trim().is_empty() {
+            match records.last_mut() {
+                Some(record) => record.seq.extend_from_slice(line.trim().as_bytes()),
+                None => return Err(Error::new(ErrorKind::InvalidData, "FASTA sequence data before the first header"))
+            }
+        }
+    }
+    Ok(records)
+}
+
+/// Read all the records of a FASTQ file. Each record must use the common four lines layout: 
+/// `@name`, sequence, `+` separator and quality string.
+pub fn read_fastq<R: BufRead>(reader: R) -> Result<Vec<Record>> {
+    let mut records = Vec::<Record>::new();
+    let mut lines = reader.lines();
+    while let Some(header) = lines.next() {
+        let header = header?;
+        let header = trim_line(&header);
+        if header.trim().is_empty() {
+            continue;
+        }
+        let name = match header.strip_prefix('@') {
+            Some(name) => name.to_string(),
+            None => return Err(Error::new(ErrorKind::InvalidData, "FASTQ record does not start with '@'"))
+        };
+        let mut next_line = || -> Result<String> {
+            match lines.next() {
+                Some(line) => Ok(trim_line(&line?).to_string()),
+                None => Err(Error::new(ErrorKind::UnexpectedEof, "truncated FASTQ record"))
+            }
+        };
+        let seq = next_line()?;
+        if !next_line()?.starts_with('+') {
+            return Err(Error::new(ErrorKind::InvalidData, "FASTQ separator line does not start with '+'"));
+        }
+        let quality = next_line()?;
+        if quality.len() != seq.len() {
+            return Err(Error::new(ErrorKind::InvalidData, "FASTQ quality and sequence lengths differ"));
+        }
+        records.push(Record {
+            name,
+            seq: seq.into_bytes(),
+            quality: Some(quality.into_bytes()),
+        });
+    }
+    Ok(records)
+}
+
+/// Return the longest common substring of every pair of records, the first one taken from 
+/// `first` and the second from `second`. The result is ordered by first record, then second record.
+pub fn pairwise_lcs(first: &[Record], second: &[Record], algo_spec: AlgoSpec) -> Vec<RecordMatches> {
+    let mut results = Vec::<RecordMatches>::with_capacity(first.len() * second.len());
+    for (i, a) in first.iter().enumerate() {
+        for (j, b) in second.iter().enumerate() {
+            let m = longest_common_substring(&a.seq, &b.seq, algo_spec);
+            results.push(RecordMatches {
+                first: i,
+                second: j,
+                matches: if m.length > 0 { vec![m] } else { Vec::new() },
+            });
+        }
+    }
+    results
+}
+
+/// Return all the matches of every pair of records, the first one taken from `first` and the 
+/// second from `second`. The result is ordered by first record, then second record.
+pub fn pairwise_matches(first: &[Record], second: &[Record], algo_spec: AlgoSpec) -> Vec<RecordMatches> {
+    let mut results = Vec::<RecordMatches>::with_capacity(first.len() * second.len());
+    for (i, a) in first.iter().enumerate() {
+        for (j, b) in second.iter().enumerate() {
+            results.push(RecordMatches {
+                first: i,
+                second: j,
+                matches: MatchIterator::new(&a.seq, &b.seq, algo_spec).collect(),
+            });
+        }
+    }
+    results
+}
@@ -0,0 +1,88 @@
+//! Attribution of the content of a piece of data to several candidate ancestors.
+//!
+//! A [`patch_set`](../fn.patch_set.html) or a [`provenance`](../fn.provenance.html) map explains a
+//! target using a single source. When the target was assembled from several earlier pieces of
+//! data, like a binary linked from the objects of different builds,
+//! [`blame`](fn.blame.html) compares it with all of them at once and attributes each region to the
+//! ancestor it most plausibly came from.
+
+use {AlgoSpec, Region, patch_set};
+
+/// Where a region of the target was copied from.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct Origin {
+    /// The index of the ancestor in the list passed to [`blame`](fn.blame.html).
+    pub ancestor: usize,
+    /// The offset in the ancestor of the first byte of the region.
+    pub offset: usize,
+}
+
+/// A region of the target with its origin, as returned by [`blame`](fn.blame.html).
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct Blame {
+    /// The region of the target.
+    pub region: Region,
+    /// Where the region was copied from, or `None` if it can't be found in any ancestor.
+    pub origin: Option<Origin>,
+}
+
+/// Attribute every byte of `target` to one of the `ancestors` or mark it as novel.
+///
+/// The [`patch_set`](../fn.patch_set.html) of each ancestor is computed and every byte is
+/// attributed to the longest patch covering it, a long copied run being a much stronger evidence
+/// than a short one. On ties the ancestor appearing first in the list wins, so ancestors should be
+/// listed from the most to the least likely. The returned regions are sorted, cover the whole
+/// target and each of them is copied contiguously from a single ancestor or is novel.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::{AlgoSpec, Region};
+/// use bcmp::blame::{Blame, Origin, blame};
+///
+/// let old: &[u8] = b"0123456789abcdef";
+/// let new: &[u8] = b"ABCDEFGHIJKLMNOP";
+/// let blamed = blame(&[old, new], b"0123ABCDEF++cdef", AlgoSpec::TreeMatch(4));
+/// assert!(blamed == [
+///     Blame { region: Region::new(0, 4), origin: Some(Origin { ancestor: 0, offset: 0 }) },
+///     Blame { region: Region::new(4, 10), origin: Some(Origin { ancestor: 1, offset: 0 }) },
+///     Blame { region: Region::new(10, 12), origin: None },
+///     Blame { region: Region::new(12, 16), origin: Some(Origin { ancestor: 0, offset: 12 }) },
+/// ]);
+/// ```
+pub fn blame(ancestors: &[&[u8]], target: &[u8], algo_spec: AlgoSpec) -> Vec<Blame> {
+    // Length of the longest patch covering each byte, with its origin
+    let mut best: Vec<(usize, Option<Origin>)> = vec![(0, None); target.len()];
+    let minimal_length = algo_spec.minimal_length().max(1);
+    for (ancestor, data) in ancestors.iter().enumerate() {
+        if data.len() < minimal_length || target.len() < minimal_length {
+            continue;
+        }
+        for patch in patch_set(data, target, algo_spec) {
+            for (i, byte) in best[patch.second_pos..patch.second_end()].iter_mut().enumerate() {
+                if patch.length > byte.0 {
+                    *byte = (patch.length, Some(Origin { ancestor, offset: patch.first_pos + i }));
+                }
+            }
+        }
+    }
+    let mut blamed: Vec<Blame> = Vec::new();
+    for (pos, &(_, origin)) in best.iter().enumerate() {
+        if let Some(last) = blamed.last_mut() {
+            let continued = match (last.origin, origin) {
+                (None, None) => true,
+                (Some(l), Some(o)) => l.ancestor == o.ancestor && l.offset + last.region.len() == o.offset,
+                _ => false
+            };
+            if continued {
+                last.region.end = pos + 1;
+                continue;
+            }
+        }
+        blamed.push(Blame {
+            region: Region::new(pos, pos + 1),
+            origin,
+        });
+    }
+    blamed
+}
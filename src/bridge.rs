@@ -0,0 +1,92 @@
+//! Bridging of small gaps between exact matches.
+//!
+//! A single substituted byte splits an otherwise identical region into two maximal matches, so a
+//! few localized edits, like patched constants or changed timestamps, shatter what is conceptually
+//! one match into dozens. Matches on the same diagonal, the same `first_pos - second_pos`, are
+//! aligned with each other: [`bridge_gaps`](fn.bridge_gaps.html) merges the ones separated by at
+//! most a given number of bytes into an [`ApproximateMatch`](struct.ApproximateMatch.html) which
+//! records the positions of the gaps. Since both matches are on the same diagonal, a gap has the
+//! same length in both pieces of data: bridging handles substitutions, not insertions or
+//! deletions.
+//!
+//! # Examples
+//!
+//! ```
+//! use bcmp::{AlgoSpec, Match, MatchIterator, Region};
+//! use bcmp::bridge::bridge_gaps;
+//!
+//! let a = b"The quick brown fox jumps over the lazy dog";
+//! let b = b"The quick brown cat jumps over the lazy dog";
+//! let bridged = bridge_gaps(MatchIterator::new(a, b, AlgoSpec::TreeMatch(4)), 4);
+//! assert!(bridged.len() == 1);
+//! assert!(bridged[0].span == Match::new(0, 0, a.len()));
+//! assert!(bridged[0].gaps == [Region::new(16, 19)]);
+//! ```
+
+use {Match, Region};
+
+/// A match between two pieces of data which are equal except in a few gaps.
+#[derive(Clone,Debug,PartialEq,Eq,PartialOrd,Ord,Hash)]
+pub struct ApproximateMatch {
+    /// The extent of the match, gaps included.
+    pub span: Match,
+    /// The regions of the second piece of data which don't match, in ascending order. The
+    /// corresponding regions of the first piece of data are at the same offset from the start of
+    /// the span.
+    pub gaps: Vec<Region>,
+}
+
+impl ApproximateMatch {
+    /// The number of matching bytes: the length of the span minus the length of the gaps.
+    pub fn matched_len(&self) -> usize {
+        self.span.length - self.gaps.iter().map(Region::len).sum::<usize>()
+    }
+
+    /// The exact matches between the gaps, in ascending order.
+    pub fn parts(&self) -> Vec<Match> {
+        let offset = |pos: usize| pos - self.span.second_pos;
+        let mut parts = Vec::with_capacity(self.gaps.len() + 1);
+        let mut start = self.span.second_pos;
+        for gap in self.gaps.iter().chain(Some(&Region::new(self.span.second_end(), self.span.second_end()))) {
+            parts.push(Match::new(self.span.first_pos + offset(start), start, gap.start - start));
+            start = gap.end;
+        }
+        parts
+    }
+}
+
+/// Merge the matches on the same diagonal separated by at most `max_gap` bytes into approximate
+/// matches, sorted in the natural order of their span.
+///
+/// The matches can be given in any order, for example straight from a
+/// [`MatchIterator`](../struct.MatchIterator.html). Overlapping or adjacent matches on the same
+/// diagonal are merged without a gap, and the matches which aren't merged are returned as
+/// approximate matches without gaps. A `max_gap` of 0 thus only merges the matches touching each
+/// other.
+pub fn bridge_gaps<I: IntoIterator<Item=Match>>(matches: I, max_gap: usize) -> Vec<ApproximateMatch> {
+    let mut matches: Vec<Match> = matches.into_iter().collect();
+    // Diagonals are computed modulo 2^64 so that they are only compared for equality
+    matches.sort_by_key(|m| (m.first_pos.wrapping_sub(m.second_pos), m.second_pos));
+    let mut bridged: Vec<ApproximateMatch> = Vec::new();
+    let mut diagonal = None;
+    for m in matches {
+        let d = m.first_pos.wrapping_sub(m.second_pos);
+        if let Some(last) = bridged.last_mut() {
+            let end = last.span.second_end();
+            if diagonal == Some(d) && m.second_pos <= end.saturating_add(max_gap) {
+                if m.second_pos > end {
+                    last.gaps.push(Region::new(end, m.second_pos));
+                }
+                last.span.length = last.span.length.max(m.second_end() - last.span.second_pos);
+                continue;
+            }
+        }
+        diagonal = Some(d);
+        bridged.push(ApproximateMatch {
+            span: m,
+            gaps: Vec::new(),
+        });
+    }
+    bridged.sort();
+    bridged
+}
@@ -0,0 +1,364 @@
+//! Chaining of matches into collinear alignments.
+//!
+//! A coverage or similarity score tells how much content two pieces of data share but not whether
+//! it appears in the same order. A chain is a list of matches which don't overlap and appear in the
+//! same order in both pieces of data: the heaviest chain measures the content shared in the same
+//! order, which separates an edited file from a reshuffled one.
+//!
+//! [`chain_align`](fn.chain_align.html) builds on this to align heavily rearranged inputs the way
+//! MUMmer does: anchors are extracted, chained with gap penalties and the gaps between them are
+//! optionally closed with a finer comparison.
+
+use {AlgoSpec, Match, MatchIterator, Region};
+use suffixarray::SuffixArray;
+
+/// A collinear chain of matches, as returned by [`collinear_chain`](fn.collinear_chain.html).
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Chain {
+    /// The matches of the chain, in ascending order of position in both pieces of data.
+    pub matches: Vec<Match>,
+    /// The number of bytes covered by the chain in each piece of data.
+    pub covered: usize,
+}
+
+// Fenwick tree answering prefix maximum queries of (weight, match index), empty positions holding 
+// (empty, usize::MAX)
+struct PrefixMax<W> {
+    tree: Vec<(W, usize)>,
+    empty: W,
+}
+
+impl<W: Copy + Ord> PrefixMax<W> {
+    fn new(size: usize, empty: W) -> PrefixMax<W> {
+        PrefixMax {
+            tree: vec![(empty, usize::MAX); size + 1],
+            empty,
+        }
+    }
+
+    fn update(&mut self, pos: usize, value: (W, usize)) {
+        let mut i = pos + 1;
+        while i < self.tree.len() {
+            if value.0 > self.tree[i].0 {
+                self.tree[i] = value;
+            }
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    // Maximum over the positions strictly lower than end
+    fn query(&self, end: usize) -> (W, usize) {
+        let mut best = (self.empty, usize::MAX);
+        let mut i = end;
+        while i > 0 {
+            if self.tree[i].0 > best.0 {
+                best = self.tree[i];
+            }
+            i -= i & i.wrapping_neg();
+        }
+        best
+    }
+}
+
+/// Return the heaviest collinear chain of `matches`: the non overlapping matches, in ascending
+/// order of position in both pieces of data, with the largest total length.
+///
+/// This is a longest increasing subsequence over the positions in both pieces of data, weighted
+/// by the match lengths, computed in `O(n log n)`. Among chains of the same weight, the result is
+/// deterministic but unspecified.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::Match;
+/// use bcmp::chain::collinear_chain;
+///
+/// let matches = [Match::new(0, 0, 10), Match::new(30, 12, 5), Match::new(12, 20, 8)];
+/// let chain = collinear_chain(&matches);
+/// assert!(chain.matches == [Match::new(0, 0, 10), Match::new(12, 20, 8)]);
+/// assert!(chain.covered == 18);
+/// ```
+pub fn collinear_chain(matches: &[Match]) -> Chain {
+    let mut matches = matches.to_vec();
+    matches.sort();
+    // Compressed end positions in first
+    let mut first_ends: Vec<usize> = matches.iter().map(|m| m.first_end()).collect();
+    first_ends.sort_unstable();
+    first_ends.dedup();
+    // Matches become available as predecessors once their end in second is reached
+    let mut by_second_end: Vec<usize> = (0..matches.len()).collect();
+    by_second_end.sort_by_key(|&i| matches[i].second_end());
+    let mut available = PrefixMax::new(first_ends.len(), 0);
+    let mut weights = vec![0; matches.len()];
+    let mut previous = vec![usize::MAX; matches.len()];
+    let mut next_available = 0;
+    let mut best = (0, usize::MAX);
+    for i in 0..matches.len() {
+        let m = matches[i];
+        while next_available < by_second_end.len() && matches[by_second_end[next_available]].second_end() <= m.second_pos {
+            let j = by_second_end[next_available];
+            available.update(first_ends.binary_search(&matches[j].first_end()).unwrap(), (weights[j], j));
+            next_available += 1;
+        }
+        // Predecessors ending in first at or before the start of this match
+        let (weight, predecessor) = available.query(first_ends.partition_point(|&e| e <= m.first_pos));
+        weights[i] = weight + m.length;
+        previous[i] = predecessor;
+        if weights[i] > best.0 {
+            best = (weights[i], i);
+        }
+    }
+    let mut chain = Vec::new();
+    let mut i = best.1;
+    while i != usize::MAX {
+        chain.push(matches[i]);
+        i = previous[i];
+    }
+    chain.reverse();
+    Chain {
+        matches: chain,
+        covered: best.0,
+    }
+}
+
+/// Return the synteny score of two byte slices, between 0 and 1: the fraction of the bytes of
+/// both slices covered by the heaviest [`collinear_chain`](fn.collinear_chain.html) of their
+/// matches.
+///
+/// Unlike [`similarity`](../fn.similarity.html), which only measures shared content, reordered
+/// content lowers the score. Two empty slices are considered identical.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::{AlgoSpec, similarity};
+/// use bcmp::chain::synteny;
+///
+/// let a = b"0123456789abcdefghij";
+/// let b = b"abcdefghij0123456789";
+/// assert!(similarity(a, b, AlgoSpec::TreeMatch(4)) == 1.0);
+/// assert!(synteny(a, b, AlgoSpec::TreeMatch(4)) == 0.5);
+/// ```
+pub fn synteny(first: &[u8], second: &[u8], algo_spec: AlgoSpec) -> f64 {
+    let total = first.len() + second.len();
+    if total == 0 {
+        return 1.0;
+    }
+    if first.len() < algo_spec.minimal_length() || second.len() < algo_spec.minimal_length() {
+        return 0.0;
+    }
+    let matches: Vec<Match> = MatchIterator::new(first, second, algo_spec).collect();
+    let chain = collinear_chain(&matches);
+    (2 * chain.covered) as f64 / total as f64
+}
+
+/// Options of [`chain_align`](fn.chain_align.html).
+#[derive(Clone,Copy,Debug)]
+pub struct ChainOptions {
+    /// The algorithm and minimal length used to find the anchors.
+    pub anchors: AlgoSpec,
+    /// Only use the anchors whose content occurs exactly once in each piece of data, the maximal
+    /// unique matches, instead of all the maximal exact matches.
+    pub unique_anchors: bool,
+    /// The cost of each byte of shift between the diagonals of two consecutive anchors, subtracted
+    /// from the length of the chain.
+    pub gap_penalty: usize,
+    /// The algorithm used to find the matches inside the gaps between the anchors, if any.
+    pub gap_closing: Option<AlgoSpec>,
+}
+
+impl Default for ChainOptions {
+    /// Unique anchors of at least 20 bytes, a gap penalty of 1 and gaps closed with matches of at
+    /// least 8 bytes.
+    fn default() -> ChainOptions {
+        ChainOptions {
+            anchors: AlgoSpec::TreeMatch(20),
+            unique_anchors: true,
+            gap_penalty: 1,
+            gap_closing: Some(AlgoSpec::TreeMatch(8)),
+        }
+    }
+}
+
+/// A gap between two consecutive anchors of a [`ChainAlignment`](struct.ChainAlignment.html).
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Gap {
+    /// The region of the first piece of data between the anchors.
+    pub first: Region,
+    /// The region of the second piece of data between the anchors.
+    pub second: Region,
+    /// The collinear matches found inside the gap when gap closing is enabled.
+    pub matches: Vec<Match>,
+}
+
+/// The result of [`chain_align`](fn.chain_align.html).
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct ChainAlignment {
+    /// The chained anchors, in ascending order of position in both pieces of data.
+    pub anchors: Vec<Match>,
+    /// The gaps before, between and after the anchors where both regions aren't empty, in order.
+    pub gaps: Vec<Gap>,
+}
+
+// Keep the anchors whose content occurs only once in each piece of data
+pub(crate) fn unique_anchors(first: &[u8], second: &[u8], anchors: Vec<Match>) -> Vec<Match> {
+    let first_array = SuffixArray::new(first);
+    let second_array = SuffixArray::new(second);
+    anchors.into_iter().filter(|m| {
+        let pattern = &first[m.first_pos..m.first_end()];
+        first_array.find(first, pattern).len() == 1 && second_array.find(second, pattern).len() == 1
+    }).collect()
+}
+
+// The diagonal of a match, second_pos - first_pos
+fn diagonal(m: &Match) -> i128 {
+    m.second_pos as i128 - m.first_pos as i128
+}
+
+// Chain the anchors maximizing their total length minus the penalty of the diagonal shifts
+pub(crate) fn chain_anchors(mut anchors: Vec<Match>, gap_penalty: usize) -> Vec<Match> {
+    anchors.sort();
+    let mut scores: Vec<i128> = anchors.iter().map(|m| m.length as i128).collect();
+    let mut previous = vec![usize::MAX; anchors.len()];
+    link_anchors(&anchors, gap_penalty as i128, 0, anchors.len(), &mut scores, &mut previous);
+    let mut best = usize::MAX;
+    for i in 0..anchors.len() {
+        if best == usize::MAX || scores[i] > scores[best] {
+            best = i;
+        }
+    }
+    let mut chain = Vec::new();
+    let mut i = best;
+    while i != usize::MAX {
+        chain.push(anchors[i]);
+        i = previous[i];
+    }
+    chain.reverse();
+    chain
+}
+
+// Divide and conquer over the sorted anchors[start..end], where the predecessors of an anchor come 
+// before it: once the scores of the left half are final, link every anchor of the right half to 
+// its best predecessor in the left half, then recurse in the right half. Chaining p before m costs 
+// the penalty times |diagonal(m) - diagonal(p)|. If p is on a lower or equal diagonal, ending 
+// before m in first implies ending before it in second, and the other way around if p is on a 
+// higher diagonal: each case is a sweep over one piece of data with a prefix maximum over the 
+// diagonals, which takes O(n log² n) overall.
+fn link_anchors(anchors: &[Match], penalty: i128, start: usize, end: usize, scores: &mut [i128], previous: &mut [usize]) {
+    if end - start < 2 {
+        return;
+    }
+    let mid = start + (end - start) / 2;
+    link_anchors(anchors, penalty, start, mid, scores, previous);
+    let mut diagonals: Vec<i128> = anchors[start..end].iter().map(diagonal).collect();
+    diagonals.sort_unstable();
+    diagonals.dedup();
+    let rank = |m: &Match| diagonals.binary_search(&diagonal(m)).unwrap();
+    let mut left: Vec<usize> = (start..mid).collect();
+    let mut right: Vec<usize> = (mid..end).collect();
+    let mut link = |i: usize, (score, j): (i128, usize), scores: &mut [i128]| {
+        if j != usize::MAX {
+            let chained = score.saturating_add(anchors[i].length as i128);
+            if chained > scores[i] {
+                scores[i] = chained;
+                previous[i] = j;
+            }
+        }
+    };
+    // Predecessors on lower or equal diagonals
+    left.sort_by_key(|&j| anchors[j].first_end());
+    right.sort_by_key(|&i| anchors[i].first_pos);
+    let mut lower = PrefixMax::new(diagonals.len(), i128::MIN);
+    let mut next = 0;
+    for &i in &right {
+        while next < left.len() && anchors[left[next]].first_end() <= anchors[i].first_pos {
+            let j = left[next];
+            lower.update(rank(&anchors[j]), (scores[j].saturating_add(penalty.saturating_mul(diagonal(&anchors[j]))), j));
+            next += 1;
+        }
+        let (score, j) = lower.query(rank(&anchors[i]) + 1);
+        link(i, (score.saturating_sub(penalty.saturating_mul(diagonal(&anchors[i]))), j), scores);
+    }
+    // Predecessors on higher or equal diagonals, ranked from the highest
+    left.sort_by_key(|&j| anchors[j].second_end());
+    right.sort_by_key(|&i| anchors[i].second_pos);
+    let mut higher = PrefixMax::new(diagonals.len(), i128::MIN);
+    let mut next = 0;
+    for &i in &right {
+        while next < left.len() && anchors[left[next]].second_end() <= anchors[i].second_pos {
+            let j = left[next];
+            higher.update(diagonals.len() - 1 - rank(&anchors[j]), (scores[j].saturating_sub(penalty.saturating_mul(diagonal(&anchors[j]))), j));
+            next += 1;
+        }
+        let (score, j) = higher.query(diagonals.len() - rank(&anchors[i]));
+        link(i, (score.saturating_add(penalty.saturating_mul(diagonal(&anchors[i]))), j), scores);
+    }
+    link_anchors(anchors, penalty, mid, end, scores, previous);
+}
+
+/// Align two pieces of data by chaining anchors, the way MUMmer does.
+///
+/// The anchors are the matches found with `options.anchors`, restricted to the maximal unique
+/// matches if `options.unique_anchors` is set. They are chained by dynamic programming, each
+/// anchor scoring its length minus `options.gap_penalty` per byte of shift between its diagonal and
+/// the one of the previous anchor. Finally, if `options.gap_closing` is set, the regions between
+/// the chained anchors are compared with it and the heaviest
+/// [`collinear_chain`](fn.collinear_chain.html) of their matches is kept.
+///
+/// The anchors are chained in `O(n log² n)`, so the cost is dominated by finding them, which makes
+/// it suitable for large and heavily rearranged inputs.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::{AlgoSpec, Match};
+/// use bcmp::chain::{ChainOptions, chain_align};
+///
+/// let a = b"HEADER-0123456789abcdefghij-BODY-klmnopqrstuvwxyz-END";
+/// let b = b"HEADER+0123456789abcdefghij+body+klmnopqrstuvwxyz+END";
+/// let options = ChainOptions { anchors: AlgoSpec::TreeMatch(8), gap_closing: Some(AlgoSpec::TreeMatch(2)), ..Default::default() };
+/// let alignment = chain_align(a, b, options);
+/// assert!(alignment.anchors == [Match::new(7, 7, 20), Match::new(33, 33, 16)]);
+/// assert!(alignment.gaps[0].matches == [Match::new(0, 0, 6)]);
+/// ```
+pub fn chain_align(first: &[u8], second: &[u8], options: ChainOptions) -> ChainAlignment {
+    let minimal_length = options.anchors.minimal_length();
+    let mut anchors = Vec::new();
+    if first.len() >= minimal_length && second.len() >= minimal_length {
+        anchors = MatchIterator::new(first, second, options.anchors).collect();
+    }
+    if options.unique_anchors {
+        anchors = unique_anchors(first, second, anchors);
+    }
+    let anchors = chain_anchors(anchors, options.gap_penalty);
+    // Regions around the anchors
+    let mut gaps = Vec::new();
+    let mut first_pos = 0;
+    let mut second_pos = 0;
+    let ends = anchors.iter().map(|m| (m.first_pos, m.second_pos, m.first_end(), m.second_end()))
+        .chain(Some((first.len(), second.len(), first.len(), second.len())));
+    for (first_start, second_start, first_end, second_end) in ends {
+        let gap = Gap {
+            first: Region::new(first_pos, first_start),
+            second: Region::new(second_pos, second_start),
+            matches: Vec::new(),
+        };
+        if !gap.first.is_empty() && !gap.second.is_empty() {
+            gaps.push(gap);
+        }
+        first_pos = first_end;
+        second_pos = second_end;
+    }
+    if let Some(gap_spec) = options.gap_closing {
+        for gap in &mut gaps {
+            if gap.first.len() >= gap_spec.minimal_length() && gap.second.len() >= gap_spec.minimal_length() {
+                let matches: Vec<Match> = MatchIterator::with_regions(first, gap.first, second, gap.second, gap_spec).collect();
+                gap.matches = collinear_chain(&matches).matches;
+            }
+        }
+    }
+    ChainAlignment {
+        anchors,
+        gaps,
+    }
+}
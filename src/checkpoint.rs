@@ -0,0 +1,87 @@
+//! Checkpoints of a [`MatchIterator`](../struct.MatchIterator.html), to resume a long comparison
+//! after a restart.
+//!
+//! Comparing disk images can take hours and batch systems may preempt the process at any time.
+//! Since matches are returned in ascending order of their position in the second piece of data, the
+//! progress of an iteration is fully described by the position of the last match returned and the
+//! number of matches returned at that position. [`MatchIterator::checkpoint`](../struct.MatchIterator.html#method.checkpoint)
+//! captures it in a small [`Checkpoint`](struct.Checkpoint.html) which can be saved, and
+//! [`MatchIterator::resume`](../struct.MatchIterator.html#method.resume) brings a new iterator over
+//! the same data to the same point by seeking, without replaying the matches before it. The
+//! internal state of the backends, like the diagonals already matched, is rebuilt by the seek.
+//!
+//! The index of the first piece of data is not part of the checkpoint: it is rebuilt when the
+//! iterator is created again, or reopened from a [`HashIndex`](../hashindex/struct.HashIndex.html)
+//! or [`SuffixArray`](../suffixarray/struct.SuffixArray.html) file.
+//!
+//! # Format
+//!
+//! A serialized checkpoint is the 8 bytes magic `BCMPCK\0\x01` followed by the lengths of the
+//! first and second pieces of data, the position and the number of matches to skip, encoded as
+//! little endian `u64`.
+//!
+//! # Examples
+//!
+//! ```
+//! use bcmp::{AlgoSpec, Match, MatchIterator};
+//! use bcmp::checkpoint::Checkpoint;
+//!
+//! let a = b"abcdefg";
+//! let b = b"012abc34cdef56efg78abcdefg";
+//! let mut match_iter = MatchIterator::new(a, b, AlgoSpec::TreeMatch(3));
+//! match_iter.next();
+//! let mut saved = Vec::new();
+//! match_iter.checkpoint().write_to(&mut saved).unwrap();
+//! // After a restart
+//! let checkpoint = Checkpoint::read_from(&mut &saved[..]).unwrap();
+//! let mut resumed = MatchIterator::new(a, b, AlgoSpec::TreeMatch(3));
+//! resumed.resume(&checkpoint);
+//! assert!(resumed.next() == Some(Match::new(2, 8, 4)));
+//! ```
+
+use std::io::{self, Read, Write};
+
+use bytepack::{LEPacker, LEUnpacker};
+
+use to_usize;
+
+const MAGIC: &[u8; 8] = b"BCMPCK\x00\x01";
+
+/// The progress of a [`MatchIterator`](../struct.MatchIterator.html).
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct Checkpoint {
+    /// The length of the first piece of data compared.
+    pub first_len: usize,
+    /// The length of the second piece of data compared.
+    pub second_len: usize,
+    /// The position in the second piece of data the iteration resumes at.
+    pub second_pos: usize,
+    /// The number of matches at `second_pos` already returned.
+    pub skip: usize,
+}
+
+impl Checkpoint {
+    /// Serialize the checkpoint to `w`.
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(MAGIC)?;
+        w.pack(self.first_len as u64)?;
+        w.pack(self.second_len as u64)?;
+        w.pack(self.second_pos as u64)?;
+        w.pack(self.skip as u64)
+    }
+
+    /// Deserialize a checkpoint from `r`.
+    pub fn read_from<R: Read>(r: &mut R) -> io::Result<Checkpoint> {
+        let mut magic = [0u8; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Not a bcmp checkpoint"));
+        }
+        Ok(Checkpoint {
+            first_len: to_usize(r.unpack::<u64>()?)?,
+            second_len: to_usize(r.unpack::<u64>()?)?,
+            second_pos: to_usize(r.unpack::<u64>()?)?,
+            skip: to_usize(r.unpack::<u64>()?)?,
+        })
+    }
+}
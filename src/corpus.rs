@@ -0,0 +1,173 @@
+//! Comparison of whole corpora of samples.
+//!
+//! Scoring every pair of samples of a corpus is embarrassingly parallel:
+//! [`similarity_matrix`](fn.similarity_matrix.html) spreads the pairs over a pool of threads.
+//! With the `gpu` feature, [`gpu_similarity_matrix`](fn.gpu_similarity_matrix.html) also extends
+//! the candidate matches of every pair on a [`Gpu`](../gpu/struct.Gpu.html) while the threads
+//! enumerate the candidates of the next pairs.
+//!
+//! [`closest`](fn.closest.html) searches a corpus for the samples most similar to a query, like
+//! difflib's `get_close_matches` does for strings.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use {AlgoSpec, similar, similarity};
+#[cfg(feature = "gpu")]
+use gpu::Gpu;
+
+/// Return the matrix of the pairwise [`similarity`](../fn.similarity.html) of `samples`, computed
+/// using `threads` threads.
+///
+/// The matrix is symmetric with ones on the diagonal, so only `n * (n - 1) / 2` comparisons are
+/// performed. If `threads` is 0, the number of available CPU cores is used. Samples shorter than
+/// the minimal matching length have a similarity of 0 with every other sample.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::AlgoSpec;
+/// use bcmp::corpus::similarity_matrix;
+///
+/// let samples: [&[u8]; 3] = [b"abcdefgh01234567", b"abcdefghXXXXXXXX", b"0123456789abcdef"];
+/// let matrix = similarity_matrix(&samples, AlgoSpec::TreeMatch(8), 2);
+/// assert!(matrix[0][1] == 0.5 && matrix[1][0] == 0.5);
+/// assert!(matrix[1][2] == 0.0);
+/// ```
+pub fn similarity_matrix(samples: &[&[u8]], algo_spec: AlgoSpec, threads: usize) -> Vec<Vec<f64>> {
+    matrix_of(samples, algo_spec.minimal_length(), threads, |first, second| similarity(first, second, algo_spec))
+}
+
+/// Return the same matrix as [`similarity_matrix`](fn.similarity_matrix.html), with the candidate
+/// matches of every pair extended on `gpu` by
+/// [`Gpu::similarity`](../gpu/struct.Gpu.html#method.similarity).
+///
+/// The `threads` threads build the indexes and enumerate the candidates, and share the GPU to
+/// extend them. This function requires the `gpu` feature.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::AlgoSpec;
+/// use bcmp::corpus::{gpu_similarity_matrix, similarity_matrix};
+/// use bcmp::gpu::Gpu;
+///
+/// let samples: [&[u8]; 3] = [b"abcdefgh01234567", b"abcdefghXXXXXXXX", b"0123456789abcdef"];
+/// if let Some(gpu) = Gpu::new() {
+///     let matrix = gpu_similarity_matrix(&samples, AlgoSpec::TreeMatch(8), &gpu, 2);
+///     assert!(matrix == similarity_matrix(&samples, AlgoSpec::TreeMatch(8), 2));
+/// }
+/// ```
+#[cfg(feature = "gpu")]
+pub fn gpu_similarity_matrix(samples: &[&[u8]], algo_spec: AlgoSpec, gpu: &Gpu, threads: usize) -> Vec<Vec<f64>> {
+    matrix_of(samples, algo_spec.minimal_length(), threads, |first, second| gpu.similarity(first, second, algo_spec))
+}
+
+// Score every pair of samples with score using threads threads
+fn matrix_of<F: Fn(&[u8], &[u8]) -> f64 + Sync>(samples: &[&[u8]], mml: usize, threads: usize, score: F) -> Vec<Vec<f64>> {
+    let n = samples.len();
+    let threads = match threads {
+        0 => thread::available_parallelism().map(|p| p.get()).unwrap_or(1),
+        t => t
+    };
+    let mut matrix = vec![vec![1.0; n]; n];
+    let pairs: Vec<(usize, usize)> = (0..n).flat_map(|i| (i + 1..n).map(move |j| (i, j))).collect();
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(pairs.len()));
+    thread::scope(|s| {
+        for _ in 0..threads.min(pairs.len()) {
+            s.spawn(|| {
+                let mut local = Vec::new();
+                loop {
+                    let k = next.fetch_add(1, Ordering::Relaxed);
+                    if k >= pairs.len() {
+                        break;
+                    }
+                    let (i, j) = pairs[k];
+                    let value = if samples[i].len() < mml || samples[j].len() < mml {
+                        0.0
+                    }
+                    else {
+                        score(samples[i], samples[j])
+                    };
+                    local.push((i, j, value));
+                }
+                results.lock().unwrap().extend(local);
+            });
+        }
+    });
+    for (i, j, value) in results.into_inner().unwrap() {
+        matrix[i][j] = value;
+        matrix[j][i] = value;
+    }
+    matrix
+}
+
+// Upper bound of the Ratcliff-Obershelp similarity from the byte histograms, which is what
+// difflib's quick_ratio computes
+fn histogram_bound(first: &[usize; 256], first_len: usize, second: &[u8]) -> f64 {
+    let mut histogram = [0usize; 256];
+    for &b in second {
+        histogram[b as usize] += 1;
+    }
+    let common: usize = first.iter().zip(histogram.iter()).map(|(a, b)| *a.min(b)).sum();
+    (2 * common) as f64 / (first_len + second.len()) as f64
+}
+
+/// Return the `n` candidates the most [`similar`](../fn.similar.html) to `query` with a
+/// similarity of at least `cutoff`, as `(index, similarity)` pairs sorted by decreasing similarity
+/// then increasing index.
+///
+/// This is the binary counterpart of difflib's `get_close_matches`, with the same similarity of 
+/// each candidate with the query. Candidates are first screened
+/// with cheap upper bounds of their similarity, their length and their byte histogram, and only
+/// the ones which could still make it to the result are ranked with the exact similarity. The
+/// bounds never underestimate, so the result is the same as ranking every candidate.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::corpus::closest;
+///
+/// let candidates: [&[u8]; 4] = [b"ape", b"apple", b"peach", b"puppy"];
+/// let matches = closest(b"appel", &candidates, 0.6, 3);
+/// assert!(matches == [(1, 0.8), (0, 0.75)]);
+/// ```
+pub fn closest(query: &[u8], candidates: &[&[u8]], cutoff: f64, n: usize) -> Vec<(usize, f64)> {
+    let mut histogram = [0usize; 256];
+    for &b in query {
+        histogram[b as usize] += 1;
+    }
+    let mut bounds = Vec::new();
+    for (i, candidate) in candidates.iter().enumerate() {
+        let total = query.len() + candidate.len();
+        // Two empty buffers are identical
+        if total == 0 {
+            bounds.push((1.0, i));
+            continue;
+        }
+        let length_bound = (2 * query.len().min(candidate.len())) as f64 / total as f64;
+        if length_bound < cutoff {
+            continue;
+        }
+        let bound = histogram_bound(&histogram, query.len(), candidate);
+        if bound >= cutoff {
+            bounds.push((bound, i));
+        }
+    }
+    bounds.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+    let mut best: Vec<(usize, f64)> = Vec::with_capacity(n);
+    for (bound, i) in bounds {
+        if best.len() == n && best.last().is_none_or(|&(_, score)| bound < score) {
+            break;
+        }
+        let score = similar(candidates[i], query);
+        if score >= cutoff {
+            best.push((i, score));
+            best.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+            best.truncate(n);
+        }
+    }
+    best
+}
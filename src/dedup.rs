@@ -0,0 +1,187 @@
+//! Chunk level deduplication of several pieces of data.
+//!
+//! Storage engines don't deduplicate bytes but chunks: each piece of data is cut into chunks and
+//! every distinct chunk is stored once. [`dedup_map`](fn.dedup_map.html) computes which chunks are
+//! shared and how much space deduplication saves, which complements the byte exact view of the
+//! matching algorithms.
+//!
+//! Chunk boundaries are content defined: a boundary is placed where a gear rolling hash of the
+//! last bytes matches a mask. Boundaries thus depend only on the nearby content and an insertion
+//! only changes the chunks around it, where fixed size chunks would all be shifted.
+
+use std::collections::HashMap;
+
+use Region;
+
+// Random constants of the gear hash, generated by splitmix64 so that boundaries are the same on
+// every platform
+const GEAR: [u64; 256] = gear_table();
+
+const fn gear_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut state: u64 = 0;
+    let mut i = 0;
+    while i < 256 {
+        state = state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+}
+
+/// Options of the content defined chunking.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct ChunkOptions {
+    /// The minimal size of a chunk, except for the last chunk of a piece of data.
+    pub min_size: usize,
+    /// The average number of bytes after `min_size` before a boundary, rounded up to a power of
+    /// two.
+    pub average_size: usize,
+    /// The maximal size of a chunk. Setting it to `min_size` produces fixed size chunks.
+    pub max_size: usize,
+}
+
+impl Default for ChunkOptions {
+    /// Chunks between 2 KiB and 64 KiB, averaging about 10 KiB.
+    fn default() -> ChunkOptions {
+        ChunkOptions {
+            min_size: 2048,
+            average_size: 8192,
+            max_size: 65536,
+        }
+    }
+}
+
+/// Cut `data` into content defined chunks, returned as consecutive regions covering it.
+///
+/// It will panic if `options.min_size` is zero or larger than `options.max_size`.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::Region;
+/// use bcmp::dedup::{ChunkOptions, chunks};
+///
+/// let options = ChunkOptions { min_size: 4, average_size: 4, max_size: 4 };
+/// assert!(chunks(b"0123456789", options) == [Region::new(0, 4), Region::new(4, 8), Region::new(8, 10)]);
+/// ```
+pub fn chunks(data: &[u8], options: ChunkOptions) -> Vec<Region> {
+    assert!(options.min_size > 0, "The minimal chunk size must be at least 1");
+    assert!(options.min_size <= options.max_size, "The minimal chunk size can't exceed the maximal one");
+    span!("dedup::chunks", data.len());
+    let mask = options.average_size.next_power_of_two() as u64 - 1;
+    let mut regions = Vec::new();
+    let mut start = 0;
+    while start < data.len() {
+        let end = (start + options.max_size).min(data.len());
+        let mut boundary = end;
+        let mut hash = 0u64;
+        for (i, &b) in data[start..end].iter().enumerate() {
+            hash = (hash << 1).wrapping_add(GEAR[b as usize]);
+            if i + 1 >= options.min_size && hash & mask == 0 {
+                boundary = start + i + 1;
+                break;
+            }
+        }
+        regions.push(Region::new(start, boundary));
+        start = boundary;
+    }
+    regions
+}
+
+/// A chunk of a piece of data in a [`DedupMap`](struct.DedupMap.html).
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct Chunk {
+    /// The region of the piece of data covered by the chunk.
+    pub region: Region,
+    /// The index of the content of the chunk in
+    /// [`DedupMap::contents`](struct.DedupMap.html#structfield.contents).
+    pub id: usize,
+}
+
+/// A distinct chunk content of a [`DedupMap`](struct.DedupMap.html).
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct ChunkContent {
+    /// The size of the content.
+    pub size: usize,
+    /// The number of chunks having this content, over all the pieces of data.
+    pub occurrences: usize,
+    /// The indexes of the pieces of data containing this content, in ascending order.
+    pub buffers: Vec<usize>,
+}
+
+/// The result of [`dedup_map`](fn.dedup_map.html).
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct DedupMap {
+    /// The chunks of each piece of data, in order.
+    pub buffers: Vec<Vec<Chunk>>,
+    /// The distinct chunk contents, in order of first appearance.
+    pub contents: Vec<ChunkContent>,
+}
+
+impl DedupMap {
+    /// Return the total size of the pieces of data.
+    pub fn total_size(&self) -> usize {
+        self.contents.iter().map(|c| c.size * c.occurrences).sum()
+    }
+
+    /// Return the size needed to store every distinct chunk once.
+    pub fn deduplicated_size(&self) -> usize {
+        self.contents.iter().map(|c| c.size).sum()
+    }
+
+    /// Return true if the content `id` appears in more than one piece of data.
+    pub fn is_shared(&self, id: usize) -> bool {
+        self.contents[id].buffers.len() > 1
+    }
+}
+
+/// Chunk every piece of data of `buffers` with [`chunks`](fn.chunks.html) and identify the chunks
+/// having the same content.
+///
+/// Chunks are compared by content, not by hash, so two chunks have the same id exactly when they
+/// are equal.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::dedup::{ChunkOptions, dedup_map};
+///
+/// let options = ChunkOptions { min_size: 4, average_size: 4, max_size: 4 };
+/// let map = dedup_map(&[b"0123456789ab", b"4567XXXX0123"], options);
+/// assert!(map.buffers[1].iter().map(|c| c.id).collect::<Vec<_>>() == [1, 3, 0]);
+/// assert!(map.is_shared(0) && !map.is_shared(2));
+/// assert!(map.total_size() == 24 && map.deduplicated_size() == 16);
+/// ```
+pub fn dedup_map(buffers: &[&[u8]], options: ChunkOptions) -> DedupMap {
+    let mut ids = HashMap::<&[u8], usize>::new();
+    let mut contents: Vec<ChunkContent> = Vec::new();
+    let mut layout = Vec::with_capacity(buffers.len());
+    for (buffer, data) in buffers.iter().enumerate() {
+        let mut chunked = Vec::new();
+        for region in chunks(data, options) {
+            let content = &data[region.start..region.end];
+            let id = *ids.entry(content).or_insert_with(|| {
+                contents.push(ChunkContent {
+                    size: content.len(),
+                    occurrences: 0,
+                    buffers: Vec::new(),
+                });
+                contents.len() - 1
+            });
+            contents[id].occurrences += 1;
+            if contents[id].buffers.last() != Some(&buffer) {
+                contents[id].buffers.push(buffer);
+            }
+            chunked.push(Chunk { region, id });
+        }
+        layout.push(chunked);
+    }
+    DedupMap {
+        buffers: layout,
+        contents,
+    }
+}
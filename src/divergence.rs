@@ -0,0 +1,156 @@
+//! Location of the differences between two nearly identical pieces of data.
+//!
+//! When two large buffers are expected to be identical except for a few in-place modifications,
+//! matching them is overkill: comparing them at the same offsets tells where they changed. The
+//! functions of this module compare whole chunks at once and only look at individual bytes around
+//! the differences, which makes them run at the speed of `memcmp`.
+
+use Region;
+
+const CHUNK_SIZE: usize = 4096;
+
+// Offset of the first difference between first[from..] and second[from..] within their common
+// length, if any
+fn first_difference_from(first: &[u8], second: &[u8], from: usize) -> Option<usize> {
+    let end = first.len().min(second.len());
+    let mut pos = from;
+    while pos < end {
+        let chunk_end = (pos + CHUNK_SIZE).min(end);
+        if first[pos..chunk_end] != second[pos..chunk_end] {
+            return (pos..chunk_end).find(|&i| first[i] != second[i]);
+        }
+        pos = chunk_end;
+    }
+    None
+}
+
+/// Return the offset of the first byte which differs between two pieces of data compared at the
+/// same offsets. If one of them is a prefix of the other, this is the length of the shortest one.
+/// Return `None` if they are equal.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::divergence::first_difference;
+///
+/// assert!(first_difference(b"abcdef", b"abcXef") == Some(3));
+/// assert!(first_difference(b"abc", b"abcdef") == Some(3));
+/// assert!(first_difference(b"abc", b"abc") == None);
+/// ```
+pub fn first_difference(first: &[u8], second: &[u8]) -> Option<usize> {
+    first_difference_from(first, second, 0).or_else(|| {
+        if first.len() != second.len() { Some(first.len().min(second.len())) } else { None }
+    })
+}
+
+/// Return the offset of the last byte which differs between two pieces of data compared at the
+/// same offsets. If their lengths differ, this is the last byte of the longest one. Return `None`
+/// if they are equal.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::divergence::last_difference;
+///
+/// assert!(last_difference(b"abXdeY", b"abcdef") == Some(5));
+/// assert!(last_difference(b"aXcdef", b"abcdef") == Some(1));
+/// assert!(last_difference(b"abc", b"abcdef") == Some(5));
+/// ```
+pub fn last_difference(first: &[u8], second: &[u8]) -> Option<usize> {
+    if first.len() != second.len() {
+        return Some(first.len().max(second.len()) - 1);
+    }
+    let mut end = first.len();
+    while end > 0 {
+        let chunk_start = end.saturating_sub(CHUNK_SIZE);
+        if first[chunk_start..end] != second[chunk_start..end] {
+            return (chunk_start..end).rev().find(|&i| first[i] != second[i]);
+        }
+        end = chunk_start;
+    }
+    None
+}
+
+/// Return the regions where two pieces of data compared at the same offsets differ.
+///
+/// Differences separated by less than `resync` equal bytes are merged into a single region, so
+/// `resync` controls how much equal data is needed to consider both pieces of data synchronized
+/// again. If their lengths differ, the tail of the longest one is a difference too. The regions
+/// are sorted and apply to both pieces of data.
+///
+/// Equal stretches are skipped chunk by chunk. After a difference, windows of `resync` bytes are
+/// probed at exponentially growing distances until an equal one is found, and only the bytes up to
+/// that window are compared one by one to find where the region ends. The cost is thus
+/// proportional to the size of the differing regions rather than to the size of the data.
+///
+/// It will panic if `resync` is zero.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::Region;
+/// use bcmp::divergence::divergence_map;
+///
+/// let a = b"0123456789abcdefghij";
+/// let b = b"01X3456789abcYZfghijkl";
+/// assert!(divergence_map(a, b, 4) == [Region::new(2, 3), Region::new(13, 15), Region::new(20, 22)]);
+/// assert!(divergence_map(a, b, 12) == [Region::new(2, 22)]);
+/// ```
+pub fn divergence_map(first: &[u8], second: &[u8], resync: usize) -> Vec<Region> {
+    assert!(resync > 0, "The resynchronization length must be at least 1");
+    let common = first.len().min(second.len());
+    let mut regions: Vec<Region> = Vec::new();
+    let mut pos = 0;
+    while let Some(start) = first_difference_from(first, second, pos) {
+        // Probe windows further and further away until an equal one is found
+        let mut step = 1;
+        let mut bound = common;
+        while start + step < common {
+            let probe = start + step;
+            let probe_end = (probe + resync).min(common);
+            if first[probe..probe_end] == second[probe..probe_end] {
+                bound = probe_end;
+                break;
+            }
+            step *= 2;
+        }
+        // The region ends at the first run of resync equal bytes before that window
+        let mut end = bound;
+        let mut run = 0;
+        for i in start + 1..bound {
+            if first[i] == second[i] {
+                run += 1;
+                if run == resync {
+                    end = i + 1 - resync;
+                    break;
+                }
+            }
+            else {
+                run = 0;
+            }
+        }
+        // Without a full run, which only happens at the end of the common length, the trailing 
+        // equal bytes are not part of the region
+        if run < resync {
+            end = bound - run;
+        }
+        push_region(&mut regions, Region::new(start, end), resync);
+        pos = end;
+    }
+    if first.len() != second.len() {
+        push_region(&mut regions, Region::new(common, first.len().max(second.len())), resync);
+    }
+    regions
+}
+
+// Append a region, merging it with the previous one if they are separated by less than resync
+// bytes
+fn push_region(regions: &mut Vec<Region>, region: Region, resync: usize) {
+    if let Some(last) = regions.last_mut() {
+        if region.start < last.end + resync {
+            last.end = region.end;
+            return;
+        }
+    }
+    regions.push(region);
+}
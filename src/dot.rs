@@ -0,0 +1,95 @@
+//! Graphviz renderings of comparison results.
+//!
+//! [`alignment_graph`](fn.alignment_graph.html) draws a patch set as two columns of blocks, the
+//! first piece of data on the left and the second on the right, with one edge per copied block.
+//! Moved, duplicated or dropped blocks show up as crossing, fanning out or unconnected edges, which
+//! is much easier to read in a report than a list of offsets. The output is in the DOT language and
+//! can be rendered with `dot -Tsvg`.
+
+use Match;
+use interval::IntervalSet;
+
+/// Render a patch set, such as the one returned by [`patch_set`](../fn.patch_set.html), as a DOT
+/// alignment diagram.
+///
+/// `first_len` and `second_len` are the lengths of the two pieces of data. Each distinct copied
+/// range of the first piece of data becomes a block of the left column and the parts which are not
+/// copied are drawn dashed. The blocks of the right column follow the second piece of data, the
+/// parts not covered by a patch being drawn filled as new data. Every patch is an edge labeled with
+/// its length.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::{AlgoSpec, patch_set};
+/// use bcmp::dot::alignment_graph;
+///
+/// let a = b"0123456789abcdefghijklmnopqrstuvwxyz";
+/// let b = b"abcdefghij0123456789XXklmnopqrstuvwxyz";
+/// let patches = patch_set(a, b, AlgoSpec::TreeMatch(4));
+/// let dot = alignment_graph(&patches, a.len(), b.len());
+/// assert!(dot.starts_with("digraph alignment {"));
+/// assert!(dot.contains("F_0 -> S_1"));
+/// ```
+pub fn alignment_graph(patches: &[Match], first_len: usize, second_len: usize) -> String {
+    let mut by_second = patches.to_vec();
+    by_second.sort_by_key(|m| (m.second_pos, m.first_pos));
+    // Blocks of the first piece of data: the distinct copied ranges and the unused parts
+    let used: IntervalSet = patches.iter().map(|m| (m.first_pos, m.first_end())).collect();
+    let mut first_blocks: Vec<(usize, usize, bool)> = patches.iter().map(|m| (m.first_pos, m.first_end(), true)).collect();
+    first_blocks.extend(used.complement(first_len).iter().map(|r| (r.start, r.end, false)));
+    first_blocks.sort();
+    first_blocks.dedup();
+    // Blocks of the second piece of data: the patches and the new data between them
+    let mut second_blocks: Vec<(usize, usize, Option<usize>)> = Vec::new();
+    let mut cursor = 0;
+    for (i, m) in by_second.iter().enumerate() {
+        if m.second_pos > cursor {
+            second_blocks.push((cursor, m.second_pos, None));
+        }
+        second_blocks.push((m.second_pos, m.second_end(), Some(i)));
+        cursor = cursor.max(m.second_end());
+    }
+    if cursor < second_len {
+        second_blocks.push((cursor, second_len, None));
+    }
+
+    let mut dot = String::new();
+    dot.push_str("digraph alignment {\n");
+    dot.push_str("    rankdir = LR;\n");
+    dot.push_str("    node [ shape = box ];\n");
+    // One rank per column, headed by its name
+    dot.push_str("    subgraph first {\n");
+    dot.push_str("        rank = same;\n");
+    dot.push_str("        F [ label = \"first\", shape = plaintext ];\n");
+    for (i, &(start, end, copied)) in first_blocks.iter().enumerate() {
+        let style = if copied { "" } else { ", style = dashed" };
+        dot.push_str(&format!("        F_{} [ label = \"0x{:x}..0x{:x}\"{} ];\n", i, start, end, style));
+    }
+    dot.push_str("    }\n");
+    dot.push_str("    subgraph second {\n");
+    dot.push_str("        rank = same;\n");
+    dot.push_str("        S [ label = \"second\", shape = plaintext ];\n");
+    for (i, &(start, end, patch)) in second_blocks.iter().enumerate() {
+        let style = if patch.is_some() { "" } else { ", style = filled" };
+        dot.push_str(&format!("        S_{} [ label = \"0x{:x}..0x{:x}\"{} ];\n", i, start, end, style));
+    }
+    dot.push_str("    }\n");
+    // Keep both columns in data order
+    for &(prefix, count) in [("F", first_blocks.len()), ("S", second_blocks.len())].iter() {
+        let mut previous = String::from(prefix);
+        for i in 0..count {
+            dot.push_str(&format!("    {} -> {}_{} [ style = invis ];\n", previous, prefix, i));
+            previous = format!("{}_{}", prefix, i);
+        }
+    }
+    for (i, &(_, _, patch)) in second_blocks.iter().enumerate() {
+        if let Some(p) = patch {
+            let m = by_second[p];
+            let f = first_blocks.binary_search(&(m.first_pos, m.first_end(), true)).unwrap();
+            dot.push_str(&format!("    F_{} -> S_{} [ label = \"{}\" ];\n", f, i, m.length));
+        }
+    }
+    dot.push('}');
+    dot
+}
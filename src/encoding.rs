@@ -0,0 +1,128 @@
+//! Compact binary serialization of lists of matches.
+//!
+//! Analysis results over large corpora, like patch sets or all the matches between two files, are
+//! worth caching but a textual format spends dozens of bytes per match. Consecutive matches are
+//! usually close to each other in the second piece of data and on nearby diagonals, so
+//! [`write_matches`](fn.write_matches.html) stores each of them relative to the previous one with
+//! variable length integers, which typically takes 3 to 6 bytes per match.
+//!
+//! # Format
+//!
+//! A serialized list starts with the 8 bytes magic `BCMPMS\0\x01` followed by the number of
+//! matches. Each match is then encoded as three values: the distance between its start in the
+//! second piece of data and the end of the previous match there, the change of diagonal
+//! (`first_pos - second_pos`) from the previous match and its length. All the values are LEB128
+//! variable length integers, the signed ones being zigzag encoded. The first match is relative to
+//! a virtual match of length 0 at the start of both pieces of data. Matches keep their order,
+//! whatever it is.
+//!
+//! # Examples
+//!
+//! ```
+//! use bcmp::{AlgoSpec, patch_set};
+//! use bcmp::encoding::{read_matches, write_matches};
+//!
+//! let a = b"0123456789abcdefghijklmnopqrstuvwxyz";
+//! let b = b"abcdefghij__0123456789__klmnopqrstuvwxyz";
+//! let patches = patch_set(a, b, AlgoSpec::TreeMatch(4));
+//! let mut encoded = Vec::new();
+//! write_matches(&mut encoded, &patches).unwrap();
+//! assert!(encoded.len() == 8 + 1 + 3 * 3);
+//! assert!(read_matches(&mut &encoded[..]).unwrap() == patches);
+//! ```
+
+use std::io::{self, Read, Write};
+
+use bytepack::LEUnpacker;
+
+use {Match, to_usize};
+
+const MAGIC: &[u8; 8] = b"BCMPMS\x00\x01";
+// Matches allocated before reading them when deserializing
+const MAX_PREALLOCATED_MATCHES: usize = 1 << 16;
+
+// LEB128 varints, shared with the LZ codec
+pub(crate) fn write_varint(out: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        out.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+pub(crate) fn read_varint<R: Read>(r: &mut R) -> io::Result<u64> {
+    let mut v = 0u64;
+    let mut shift = 0;
+    loop {
+        let byte = r.unpack::<u8>()?;
+        if shift == 63 && byte > 1 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Variable length integer overflow"));
+        }
+        v |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(v);
+        }
+        shift += 7;
+    }
+}
+
+// Differences are computed modulo 2^64 and zigzag encoded so that small negative values stay small
+fn zigzag(v: u64) -> u64 {
+    (v << 1) ^ ((v as i64 >> 63) as u64)
+}
+
+fn unzigzag(v: u64) -> u64 {
+    (v >> 1) ^ (v & 1).wrapping_neg()
+}
+
+/// Serialize `matches` to `w`, in the order they are given.
+pub fn write_matches<W: Write>(w: &mut W, matches: &[Match]) -> io::Result<()> {
+    let mut out = Vec::with_capacity(MAGIC.len() + 10 + 4 * matches.len());
+    out.extend_from_slice(MAGIC);
+    write_varint(&mut out, matches.len() as u64);
+    let mut second_end = 0u64;
+    let mut diagonal = 0u64;
+    for m in matches {
+        let second_pos = m.second_pos as u64;
+        let first_pos = m.first_pos as u64;
+        write_varint(&mut out, zigzag(second_pos.wrapping_sub(second_end)));
+        let new_diagonal = first_pos.wrapping_sub(second_pos);
+        write_varint(&mut out, zigzag(new_diagonal.wrapping_sub(diagonal)));
+        write_varint(&mut out, m.length as u64);
+        second_end = second_pos.wrapping_add(m.length as u64);
+        diagonal = new_diagonal;
+    }
+    w.write_all(&out)
+}
+
+/// Deserialize a list of matches written by [`write_matches`](fn.write_matches.html) from `r`.
+///
+/// An `InvalidData` error is returned if the data isn't a serialized list of matches, was written
+/// by an incompatible version or describes a match ending beyond `usize::MAX`.
+pub fn read_matches<R: Read>(r: &mut R) -> io::Result<Vec<Match>> {
+    let mut magic = [0u8; 8];
+    r.read_exact(&mut magic)?;
+    if magic[..6] != MAGIC[..6] {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Not a bcmp match list"));
+    }
+    if magic != *MAGIC {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Unsupported bcmp match list version"));
+    }
+    let count = to_usize(read_varint(r)?)?;
+    let mut matches = Vec::with_capacity(count.min(MAX_PREALLOCATED_MATCHES));
+    let mut second_end = 0u64;
+    let mut diagonal = 0u64;
+    for _ in 0..count {
+        let second_pos = second_end.wrapping_add(unzigzag(read_varint(r)?));
+        diagonal = diagonal.wrapping_add(unzigzag(read_varint(r)?));
+        let length = read_varint(r)?;
+        let first_pos = second_pos.wrapping_add(diagonal);
+        let m = Match::new(to_usize(first_pos)?, to_usize(second_pos)?, to_usize(length)?);
+        if m.first_pos.checked_add(m.length).is_none() || m.second_pos.checked_add(m.length).is_none() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Match ending beyond usize::MAX"));
+        }
+        second_end = second_pos + length;
+        matches.push(m);
+    }
+    Ok(matches)
+}
@@ -0,0 +1,218 @@
+//! Comparison of two directory trees.
+//!
+//! [`diff_trees`](fn.diff_trees.html) walks two directories, pairs their files by relative path,
+//! detects renamed files by content similarity and computes a patch set for every modified file.
+//! This is the layer delta update tools need on top of [`patch_set`](../fn.patch_set.html).
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::{read, read_dir};
+use std::io::Result;
+use std::path::{Path, PathBuf};
+
+use {AlgoSpec, Match, patch_set, similarity};
+
+/// The change undergone by a single file between the old and the new tree.
+#[derive(Clone,Debug,PartialEq)]
+pub enum FileChange {
+    /// The file only exists in the new tree.
+    Added {
+        /// Path relative to the new tree root.
+        path: PathBuf,
+        /// Size of the file.
+        size: usize,
+    },
+    /// The file only exists in the old tree.
+    Removed {
+        /// Path relative to the old tree root.
+        path: PathBuf,
+        /// Size of the file.
+        size: usize,
+    },
+    /// The file exists in both trees with identical content.
+    Unchanged {
+        /// Path relative to the tree roots.
+        path: PathBuf,
+        /// Size of the file.
+        size: usize,
+    },
+    /// The file exists in both trees but its content changed.
+    Modified {
+        /// Path relative to the tree roots.
+        path: PathBuf,
+        /// The patch set building the new file from the old one.
+        patches: Vec<Match>,
+        /// Number of bytes of the new file not covered by the patch set.
+        novel: usize,
+    },
+    /// The file was moved to a different path, possibly with modifications.
+    Renamed {
+        /// Path relative to the old tree root.
+        from: PathBuf,
+        /// Path relative to the new tree root.
+        to: PathBuf,
+        /// Similarity between the old and the new content, between 0 and 1.
+        similarity: f64,
+        /// The patch set building the new file from the old one.
+        patches: Vec<Match>,
+        /// Number of bytes of the new file not covered by the patch set.
+        novel: usize,
+    },
+}
+
+/// Aggregated statistics of a [`TreeDiff`](struct.TreeDiff.html).
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq)]
+pub struct TreeSummary {
+    /// Number of added files.
+    pub added: usize,
+    /// Number of removed files.
+    pub removed: usize,
+    /// Number of modified files.
+    pub modified: usize,
+    /// Number of renamed files.
+    pub renamed: usize,
+    /// Number of unchanged files.
+    pub unchanged: usize,
+    /// Total size of the new tree.
+    pub new_bytes: usize,
+    /// Number of bytes of the new tree which could not be found in the corresponding old file: 
+    /// the content of added files and the parts of modified or renamed files not covered by 
+    /// their patch set.
+    pub novel_bytes: usize,
+}
+
+/// The result of [`diff_trees`](fn.diff_trees.html).
+#[derive(Clone,Debug,PartialEq)]
+pub struct TreeDiff {
+    /// One entry per file, sorted by path.
+    pub changes: Vec<FileChange>,
+    /// Tree level statistics.
+    pub summary: TreeSummary,
+}
+
+fn walk(root: &Path, dir: &Path, files: &mut BTreeMap<PathBuf, Vec<u8>>) -> Result<()> {
+    for entry in read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            walk(root, &path, files)?;
+        }
+        else if file_type.is_file() {
+            let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+            files.insert(relative, read(&path)?);
+        }
+    }
+    Ok(())
+}
+
+fn novel(second: &[u8], patches: &[Match]) -> usize {
+    second.len() - patches.iter().map(|m| m.length).sum::<usize>()
+}
+
+// Upper bound of the similarity of two files of these sizes when their common content isn't 
+// repeated: each covered byte of one file is then matched once in the other
+fn size_bound(first_len: usize, second_len: usize) -> f64 {
+    if first_len + second_len == 0 {
+        return 1.0;
+    }
+    2.0 * first_len.min(second_len) as f64 / (first_len + second_len) as f64
+}
+
+fn change_path(change: &FileChange) -> &Path {
+    match *change {
+        FileChange::Added { ref path, .. } | FileChange::Removed { ref path, .. } | 
+        FileChange::Unchanged { ref path, .. } | FileChange::Modified { ref path, .. } => path,
+        FileChange::Renamed { ref to, .. } => to,
+    }
+}
+
+/// Compare the directory trees rooted at `old` and `new`.
+///
+/// Regular files are paired by their path relative to the roots, symbolic links are not followed. 
+/// Each file only present in the new tree is then compared with the files only present in the old 
+/// tree: the most similar one is considered to be its origin if their similarity, the fraction of 
+/// both files covered by common substrings, is at least `rename_threshold`.
+///
+/// A file with the same content as a removed one is paired with it without any comparison. The 
+/// other candidates are only compared if their sizes allow the threshold to be reached, assuming 
+/// their common content isn't repeated: a small file made of content repeated many times in a 
+/// large one isn't considered renamed. Both trees are read entirely in memory.
+pub fn diff_trees(old: &Path, new: &Path, algo_spec: AlgoSpec, rename_threshold: f64) -> Result<TreeDiff> {
+    let mut old_files = BTreeMap::new();
+    let mut new_files = BTreeMap::new();
+    walk(old, old, &mut old_files)?;
+    walk(new, new, &mut new_files)?;
+    let mut changes = Vec::<FileChange>::new();
+    let mut summary = TreeSummary::default();
+    let mut added = Vec::<&PathBuf>::new();
+
+    for (path, data) in &new_files {
+        summary.new_bytes += data.len();
+        match old_files.remove(path) {
+            Some(ref old_data) if old_data == data => {
+                summary.unchanged += 1;
+                changes.push(FileChange::Unchanged { path: path.clone(), size: data.len() });
+            },
+            Some(old_data) => {
+                let patches = patch_set(&old_data, data, algo_spec);
+                let novel = novel(data, &patches);
+                summary.modified += 1;
+                summary.novel_bytes += novel;
+                changes.push(FileChange::Modified { path: path.clone(), patches, novel });
+            },
+            None => added.push(path)
+        }
+    }
+    // Rename detection between the remaining files
+    let mut by_content = HashMap::<&[u8], Vec<&PathBuf>>::new();
+    for (old_path, old_data) in &old_files {
+        by_content.entry(&old_data[..]).or_default().push(old_path);
+    }
+    let mut origins = BTreeSet::<&PathBuf>::new();
+    for path in added {
+        let data = &new_files[path];
+        let identical = by_content.get(&data[..]).and_then(|paths| paths.iter().find(|p| !origins.contains(*p)));
+        let best = match identical {
+            Some(&old_path) => Some((1.0, old_path)),
+            None => {
+                let mut best: Option<(f64, &PathBuf)> = None;
+                for (old_path, old_data) in &old_files {
+                    if origins.contains(old_path) || size_bound(old_data.len(), data.len()) < rename_threshold {
+                        continue;
+                    }
+                    let s = similarity(old_data, data, algo_spec);
+                    if s >= rename_threshold && best.as_ref().is_none_or(|b| s > b.0) {
+                        best = Some((s, old_path));
+                    }
+                }
+                best
+            }
+        };
+        match best {
+            Some((similarity, from)) => {
+                origins.insert(from);
+                let patches = patch_set(&old_files[from], data, algo_spec);
+                let novel = novel(data, &patches);
+                summary.renamed += 1;
+                summary.novel_bytes += novel;
+                changes.push(FileChange::Renamed { from: from.clone(), to: path.clone(), similarity, patches, novel });
+            },
+            None => {
+                summary.added += 1;
+                summary.novel_bytes += data.len();
+                changes.push(FileChange::Added { path: path.clone(), size: data.len() });
+            }
+        }
+    }
+    for (path, data) in &old_files {
+        if !origins.contains(path) {
+            summary.removed += 1;
+            changes.push(FileChange::Removed { path: path.clone(), size: data.len() });
+        }
+    }
+    changes.sort_by(|a, b| change_path(a).cmp(change_path(b)));
+    Ok(TreeDiff {
+        changes,
+        summary,
+    })
+}
@@ -0,0 +1,143 @@
+//! Generation of synthetic test data, available with the `gen` feature.
+//!
+//! Benchmarking or fuzzing a comparison requires inputs with a known relationship: unrelated
+//! random data, highly repetitive data, a copy of some data with scattered edits or a copy shifted
+//! by a few bytes. A [`Generator`](struct.Generator.html) produces all of them from a seed with
+//! its own splitmix64 generator, so the same seed gives the same data on every platform and with
+//! every version of bcmp.
+//!
+//! # Examples
+//!
+//! ```
+//! use bcmp::{AlgoSpec, similarity};
+//! use bcmp::gen::{Generator, Mutations};
+//!
+//! let mut generator = Generator::new(42);
+//! let base = generator.random(10000);
+//! let edited = generator.mutated(&base, Mutations::default());
+//! assert!(similarity(&base, &edited, AlgoSpec::TreeMatch(32)) > 0.5);
+//! ```
+
+/// The rates of the edits of [`Generator::mutated`](struct.Generator.html#method.mutated), as
+/// probabilities per byte of the base between 0 and 1.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub struct Mutations {
+    /// The probability to insert a random byte before a byte of the base.
+    pub insert: f64,
+    /// The probability to delete a byte of the base.
+    pub delete: f64,
+    /// The probability to replace a byte of the base, which isn't deleted, by a different one.
+    pub substitute: f64,
+}
+
+impl Default for Mutations {
+    /// One insertion, one deletion and one substitution per thousand bytes on average.
+    fn default() -> Mutations {
+        Mutations {
+            insert: 0.001,
+            delete: 0.001,
+            substitute: 0.001,
+        }
+    }
+}
+
+/// A deterministic generator of synthetic data.
+#[derive(Clone,Debug)]
+pub struct Generator {
+    state: u64,
+}
+
+impl Generator {
+    /// Build a generator from a seed. Two generators with the same seed produce the same data.
+    pub fn new(seed: u64) -> Generator {
+        Generator {
+            state: seed,
+        }
+    }
+
+    /// Return the next random `u64` of the splitmix64 sequence.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform float in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Return `len` uniformly random bytes.
+    pub fn random(&mut self, len: usize) -> Vec<u8> {
+        let mut data = Vec::with_capacity(len + 8);
+        while data.len() < len {
+            let v = self.next_u64();
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        data.truncate(len);
+        data
+    }
+
+    /// Return `len` random bytes drawn uniformly from the `alphabet` first byte values. Small
+    /// alphabets produce many short random matches, like DNA does.
+    ///
+    /// It will panic if `alphabet` is zero.
+    pub fn random_alphabet(&mut self, len: usize, alphabet: u8) -> Vec<u8> {
+        assert!(alphabet > 0, "The alphabet must have at least 1 symbol");
+        (0..len).map(|_| (self.next_u64() % alphabet as u64) as u8).collect()
+    }
+
+    /// Return `len` bytes repeating a random unit of `period` bytes, the last repetition being
+    /// truncated.
+    ///
+    /// It will panic if `period` is zero.
+    pub fn repetitive(&mut self, len: usize, period: usize) -> Vec<u8> {
+        assert!(period > 0, "The period must be at least 1");
+        let unit = self.random(period);
+        unit.iter().cycle().take(len).cloned().collect()
+    }
+
+    /// Return a copy of `base` with random insertions, deletions and substitutions at the given
+    /// `rates`. Inserted and substituted bytes are uniformly random, a substituted byte always
+    /// differs from the original one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bcmp::gen::{Generator, Mutations};
+    ///
+    /// let mut generator = Generator::new(7);
+    /// let base = generator.random(1000);
+    /// let edited = generator.mutated(&base, Mutations { insert: 0.0, delete: 0.0, substitute: 0.05 });
+    /// let changed = base.iter().zip(&edited).filter(|&(a, b)| a != b).count();
+    /// assert!(edited.len() == base.len() && changed > 20 && changed < 80);
+    /// ```
+    pub fn mutated(&mut self, base: &[u8], rates: Mutations) -> Vec<u8> {
+        let mut data = Vec::with_capacity(base.len());
+        for &b in base {
+            if self.next_f64() < rates.insert {
+                data.push(self.next_u64() as u8);
+            }
+            if self.next_f64() < rates.delete {
+                continue;
+            }
+            if self.next_f64() < rates.substitute {
+                data.push(b.wrapping_add(1 + (self.next_u64() % 255) as u8));
+            }
+            else {
+                data.push(b);
+            }
+        }
+        data
+    }
+
+    /// Return a copy of `base` shifted by `shift` random bytes inserted at its start, which moves
+    /// every byte of `base` to a new offset.
+    pub fn shifted(&mut self, base: &[u8], shift: usize) -> Vec<u8> {
+        let mut data = self.random(shift);
+        data.extend_from_slice(base);
+        data
+    }
+}
@@ -0,0 +1,375 @@
+//! GPU acceleration of candidate extension and similarity scoring.
+//!
+//! Comparing two pieces of data spends most of its time verifying candidate positions and
+//! extending them byte by byte, and every candidate is independent of the others. A
+//! [`Gpu`](struct.Gpu.html) runs this part as a `wgpu` compute kernel: the candidates are the
+//! pairs of positions starting with the same k-gram, enumerated on the CPU from a hash index of
+//! the first piece of data, and the GPU computes the length of the match starting at each of them
+//! in parallel. Only the left maximal candidates are sent, so each maximal match is extended once.
+//!
+//! [`Gpu::coverage_stats`](struct.Gpu.html#method.coverage_stats) gives the same counts as
+//! [`coverage_stats`](../fn.coverage_stats.html) for `HashMatch`, `TreeMatch` and
+//! `SuffixArrayMatch`, and [`gpu_similarity_matrix`](../corpus/fn.gpu_similarity_matrix.html)
+//! scores a whole corpus with it. The work falls back to the CPU backends when it doesn't suit the
+//! GPU: inputs larger than the storage buffers of the adapter or than 4 GiB, `Hybrid` and data so
+//! repetitive that the candidates outnumber the bytes by more than
+//! [`CANDIDATE_RATIO`](constant.CANDIDATE_RATIO.html).
+//!
+//! Any adapter `wgpu` supports is used: Vulkan, Metal, DirectX 12 or OpenGL, including software
+//! rasterizers. This module requires the `gpu` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use bcmp::{AlgoSpec, similarity};
+//! use bcmp::gpu::Gpu;
+//!
+//! let a = b"abcdefgh01234567";
+//! let b = b"abcdefghXXXXXXXX";
+//! if let Some(gpu) = Gpu::new() {
+//!     assert!(gpu.similarity(a, b, AlgoSpec::TreeMatch(8)) == similarity(a, b, AlgoSpec::TreeMatch(8)));
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::mpsc;
+
+use pollster;
+use wgpu;
+use wgpu::util::DeviceExt;
+
+use {AlgoSpec, CoverageStats, coverage_stats};
+use interval::IntervalSet;
+
+/// The maximal number of candidates per byte of the compared data above which
+/// [`Gpu::coverage_stats`](struct.Gpu.html#method.coverage_stats) gives up and uses the CPU
+/// backends, which handle repetitive data without enumerating every pair of repeats.
+pub const CANDIDATE_RATIO: usize = 64;
+
+// The number of candidates extended by a dispatch
+const BATCH: usize = 1 << 20;
+
+// The number of invocations of a workgroup, which must match the kernel
+const WORKGROUP: usize = 64;
+
+const KERNEL: &str = r#"
+struct Params {
+    first_len: u32,
+    second_len: u32,
+    count: u32,
+    pad: u32,
+}
+
+@group(0) @binding(0) var<storage, read> first: array<u32>;
+@group(0) @binding(1) var<storage, read> second: array<u32>;
+@group(0) @binding(2) var<storage, read> candidates: array<vec2<u32>>;
+@group(0) @binding(3) var<storage, read_write> lengths: array<u32>;
+@group(0) @binding(4) var<uniform> params: Params;
+
+fn first_byte(i: u32) -> u32 {
+    return (first[i >> 2u] >> ((i & 3u) << 3u)) & 0xffu;
+}
+
+fn second_byte(i: u32) -> u32 {
+    return (second[i >> 2u] >> ((i & 3u) << 3u)) & 0xffu;
+}
+
+@compute @workgroup_size(64)
+fn extend(@builtin(global_invocation_id) id: vec3<u32>) {
+    let k = id.x;
+    if (k >= params.count) {
+        return;
+    }
+    let c = candidates[k];
+    let limit = min(params.first_len - c.x, params.second_len - c.y);
+    var length = 0u;
+    while (length < limit && first_byte(c.x + length) == second_byte(c.y + length)) {
+        length += 1u;
+    }
+    lengths[k] = length;
+}
+"#;
+
+/// A GPU device with the compiled extension kernel.
+pub struct Gpu {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    max_binding: u64,
+    name: String,
+}
+
+// The uploaded bytes of both pieces of data and the buffers of a batch of candidates
+struct Buffers {
+    bind_group: wgpu::BindGroup,
+    candidates: wgpu::Buffer,
+    lengths: wgpu::Buffer,
+    staging: wgpu::Buffer,
+    params: wgpu::Buffer,
+    first_len: u32,
+    second_len: u32,
+}
+
+// Pack the bytes in little endian words, as the kernel reads them
+fn words(data: &[u8]) -> Vec<u8> {
+    let mut words = data.to_vec();
+    words.resize(data.len().div_ceil(4).max(1) * 4, 0);
+    words
+}
+
+// The length of the match starting at each candidate, computed on the CPU
+fn cpu_extend(first: &[u8], second: &[u8], candidates: &[(usize, usize)]) -> Vec<usize> {
+    candidates.iter()
+        .map(|&(i, j)| first[i..].iter().zip(&second[j..]).take_while(|&(a, b)| a == b).count())
+        .collect()
+}
+
+// The first k bytes at pos as a little endian integer
+fn key_at(data: &[u8], pos: usize, k: usize) -> u64 {
+    data[pos..pos + k].iter().rev().fold(0, |key, &b| (key << 8) | b as u64)
+}
+
+impl Gpu {
+    /// Open the highest performance GPU adapter available and compile the extension kernel, or
+    /// return `None` if there isn't any adapter.
+    pub fn new() -> Option<Gpu> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle_from_env());
+        let options = wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            ..Default::default()
+        };
+        let adapter = pollster::block_on(instance.request_adapter(&options)).ok()?;
+        let limits = adapter.limits();
+        let descriptor = wgpu::DeviceDescriptor {
+            label: Some("bcmp"),
+            required_limits: limits.clone(),
+            ..Default::default()
+        };
+        let (device, queue) = pollster::block_on(adapter.request_device(&descriptor)).ok()?;
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("bcmp extend"),
+            source: wgpu::ShaderSource::Wgsl(KERNEL.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("bcmp extend"),
+            layout: None,
+            module: &module,
+            entry_point: Some("extend"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        Some(Gpu {
+            device,
+            queue,
+            pipeline,
+            max_binding: limits.max_storage_buffer_binding_size.min(limits.max_buffer_size),
+            name: adapter.get_info().name,
+        })
+    }
+
+    /// Return the name of the adapter.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // Whether both pieces of data fit in the storage buffers with u32 positions
+    fn fits(&self, first: &[u8], second: &[u8]) -> bool {
+        [first.len(), second.len()].iter().all(|&len| {
+            u32::try_from(len).is_ok() && (len.div_ceil(4).max(1) * 4) as u64 <= self.max_binding
+        }) && (BATCH * 8) as u64 <= self.max_binding
+    }
+
+    fn upload(&self, first: &[u8], second: &[u8]) -> Buffers {
+        let storage = |contents: &[u8]| self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents,
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let first_buffer = storage(&words(first));
+        let second_buffer = storage(&words(second));
+        let buffer = |size: usize, usage: wgpu::BufferUsages| self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: size as u64,
+            usage,
+            mapped_at_creation: false,
+        });
+        let candidates = buffer(BATCH * 8, wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST);
+        let lengths = buffer(BATCH * 4, wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC);
+        let staging = buffer(BATCH * 4, wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST);
+        let params = buffer(16, wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST);
+        let entries: Vec<wgpu::BindGroupEntry> = [&first_buffer, &second_buffer, &candidates, &lengths, &params].iter()
+            .enumerate()
+            .map(|(i, b)| wgpu::BindGroupEntry {
+                binding: i as u32,
+                resource: b.as_entire_binding(),
+            })
+            .collect();
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &entries,
+        });
+        Buffers {
+            bind_group,
+            candidates,
+            lengths,
+            staging,
+            params,
+            first_len: first.len() as u32,
+            second_len: second.len() as u32,
+        }
+    }
+
+    // Extend at most BATCH candidates with the uploaded data
+    fn dispatch(&self, buffers: &Buffers, candidates: &[(usize, usize)]) -> Vec<usize> {
+        if candidates.is_empty() {
+            return Vec::new();
+        }
+        let mut packed = Vec::with_capacity(candidates.len() * 8);
+        for &(i, j) in candidates {
+            packed.extend_from_slice(&(i as u32).to_le_bytes());
+            packed.extend_from_slice(&(j as u32).to_le_bytes());
+        }
+        let mut params = Vec::with_capacity(16);
+        for word in [buffers.first_len, buffers.second_len, candidates.len() as u32, 0] {
+            params.extend_from_slice(&word.to_le_bytes());
+        }
+        self.queue.write_buffer(&buffers.candidates, 0, &packed);
+        self.queue.write_buffer(&buffers.params, 0, &params);
+        let size = (candidates.len() * 4) as u64;
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&Default::default());
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &buffers.bind_group, &[]);
+            pass.dispatch_workgroups(candidates.len().div_ceil(WORKGROUP) as u32, 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(&buffers.lengths, 0, &buffers.staging, 0, size);
+        self.queue.submit(Some(encoder.finish()));
+        let (sender, receiver) = mpsc::channel();
+        buffers.staging.map_async(wgpu::MapMode::Read, 0..size, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::PollType::wait_indefinitely()).expect("GPU device lost");
+        receiver.recv().unwrap().expect("Failed to map the GPU buffer");
+        let lengths = {
+            let view = buffers.staging.get_mapped_range(0..size).expect("Failed to map the GPU buffer");
+            view.chunks(4).map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]]) as usize).collect()
+        };
+        buffers.staging.unmap();
+        lengths
+    }
+
+    // Extend a batch of candidates and record the ranges of the matches of at least mml bytes
+    fn cover(&self, buffers: &Buffers, batch: &[(usize, usize)], mml: usize, first_ranges: &mut Vec<(usize, usize)>, second_ranges: &mut Vec<(usize, usize)>) {
+        for (&(i, j), length) in batch.iter().zip(self.dispatch(buffers, batch)) {
+            if length >= mml {
+                first_ranges.push((i, i + length));
+                second_ranges.push((j, j + length));
+            }
+        }
+    }
+
+    /// Return the length of the match starting at each `(first_pos, second_pos)` candidate: the
+    /// number of equal bytes from these positions, 0 if the candidate doesn't match.
+    ///
+    /// The lengths are computed on the CPU if the data doesn't fit in the buffers of the adapter.
+    ///
+    /// It will panic if a candidate is beyond the end of the data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bcmp::gpu::Gpu;
+    ///
+    /// if let Some(gpu) = Gpu::new() {
+    ///     let lengths = gpu.extend(b"abcdefgh", b"XXcdefXX", &[(2, 2), (0, 0), (3, 3)]);
+    ///     assert!(lengths == [4, 0, 3]);
+    /// }
+    /// ```
+    pub fn extend(&self, first: &[u8], second: &[u8], candidates: &[(usize, usize)]) -> Vec<usize> {
+        assert!(candidates.iter().all(|&(i, j)| i <= first.len() && j <= second.len()), "Candidate beyond the end of the data");
+        if !self.fits(first, second) {
+            return cpu_extend(first, second, candidates);
+        }
+        let buffers = self.upload(first, second);
+        candidates.chunks(BATCH).flat_map(|batch| self.dispatch(&buffers, batch)).collect()
+    }
+
+    /// Count the bytes of each piece of data which are covered by a match with the other one,
+    /// extending the candidate matches on the GPU.
+    ///
+    /// The counts are the ones of [`coverage_stats`](../fn.coverage_stats.html). `Hybrid`, data
+    /// which doesn't fit in the buffers of the adapter and data with more than
+    /// [`CANDIDATE_RATIO`](constant.CANDIDATE_RATIO.html) candidates per byte are compared on the
+    /// CPU.
+    ///
+    /// It will panic if the [`AlgoSpec`](../enum.AlgoSpec.html) is not supported.
+    pub fn coverage_stats(&self, first: &[u8], second: &[u8], algo_spec: AlgoSpec) -> CoverageStats {
+        assert!(algo_spec.is_supported(), "Unsupported AlgoSpec");
+        span!("gpu_coverage_stats", first.len() + second.len());
+        let mml = algo_spec.minimal_length().max(1);
+        let mut stats = CoverageStats {
+            first_len: first.len(),
+            second_len: second.len(),
+            first_covered: 0,
+            second_covered: 0,
+        };
+        if let AlgoSpec::Hybrid(..) = algo_spec {
+            return coverage_stats(first, second, algo_spec);
+        }
+        if first.len() < mml || second.len() < mml {
+            return stats;
+        }
+        if !self.fits(first, second) {
+            return coverage_stats(first, second, algo_spec);
+        }
+        // Candidates start with the same k bytes, the matches shorter than mml are dropped
+        let k = mml.min(8);
+        let mut index: HashMap<u64, Vec<u32>> = HashMap::new();
+        for i in 0..=first.len() - k {
+            index.entry(key_at(first, i, k)).or_default().push(i as u32);
+        }
+        let budget = CANDIDATE_RATIO.saturating_mul(first.len() + second.len());
+        let buffers = self.upload(first, second);
+        let mut count = 0;
+        let mut batch = Vec::with_capacity(BATCH);
+        let mut first_ranges = Vec::new();
+        let mut second_ranges = Vec::new();
+        for j in 0..=second.len() - k {
+            let positions = match index.get(&key_at(second, j, k)) {
+                Some(positions) => positions,
+                None => continue
+            };
+            count += positions.len();
+            if count > budget {
+                return coverage_stats(first, second, algo_spec);
+            }
+            for &i in positions {
+                let i = i as usize;
+                // Only the left maximal candidates start a maximal match
+                if i > 0 && j > 0 && first[i - 1] == second[j - 1] {
+                    continue;
+                }
+                batch.push((i, j));
+                if batch.len() == BATCH {
+                    self.cover(&buffers, &batch, mml, &mut first_ranges, &mut second_ranges);
+                    batch.clear();
+                }
+            }
+        }
+        self.cover(&buffers, &batch, mml, &mut first_ranges, &mut second_ranges);
+        first_ranges.sort();
+        second_ranges.sort();
+        stats.first_covered = first_ranges.into_iter().collect::<IntervalSet>().total_len();
+        stats.second_covered = second_ranges.into_iter().collect::<IntervalSet>().total_len();
+        stats
+    }
+
+    /// Return the [`similarity`](../fn.similarity.html) of two byte slices, computed from
+    /// [`Gpu::coverage_stats`](#method.coverage_stats).
+    pub fn similarity(&self, first: &[u8], second: &[u8], algo_spec: AlgoSpec) -> f64 {
+        self.coverage_stats(first, second, algo_spec).similarity()
+    }
+}
@@ -0,0 +1,138 @@
+//! Comparison of human-readable text aligned on grapheme clusters.
+//!
+//! Snapping matches to character boundaries, like [`text`](../text/index.html) does, can still
+//! split what a reader sees as a single character: an `e` followed by a combining accent, a flag
+//! made of two regional indicators or an emoji sequence joined by zero width joiners are several
+//! characters but one extended grapheme cluster. [`grapheme_matches`](fn.grapheme_matches.html)
+//! shrinks every match to the grapheme boundaries of both strings, as segmented by the
+//! `unicode-segmentation` crate, and reports its position both in bytes and in graphemes.
+//!
+//! The segmentation depends on the context, so the same bytes can be segmented differently in both
+//! strings: a match starts at the first position inside it which is a boundary in both strings and
+//! ends at the last one. The matches are returned in the order of
+//! [`MatchIterator`](../struct.MatchIterator.html), which shrinking can make slightly out of order.
+//!
+//! This module requires the `grapheme` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use bcmp::AlgoSpec;
+//! use bcmp::grapheme::grapheme_matches;
+//!
+//! // "e\u{301}" is an 'e' with a combining acute accent, one grapheme of 3 bytes
+//! let a = "cafe\u{301} au lait";
+//! let b = "un cafe noir";
+//! let matches: Vec<_> = grapheme_matches(a, b, AlgoSpec::TreeMatch(3)).collect();
+//! assert!(matches.len() == 1);
+//! assert!(&a[matches[0].bytes.first_pos..matches[0].bytes.first_end()] == "caf");
+//! assert!(matches[0].second_graphemes == (3..6));
+//! ```
+
+use std::iter::FusedIterator;
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use {AlgoSpec, Match, MatchIterator};
+
+/// A match between two strings aligned on grapheme clusters.
+#[derive(Clone,Debug,PartialEq,Eq,Hash)]
+pub struct GraphemeMatch {
+    /// The match in bytes, which can be used to slice both strings.
+    pub bytes: Match,
+    /// The indices of the graphemes of the match in the first string.
+    pub first_graphemes: Range<usize>,
+    /// The indices of the graphemes of the match in the second string.
+    pub second_graphemes: Range<usize>,
+}
+
+// The byte offsets of the extended grapheme cluster boundaries of s, including its end
+fn boundaries(s: &str) -> Vec<usize> {
+    s.grapheme_indices(true).map(|(i, _)| i).chain(Some(s.len())).collect()
+}
+
+// Shrink m to the grapheme boundaries common to both strings, given as sorted byte offsets
+fn snap(first: &[usize], second: &[usize], m: Match) -> Option<GraphemeMatch> {
+    // Indices of the first boundaries at or after the start of the match
+    let mut i = first.partition_point(|&b| b < m.first_pos);
+    let mut j = second.partition_point(|&b| b < m.second_pos);
+    loop {
+        let (d1, d2) = (first.get(i)? - m.first_pos, second.get(j)? - m.second_pos);
+        if d1 > m.length || d2 > m.length {
+            return None;
+        }
+        if d1 == d2 {
+            break;
+        }
+        else if d1 < d2 {
+            i += 1;
+        }
+        else {
+            j += 1;
+        }
+    }
+    // Indices of the last boundaries at or before the end of the match
+    let mut k = first.partition_point(|&b| b <= m.first_end()) - 1;
+    let mut l = second.partition_point(|&b| b <= m.second_end()) - 1;
+    while first[k] - m.first_pos != second[l] - m.second_pos {
+        if first[k] - m.first_pos > second[l] - m.second_pos {
+            k -= 1;
+        }
+        else {
+            l -= 1;
+        }
+    }
+    if k <= i {
+        return None;
+    }
+    Some(GraphemeMatch {
+        bytes: Match::new(first[i], second[j], first[k] - first[i]),
+        first_graphemes: i..k,
+        second_graphemes: j..l,
+    })
+}
+
+/// An iterator over the [`GraphemeMatch`](struct.GraphemeMatch.html) between two strings, see
+/// [`grapheme_matches`](fn.grapheme_matches.html).
+#[derive(Clone)]
+pub struct GraphemeMatches<'a> {
+    first_boundaries: Vec<usize>,
+    second_boundaries: Vec<usize>,
+    minimal_length: usize,
+    iter: MatchIterator<'a>,
+}
+
+impl<'a> Iterator for GraphemeMatches<'a> {
+    type Item = GraphemeMatch;
+    fn next(&mut self) -> Option<GraphemeMatch> {
+        for m in self.iter.by_ref() {
+            if let Some(gm) = snap(&self.first_boundaries, &self.second_boundaries, m) {
+                if gm.bytes.length >= self.minimal_length {
+                    return Some(gm);
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<'a> FusedIterator for GraphemeMatches<'a> {}
+
+/// Return an iterator over the matches between two strings, shrunk to the grapheme cluster
+/// boundaries of both of them.
+///
+/// The matches are the ones of [`MatchIterator::new`](../struct.MatchIterator.html#method.new)
+/// over the bytes of the strings. The matches which become shorter than the minimal length of
+/// `algo_spec`, in bytes, are dropped. Both strings are segmented upfront, which takes a `usize`
+/// per grapheme.
+///
+/// It will panic if the [`AlgoSpec`](../enum.AlgoSpec.html) is not supported.
+pub fn grapheme_matches<'a>(first: &'a str, second: &'a str, algo_spec: AlgoSpec) -> GraphemeMatches<'a> {
+    GraphemeMatches {
+        first_boundaries: boundaries(first),
+        second_boundaries: boundaries(second),
+        minimal_length: algo_spec.minimal_length(),
+        iter: MatchIterator::new(first.as_bytes(), second.as_bytes(), algo_spec),
+    }
+}
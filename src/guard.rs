@@ -0,0 +1,170 @@
+//! Protection against pathological inputs.
+//!
+//! Every backend looks up each position of the second piece of data in an index of the first one
+//! and then goes through all the candidate positions sharing the same prefix of the minimal
+//! matching length. On highly repetitive data, like runs of zeros, tandem repeats or binary data
+//! over a tiny alphabet, a prefix can occur millions of times and the iteration becomes quadratic:
+//! it seems to hang.
+//!
+//! [`guarded`](fn.guarded.html) estimates the size of the largest candidate list on a window of
+//! the indexed data before building the iterator. When it exceeds
+//! [`max_candidates`](struct.GuardOptions.html#structfield.max_candidates), the minimal matching
+//! length is doubled until the candidates are few enough, which only keeps the longer matches. A
+//! length `HashMatch` doesn't support switches to `SuffixArrayMatch`. If no length up to
+//! [`max_length`](struct.GuardOptions.html#structfield.max_length) helps, the data is periodic:
+//! the requested length is kept but the iteration switches to `TreeMatch`, which gets the length of
+//! all the candidates from its tree instead of comparing them, and samples the second piece of
+//! data sparsely by making the iterator [`skip_ahead`](../struct.MatchIterator.html#method.skip_ahead)
+//! over its matches. The decision is reported in the [`Diagnostics`](struct.Diagnostics.html).
+//!
+//! # Examples
+//!
+//! ```
+//! use bcmp::AlgoSpec;
+//! use bcmp::guard::{GuardOptions, guarded};
+//!
+//! let zeros = vec![0u8; 100000];
+//! let (match_iter, diagnostics) = guarded(&zeros, &zeros, AlgoSpec::HashMatch(8), GuardOptions::default());
+//! assert!(diagnostics.skip_ahead && diagnostics.is_degenerate());
+//! assert!(match_iter.count() > 0);
+//! ```
+
+use std::collections::HashMap;
+use std::mem::discriminant;
+
+use {AlgoSpec, MatchIterator};
+use probe::window;
+use suffixtree;
+
+// Base of the rolling hash of the prefixes
+const BASE: u64 = 0x100000001b3;
+
+/// Options of [`guarded`](fn.guarded.html).
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct GuardOptions {
+    /// The largest number of candidate positions per prefix considered healthy.
+    pub max_candidates: usize,
+    /// The longest minimal matching length the guard may raise the requested one to.
+    pub max_length: usize,
+    /// The size of the window of the indexed data the candidates are counted on.
+    pub sample_size: usize,
+}
+
+impl Default for GuardOptions {
+    /// At most 1024 candidates, minimal lengths up to 1024 bytes and windows of 1 MiB.
+    fn default() -> GuardOptions {
+        GuardOptions {
+            max_candidates: 1024,
+            max_length: 1024,
+            sample_size: 1024 * 1024,
+        }
+    }
+}
+
+/// The decision of [`guarded`](fn.guarded.html).
+#[derive(Clone,Copy,Debug)]
+pub struct Diagnostics {
+    /// The backend requested.
+    pub requested: AlgoSpec,
+    /// The backend used, possibly with a larger minimal matching length or switched to another
+    /// backend.
+    pub algo_spec: AlgoSpec,
+    /// The estimated size of the largest candidate list with the requested minimal length.
+    pub candidates: usize,
+    /// The estimated size of the largest candidate list with the minimal length used.
+    pub final_candidates: usize,
+    /// True if the iterator skips ahead over its matches.
+    pub skip_ahead: bool,
+}
+
+impl Diagnostics {
+    /// Return true if the input was found pathological and the strategy changed.
+    pub fn is_degenerate(&self) -> bool {
+        self.skip_ahead || self.algo_spec.minimal_length() != self.requested.minimal_length()
+            || discriminant(&self.algo_spec) != discriminant(&self.requested)
+    }
+}
+
+// The number of occurrences of the most frequent substring of length bytes of data, up to hash
+// collisions
+fn max_occurrences(data: &[u8], length: usize) -> usize {
+    let mut power = 1u64;
+    for _ in 0..length {
+        power = power.wrapping_mul(BASE);
+    }
+    let mut counts = HashMap::<u64, usize>::new();
+    let mut max = 0;
+    let mut hash = 0u64;
+    for (i, &byte) in data.iter().enumerate() {
+        hash = hash.wrapping_mul(BASE).wrapping_add(byte as u64 + 1);
+        if i >= length {
+            hash = hash.wrapping_sub(power.wrapping_mul(data[i - length] as u64 + 1));
+        }
+        if i + 1 >= length {
+            let count = counts.entry(hash).or_insert(0);
+            *count += 1;
+            max = max.max(*count);
+        }
+    }
+    max
+}
+
+// The same backend with another minimal length, as a SuffixArrayMatch if HashMatch doesn't support
+// any length between it and 64 bytes, and as a HashMatch if it exceeds the key of a Hybrid
+fn with_length(algo_spec: AlgoSpec, length: usize) -> AlgoSpec {
+    match algo_spec {
+        AlgoSpec::HashMatch(_) => (length..65).map(AlgoSpec::HashMatch).find(AlgoSpec::is_supported)
+            .unwrap_or(AlgoSpec::SuffixArrayMatch(length)),
+        AlgoSpec::TreeMatch(_) => AlgoSpec::TreeMatch(length),
+        AlgoSpec::SuffixArrayMatch(_) => AlgoSpec::SuffixArrayMatch(length),
+        AlgoSpec::Hybrid(key, _) if length <= key => AlgoSpec::Hybrid(key, length),
+        AlgoSpec::Hybrid(..) => with_length(AlgoSpec::HashMatch(length), length),
+    }
+}
+
+/// Build a [`MatchIterator`](../struct.MatchIterator.html) between `first` and `second`, changing
+/// `algo_spec` if the indexed data would make the iteration quadratic, and return it with the
+/// [`Diagnostics`](struct.Diagnostics.html) of the decision.
+///
+/// The candidates are counted on the window of `options.sample_size` bytes in the middle of the
+/// first piece of data, which [`MatchIterator::new`](../struct.MatchIterator.html#method.new)
+/// indexes, and extrapolated to its whole length. Only the longer matches are returned when the minimal length is raised
+/// and the overlapping ones are missed when skipping ahead, see the [module](index.html)
+/// documentation. Data longer than [`MAX_LEN`](../suffixtree/constant.MAX_LEN.html) skips ahead
+/// with `SuffixArrayMatch` instead of `TreeMatch`.
+///
+/// It will panic if `algo_spec` is not supported.
+pub fn guarded<'a>(first: &'a [u8], second: &'a [u8], algo_spec: AlgoSpec, options: GuardOptions) -> (MatchIterator<'a>, Diagnostics) {
+    assert!(algo_spec.is_supported(), "Unsupported AlgoSpec");
+    let sample = window(first, options.sample_size);
+    let estimate = |length: usize| {
+        let occurrences = max_occurrences(sample, length) as u128 * first.len() as u128 / sample.len().max(1) as u128;
+        occurrences.min(usize::MAX as u128) as usize
+    };
+    let requested = algo_spec.minimal_length().max(1);
+    let candidates = estimate(requested);
+    let mut length = requested;
+    let mut final_candidates = candidates;
+    while final_candidates > options.max_candidates && length < options.max_length {
+        length = length.saturating_mul(2).min(options.max_length);
+        final_candidates = estimate(length);
+    }
+    let mut diagnostics = Diagnostics {
+        requested: algo_spec,
+        algo_spec,
+        candidates,
+        final_candidates: candidates,
+        skip_ahead: false,
+    };
+    if final_candidates > options.max_candidates {
+        // Periodic data, no length helps
+        diagnostics.algo_spec = if first.len() <= suffixtree::MAX_LEN { AlgoSpec::TreeMatch(requested) } else { AlgoSpec::SuffixArrayMatch(requested) };
+        diagnostics.skip_ahead = true;
+        return (MatchIterator::new(first, second, diagnostics.algo_spec).skip_ahead(), diagnostics);
+    }
+    if length != requested {
+        diagnostics.algo_spec = with_length(algo_spec, length);
+        diagnostics.final_candidates = final_candidates;
+    }
+    (MatchIterator::new(first, second, diagnostics.algo_spec), diagnostics)
+}
@@ -0,0 +1,218 @@
+//! A persistable form of the [`HashMatch`](../hashmatch/index.html) index.
+//!
+//! Building the `HashMap` of a large reference dominates the cost of a
+//! [`HashMatchIterator`](../hashmatch/struct.HashMatchIterator.html) when the same reference is
+//! compared against many inputs, like a golden firmware image or a base set of game assets.
+//! [`HashIndex`](struct.HashIndex.html) stores the same information as a flat table which can be
+//! saved to a file and later reopened, or memory-mapped with the `mmap` feature, without any
+//! construction work.
+//!
+//! # Index format
+//!
+//! The index file starts with the 8 bytes magic `BCMPHI\0\x01` followed by the key length, the
+//! length of the indexed data and the positions of every key sorted by key bytes then by position,
+//! all encoded as little endian `u64`.
+
+use std::fs::{File, read};
+use std::io::{Error, ErrorKind, Result, Write};
+use std::mem::size_of;
+use std::ops::Range;
+use std::path::Path;
+
+#[cfg(feature = "mmap")]
+use memmap2::Mmap;
+
+use {Match, to_usize};
+use suffixarray::u64_at;
+
+const MAGIC: &[u8; 8] = b"BCMPHI\x00\x01";
+const HEADER_LEN: usize = 24;
+
+/// A serialized hash index over a reference, keyed by substrings of `key_len` bytes.
+///
+/// Like the [`SuffixTree`](../suffixtree/struct.SuffixTree.html), the index does not hold the
+/// indexed data which has to be given back to every query.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::Match;
+/// use bcmp::hashindex::HashIndex;
+///
+/// let reference = b"abcdefghijabcdxyz";
+/// let index = HashIndex::new(reference, 4);
+/// assert!(index.positions(reference, b"abcd").collect::<Vec<_>>() == [0, 10]);
+/// let matches = index.matches(reference, b"__cdefgh__");
+/// assert!(matches == [Match::new(2, 2, 6)]);
+/// ```
+pub struct HashIndex<S: AsRef<[u8]>> {
+    storage: S,
+    key_len: usize,
+    data_len: usize,
+}
+
+impl HashIndex<Vec<u8>> {
+    /// Build the index of `data` for keys of `key_len` bytes.
+    pub fn new(data: &[u8], key_len: usize) -> HashIndex<Vec<u8>> {
+        assert!(key_len > 0, "The key length must be at least 1");
+        span!("hashindex::build", data.len());
+        let mut positions: Vec<usize> = (0..(data.len() + 1).saturating_sub(key_len)).collect();
+        positions.sort_by_key(|&p| &data[p..p + key_len]);
+        let mut storage = Vec::with_capacity(HEADER_LEN + positions.len() * size_of::<u64>());
+        storage.extend_from_slice(MAGIC);
+        storage.extend_from_slice(&(key_len as u64).to_le_bytes());
+        storage.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        for p in positions {
+            storage.extend_from_slice(&(p as u64).to_le_bytes());
+        }
+        HashIndex {
+            storage,
+            key_len,
+            data_len: data.len(),
+        }
+    }
+
+    /// Load an index file in memory.
+    pub fn open(path: &Path) -> Result<HashIndex<Vec<u8>>> {
+        HashIndex::from_bytes(read(path)?)
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl HashIndex<Mmap> {
+    /// Memory-map an index file.
+    ///
+    /// The file must not be modified while it is mapped.
+    pub fn map(path: &Path) -> Result<HashIndex<Mmap>> {
+        let file = File::open(path)?;
+        // Safety: the mapping is read only and the caller guarantees the file is not modified
+        let mmap = unsafe { Mmap::map(&file)? };
+        HashIndex::from_bytes(mmap)
+    }
+}
+
+impl<S: AsRef<[u8]>> HashIndex<S> {
+    /// Wrap a serialized index, checking its header, its size and that every key position is 
+    /// within the indexed data and appears once.
+    ///
+    /// The order of the positions depends on the indexed data, use [`check`](#method.check) to 
+    /// verify it.
+    pub fn from_bytes(storage: S) -> Result<HashIndex<S>> {
+        let bytes = storage.as_ref();
+        if bytes.len() < HEADER_LEN || &bytes[..8] != MAGIC {
+            return Err(Error::new(ErrorKind::InvalidData, "Not a bcmp hash index"));
+        }
+        let key_len = to_usize(u64_at(bytes, 8))?;
+        let data_len = to_usize(u64_at(bytes, 16))?;
+        let count = data_len.saturating_add(1).saturating_sub(key_len);
+        let expected = count.checked_mul(size_of::<u64>()).and_then(|size| size.checked_add(HEADER_LEN));
+        if key_len == 0 || expected != Some(bytes.len()) {
+            return Err(Error::new(ErrorKind::InvalidData, "Corrupted bcmp hash index"));
+        }
+        let mut seen = vec![false; count];
+        for p in bytes[HEADER_LEN..].chunks_exact(size_of::<u64>()).map(|p| u64_at(p, 0)) {
+            if p >= count as u64 || seen[p as usize] {
+                return Err(Error::new(ErrorKind::InvalidData, "Corrupted bcmp hash index"));
+            }
+            seen[p as usize] = true;
+        }
+        Ok(HashIndex {
+            storage,
+            key_len,
+            data_len,
+        })
+    }
+
+    /// Return the serialized form of the index, as stored in an index file.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.storage.as_ref()
+    }
+
+    /// Write the index to a file.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        File::create(path)?.write_all(self.as_bytes())
+    }
+
+    /// Return the length of the keys, which is also the minimal matching length.
+    pub fn key_len(&self) -> usize {
+        self.key_len
+    }
+
+    /// Return the length of the indexed data.
+    pub fn data_len(&self) -> usize {
+        self.data_len
+    }
+
+    /// Check that `data` is the indexed data and that the positions are sorted by key bytes then 
+    /// by position, as the queries expect.
+    pub fn check(&self, data: &[u8]) -> Result<()> {
+        if data.len() != self.data_len {
+            return Err(Error::new(ErrorKind::InvalidData, "The data does not match the index"));
+        }
+        for i in 1..self.len() {
+            let (p, q) = (self.get(i - 1), self.get(i));
+            if (&data[p..p + self.key_len], p) > (&data[q..q + self.key_len], q) {
+                return Err(Error::new(ErrorKind::InvalidData, "Unsorted bcmp hash index"));
+            }
+        }
+        Ok(())
+    }
+
+    fn len(&self) -> usize {
+        (self.storage.as_ref().len() - HEADER_LEN) / size_of::<u64>()
+    }
+
+    fn get(&self, i: usize) -> usize {
+        u64_at(self.storage.as_ref(), HEADER_LEN + i * size_of::<u64>()) as usize
+    }
+
+    fn rows(&self, data: &[u8], key: &[u8]) -> Range<usize> {
+        let lower = |upper: bool| {
+            let mut lo = 0;
+            let mut hi = self.len();
+            while lo < hi {
+                let mid = (lo + hi) / 2;
+                let p = self.get(mid);
+                let k = &data[p..p + self.key_len];
+                if k < key || (upper && k == key) {
+                    lo = mid + 1;
+                }
+                else {
+                    hi = mid;
+                }
+            }
+            lo
+        };
+        lower(false)..lower(true)
+    }
+
+    /// Return the ascending positions of `key` in the indexed data.
+    ///
+    /// It will panic if `key` is not `key_len` bytes long or if `data` is not the indexed data.
+    pub fn positions<'a>(&'a self, data: &[u8], key: &[u8]) -> impl Iterator<Item=usize> + 'a {
+        assert!(key.len() == self.key_len, "The key length does not match the index");
+        assert!(data.len() == self.data_len, "The data does not match the index");
+        self.rows(data, key).map(move |i| self.get(i))
+    }
+
+    /// Return all the [`Match`](../struct.Match.html) between the indexed data and `second`, in
+    /// the order a [`HashMatchIterator`](../hashmatch/struct.HashMatchIterator.html) using the
+    /// same key length would return them.
+    pub fn matches(&self, data: &[u8], second: &[u8]) -> Vec<Match> {
+        let mut matches = Vec::new();
+        if second.len() < self.key_len {
+            return matches;
+        }
+        for j in 0..second.len() - self.key_len + 1 {
+            for p in self.positions(data, &second[j..j + self.key_len]) {
+                // Skip matches which are the continuation of an already returned one
+                if p > 0 && j > 0 && data[p - 1] == second[j - 1] {
+                    continue;
+                }
+                let length = data[p..].iter().zip(second[j..].iter()).take_while(|&(a, b)| a == b).count();
+                matches.push(Match::new(p, j, length));
+            }
+        }
+        matches
+    }
+}
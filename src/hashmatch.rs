@@ -6,16 +6,18 @@
 //! [`HashMatchKey`](trait.HashMatchKey.html) offers a tradeoff between the speed and the minimal 
 //! matching length.
 
+use std::borrow::Cow;
 use std::cmp::Eq;
 use std::collections::HashMap;
 use std::hash::Hash;
-use std::io::Cursor;
-use std::iter::Iterator;
+use std::io::{Cursor, Read, Result};
+use std::iter::{FusedIterator, Iterator};
 use std::mem::size_of;
 
-use bytepack::{Packed, Unpacker};
+use bytepack::{LEUnpacker, Packed};
 
 use Match;
+use source::ByteSource;
 
 /// Trait marking types which can be used as a matching key in the `HashMap`.
 ///
@@ -23,6 +25,9 @@ use Match;
 /// length will proportionally increase. For example a `u32` `HashMatchKey` allows to find common 
 /// substring equal or longer than 4 bytes while a `u64` `HashMatchKey` will be significantly faster 
 /// but only allows to find common substring equal or longer than 8 bytes.
+///
+/// Keys are always read from the data in little endian so that a key has the same value on every
+/// platform.
 pub trait HashMatchKey: Packed + Hash + Eq + Copy {}
 
 impl HashMatchKey for u8      {}
@@ -58,12 +63,57 @@ impl HashMatchKey for [u64;6] {}
 impl HashMatchKey for [u64;7] {}
 impl HashMatchKey for [u64;8] {}
 
-fn build_map<T: HashMatchKey>(c: &mut Cursor<&[u8]>) -> HashMap<T,Vec<usize>> {
-    let size = c.get_ref().len() - size_of::<T>() + 1;
+// Reads the bytes of a source from a position, so that keys can be unpacked from any source
+struct SourceReader<'a, S: ByteSource + ?Sized + 'a> {
+    source: &'a S,
+    pos: usize,
+}
+
+impl<'a, S: ByteSource + ?Sized> Read for SourceReader<'a, S> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let count = buf.len().min(self.source.len() - self.pos);
+        for (i, b) in buf[..count].iter_mut().enumerate() {
+            *b = self.source.byte_at(self.pos + i);
+        }
+        self.pos += count;
+        Ok(count)
+    }
+}
+
+// Return the key at pos in data. It will panic if the key extends beyond the end of data.
+#[inline]
+fn key_at<T: HashMatchKey, S: ByteSource + ?Sized>(data: &S, pos: usize) -> T {
+    match data.as_slice() {
+        Some(bytes) => Cursor::new(&bytes[pos..]).unpack::<T>().unwrap(),
+        None => SourceReader { source: data, pos }.unpack::<T>().unwrap()
+    }
+}
+
+// Length of the common prefix of the suffixes of first at i and of second at j
+#[inline]
+fn common_length<F: ByteSource + ?Sized, S: ByteSource + ?Sized>(first: &F, i: usize, second: &S, j: usize) -> usize {
+    if let (Some(first), Some(second)) = (first.as_slice(), second.as_slice()) {
+        return first[i..].iter().zip(second[j..].iter()).take_while(|&(a, b)| a == b).count();
+    }
+    let mut length = 0;
+    while i + length < first.len() && j + length < second.len() && first.byte_at(i + length) == second.byte_at(j + length) {
+        length += 1;
+    }
+    length
+}
+
+pub(crate) fn build_map<T: HashMatchKey>(c: &mut Cursor<&[u8]>) -> HashMap<T,Vec<usize>> {
+    build_source_map(*c.get_ref())
+}
+
+#[allow(clippy::map_entry, clippy::needless_return)]
+pub(crate) fn build_source_map<T: HashMatchKey, S: ByteSource + ?Sized>(data: &S) -> HashMap<T,Vec<usize>> {
+    span!("hashmatch::build_map", data.len());
+    // Data shorter than the key has no key at all
+    let size = (data.len() + 1).saturating_sub(size_of::<T>());
     let mut map = HashMap::<T, Vec<usize>>::with_capacity(size);
     for i in 0..size {
-        c.set_position(i as u64);
-        let v = c.unpack::<T>().unwrap();
+        let v = key_at::<T, S>(data, i);
         if !map.contains_key(&v) {
             map.insert(v, Vec::<usize>::new());
         }
@@ -72,8 +122,32 @@ fn build_map<T: HashMatchKey>(c: &mut Cursor<&[u8]>) -> HashMap<T,Vec<usize>> {
     return map;
 }
 
+// Call f(first_pos, second_pos, length) for every match between first, which map was built from, 
+// and second, in ascending order of second_pos. Only the left maximal occurrences of a key are 
+// extended, which finds the same matches as HashMatchIterator without tracking the diagonals.
+pub(crate) fn scan<T: HashMatchKey, F: FnMut(usize, usize, usize)>(first: &[u8], map: &HashMap<T,Vec<usize>>, second: &[u8], mut f: F) {
+    let mut cursor = Cursor::new(second);
+    for j in 0..(second.len() + 1).saturating_sub(size_of::<T>()) {
+        cursor.set_position(j as u64);
+        let v = cursor.unpack::<T>().unwrap();
+        if let Some(positions) = map.get(&v) {
+            for &first_pos in positions {
+                if j > 0 && first_pos > 0 && first[first_pos - 1] == second[j - 1] {
+                    continue;
+                }
+                let length = first[first_pos..].iter().zip(second[j..].iter()).take_while(|&(a, b)| a == b).count();
+                f(first_pos, j, length);
+            }
+        }
+    }
+}
+
 /// An iterator over all the [`Match`](../struct.Match.html) bewteen two pieces of data.
 ///
+/// Only maximal exact matches are returned: they can neither be extended to the left nor to the 
+/// right. The pieces of data can be byte slices or any other 
+/// [`ByteSource`](../source/trait.ByteSource.html).
+///
 /// # Examples
 /// 
 /// ```
@@ -86,73 +160,138 @@ fn build_map<T: HashMatchKey>(c: &mut Cursor<&[u8]>) -> HashMap<T,Vec<usize>> {
 ///     println!("Match: {:}", &a[m.first_pos..m.first_end()]);
 /// }
 /// ```
-pub struct HashMatchIterator<'a, T: HashMatchKey> {
-    first: Cursor<&'a [u8]>,
-    second: Cursor<&'a [u8]>,
+pub struct HashMatchIterator<'a, T: HashMatchKey, F: ByteSource + ?Sized + 'a = [u8], S: ByteSource + ?Sized + 'a = [u8]> {
+    first: &'a F,
+    second: &'a S,
     second_len: usize,
     i: usize,
     j: usize,
-    map: HashMap<T,Vec<usize>>,
-    matched: HashMap<isize, usize>
+    map: Cow<'a, HashMap<T,Vec<usize>>>,
+    max_positions: usize,
+    seek_pos: usize,
+    matched: HashMap<i64, usize>,
+    skip_ahead: bool,
+    // End in second of the matches returned at the current position
+    skip_to: usize
 }
 
-impl<'a, T: HashMatchKey> HashMatchIterator<'a, T> {
-    /// Allocate a new iterator over the matches between two byte slices
-    pub fn new(first: &'a [u8], second: &'a [u8]) -> HashMatchIterator<'a, T> {
-        let second_len = second.len() - size_of::<T>() + 1;
-        let mut first_cursor = Cursor::new(first);
-        let second_cursor = Cursor::new(second);
-        let map = build_map(&mut first_cursor);
+impl<'a, T: HashMatchKey, F: ByteSource + ?Sized, S: ByteSource + ?Sized> HashMatchIterator<'a, T, F, S> {
+    /// Allocate a new iterator over the matches between two pieces of data. There is no match if 
+    /// one of them is shorter than the key.
+    pub fn new(first: &'a F, second: &'a S) -> HashMatchIterator<'a, T, F, S> {
+        let map = build_source_map(first);
+        HashMatchIterator::from_map(first, Cow::Owned(map), second)
+    }
+
+    /// Allocate a new iterator over the matches between two pieces of data reusing the map built 
+    /// by [`build_map`](fn.build_map.html) for `first`.
+    pub(crate) fn from_map(first: &'a F, map: Cow<'a, HashMap<T,Vec<usize>>>, second: &'a S) -> HashMatchIterator<'a, T, F, S> {
+        let max_positions = map.values().map(Vec::len).max().unwrap_or(0);
         HashMatchIterator {
-            first: first_cursor,
-            second: second_cursor,
-            second_len: second_len,
+            first,
+            second,
+            second_len: (second.len() + 1).saturating_sub(size_of::<T>()),
             i: 0,
             j: 0,
-            map: map,
-            matched: HashMap::new()
+            map,
+            max_positions,
+            seek_pos: 0,
+            matched: HashMap::new(),
+            skip_ahead: false,
+            skip_to: 0
         }
     }
+
+    /// Make the iterator skip ahead: once the matches at a position of the second piece of data 
+    /// are returned, the scan resumes at the end of the longest of them instead of the next 
+    /// position, as if [`seek`](#method.seek) was called there.
+    ///
+    /// On mostly identical data this avoids looking up the key at every position of a long match. 
+    /// The cost is that the matches starting inside a returned match, including the ones 
+    /// overlapping its end, are never returned.
+    pub fn skip_ahead(mut self) -> HashMatchIterator<'a, T, F, S> {
+        self.skip_ahead = true;
+        self
+    }
     /// Reset the iterator to its start. This allows to iterate multiple times over the matches 
     /// without wasting time regenerating the `HashMap`.
     pub fn reset(&mut self) {
+        self.seek(0);
+    }
+
+    /// Move the iterator to `second_pos`: it then returns the matches at or after that position in 
+    /// the second piece of data, exactly as a complete iteration would. The position can be before 
+    /// or after the current one.
+    pub fn seek(&mut self, second_pos: usize) {
         self.i = 0;
-        self.j = 0;
+        self.j = second_pos;
+        self.seek_pos = second_pos;
         self.matched.clear();
+        self.skip_to = 0;
+    }
+}
+
+impl<'a, T: HashMatchKey, F: ByteSource + ?Sized, S: ByteSource + ?Sized> Clone for HashMatchIterator<'a, T, F, S> {
+    fn clone(&self) -> HashMatchIterator<'a, T, F, S> {
+        HashMatchIterator {
+            first: self.first,
+            second: self.second,
+            second_len: self.second_len,
+            i: self.i,
+            j: self.j,
+            map: self.map.clone(),
+            max_positions: self.max_positions,
+            seek_pos: self.seek_pos,
+            matched: self.matched.clone(),
+            skip_ahead: self.skip_ahead,
+            skip_to: self.skip_to
+        }
     }
 }
 
-impl<'a, T: HashMatchKey> Iterator for HashMatchIterator<'a, T> {
+impl<'a, T: HashMatchKey, F: ByteSource + ?Sized, S: ByteSource + ?Sized> Iterator for HashMatchIterator<'a, T, F, S> {
     type Item = Match;
+    #[allow(clippy::needless_return)]
     fn next(&mut self) -> Option<Match> {
         while self.j < self.second_len {
-            self.second.set_position(self.j as u64);
-            let v = self.second.unpack::<T>().unwrap();
+            let v = key_at::<T, S>(self.second, self.j);
             if let Some(positions) = self.map.get(&v) {
                 while self.i < positions.len() {
                     let first_pos = positions[self.i];
                     self.i += 1;
                     // Check if this is a not part of a match already returned
-                    let delta = first_pos as isize - self.j as isize;
+                    let delta = first_pos as i64 - self.j as i64;
                     if !(self.matched.contains_key(&delta) && self.matched.get(&delta).unwrap() >= &self.j) {
-                        let first_data = self.first.get_ref();
-                        let second_data = self.second.get_ref();
+                        let first_data = self.first;
+                        let second_data = self.second;
                         // Compute match length
-                        let mut idx = 0;
-                        while (first_pos + idx) < first_data.len() && 
-                              (self.j + idx) < second_data.len() &&
-                              first_data[first_pos + idx] == second_data[self.j + idx] {
-                            idx += 1;
-                        }
+                        let idx = common_length(first_data, first_pos, second_data, self.j);
                         // Update matched
                         self.matched.insert(delta, self.j + idx);
+                        // The rest of a match starting before the seek position isn't a match
+                        if self.j == self.seek_pos && self.j > 0 && first_pos > 0 && first_data.byte_at(first_pos - 1) == second_data.byte_at(self.j - 1) {
+                            continue;
+                        }
+                        self.skip_to = self.skip_to.max(self.j + idx);
                         return Some(Match::new(first_pos, self.j, idx));
                     }
                 }
             }
             self.j += 1;
             self.i = 0;
+            if self.skip_ahead && self.skip_to > self.j {
+                self.j = self.skip_to;
+                self.seek_pos = self.skip_to;
+            }
         }
         return None;
     }
+
+    // Each remaining position in second can start at most one match per occurrence of its key
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.second_len.saturating_sub(self.j);
+        (0, remaining.checked_mul(self.max_positions))
+    }
 }
+
+impl<'a, T: HashMatchKey, F: ByteSource + ?Sized, S: ByteSource + ?Sized> FusedIterator for HashMatchIterator<'a, T, F, S> {}
@@ -0,0 +1,82 @@
+//! Combination of `HashMatch` and `TreeMatch`, used by [`AlgoSpec::Hybrid`](../enum.AlgoSpec.html#variant.Hybrid).
+//!
+//! `HashMatch` with a long key quickly finds the long matches between large inputs but misses the
+//! short ones, while `TreeMatch` with a short minimal length finds everything but its index and
+//! scan are much slower. `AlgoSpec::Hybrid(key, minimal_length)` runs `HashMatch(key)` over the
+//! whole inputs to find anchors, chains them with
+//! [`collinear_chain`](../chain/fn.collinear_chain.html) and only runs
+//! `TreeMatch(minimal_length)` between each region of the second piece of data left between two
+//! consecutive anchors and the corresponding region of the first one. Regions larger than
+//! [`MAX_LEN`](../suffixtree/constant.MAX_LEN.html) are compared with `SuffixArrayMatch` instead.
+//!
+//! The matches found between the anchors are extended to be maximal in the whole pieces of data,
+//! so the result is a subset of the matches of `TreeMatch(minimal_length)`: all the ones of at
+//! least `key` bytes and the shorter ones found between the anchors. The shorter matches relating
+//! bytes on both sides of an anchor, typically moved content, are missed. All the matches are
+//! collected before the first one is returned.
+//!
+//! # Examples
+//!
+//! ```
+//! use bcmp::{AlgoSpec, Match, MatchIterator};
+//!
+//! let a = b"0123456789abcdefghij-xyz-klmnopqrstuvwxyz";
+//! let b = b"0123456789abcdefghij+xyz+klmnopqrstuvwxyz";
+//! let matches: Vec<Match> = MatchIterator::new(a, b, AlgoSpec::Hybrid(8, 3)).collect();
+//! assert!(matches == [Match::new(0, 0, 20), Match::new(21, 21, 3), Match::new(25, 25, 16)]);
+//! ```
+
+use std::borrow::Cow;
+
+use {AlgoSpec, Match, Region, source_backend};
+use chain::collinear_chain;
+use source::{ByteSource, Part};
+use suffixarray::SuffixArrayMatchIterator;
+use suffixtree::{self, SuffixTree};
+use treematch::TreeMatchIterator;
+
+// Extend m to the left and to the right as long as the bytes are equal
+fn extend<F: ByteSource + ?Sized, S: ByteSource + ?Sized>(first: &F, second: &S, mut m: Match) -> Match {
+    while m.first_pos > 0 && m.second_pos > 0 && first.byte_at(m.first_pos - 1) == second.byte_at(m.second_pos - 1) {
+        m.first_pos -= 1;
+        m.second_pos -= 1;
+        m.length += 1;
+    }
+    while m.first_end() < first.len() && m.second_end() < second.len() && first.byte_at(m.first_end()) == second.byte_at(m.second_end()) {
+        m.length += 1;
+    }
+    m
+}
+
+// The matches of AlgoSpec::Hybrid(key, minimal_length) in ascending order
+pub(crate) fn matches<F: ByteSource + ?Sized, S: ByteSource + ?Sized>(first: &F, second: &S, key: usize, minimal_length: usize) -> Vec<Match> {
+    span!("hybrid", first.len() + second.len());
+    let mut matches: Vec<Match> = source_backend(first, second, AlgoSpec::HashMatch(key)).collect();
+    let anchors = collinear_chain(&matches).matches;
+    let mut first_pos = 0;
+    let mut second_pos = 0;
+    let ends = anchors.iter().map(|m| (m.first_pos, m.second_pos, m.first_end(), m.second_end()))
+        .chain(Some((first.len(), second.len(), first.len(), second.len())));
+    for (first_start, second_start, first_end, second_end) in ends {
+        let first_region = Region::new(first_pos, first_start);
+        let second_region = Region::new(second_pos, second_start);
+        if first_region.len() >= minimal_length && second_region.len() >= minimal_length {
+            let first_part = Part::new(first, first_region);
+            let second_part = Part::new(second, second_region);
+            let gap: Box<dyn Iterator<Item=Match>> = if first_region.len() <= suffixtree::MAX_LEN {
+                let tree = SuffixTree::<u32>::build(&first_part);
+                Box::new(TreeMatchIterator::with_tree(&first_part, Cow::Owned(tree), &second_part, minimal_length))
+            }
+            else {
+                Box::new(SuffixArrayMatchIterator::new(&first_part, &second_part, minimal_length))
+            };
+            matches.extend(gap.map(|m| extend(first, second, Match::new(m.first_pos + first_region.start, m.second_pos + second_region.start, m.length))));
+        }
+        first_pos = first_end;
+        second_pos = second_end;
+    }
+    // The matches reaching the anchors can be extended to the same match, or to an anchor
+    matches.sort();
+    matches.dedup();
+    matches
+}
@@ -0,0 +1,146 @@
+//! Incremental update of a match set after small edits of the second piece of data.
+//!
+//! Editor-like applications compare a buffer against a reference after every small change.
+//! [`update_matches`](fn.update_matches.html) reuses the matches computed before the change: the
+//! matches far enough from the edits are shifted and only a small window around each edit is
+//! compared again.
+
+use std::cmp::{max, min};
+
+use {AlgoSpec, Match, MatchIterator};
+
+/// An edit of the second piece of data: `removed` bytes at `position` were replaced by
+/// `inserted` bytes.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct Edit {
+    /// Position of the edit in the data before the edit.
+    pub position: usize,
+    /// Number of bytes removed at `position`.
+    pub removed: usize,
+    /// Number of bytes inserted at `position`.
+    pub inserted: usize,
+}
+
+impl Edit {
+    /// Describe the replacement of `removed` bytes at `position` by `inserted` bytes.
+    pub fn new(position: usize, removed: usize, inserted: usize) -> Edit {
+        Edit {
+            position,
+            removed,
+            inserted,
+        }
+    }
+}
+
+// An edit located in both the old and the new data
+struct Located {
+    old_start: usize,
+    old_end: usize,
+    new_start: usize,
+    new_end: usize,
+}
+
+// Whether the maximality of a match in the old data could be affected by the edit: the bytes right 
+// before and right after a match decide its maximality
+fn touches(second_pos: usize, second_end: usize, start: usize, end: usize) -> bool {
+    second_end >= start && second_pos <= end
+}
+
+/// Update the matches between `first` and the second piece of data after `edits` were applied to
+/// it, `second` being the data after the edits.
+///
+/// `previous` must hold all the matches between `first` and the data before the edits, as
+/// returned by a [`MatchIterator`](../struct.MatchIterator.html) with the same `algo_spec`, in any
+/// order. The edits must be sorted by position and must not overlap, their positions being
+/// expressed in the data before the edits. The result is the same set of matches a
+/// `MatchIterator` would return over `first` and `second`, sorted in their natural order.
+///
+/// # Panics
+///
+/// It will panic if `algo_spec` is [`Hybrid`](../enum.AlgoSpec.html#variant.Hybrid): an edit can
+/// change the anchors chained over the whole data, so its matches can not be updated locally.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::{AlgoSpec, Match, MatchIterator};
+/// use bcmp::incremental::{Edit, update_matches};
+///
+/// let first = b"abcdefghijklmnopqrstuvwxyz";
+/// let old = b"__abcdefgh__mnopqrstu__";
+/// let new = b"__abcdefghijklmnopqrstu__";
+/// let previous: Vec<Match> = MatchIterator::new(first, old, AlgoSpec::TreeMatch(4)).collect();
+/// let updated = update_matches(first, new, &previous, &[Edit::new(10, 2, 4)], AlgoSpec::TreeMatch(4));
+/// assert!(updated == [Match::new(0, 2, 21)]);
+/// ```
+pub fn update_matches(first: &[u8], second: &[u8], previous: &[Match], edits: &[Edit], algo_spec: AlgoSpec) -> Vec<Match> {
+    assert!(!matches!(algo_spec, AlgoSpec::Hybrid(..)), "Hybrid matches can not be updated incrementally");
+    let mml = algo_spec.minimal_length();
+    let mut located = Vec::with_capacity(edits.len());
+    let mut shift = 0isize;
+    for e in edits {
+        let new_start = (e.position as isize + shift) as usize;
+        located.push(Located {
+            old_start: e.position,
+            old_end: e.position + e.removed,
+            new_start,
+            new_end: new_start + e.inserted,
+        });
+        shift += e.inserted as isize - e.removed as isize;
+    }
+    // Map a position of the old data to the new data, positions inside an edit being mapped to its 
+    // start
+    let map = |pos: usize| {
+        let mut shift = 0isize;
+        for l in &located {
+            if pos < l.old_start {
+                break;
+            }
+            if pos < l.old_end {
+                return l.new_start;
+            }
+            shift = l.new_end as isize - l.old_end as isize;
+        }
+        (pos as isize + shift) as usize
+    };
+    // Window of the new data to compare again around each edit
+    let mut windows: Vec<(usize, usize)> = located.iter()
+        .map(|l| (l.new_start.saturating_sub(mml), min(second.len(), l.new_end + mml))).collect();
+    let mut matches = Vec::new();
+    for m in previous {
+        let mut kept = true;
+        for (l, w) in located.iter().zip(windows.iter_mut()) {
+            if touches(m.second_pos, m.second_end(), l.old_start, l.old_end) {
+                kept = false;
+                w.0 = min(w.0, map(m.second_pos));
+                w.1 = min(second.len(), max(w.1, map(m.second_end())));
+            }
+        }
+        if kept {
+            matches.push(Match::new(m.first_pos, map(m.second_pos), m.length));
+        }
+    }
+    // Merge the overlapping windows and compare them again
+    windows.sort();
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for w in windows {
+        match merged.last_mut() {
+            Some(last) if w.0 <= last.1 => last.1 = max(last.1, w.1),
+            _ => merged.push(w)
+        }
+    }
+    for (lo, hi) in merged {
+        if hi - lo < mml || first.len() < mml {
+            continue;
+        }
+        for m in MatchIterator::new(first, &second[lo..hi], algo_spec) {
+            let m = Match::new(m.first_pos, m.second_pos + lo, m.length);
+            // The matches not touching any edit are already known
+            if located.iter().any(|l| touches(m.second_pos, m.second_end(), l.new_start, l.new_end)) {
+                matches.push(m);
+            }
+        }
+    }
+    matches.sort();
+    matches
+}
@@ -0,0 +1,195 @@
+//! A set of byte ranges supporting the usual set algebra.
+//!
+//! [`IntervalSet`](struct.IntervalSet.html) is what the coverage related functions return. It
+//! makes questions like "which bytes of second are covered by matches but not inside an ignored
+//! range" a matter of combining sets instead of juggling overlapping intervals by hand.
+
+use std::cmp::{max, min};
+use std::iter::FromIterator;
+use std::slice::Iter;
+
+use Region;
+
+/// A set of disjoint half-open byte ranges, stored as [`Region`](../struct.Region.html).
+///
+/// The ranges are kept sorted, non-empty and non-adjacent: inserting `[0..4)` and `[4..8)` results
+/// in a single `[0..8)` range.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::Region;
+/// use bcmp::interval::IntervalSet;
+///
+/// let covered: IntervalSet = vec![(0, 10), (20, 30)].into_iter().collect();
+/// let ignored: IntervalSet = vec![(5, 25)].into_iter().collect();
+/// let interesting = covered.difference(&ignored);
+/// assert!(interesting.regions() == [Region::new(0, 5), Region::new(25, 30)]);
+/// assert!(interesting.total_len() == 10);
+/// ```
+#[derive(Clone,Debug,PartialEq,Eq,Default)]
+pub struct IntervalSet {
+    regions: Vec<Region>,
+}
+
+impl IntervalSet {
+    /// Allocate a new empty `IntervalSet`.
+    pub fn new() -> IntervalSet {
+        IntervalSet {
+            regions: Vec::new(),
+        }
+    }
+
+    /// Add the range `[start..end)` to the set, merging it with the ranges it overlaps or touches.
+    /// Empty ranges are ignored.
+    pub fn insert(&mut self, start: usize, end: usize) {
+        if start >= end {
+            return;
+        }
+        // Fast path: ranges are very often inserted in ascending order
+        if let Some(last) = self.regions.last_mut() {
+            if start >= last.start {
+                if start <= last.end {
+                    last.end = max(last.end, end);
+                }
+                else {
+                    self.regions.push(Region::new(start, end));
+                }
+                return;
+            }
+        }
+        else {
+            self.regions.push(Region::new(start, end));
+            return;
+        }
+        // First range which ends at or after start and last range which starts at or before end
+        let lo = self.regions.iter().position(|r| r.end >= start).unwrap_or(self.regions.len());
+        let hi = self.regions.iter().rposition(|r| r.start <= end).map(|i| i + 1).unwrap_or(0);
+        if lo >= hi {
+            self.regions.insert(lo, Region::new(start, end));
+        }
+        else {
+            let merged = Region::new(min(start, self.regions[lo].start), max(end, self.regions[hi - 1].end));
+            self.regions.drain(lo..hi);
+            self.regions.insert(lo, merged);
+        }
+    }
+
+    /// Return true if `pos` is inside one of the ranges of the set.
+    pub fn contains(&self, pos: usize) -> bool {
+        match self.regions.binary_search_by(|r| r.start.cmp(&pos)) {
+            Ok(_) => true,
+            Err(0) => false,
+            Err(i) => pos < self.regions[i - 1].end,
+        }
+    }
+
+    /// Return true if the set does not contain any byte.
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    /// Total number of bytes contained in the set.
+    pub fn total_len(&self) -> usize {
+        self.regions.iter().map(|r| r.end - r.start).sum()
+    }
+
+    /// The sorted list of disjoint regions composing the set.
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    /// Iterate over the sorted list of disjoint regions composing the set.
+    pub fn iter(&self) -> Iter<'_, Region> {
+        self.regions.iter()
+    }
+
+    /// Return the set of bytes contained in `self` or `other`.
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        let mut result = self.clone();
+        for r in &other.regions {
+            result.insert(r.start, r.end);
+        }
+        result
+    }
+
+    /// Return the set of bytes contained in both `self` and `other`.
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let mut result = IntervalSet::new();
+        let mut i = 0;
+        let mut j = 0;
+        while i < self.regions.len() && j < other.regions.len() {
+            let a = self.regions[i];
+            let b = other.regions[j];
+            let start = max(a.start, b.start);
+            let end = min(a.end, b.end);
+            if start < end {
+                result.regions.push(Region::new(start, end));
+            }
+            // Advance the range ending first
+            if a.end < b.end {
+                i += 1;
+            }
+            else {
+                j += 1;
+            }
+        }
+        result
+    }
+
+    /// Return the set of bytes of `[0..len)` which are not contained in `self`.
+    pub fn complement(&self, len: usize) -> IntervalSet {
+        let mut result = IntervalSet::new();
+        let mut covered = 0;
+        for r in &self.regions {
+            if r.start >= len {
+                break;
+            }
+            if r.start > covered {
+                result.regions.push(Region::new(covered, r.start));
+            }
+            covered = r.end;
+        }
+        if covered < len {
+            result.regions.push(Region::new(covered, len));
+        }
+        result
+    }
+
+    /// Return the set of bytes contained in `self` but not in `other`.
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        let end = match self.regions.last() {
+            Some(r) => r.end,
+            None => return IntervalSet::new(),
+        };
+        self.intersection(&other.complement(end))
+    }
+}
+
+impl FromIterator<Region> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item=Region>>(iter: I) -> IntervalSet {
+        let mut set = IntervalSet::new();
+        for r in iter {
+            set.insert(r.start, r.end);
+        }
+        set
+    }
+}
+
+impl FromIterator<(usize, usize)> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item=(usize, usize)>>(iter: I) -> IntervalSet {
+        let mut set = IntervalSet::new();
+        for (start, end) in iter {
+            set.insert(start, end);
+        }
+        set
+    }
+}
+
+impl<'a> IntoIterator for &'a IntervalSet {
+    type Item = &'a Region;
+    type IntoIter = Iter<'a, Region>;
+    fn into_iter(self) -> Iter<'a, Region> {
+        self.regions.iter()
+    }
+}
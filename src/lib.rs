@@ -39,21 +39,151 @@
 //!     println!("b[0x{:x}..0x{:x}] == a[0x{:x}..0x{:x}]", patch.second_pos, patch.second_end(), patch.first_pos, patch.first_end());
 //! }
 //! ```
+//!
+//! # Empty and short inputs
+//!
+//! Every function accepts byte slices of any length, including empty ones. A byte slice shorter 
+//! than the minimal matching length, or than the [`HashMatchKey`](hashmatch/trait.HashMatchKey.html), 
+//! simply has no match: the patch set is empty, the whole other byte slice is a unique string and 
+//! the similarity is 0, except for two empty byte slices which are considered identical.
+//!
+//! # Large inputs
+//!
+//! Offsets and lengths are `usize`, so inputs larger than 4 GiB, like disk images, are supported
+//! on 64-bit platforms. `TreeMatch` indexes up to [`MAX_LEN`](suffixtree/constant.MAX_LEN.html)
+//! bytes, about 2 GiB, with a compact suffix tree using `u32` indices and switches to `usize`
+//! indices beyond, which needs half as much memory again, see
+//! [`estimate_memory`](fn.estimate_memory.html).
+//!
+//! Every binary format stores offsets and lengths as `u64`: reading a file whose offsets don't fit
+//! in a `usize` fails with an `InvalidData` error. Corrupted lengths, compressed operations
+//! included, are checked against the data actually read, so they fail the same way rather than
+//! overflowing or allocating the announced size. Likewise a
+//! [`StreamMatchIterator`](stream/struct.StreamMatchIterator.html) returns an error once its reader
+//! goes beyond `usize::MAX` bytes.
+//!
+//! # Determinism
+//!
+//! Identical inputs and parameters produce identical outputs on every platform, whatever its
+//! endianness or pointer width: the matches, their order, the patch sets and the serialized
+//! [`Delta`](patch/struct.Delta.html) bytes. [`HashMatchKey`](hashmatch/trait.HashMatchKey.html)
+//! values are always read in little endian, every binary format is little endian, and no output
+//! depends on the iteration order of a `HashMap`, which is randomized per process.
 
 extern crate bytepack;
+#[cfg(feature = "async")]
+extern crate futures;
+#[cfg(feature = "mmap")]
+extern crate memmap2;
+#[cfg(feature = "gpu")]
+extern crate pollster;
+// The pyo3 macros refer to `::core` which needs to be in the crate root with the 2015 edition
+#[cfg(feature = "python")]
+extern crate core;
+#[cfg(feature = "python")]
+extern crate pyo3;
+#[cfg(feature = "tracing")]
+extern crate tracing;
+#[cfg(feature = "grapheme")]
+extern crate unicode_segmentation;
+#[cfg(feature = "gpu")]
+extern crate wgpu;
+#[cfg(feature = "lzma")]
+extern crate xz2;
+#[cfg(feature = "zstd")]
+extern crate zstd;
 
+// Open a span of the `profiling` and `tracing` features, closed at the end of the enclosing block
+macro_rules! span {
+    ($name:expr, $bytes:expr) => {
+        #[cfg(feature = "profiling")]
+        let _span = ::trace::Guard::enter($name, $bytes);
+        #[cfg(feature = "tracing")]
+        let _tracing_span = ::tracing::info_span!($name, bytes = $bytes).entered();
+    }
+}
+
+pub mod anchor;
+#[cfg(feature = "async")]
+pub mod asyncmatch;
+#[cfg(feature = "bio")]
+pub mod bio;
+pub mod blame;
+pub mod bridge;
+pub mod chain;
+pub mod checkpoint;
+pub mod corpus;
+pub mod dedup;
+mod digest;
+pub mod divergence;
+pub mod dot;
+pub mod encoding;
+pub mod fs;
+#[cfg(feature = "gen")]
+pub mod gen;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+#[cfg(feature = "grapheme")]
+pub mod grapheme;
+pub mod guard;
+pub mod hashindex;
 pub mod hashmatch;
+pub mod hybrid;
+pub mod incremental;
+pub mod interval;
+mod lz;
+#[cfg(feature = "naive")]
+pub mod naive;
+pub mod normalize;
+pub mod numeric;
+pub mod patch;
+pub mod probe;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod repeats;
+pub mod reverse;
+pub mod scatter;
+pub mod segmap;
+pub mod signature;
+pub mod source;
+pub mod stream;
+pub mod suffixarray;
+pub mod suffixtree;
+pub mod text;
+#[cfg(feature = "profiling")]
+pub mod trace;
 pub mod treematch;
+pub mod walker;
+pub mod window;
 #[cfg(test)]
 mod tests;
 
-use std::iter::Iterator;
+use std::borrow::Cow;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{self, Cursor};
+use std::iter::{FusedIterator, Iterator, Peekable};
+use std::mem::size_of;
+use std::ops::Range;
 
-use hashmatch::HashMatchIterator;
+use chain::{collinear_chain, unique_anchors};
+use checkpoint::Checkpoint;
+use hashmatch::{HashMatchIterator, build_map};
+use interval::IntervalSet;
+use source::ByteSource;
+use suffixarray::{SuffixArray, SuffixArrayMatchIterator};
+use suffixtree::SuffixTree;
 use treematch::TreeMatchIterator;
 
 /// A structure representing a matching substring between two pieces of data.
-#[derive(Clone,Copy,Debug,PartialEq, Eq)]
+///
+/// `Match` is ordered by [`second_pos`](#structfield.second_pos), then 
+/// [`first_pos`](#structfield.first_pos) and finally [`length`](#structfield.length). The 
+/// backends other than [`Hybrid`](enum.AlgoSpec.html#variant.Hybrid) return the same matches in 
+/// different orders, so sorting a collected list of matches gives a backend independent result.
+#[derive(Clone,Copy,Debug,PartialEq, Eq, Hash)]
 pub struct Match {
     /// Start of the string in the first piece of data.
     pub first_pos: usize,
@@ -65,6 +195,7 @@ pub struct Match {
 
 impl Match {
     /// Allocate a new `Match`.
+    #[allow(clippy::redundant_field_names)]
     pub fn new(first_pos: usize, second_pos: usize, length: usize) -> Match {
         Match {
             first_pos: first_pos,
@@ -72,40 +203,542 @@ impl Match {
             length: length,
         }
     }
-    /// `first_pos + length`
+    /// `first_pos + length`. It will panic if the sum overflows a `usize`.
     pub fn first_end(&self) -> usize {
-        self.first_pos + self.length
+        self.first_pos.checked_add(self.length).expect("The match ends beyond usize::MAX")
     }
-    /// `second_pos + length`
+    /// `second_pos + length`. It will panic if the sum overflows a `usize`.
     pub fn second_end(&self) -> usize {
-        self.second_pos + self.length
+        self.second_pos.checked_add(self.length).expect("The match ends beyond usize::MAX")
+    }
+    /// Return true if the match can neither be extended to the left nor to the right in `first` 
+    /// and `second`, the pieces of data it was found in.
+    pub fn is_maximal(&self, first: &[u8], second: &[u8]) -> bool {
+        let left = self.first_pos == 0 || self.second_pos == 0 
+            || first[self.first_pos - 1] != second[self.second_pos - 1];
+        let right = self.first_end() == first.len() || self.second_end() == second.len() 
+            || first[self.first_end()] != second[self.second_end()];
+        left && right
+    }
+}
+
+impl Ord for Match {
+    fn cmp(&self, other: &Match) -> Ordering {
+        self.second_pos.cmp(&other.second_pos)
+            .then(self.first_pos.cmp(&other.first_pos))
+            .then(self.length.cmp(&other.length))
     }
 }
 
-/// An enumeration describing the algorithm specification: either [`HashMatch`](hashmatch/index.html) 
-/// or [`TreeMatch`](treematch/index.html) with the minimal matching length parameter.
+impl PartialOrd for Match {
+    fn partial_cmp(&self, other: &Match) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A structure representing a half-open range `[start..end)` of a piece of data.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,PartialOrd,Ord,Hash)]
+pub struct Region {
+    /// Start of the region.
+    pub start: usize,
+    /// End of the region, exclusive.
+    pub end: usize,
+}
+
+impl Region {
+    /// Allocate a new `Region`.
+    pub fn new(start: usize, end: usize) -> Region {
+        Region {
+            start,
+            end,
+        }
+    }
+    /// `end - start`
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+    /// Return true if the region does not contain any byte.
+    pub fn is_empty(&self) -> bool {
+        self.start >= self.end
+    }
+    /// The region as a `Range`, suitable for slicing.
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+    /// The bytes of `data` covered by this region.
+    pub fn bytes<'a>(&self, data: &'a [u8]) -> &'a [u8] {
+        &data[self.start..self.end]
+    }
+}
+
+impl From<Range<usize>> for Region {
+    fn from(range: Range<usize>) -> Region {
+        Region::new(range.start, range.end)
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[0x{:x}..0x{:x}]", self.start, self.end)
+    }
+}
+
+/// A [`Match`](struct.Match.html) together with the matched bytes, as returned by 
+/// [`MatchIterator::slices`](struct.MatchIterator.html#method.slices).
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct MatchedSlices<'a> {
+    /// The positions and length of the match.
+    pub m: Match,
+    /// The matched bytes in the first piece of data.
+    pub first: &'a [u8],
+    /// The matched bytes in the second piece of data, equal to `first`.
+    pub second: &'a [u8],
+}
+
+/// A unique string of the second piece of data together with the matches surrounding it, as 
+/// returned by [`unique_strings_with_context`](fn.unique_strings_with_context.html).
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct UniqueString {
+    /// The region of the second piece of data which can't be found in the first.
+    pub region: Region,
+    /// The longest match ending right before the region, if any.
+    pub before: Option<Match>,
+    /// The longest match starting right after the region, if any.
+    pub after: Option<Match>,
+}
+
+impl UniqueString {
+    /// The region of the first piece of data lying between the two surrounding matches. This is 
+    /// what the unique string most likely replaced. It is only defined if both matches exist and 
+    /// appear in the same order in both pieces of data.
+    pub fn replaced(&self) -> Option<Region> {
+        match (self.before, self.after) {
+            (Some(before), Some(after)) if before.first_end() <= after.first_pos => {
+                Some(Region::new(before.first_end(), after.first_pos))
+            },
+            _ => None
+        }
+    }
+}
+
+/// The regions of two pieces of data which can't be found in the other, as returned by 
+/// [`diff_regions`](fn.diff_regions.html).
+#[derive(Clone,Debug,PartialEq,Eq,Default)]
+pub struct DiffRegions {
+    /// Regions of the first piece of data which can't be found in the second (deletions).
+    pub only_first: Vec<Region>,
+    /// Regions of the second piece of data which can't be found in the first (insertions).
+    pub only_second: Vec<Region>,
+}
+
+/// The number of bytes of two pieces of data covered by a match with the other one, as returned 
+/// by [`coverage_stats`](fn.coverage_stats.html).
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Default)]
+pub struct CoverageStats {
+    /// The length of the first piece of data.
+    pub first_len: usize,
+    /// The length of the second piece of data.
+    pub second_len: usize,
+    /// The number of bytes of the first piece of data covered by a match.
+    pub first_covered: usize,
+    /// The number of bytes of the second piece of data covered by a match.
+    pub second_covered: usize,
+}
+
+impl CoverageStats {
+    /// Return the fraction of the bytes of both pieces of data covered by a match, as 
+    /// [`similarity`](fn.similarity.html) does. Two empty pieces of data are considered identical.
+    pub fn similarity(&self) -> f64 {
+        let total = self.first_len + self.second_len;
+        if total == 0 {
+            return 1.0;
+        }
+        (self.first_covered + self.second_covered) as f64 / total as f64
+    }
+}
+
+/// Sort matches in ascending order of [`second_pos`](struct.Match.html#structfield.second_pos). 
+/// This is the natural [`Match`](struct.Match.html) ordering.
+pub fn sort_by_second(matches: &mut [Match]) {
+    matches.sort();
+}
+
+/// Sort matches in ascending order of [`first_pos`](struct.Match.html#structfield.first_pos), 
+/// then [`second_pos`](struct.Match.html#structfield.second_pos) and 
+/// [`length`](struct.Match.html#structfield.length).
+pub fn sort_by_first(matches: &mut [Match]) {
+    matches.sort_by(|a, b| {
+        a.first_pos.cmp(&b.first_pos)
+            .then(a.second_pos.cmp(&b.second_pos))
+            .then(a.length.cmp(&b.length))
+    });
+}
+
+/// Sort matches in decreasing order of [`length`](struct.Match.html#structfield.length). Matches 
+/// of equal length are kept in their natural order.
+pub fn sort_by_length_desc(matches: &mut [Match]) {
+    matches.sort_by(|a, b| b.length.cmp(&a.length).then(a.cmp(b)));
+}
+
+/// An enumeration describing the algorithm specification: [`HashMatch`](hashmatch/index.html), 
+/// [`TreeMatch`](treematch/index.html), [`SuffixArrayMatch`](suffixarray/index.html) or a
+/// [`Hybrid`](hybrid/index.html) of them with the minimal matching length parameter.
+///
+/// More algorithms can be added without a breaking change, so matching on an `AlgoSpec` outside
+/// of this crate requires a wildcard arm.
 #[derive(Clone,Copy,Debug)]
+#[non_exhaustive]
 pub enum AlgoSpec {
     /// The parameter is the minimal matching length which will determine the 
     /// [`HashMatchKey`](hashmatch/trait.HashMatchKey.html) used.
     HashMatch(usize),
     /// The parameter is the minimal matching length.
-    TreeMatch(usize)
+    TreeMatch(usize),
+    /// The parameter is the minimal matching length, which must be at least 1. It returns the same 
+    /// matches as `TreeMatch` but indexes the first piece of data with a suffix array, using an 
+    /// order of magnitude less memory for large references at the cost of slower enumeration.
+    SuffixArrayMatch(usize),
+    /// The first parameter is the minimal length of the anchors found with `HashMatch`, which must
+    /// support it, and the second one is the minimal matching length used with `TreeMatch` between
+    /// the anchors, between 1 and the first parameter. See the [`hybrid`](hybrid/index.html) module
+    /// for the matches it returns.
+    Hybrid(usize, usize)
+}
+
+impl AlgoSpec {
+    /// Return the minimal matching length of the algorithm specification.
+    pub fn minimal_length(&self) -> usize {
+        match *self {
+            AlgoSpec::HashMatch(mml) | AlgoSpec::TreeMatch(mml) | AlgoSpec::SuffixArrayMatch(mml) | AlgoSpec::Hybrid(_, mml) => mml
+        }
+    }
+
+    /// Return true if a [`MatchIterator`](struct.MatchIterator.html) can be built with this 
+    /// specification, see [`MatchIterator::new`](struct.MatchIterator.html#method.new).
+    pub fn is_supported(&self) -> bool {
+        match *self {
+            AlgoSpec::HashMatch(mml) => hash_supported(mml),
+            AlgoSpec::TreeMatch(_) => true,
+            AlgoSpec::SuffixArrayMatch(mml) => mml > 0,
+            AlgoSpec::Hybrid(key, mml) => hash_supported(key) && mml > 0 && mml <= key,
+        }
+    }
+}
+
+/// The memory needed to compare two pieces of data, as estimated by
+/// [`estimate_memory`](fn.estimate_memory.html), in bytes.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct MemoryEstimate {
+    /// The pieces of data themselves, which are borrowed by the algorithms.
+    pub data: usize,
+    /// The index of the first piece of data, kept during the whole iteration.
+    pub index: usize,
+    /// The temporary memory used on top of the index while it is built.
+    pub working_set: usize,
+}
+
+impl MemoryEstimate {
+    /// Return the peak memory usage: the data, the index and the working set.
+    pub fn total(&self) -> usize {
+        self.data.saturating_add(self.index).saturating_add(self.working_set)
+    }
+}
+
+// Size ratio from which coverage_stats indexes the second piece of data with HashMatch
+const HASH_SWAP_RATIO: usize = 4;
+
+// Bytes per indexed byte of a suffix tree and of its construction, measured on random, DNA like and
+// binary data
+const TREE_INDEX_FACTOR: usize = 116;
+// The same with usize indices, used beyond suffixtree::MAX_LEN
+const WIDE_TREE_INDEX_FACTOR: usize = 174;
+const TREE_WORKING_FACTOR: usize = 40;
+
+/// Estimate the memory needed to compare pieces of data of `first_len` and `second_len` bytes
+/// with `algo_spec`, before running anything.
+///
+/// The estimates are upper bounds for the usual data and follow the backends closely:
+///
+/// * `HashMatch` stores a `HashMap` entry for every distinct key and 8 bytes for every position
+///   of the indexed piece of data, plus the unused capacity of the vectors. High entropy data, 
+///   where every key is distinct, reaches the estimate while repetitive data uses about a quarter 
+///   less. Like [`MatchIterator::new`](struct.MatchIterator.html#method.new), the first piece of 
+///   data is indexed, use the swapped lengths for 
+///   [`MatchIterator::with_smaller_index`](struct.MatchIterator.html#method.with_smaller_index).
+/// * `TreeMatch` needs about 116 bytes per byte of the first piece of data for its suffix tree
+///   and 40 more while building it. Beyond [`MAX_LEN`](suffixtree/constant.MAX_LEN.html) the 
+///   tree uses `usize` indices and about 174 bytes per byte.
+/// * `SuffixArrayMatch` needs 8 bytes per byte of the first piece of data and 24 more while
+///   building its suffix array.
+///
+/// The state tracking the matches already returned during the iteration, which grows with the
+/// number of matches, is not included.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::{AlgoSpec, estimate_memory};
+///
+/// let estimate = estimate_memory(AlgoSpec::SuffixArrayMatch(8), 1 << 20, 1 << 10);
+/// assert!(estimate.index == 16 + 8 * (1 << 20) && estimate.working_set == 24 * (1 << 20));
+/// assert!(estimate_memory(AlgoSpec::TreeMatch(8), 1 << 20, 1 << 10).total() > estimate.total());
+/// ```
+pub fn estimate_memory(algo_spec: AlgoSpec, first_len: usize, second_len: usize) -> MemoryEstimate {
+    let (index, working_set) = match algo_spec {
+        AlgoSpec::HashMatch(mml) => {
+            let positions = first_len.saturating_add(1).saturating_sub(mml);
+            let distinct = 1usize.checked_shl(8 * mml as u32).map_or(positions, |keys| positions.min(keys));
+            // The table is allocated for one entry per position, each bucket holding a key, a
+            // vector and a control byte
+            let buckets = match positions {
+                0..=3 => 4,
+                4..=7 => 8,
+                _ => positions.saturating_mul(8).checked_div(7).and_then(usize::checked_next_power_of_two).unwrap_or(usize::MAX),
+            };
+            let entry = mml.div_ceil(8) * 8 + size_of::<Vec<usize>>() + 1;
+            // A vector holds at least 4 positions and up to twice its positions
+            let vectors = distinct.saturating_mul(4).max(positions.saturating_mul(2)).saturating_mul(size_of::<usize>());
+            (buckets.saturating_mul(entry).saturating_add(vectors), 0)
+        },
+        AlgoSpec::TreeMatch(_) => {
+            let factor = if first_len <= suffixtree::MAX_LEN { TREE_INDEX_FACTOR } else { WIDE_TREE_INDEX_FACTOR };
+            (first_len.saturating_mul(factor), first_len.saturating_mul(TREE_WORKING_FACTOR))
+        },
+        AlgoSpec::SuffixArrayMatch(_) => (first_len.saturating_mul(8).saturating_add(16), first_len.saturating_mul(24)),
+        // The trees between the anchors are built one at a time and at most as large as first
+        AlgoSpec::Hybrid(key, _) => (estimate_memory(AlgoSpec::HashMatch(key), first_len, second_len).index,
+            first_len.saturating_mul(TREE_INDEX_FACTOR + TREE_WORKING_FACTOR)),
+    };
+    MemoryEstimate {
+        data: first_len.saturating_add(second_len),
+        index,
+        working_set,
+    }
+}
+
+// Generate the enumerations of the possible MatchIterator backends and of their prepared indexes 
+// with the HashMatchKey used for each supported HashMatch minimal length.
+macro_rules! match_backends {
+    ($($mml:pat => $variant:ident($key:ty)),*) => {
+        #[derive(Clone)]
+        enum Backend<'a> {
+            // The compact tree is used when the first piece of data fits, the wide one otherwise
+            Tree(TreeMatchIterator<'a, [u8], [u8], u32>),
+            WideTree(TreeMatchIterator<'a>),
+            Array(SuffixArrayMatchIterator<'a>),
+            // The matches, the index of the next one and, once skipping ahead, the position of the 
+            // last returned match with the end of the longest returned match
+            Collected(Vec<Match>, usize, Option<(usize, usize)>),
+            $($variant(HashMatchIterator<'a, $key>)),*
+        }
+
+        // Return true if HashMatch supports the minimal length
+        fn hash_supported(mml: usize) -> bool {
+            match mml {
+                $($mml)|* => true,
+                _ => false
+            }
+        }
+
+        // Call f(first_pos, second_pos, length) for every match, in ascending order of second_pos, 
+        // without building a Backend
+        fn scan<F: FnMut(usize, usize, usize)>(first: &[u8], second: &[u8], algo_spec: AlgoSpec, f: F) {
+            match algo_spec {
+                AlgoSpec::TreeMatch(mml) if first.len() <= suffixtree::MAX_LEN => treematch::scan(&SuffixTree::<u32>::build(first), first, second, mml, f),
+                AlgoSpec::TreeMatch(mml) => treematch::scan(&SuffixTree::new(first), first, second, mml, f),
+                AlgoSpec::SuffixArrayMatch(mml) => SuffixArray::new(first).scan(first, second, mml, f),
+                AlgoSpec::Hybrid(key, mml) if algo_spec.is_supported() => {
+                    let mut f = f;
+                    for m in hybrid::matches(first, second, key, mml) {
+                        f(m.first_pos, m.second_pos, m.length);
+                    }
+                },
+                $(AlgoSpec::HashMatch($mml) => hashmatch::scan(first, &build_map::<$key>(&mut Cursor::new(first)), second, f),)*
+                _ => panic!("Unsupported AlgoSpec")
+            }
+        }
+
+        // The matches between two byte sources, in ascending order of second_pos
+        fn source_backend<'a, F: ByteSource + ?Sized, S: ByteSource + ?Sized>(first: &'a F, second: &'a S, algo_spec: AlgoSpec) -> Box<dyn Iterator<Item=Match> + 'a> {
+            match algo_spec {
+                AlgoSpec::TreeMatch(mml) if first.len() <= suffixtree::MAX_LEN =>
+                    Box::new(TreeMatchIterator::with_tree(first, Cow::Owned(SuffixTree::<u32>::build(first)), second, mml)),
+                AlgoSpec::TreeMatch(mml) => Box::new(TreeMatchIterator::new(first, second, mml)),
+                AlgoSpec::SuffixArrayMatch(mml) => Box::new(SuffixArrayMatchIterator::new(first, second, mml)),
+                AlgoSpec::Hybrid(key, mml) if algo_spec.is_supported() => Box::new(hybrid::matches(first, second, key, mml).into_iter()),
+                $(AlgoSpec::HashMatch($mml) => Box::new(HashMatchIterator::<$key, F, S>::new(first, second)),)*
+                _ => panic!("Unsupported AlgoSpec")
+            }
+        }
+
+        impl<'a> Backend<'a> {
+            fn new(first: &'a [u8], second: &'a [u8], algo_spec: AlgoSpec) -> Backend<'a> {
+                match algo_spec {
+                    AlgoSpec::TreeMatch(mml) if first.len() <= suffixtree::MAX_LEN =>
+                        Backend::Tree(TreeMatchIterator::with_tree(first, Cow::Owned(SuffixTree::build(first)), second, mml)),
+                    AlgoSpec::TreeMatch(mml) => Backend::WideTree(TreeMatchIterator::new(first, second, mml)),
+                    AlgoSpec::SuffixArrayMatch(mml) => Backend::Array(SuffixArrayMatchIterator::new(first, second, mml)),
+                    AlgoSpec::Hybrid(key, mml) if algo_spec.is_supported() => Backend::Collected(hybrid::matches(first, second, key, mml), 0, None),
+                    $(AlgoSpec::HashMatch($mml) => Backend::$variant(HashMatchIterator::<$key>::new(first, second)),)*
+                    _ => panic!("Unsupported AlgoSpec")
+                }
+            }
+
+            #[inline]
+            fn next(&mut self) -> Option<Match> {
+                match *self {
+                    Backend::Tree(ref mut iter) => iter.next(),
+                    Backend::WideTree(ref mut iter) => iter.next(),
+                    Backend::Array(ref mut iter) => iter.next(),
+                    Backend::Collected(ref matches, ref mut idx, ref mut skip) => {
+                        while let Some(m) = matches.get(*idx).cloned() {
+                            *idx += 1;
+                            // Drop the matches the lazy backends skip over
+                            if let Some((ref mut pos, ref mut skip_to)) = *skip {
+                                if m.second_pos != *pos && m.second_pos < *skip_to {
+                                    continue;
+                                }
+                                *pos = m.second_pos;
+                                *skip_to = (*skip_to).max(m.second_end());
+                            }
+                            return Some(m);
+                        }
+                        None
+                    },
+                    $(Backend::$variant(ref mut iter) => iter.next()),*
+                }
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                match *self {
+                    Backend::Tree(ref iter) => iter.size_hint(),
+                    Backend::WideTree(ref iter) => iter.size_hint(),
+                    Backend::Array(ref iter) => iter.size_hint(),
+                    Backend::Collected(ref matches, idx, None) => (matches.len() - idx, Some(matches.len() - idx)),
+                    Backend::Collected(ref matches, idx, Some(_)) => (0, Some(matches.len() - idx)),
+                    $(Backend::$variant(ref iter) => iter.size_hint()),*
+                }
+            }
+
+            fn skip_ahead(self) -> Backend<'a> {
+                match self {
+                    Backend::Tree(iter) => Backend::Tree(iter.skip_ahead()),
+                    Backend::WideTree(iter) => Backend::WideTree(iter.skip_ahead()),
+                    Backend::Array(iter) => Backend::Array(iter.skip_ahead()),
+                    Backend::Collected(matches, idx, skip) => Backend::Collected(matches, idx, skip.or(Some((0, 0)))),
+                    $(Backend::$variant(iter) => Backend::$variant(iter.skip_ahead())),*
+                }
+            }
+
+            fn seek(&mut self, second_pos: usize) {
+                match *self {
+                    Backend::Tree(ref mut iter) => iter.seek(second_pos),
+                    Backend::WideTree(ref mut iter) => iter.seek(second_pos),
+                    Backend::Array(ref mut iter) => iter.seek(second_pos),
+                    Backend::Collected(ref matches, ref mut idx, ref mut skip) => {
+                        *idx = matches.partition_point(|m| m.second_pos < second_pos);
+                        // Restart the skipping from the new position
+                        if skip.is_some() {
+                            *skip = Some((second_pos, 0));
+                        }
+                    },
+                    $(Backend::$variant(ref mut iter) => iter.seek(second_pos)),*
+                }
+            }
+        }
+
+        #[derive(Clone)]
+        enum Prepared {
+            Tree(SuffixTree<u32>, usize),
+            WideTree(SuffixTree, usize),
+            Array(SuffixArray<Vec<u8>>, usize),
+            // Nothing is indexed upfront, the regions between the anchors depend on second
+            Hybrid(usize, usize),
+            $($variant(HashMap<$key, Vec<usize>>)),*
+        }
+
+        impl Prepared {
+            fn new(first: &[u8], algo_spec: AlgoSpec) -> Prepared {
+                match algo_spec {
+                    AlgoSpec::TreeMatch(mml) if first.len() <= suffixtree::MAX_LEN => Prepared::Tree(SuffixTree::build(first), mml),
+                    AlgoSpec::TreeMatch(mml) => Prepared::WideTree(SuffixTree::new(first), mml),
+                    AlgoSpec::SuffixArrayMatch(mml) => Prepared::Array(SuffixArray::new(first), mml),
+                    AlgoSpec::Hybrid(key, mml) if algo_spec.is_supported() => Prepared::Hybrid(key, mml),
+                    $(AlgoSpec::HashMatch($mml) => Prepared::$variant(build_map::<$key>(&mut Cursor::new(first))),)*
+                    _ => panic!("Unsupported AlgoSpec")
+                }
+            }
+
+            fn backend<'a>(&'a self, first: &'a [u8], second: &'a [u8]) -> Backend<'a> {
+                match *self {
+                    Prepared::Tree(ref tree, mml) => Backend::Tree(TreeMatchIterator::with_tree(first, Cow::Borrowed(tree), second, mml)),
+                    Prepared::WideTree(ref tree, mml) => Backend::WideTree(TreeMatchIterator::with_tree(first, Cow::Borrowed(tree), second, mml)),
+                    Prepared::Array(ref array, mml) => Backend::Array(SuffixArrayMatchIterator::with_array(first, Cow::Borrowed(array), second, mml)),
+                    Prepared::Hybrid(key, mml) => Backend::Collected(hybrid::matches(first, second, key, mml), 0, None),
+                    $(Prepared::$variant(ref map) => Backend::$variant(HashMatchIterator::from_map(first, Cow::Borrowed(map), second))),*
+                }
+            }
+        }
+    }
+}
+
+match_backends! {
+    1 => Hash1(u8),
+    2 => Hash2(u16),
+    3 => Hash3([u8;3]),
+    4 => Hash4(u32),
+    5 => Hash5([u8;5]),
+    6 => Hash6([u16;3]),
+    7 => Hash7([u8;7]),
+    8 => Hash8(u64),
+    10 => Hash10([u16;5]),
+    12 => Hash12([u32;3]),
+    14 => Hash14([u16;7]),
+    16 => Hash16([u64;2]),
+    20 => Hash20([u32;5]),
+    24 => Hash24([u64;3]),
+    28 => Hash28([u32;7]),
+    32 => Hash32([u64;4]),
+    40 => Hash40([u64;5]),
+    48 => Hash48([u64;6]),
+    56 => Hash56([u64;7]),
+    64 => Hash64([u64;8])
 }
 
-/// A generic wrapper for [`HashMatchIterator`](hashmatch/struct.HashMatchIterator.html) and 
-/// [`TreeMatchIterator`](treematch/struct.TreeMatchIterator.html).
+/// A generic wrapper for [`HashMatchIterator`](hashmatch/struct.HashMatchIterator.html), 
+/// [`TreeMatchIterator`](treematch/struct.TreeMatchIterator.html) and 
+/// [`SuffixArrayMatchIterator`](suffixarray/struct.SuffixArrayMatchIterator.html).
 ///
-/// Both algorithms will return the same matches but the exact order may vary. 
+/// All algorithms will return the same matches but the exact order may vary, except 
+/// [`Hybrid`](enum.AlgoSpec.html#variant.Hybrid) which only returns a subset of them. 
 /// The only ordering guarantee is that the [`Match`](struct.Match.html) will be returned in 
 /// ascending order of the [`second_pos`](struct.Match.html#second_pos.v) field.
 ///
-/// For efficiency reasons, submatches are never returned. This means if we iterate over the 
+/// Every backend other than Hybrid returns exactly the maximal exact matches of at least the 
+/// minimal matching length, each of them once: a match is left maximal, it starts at the 
+/// beginning of one of the pieces of data or the bytes preceding it differ, and right maximal, it 
+/// ends at the end of one of the pieces of data or the bytes following it differ. This means if we iterate over the 
 /// [`Match`](struct.Match.html) of `"abcd"` and `"012abcd34"`, only `"abcd"` is returned. The 
 /// submatches `"abc"`, `"bcd"`, `"ab"`, ... are never returned but can easily be computed from the 
-/// encompassing [`Match`](struct.Match.html).
+/// encompassing [`Match`](struct.Match.html). Hybrid returns the long matches along the chained 
+/// anchors and the ones between them, which are maximal exact matches too but not all of them. 
+/// Debug builds assert the maximality of every match returned.
+///
+/// A `MatchIterator` is `Send` and `Sync`: it can be moved to a worker thread or stored in an 
+/// async task. Cloning it forks the iteration state, including the index of the first piece of 
+/// data: both clones then continue independently from the same position.
+#[derive(Clone)]
 pub struct MatchIterator<'a> {
-    iter: Box<Iterator<Item=Match> + 'a>
+    first: &'a [u8],
+    second: &'a [u8],
+    // Position of first and second in the buffers they were sliced from
+    first_offset: usize,
+    second_offset: usize,
+    minimal_length: usize,
+    // Position of the last match returned, or of the last seek, and number of matches returned at
+    // that position
+    progress: (usize, usize),
+    backend: Backend<'a>
 }
 
 impl<'a> MatchIterator<'a> {
@@ -114,83 +747,820 @@ impl<'a> MatchIterator<'a> {
     /// # Panics
     ///
     /// It will panic if the [`AlgoSpec`](enum.AlgoSpec.html) is not supported. 
-    /// [`TreeMatch`](treematch/index.html) supports any minimum matching length but 
+    /// [`TreeMatch`](treematch/index.html) supports any minimum matching length, 
+    /// [`SuffixArrayMatch`](suffixarray/index.html) any non zero one but 
     /// [`HashMatch`](hashmatch/index.html) only supports length of 1, 2, 3, 4, 5, 6, 7, 8, 10, 12, 
-    /// 14, 16, 20, 24, 28, 32, 40, 48, 56 and 64 bytes.
+    /// 14, 16, 20, 24, 28, 32, 40, 48, 56 and 64 bytes. [`Hybrid`](hybrid/index.html) needs an 
+    /// anchor length supported by `HashMatch` and a non zero minimal length not exceeding it.
+    ///
+    /// The first piece of data is indexed and the matches are found lazily while scanning the 
+    /// second one, except with [`Hybrid`](hybrid/index.html). When the second piece of data is much 
+    /// smaller, [`with_smaller_index`](#method.with_smaller_index) indexes it instead.
     pub fn new(first: &'a [u8], second: &'a [u8], algo_spec: AlgoSpec) -> MatchIterator<'a> {
         MatchIterator {
-            iter: match algo_spec {
-                AlgoSpec::TreeMatch(mml) => Box::new(TreeMatchIterator::new(first, second, mml)),
-                AlgoSpec::HashMatch(1) => Box::new(HashMatchIterator::<u8>::new(first, second)),
-                AlgoSpec::HashMatch(2) => Box::new(HashMatchIterator::<u16>::new(first, second)),
-                AlgoSpec::HashMatch(3) => Box::new(HashMatchIterator::<[u8;3]>::new(first, second)),
-                AlgoSpec::HashMatch(4) => Box::new(HashMatchIterator::<u32>::new(first, second)),
-                AlgoSpec::HashMatch(5) => Box::new(HashMatchIterator::<[u8;5]>::new(first, second)),
-                AlgoSpec::HashMatch(6) => Box::new(HashMatchIterator::<[u16;3]>::new(first, second)),
-                AlgoSpec::HashMatch(7) => Box::new(HashMatchIterator::<[u8;7]>::new(first, second)),
-                AlgoSpec::HashMatch(8) => Box::new(HashMatchIterator::<u64>::new(first, second)),
-                AlgoSpec::HashMatch(10) => Box::new(HashMatchIterator::<[u16;5]>::new(first, second)),
-                AlgoSpec::HashMatch(12) => Box::new(HashMatchIterator::<[u32;3]>::new(first, second)),
-                AlgoSpec::HashMatch(14) => Box::new(HashMatchIterator::<[u16;7]>::new(first, second)),
-                AlgoSpec::HashMatch(16) => Box::new(HashMatchIterator::<[u64;2]>::new(first, second)),
-                AlgoSpec::HashMatch(20) => Box::new(HashMatchIterator::<[u32;5]>::new(first, second)),
-                AlgoSpec::HashMatch(24) => Box::new(HashMatchIterator::<[u64;3]>::new(first, second)),
-                AlgoSpec::HashMatch(28) => Box::new(HashMatchIterator::<[u32;7]>::new(first, second)),
-                AlgoSpec::HashMatch(32) => Box::new(HashMatchIterator::<[u64;4]>::new(first, second)),
-                AlgoSpec::HashMatch(40) => Box::new(HashMatchIterator::<[u64;5]>::new(first, second)),
-                AlgoSpec::HashMatch(48) => Box::new(HashMatchIterator::<[u64;6]>::new(first, second)),
-                AlgoSpec::HashMatch(56) => Box::new(HashMatchIterator::<[u64;7]>::new(first, second)),
-                AlgoSpec::HashMatch(64) => Box::new(HashMatchIterator::<[u64;8]>::new(first, second)),
-                _ => panic!("Unsupported AlgoSpec")
+            first,
+            second,
+            first_offset: 0,
+            second_offset: 0,
+            minimal_length: algo_spec.minimal_length(),
+            progress: (0, 0),
+            backend: Backend::new(first, second, algo_spec)
+        }
+    }
+
+    /// Build a new `MatchIterator` restricted to a region of each piece of data.
+    ///
+    /// Only the bytes inside `first_region` and `second_region` are compared, so matches are 
+    /// clipped at the region boundaries, but their positions are reported in the coordinates of 
+    /// the whole `first` and `second`. The regions are borrowed from the buffers, nothing is 
+    /// copied. Otherwise this is the same as [`new`](#method.new).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bcmp::{AlgoSpec, Match, MatchIterator, Region};
+    ///
+    /// let a = b"header:abcdefg";
+    /// let b = b"header:012abc34cdef";
+    /// let matches: Vec<Match> = MatchIterator::with_regions(a, Region::new(7, 14), b, Region::new(7, 19), AlgoSpec::TreeMatch(3)).collect();
+    /// assert!(matches == [Match::new(7, 10, 3), Match::new(9, 15, 4)]);
+    /// ```
+    pub fn with_regions(first: &'a [u8], first_region: Region, second: &'a [u8], second_region: Region, algo_spec: AlgoSpec) -> MatchIterator<'a> {
+        let mut match_iter = MatchIterator::new(first_region.bytes(first), second_region.bytes(second), algo_spec);
+        match_iter.first_offset = first_region.start;
+        match_iter.second_offset = second_region.start;
+        match_iter
+    }
+
+    /// Build a new `MatchIterator` which indexes the smaller of the two pieces of data.
+    ///
+    /// Both algorithms index the first piece of data and scan the second one. When the second 
+    /// piece of data is smaller, swapping their roles saves most of the time and memory spent on 
+    /// the index. The matches are then transposed back and returned in their natural order, which 
+    /// keeps the ordering guarantee but requires collecting all of them before the first one is 
+    /// returned. Otherwise this is the same as [`new`](#method.new).
+    ///
+    /// Hybrid chains the anchors of the first piece of data, so swapping the roles would return 
+    /// other matches: it always falls back to [`new`](#method.new).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bcmp::{AlgoSpec, Match, MatchIterator};
+    ///
+    /// let reference = b"The quick brown fox jumps over the lazy dog";
+    /// let sample = b"lazy fox";
+    /// let matches: Vec<Match> = MatchIterator::with_smaller_index(reference, sample, AlgoSpec::TreeMatch(3)).collect();
+    /// assert!(matches == [Match::new(35, 0, 5), Match::new(15, 4, 4)]);
+    /// ```
+    pub fn with_smaller_index(first: &'a [u8], second: &'a [u8], algo_spec: AlgoSpec) -> MatchIterator<'a> {
+        if second.len() >= first.len() || matches!(algo_spec, AlgoSpec::Hybrid(..)) {
+            return MatchIterator::new(first, second, algo_spec);
+        }
+        let mut matches: Vec<Match> = MatchIterator::new(second, first, algo_spec)
+            .map(|m| Match::new(m.second_pos, m.first_pos, m.length))
+            .collect();
+        matches.sort();
+        MatchIterator {
+            first,
+            second,
+            first_offset: 0,
+            second_offset: 0,
+            minimal_length: algo_spec.minimal_length(),
+            progress: (0, 0),
+            backend: Backend::Collected(matches, 0, None)
+        }
+    }
+
+    /// Turn this iterator into an iterator over the [`MatchedSlices`](struct.MatchedSlices.html), 
+    /// returning the matched bytes of both pieces of data along with each match.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bcmp::{AlgoSpec, MatchIterator};
+    ///
+    /// let a = b"abcdefg";
+    /// let b = b"012abc34cdef56efg78abcdefg";
+    /// for s in MatchIterator::new(a, b, AlgoSpec::HashMatch(2)).slices() {
+    ///     assert!(s.first == s.second);
+    ///     println!("{:?} at 0x{:x}", s.first, s.m.second_pos);
+    /// }
+    /// ```
+    pub fn slices(self) -> Slices<'a> {
+        Slices {
+            iter: self
+        }
+    }
+
+    /// Turn this iterator into an iterator over the maximal exact matches only: the matches which 
+    /// can neither be extended to the left nor to the right in both pieces of data.
+    ///
+    /// Every match is checked with [`Match::is_maximal`](struct.Match.html#method.is_maximal), so 
+    /// the guarantee is enforced in release builds too, whatever the backend. Since every backend 
+    /// already returns maximal matches only, the check never discards anything: 
+    /// [`TreeMatch`](treematch/index.html) and [`HashMatch`](hashmatch/index.html) extend every 
+    /// match to the right until a mismatch and skip the positions of second covered by a match 
+    /// already returned on the same diagonal, while [`SuffixArrayMatch`](suffixarray/index.html) 
+    /// discards the matches which extend to the left. Downstream chaining code can rely on this 
+    /// iterator without depending on that implementation detail. For an iterator built with 
+    /// [`with_regions`](#method.with_regions), the matches are maximal within the regions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bcmp::{AlgoSpec, MatchIterator};
+    ///
+    /// let a = b"xabcdy";
+    /// let b = b"zabcdw";
+    /// let mems: Vec<_> = MatchIterator::new(a, b, AlgoSpec::TreeMatch(2)).maximal().collect();
+    /// assert!(mems.len() == 1 && mems[0].first_pos == 1 && mems[0].length == 4);
+    /// ```
+    pub fn maximal(self) -> Maximal<'a> {
+        Maximal {
+            iter: self
+        }
+    }
+
+    /// Turn this iterator into an iterator over every occurrence of the common substrings: all the 
+    /// pairs of positions in both pieces of data where at least the minimal matching length of 
+    /// bytes are equal, not only the maximal exact matches.
+    ///
+    /// Each maximal match is expanded into its suffixes at least as long as the minimal matching 
+    /// length, each of them extended to the right as far as possible. The result is much larger 
+    /// but complete, which is what clone detection needs. The matches are returned in their 
+    /// natural order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bcmp::{AlgoSpec, Match, MatchIterator};
+    ///
+    /// let a = b"abcd";
+    /// let b = b"xabcd";
+    /// let all: Vec<Match> = MatchIterator::new(a, b, AlgoSpec::HashMatch(2)).all_occurrences().collect();
+    /// assert!(all == [Match::new(0, 1, 4), Match::new(1, 2, 3), Match::new(2, 3, 2)]);
+    /// ```
+    pub fn all_occurrences(self) -> AllOccurrences<'a> {
+        AllOccurrences {
+            minimal_length: self.minimal_length,
+            splitting: Splitting::new(self)
+        }
+    }
+
+    /// Turn this iterator into an iterator over matches of at most `max_length` bytes: longer 
+    /// matches are split into consecutive pieces of `max_length` bytes followed by the remainder. 
+    /// The pieces are returned in their natural order. This suits encoders with fixed width 
+    /// length fields.
+    ///
+    /// It will panic if `max_length` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bcmp::{AlgoSpec, Match, MatchIterator};
+    ///
+    /// let a = b"abcdefghij";
+    /// let b = b"xabcdefghij";
+    /// let pieces: Vec<Match> = MatchIterator::new(a, b, AlgoSpec::TreeMatch(2)).capped(4).collect();
+    /// assert!(pieces == [Match::new(0, 1, 4), Match::new(4, 5, 4), Match::new(8, 9, 2)]);
+    /// ```
+    pub fn capped(self, max_length: usize) -> Capped<'a> {
+        assert!(max_length > 0, "The maximum length must be at least 1");
+        Capped {
+            max_length,
+            splitting: Splitting::new(self)
+        }
+    }
+
+    /// Turn this iterator into an iterator returning the matches from the end of the second piece 
+    /// of data: in descending order of [`second_pos`](struct.Match.html#structfield.second_pos) 
+    /// and then of [`first_pos`](struct.Match.html#structfield.first_pos).
+    ///
+    /// The second piece of data is processed block by block from its end using 
+    /// [`seek`](#method.seek), so only the matches of one block are held in memory at a time 
+    /// instead of all of them. Blocks without any match are merged with the preceding ones, which 
+    /// keeps the total amount of work proportional to a forward iteration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bcmp::{AlgoSpec, Match, MatchIterator};
+    ///
+    /// let a = b"abcdefg";
+    /// let b = b"012abc34cdef56efg78abcdefg";
+    /// let last: Vec<Match> = MatchIterator::new(a, b, AlgoSpec::TreeMatch(3)).from_end().take(2).collect();
+    /// assert!(last == [Match::new(0, 19, 7), Match::new(4, 14, 3)]);
+    /// ```
+    pub fn from_end(self) -> FromEnd<'a> {
+        FromEnd {
+            end: self.second_offset + self.second.len(),
+            iter: self,
+            block: FROM_END_BLOCK,
+            buffer: Vec::new()
+        }
+    }
+
+    /// Make the iterator skip ahead: once the matches at a position of the second piece of data 
+    /// are returned, the scan resumes at the end of the longest of them instead of the next 
+    /// position, as if [`seek`](#method.seek) was called there.
+    ///
+    /// On mostly identical data a single long match covers what would otherwise be millions of 
+    /// positions each probed for a match, so skipping them saves most of the scan. This is at the 
+    /// cost of completeness: the matches starting inside a returned match, including the ones 
+    /// overlapping its end and extending beyond it, are never returned, so some alternatives a 
+    /// full iteration would offer are missed. A later [`seek`](#method.seek) restarts the skipping 
+    /// from its position. Every backend other than Hybrid returns the same matches.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bcmp::{AlgoSpec, Match, MatchIterator};
+    ///
+    /// let a = b"abcdefghijkl";
+    /// let b = b"abcdefgh_defghijkl";
+    /// let all: Vec<Match> = MatchIterator::new(a, b, AlgoSpec::TreeMatch(3)).collect();
+    /// assert!(all == [Match::new(0, 0, 8), Match::new(3, 9, 9)]);
+    /// let greedy: Vec<Match> = MatchIterator::new(a, b, AlgoSpec::TreeMatch(3)).skip_ahead().collect();
+    /// assert!(greedy == all);
+    /// // "cdefg" overlaps the end of "abcd" and is missed
+    /// let missed: Vec<Match> = MatchIterator::new(b"abcdXcdefg", b"abcdefg", AlgoSpec::TreeMatch(3)).skip_ahead().collect();
+    /// assert!(missed == [Match::new(0, 0, 4)]);
+    /// ```
+    pub fn skip_ahead(mut self) -> MatchIterator<'a> {
+        self.backend = self.backend.skip_ahead();
+        self
+    }
+
+    /// Move the iterator to `second_pos`: the following matches are the ones at or after that 
+    /// position in the second piece of data, exactly as a complete iteration would return them. 
+    /// The position can be before or after the current one.
+    ///
+    /// The positions skipped are never scanned, which makes it cheap to only look at a region of 
+    /// the second piece of data. A match starting before `second_pos` and overlapping it is not 
+    /// returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bcmp::{AlgoSpec, Match, MatchIterator};
+    ///
+    /// let a = b"abcdefg";
+    /// let b = b"012abc34cdef56efg78abcdefg";
+    /// let mut match_iter = MatchIterator::new(a, b, AlgoSpec::TreeMatch(3));
+    /// match_iter.seek(10);
+    /// assert!(match_iter.next() == Some(Match::new(4, 14, 3)));
+    /// match_iter.seek(0);
+    /// assert!(match_iter.next() == Some(Match::new(0, 3, 3)));
+    /// ```
+    pub fn seek(&mut self, second_pos: usize) {
+        self.backend.seek(second_pos.saturating_sub(self.second_offset));
+        self.progress = (second_pos, 0);
+    }
+
+    /// Return a [`Checkpoint`](checkpoint/struct.Checkpoint.html) of the iteration, from which an
+    /// iterator over the same pieces of data can [`resume`](#method.resume).
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            first_len: self.first.len(),
+            second_len: self.second.len(),
+            second_pos: self.progress.0,
+            skip: self.progress.1,
+        }
+    }
+
+    /// Move the iterator to the point where `checkpoint` was taken: the following matches are 
+    /// exactly the ones the checkpointed iterator would have returned. The iterator must have been 
+    /// built the same way, with the same pieces of data and [`AlgoSpec`](enum.AlgoSpec.html).
+    ///
+    /// It will panic if the lengths of the pieces of data differ from the ones of the checkpoint.
+    pub fn resume(&mut self, checkpoint: &Checkpoint) {
+        assert!(checkpoint.first_len == self.first.len() && checkpoint.second_len == self.second.len(),
+                "The checkpoint was taken on different data");
+        self.seek(checkpoint.second_pos);
+        for _ in 0..checkpoint.skip {
+            if self.next().is_none() {
+                break;
             }
         }
     }
+
+    // Translate a match of the regions compared to the coordinates of the whole buffers
+    fn to_buffers(&self, m: Match) -> Match {
+        Match::new(m.first_pos + self.first_offset, m.second_pos + self.second_offset, m.length)
+    }
+
+    // Translate a match back to the coordinates of the regions compared
+    fn to_regions(&self, m: Match) -> Match {
+        Match::new(m.first_pos - self.first_offset, m.second_pos - self.second_offset, m.length)
+    }
 }
 
 impl<'a> Iterator for MatchIterator<'a> {
     type Item = Match;
     #[inline]
     fn next(&mut self) -> Option<Match> {
-        self.iter.next()
+        let m = self.backend.next();
+        debug_assert!(m.is_none_or(|m| m.is_maximal(self.first, self.second)), "{:?} is not maximal", m);
+        let m = m.map(|m| self.to_buffers(m));
+        if let Some(m) = m {
+            if m.second_pos == self.progress.0 {
+                self.progress.1 += 1;
+            }
+            else {
+                self.progress = (m.second_pos, 1);
+            }
+        }
+        m
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.backend.size_hint()
+    }
+}
+
+impl<'a> FusedIterator for MatchIterator<'a> {}
+
+/// An iterator over the [`MatchedSlices`](struct.MatchedSlices.html) of two pieces of data, 
+/// created by [`MatchIterator::slices`](struct.MatchIterator.html#method.slices).
+#[derive(Clone)]
+pub struct Slices<'a> {
+    iter: MatchIterator<'a>
+}
+
+impl<'a> Iterator for Slices<'a> {
+    type Item = MatchedSlices<'a>;
+    fn next(&mut self) -> Option<MatchedSlices<'a>> {
+        let m = self.iter.next()?;
+        let local = self.iter.to_regions(m);
+        Some(MatchedSlices {
+            m,
+            first: &self.iter.first[local.first_pos..local.first_end()],
+            second: &self.iter.second[local.second_pos..local.second_end()],
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a> FusedIterator for Slices<'a> {}
+
+/// An iterator over the maximal exact matches of two pieces of data, created by 
+/// [`MatchIterator::maximal`](struct.MatchIterator.html#method.maximal).
+#[derive(Clone)]
+pub struct Maximal<'a> {
+    iter: MatchIterator<'a>
+}
+
+impl<'a> Iterator for Maximal<'a> {
+    type Item = Match;
+    fn next(&mut self) -> Option<Match> {
+        let (first, second) = (self.iter.first, self.iter.second);
+        let (first_offset, second_offset) = (self.iter.first_offset, self.iter.second_offset);
+        self.iter.by_ref().find(|m| {
+            Match::new(m.first_pos - first_offset, m.second_pos - second_offset, m.length).is_maximal(first, second)
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+impl<'a> FusedIterator for Maximal<'a> {}
+
+const FROM_END_BLOCK: usize = 4096;
+
+/// An iterator over the matches of two pieces of data from the end of the second one, created by 
+/// [`MatchIterator::from_end`](struct.MatchIterator.html#method.from_end).
+#[derive(Clone)]
+pub struct FromEnd<'a> {
+    iter: MatchIterator<'a>,
+    // Every match at or after end has already been returned
+    end: usize,
+    block: usize,
+    // Matches of the current block in ascending order
+    buffer: Vec<Match>
+}
+
+impl<'a> Iterator for FromEnd<'a> {
+    type Item = Match;
+    fn next(&mut self) -> Option<Match> {
+        loop {
+            if let Some(m) = self.buffer.pop() {
+                return Some(m);
+            }
+            if self.end == self.iter.second_offset {
+                return None;
+            }
+            let start = self.end.saturating_sub(self.block).max(self.iter.second_offset);
+            self.iter.seek(start);
+            let end = self.end;
+            self.buffer = self.iter.by_ref().take_while(|m| m.second_pos < end).collect();
+            self.buffer.sort();
+            self.end = start;
+            // Grow the blocks over stretches without matches, which are scanned again every time
+            self.block = if self.buffer.is_empty() { self.block * 2 } else { FROM_END_BLOCK };
+        }
+    }
+}
+
+impl<'a> FusedIterator for FromEnd<'a> {}
+
+// Iterate over the matches of a MatchIterator, splitting each of them into a head which is returned 
+// and a tail starting further in second which is split again later, while keeping the natural order
+#[derive(Clone)]
+struct Splitting<'a> {
+    iter: Peekable<MatchIterator<'a>>,
+    // The tails still to split
+    pending: BinaryHeap<Reverse<Match>>
+}
+
+impl<'a> Splitting<'a> {
+    fn new(iter: MatchIterator<'a>) -> Splitting<'a> {
+        Splitting {
+            iter: iter.peekable(),
+            pending: BinaryHeap::new()
+        }
+    }
+
+    fn next<F: FnOnce(Match) -> (Match, Option<Match>)>(&mut self, split: F) -> Option<Match> {
+        // Tails start after their match, so once every match starting at or before the smallest 
+        // pending one has been queued, it is the next one in the natural order
+        loop {
+            match (self.iter.peek(), self.pending.peek()) {
+                (Some(m), Some(&Reverse(p))) if m.second_pos <= p.second_pos => {},
+                (Some(_), None) => {},
+                (None, None) => return None,
+                _ => break
+            }
+            let m = self.iter.next().unwrap();
+            self.pending.push(Reverse(m));
+        }
+        let Reverse(m) = self.pending.pop().unwrap();
+        let (head, tail) = split(m);
+        if let Some(tail) = tail {
+            self.pending.push(Reverse(tail));
+        }
+        Some(head)
+    }
+}
+
+/// An iterator over every occurrence of the common substrings of two pieces of data, created by 
+/// [`MatchIterator::all_occurrences`](struct.MatchIterator.html#method.all_occurrences).
+#[derive(Clone)]
+pub struct AllOccurrences<'a> {
+    splitting: Splitting<'a>,
+    minimal_length: usize
+}
+
+impl<'a> Iterator for AllOccurrences<'a> {
+    type Item = Match;
+    fn next(&mut self) -> Option<Match> {
+        let minimal_length = self.minimal_length;
+        self.splitting.next(|m| {
+            let suffix = Match::new(m.first_pos + 1, m.second_pos + 1, m.length.saturating_sub(1));
+            (m, if m.length > minimal_length { Some(suffix) } else { None })
+        })
+    }
+}
+
+impl<'a> FusedIterator for AllOccurrences<'a> {}
+
+/// An iterator over the matches of two pieces of data split into pieces of a maximum length, 
+/// created by [`MatchIterator::capped`](struct.MatchIterator.html#method.capped).
+#[derive(Clone)]
+pub struct Capped<'a> {
+    splitting: Splitting<'a>,
+    max_length: usize
+}
+
+impl<'a> Iterator for Capped<'a> {
+    type Item = Match;
+    fn next(&mut self) -> Option<Match> {
+        let max_length = self.max_length;
+        self.splitting.next(|m| {
+            if m.length > max_length {
+                let rest = Match::new(m.first_pos + max_length, m.second_pos + max_length, m.length - max_length);
+                (Match::new(m.first_pos, m.second_pos, max_length), Some(rest))
+            }
+            else {
+                (m, None)
+            }
+        })
+    }
+}
+
+impl<'a> FusedIterator for Capped<'a> {}
+
+/// The index of a piece of data, built once for an [`AlgoSpec`](enum.AlgoSpec.html) and reused to 
+/// compare it against many other pieces of data.
+///
+/// Every [`MatchIterator`](struct.MatchIterator.html) builds the suffix tree or the `HashMap` of 
+/// its first piece of data. When scanning thousands of samples against the same reference, 
+/// `PreparedIndex` avoids rebuilding that index for every sample.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::{AlgoSpec, PreparedIndex};
+///
+/// let reference = b"The quick brown fox jumps over the lazy dog";
+/// let index = PreparedIndex::new(reference, AlgoSpec::TreeMatch(4));
+/// for sample in [&b"the lazy fox"[..], &b"quick brown cat"[..]].iter() {
+///     let lcs = index.longest_common_substring(sample);
+///     println!("{:?}", &reference[lcs.first_pos..lcs.first_end()]);
+/// }
+/// ```
+#[derive(Clone)]
+pub struct PreparedIndex<'a> {
+    first: &'a [u8],
+    algo_spec: AlgoSpec,
+    prepared: Prepared,
+}
+
+impl<'a> PreparedIndex<'a> {
+    /// Build the index of `first` for the given [`AlgoSpec`](enum.AlgoSpec.html).
+    ///
+    /// # Panics
+    ///
+    /// It will panic if the [`AlgoSpec`](enum.AlgoSpec.html) is not supported, see 
+    /// [`MatchIterator::new`](struct.MatchIterator.html#method.new).
+    pub fn new(first: &'a [u8], algo_spec: AlgoSpec) -> PreparedIndex<'a> {
+        PreparedIndex {
+            first,
+            algo_spec,
+            prepared: Prepared::new(first, algo_spec),
+        }
+    }
+
+    /// Return the indexed piece of data.
+    pub fn first(&self) -> &'a [u8] {
+        self.first
+    }
+
+    /// Return an iterator over the matches between the indexed data and `second`.
+    pub fn matches<'b>(&'b self, second: &'b [u8]) -> MatchIterator<'b> {
+        MatchIterator {
+            first: self.first,
+            second,
+            first_offset: 0,
+            second_offset: 0,
+            minimal_length: self.algo_spec.minimal_length(),
+            progress: (0, 0),
+            backend: self.prepared.backend(self.first, second),
+        }
+    }
+
+    /// Same as [`longest_common_substring`](fn.longest_common_substring.html) with the indexed 
+    /// data as the first byte slice.
+    pub fn longest_common_substring(&self, second: &[u8]) -> Match {
+        longest_of(self.matches(second))
+    }
+
+    /// Same as [`patch_set`](fn.patch_set.html) with the indexed data as the first byte slice.
+    pub fn patch_set(&self, second: &[u8]) -> Vec<Match> {
+        patches_of(self.matches(second))
+    }
+
+    /// Same as [`coverage`](fn.coverage.html) with the indexed data as the first byte slice.
+    pub fn coverage(&self, second: &[u8]) -> IntervalSet {
+        self.matches(second).map(|m| (m.second_pos, m.second_end())).collect()
+    }
+
+    /// Same as [`unique_strings`](fn.unique_strings.html) with the indexed data as the first byte 
+    /// slice.
+    pub fn unique_strings(&self, second: &[u8]) -> Vec<Region> {
+        self.coverage(second).complement(second.len()).regions().to_vec()
     }
 }
 
 /// Return the longest common substring between two byte slices.
+///
+/// The search stops as soon as a match as long as the shortest byte slice is found, since it can't 
+/// be beaten.
 pub fn longest_common_substring(first: &[u8], second: &[u8], algo_spec: AlgoSpec) -> Match {
+    longest_of(MatchIterator::new(first, second, algo_spec))
+}
+
+#[allow(clippy::needless_return)]
+fn longest_of(match_iter: MatchIterator) -> Match {
+    let bound = match_iter.first.len().min(match_iter.second.len());
     let mut longest = Match::new(0,0,0);
-    let match_iter = MatchIterator::new(first, second, algo_spec);
     for m in match_iter {
         if m.length > longest.length {
             longest = m;
+            if longest.length == bound {
+                break;
+            }
         }
     }
     return longest;
 }
 
+/// Return the first common substring of at least `threshold` bytes between two byte slices, in 
+/// ascending order of [`second_pos`](struct.Match.html#structfield.second_pos), or `None` if 
+/// there isn't any.
+///
+/// The matches are enumerated only until one is long enough, which is much faster than computing 
+/// the [`longest_common_substring`](fn.longest_common_substring.html) when a quick decision is all 
+/// that's needed.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::{AlgoSpec, Match, longest_common_substring_at_least};
+///
+/// let a = b"abcdefg";
+/// let b = b"012abc34cdef56efg78abcdefg";
+/// assert!(longest_common_substring_at_least(a, b, AlgoSpec::HashMatch(2), 4) == Some(Match::new(2, 8, 4)));
+/// assert!(longest_common_substring_at_least(a, b, AlgoSpec::HashMatch(2), 8) == None);
+/// ```
+pub fn longest_common_substring_at_least(first: &[u8], second: &[u8], algo_spec: AlgoSpec, threshold: usize) -> Option<Match> {
+    MatchIterator::new(first, second, algo_spec).find(|m| m.length >= threshold)
+}
+
+const ROLLING_BASE: u64 = 0x100000001b3;
+
+// Polynomial hashes of all the windows of `length` bytes of data, with their position
+pub(crate) fn rolling_hashes<'a>(data: &'a [u8], length: usize) -> impl Iterator<Item=(usize, u64)> + 'a {
+    // Weight of the byte leaving the window
+    let leaving = (1..length).fold(1u64, |w, _| w.wrapping_mul(ROLLING_BASE));
+    let initial = data[..length].iter().fold(0u64, |h, &b| h.wrapping_mul(ROLLING_BASE).wrapping_add(b as u64 + 1));
+    (0..data.len() - length + 1).scan(initial, move |h, pos| {
+        if pos > 0 {
+            *h = h.wrapping_sub((data[pos - 1] as u64 + 1).wrapping_mul(leaving))
+                .wrapping_mul(ROLLING_BASE)
+                .wrapping_add(data[pos + length - 1] as u64 + 1);
+        }
+        Some((pos, *h))
+    })
+}
+
+// Convert an offset or a length read from a serialized format, which may not fit in a usize on
+// 32-bit platforms
+pub(crate) fn to_usize(v: u64) -> io::Result<usize> {
+    usize::try_from(v).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Offset too large for this platform"))
+}
+
+/// Return true if the two byte slices have a common substring of at least `min_length` bytes.
+///
+/// This is the yes or no version of 
+/// [`longest_common_substring_at_least`](fn.longest_common_substring_at_least.html), optimized 
+/// for filtering pipelines: the windows of the shortest byte slice are indexed by a rolling hash, 
+/// the longest one is scanned in a single pass and the scan stops at the first window found in 
+/// both. No match is ever built.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::has_common_substring;
+///
+/// assert!(has_common_substring(b"abcdefg", b"012abc34cdef56efg", 4));
+/// assert!(!has_common_substring(b"abcdefg", b"012abc34cdef56efg", 5));
+/// ```
+pub fn has_common_substring(first: &[u8], second: &[u8], min_length: usize) -> bool {
+    if min_length == 0 {
+        return true;
+    }
+    if first.len() < min_length || second.len() < min_length {
+        return false;
+    }
+    let (short, long) = if first.len() <= second.len() { (first, second) } else { (second, first) };
+    let mut windows = HashMap::<u64, Vec<usize>>::with_capacity(short.len() - min_length + 1);
+    for (pos, h) in rolling_hashes(short, min_length) {
+        windows.entry(h).or_default().push(pos);
+    }
+    rolling_hashes(long, min_length).any(|(pos, h)| {
+        // The hash might collide
+        windows.get(&h).is_some_and(|positions| {
+            positions.iter().any(|&p| short[p..p + min_length] == long[pos..pos + min_length])
+        })
+    })
+}
+
+/// Which of the pieces of data should be considered circular by 
+/// [`circular_longest_common_substring`](fn.circular_longest_common_substring.html).
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub enum Circular {
+    /// Only the first piece of data wraps around.
+    First,
+    /// Only the second piece of data wraps around.
+    Second,
+    /// Both pieces of data wrap around.
+    Both,
+}
+
+// Concatenate data with itself minus its last byte, such that every rotation of data is a 
+// substring of the result.
+fn unroll(data: &[u8]) -> Vec<u8> {
+    let mut unrolled = Vec::<u8>::with_capacity(2 * data.len());
+    unrolled.extend_from_slice(data);
+    if !data.is_empty() {
+        unrolled.extend_from_slice(&data[..data.len() - 1]);
+    }
+    unrolled
+}
+
+/// Return the longest common substring between two byte slices where one or both of them are 
+/// considered circular: the end wraps around to the start.
+///
+/// This finds matches crossing the wrap point, which is what is needed to compare rotated copies 
+/// of the same data like ring buffer dumps. The returned positions are always smaller than the 
+/// length of their piece of data but, for a circular piece of data, `first_end()` or 
+/// `second_end()` may exceed it: the match then continues from the start. The length of the 
+/// match never exceeds the length of a circular piece of data.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::{AlgoSpec, Circular, Match, circular_longest_common_substring};
+///
+/// let a = "fghabcde";
+/// let b = "abcdefgh";
+/// let m = circular_longest_common_substring(a.as_bytes(), b.as_bytes(), AlgoSpec::TreeMatch(2), Circular::First);
+/// assert!(m == Match::new(3, 0, 8));
+/// ```
+pub fn circular_longest_common_substring(first: &[u8], second: &[u8], algo_spec: AlgoSpec, circular: Circular) -> Match {
+    let unrolled_first;
+    let unrolled_second;
+    let mut max_length = usize::MAX;
+    let mut a = first;
+    let mut b = second;
+    if circular != Circular::Second {
+        unrolled_first = unroll(first);
+        a = &unrolled_first;
+        max_length = first.len();
+    }
+    if circular != Circular::First {
+        unrolled_second = unroll(second);
+        b = &unrolled_second;
+        max_length = max_length.min(second.len());
+    }
+    let mut longest = longest_common_substring(a, b, algo_spec);
+    if longest.length == 0 {
+        return longest;
+    }
+    longest.length = longest.length.min(max_length);
+    longest.first_pos %= first.len();
+    longest.second_pos %= second.len();
+    longest
+}
+
 /// Return the `N` longest common substrings between two byte slices. The vector is sorted in 
-/// decreasing order of  [`Match`](struct.Match.html) length.
+/// decreasing order of  [`Match`](struct.Match.html) length. Matches of the same length are sorted 
+/// in decreasing natural order, so the result doesn't depend on the algorithm used.
 pub fn longest_common_substrings(first: &[u8], second: &[u8], algo_spec: AlgoSpec, number: usize) -> Vec<Match> {
-    let match_iter = MatchIterator::new(first, second, algo_spec);
-    // Number +1 to avoid realocation when inserting
-    let mut top = Vec::<Match>::with_capacity(number + 1);
-    let mut threshold = 0;
+    longest_common_substrings_by(first, second, algo_spec, number, |m| m.length as u64)
+}
 
-    for m in match_iter {
-        if m.length > threshold {
-            // Find an insertion position
-            let mut insert_pos = 0;
-            while insert_pos < top.len() && top[insert_pos].length > m.length {
-                insert_pos += 1;
-            }
-            top.insert(insert_pos, m);
-            if top.len() > number {
-                top.truncate(number);
-                threshold = top.last().unwrap().length;
-            }
+/// Return the `N` common substrings between two byte slices with the highest `score`. The vector 
+/// is sorted in decreasing order of score.
+///
+/// This is a generalization of [`longest_common_substrings`](fn.longest_common_substrings.html), 
+/// which scores a [`Match`](struct.Match.html) by its length. The score can for example penalize 
+/// large offsets between both positions or low entropy content. Matches with a score of zero are 
+/// never returned and ties are sorted in decreasing natural order of the matches.
+///
+/// The selection keeps the best matches in a heap of `number` elements, so asking for a large 
+/// number of them only costs a logarithmic factor per match.
+///
+/// # Examples
+///
+/// Ignore runs of padding bytes:
+///
+/// ```
+/// use bcmp::{AlgoSpec, longest_common_substrings_by};
+///
+/// let a = b"\0\0\0\0\0\0\0\0\0\0\0\0magic";
+/// let b = b"magic\0\0\0\0\0\0\0\0\0\0\0\0";
+/// let best = longest_common_substrings_by(a, b, AlgoSpec::TreeMatch(4), 1, |m| {
+///     if a[m.first_pos..m.first_end()].iter().all(|&x| x == 0) { 0 } else { m.length as u64 }
+/// });
+/// assert!(best[0].first_pos == 12 && best[0].length == 5);
+/// ```
+pub fn longest_common_substrings_by<F: Fn(&Match) -> u64>(first: &[u8], second: &[u8], algo_spec: AlgoSpec, number: usize, score: F) -> Vec<Match> {
+    if number == 0 {
+        return Vec::new();
+    }
+    // Min heap of the best matches so far, the worst one being on top
+    let mut top = BinaryHeap::<Reverse<(u64, Match)>>::with_capacity(number + 1);
+    for m in MatchIterator::new(first, second, algo_spec) {
+        let s = score(&m);
+        if s == 0 {
+            continue;
+        }
+        if top.len() < number {
+            top.push(Reverse((s, m)));
+        }
+        else if top.peek().is_some_and(|&Reverse(worst)| (s, m) > worst) {
+            top.pop();
+            top.push(Reverse((s, m)));
         }
     }
-
-    return top;
+    top.into_sorted_vec().into_iter().map(|Reverse((_, m))| m).collect()
 }
 
 /// Identify the smallest set of patches needed the build the second byte slice from the first.
@@ -198,7 +1568,149 @@ pub fn longest_common_substrings(first: &[u8], second: &[u8], algo_spec: AlgoSpe
 /// The returned set might be incomplete if some part of the second byte slice could not be found 
 /// in the first. The result is highly dependent on the minimal matching length chosen.
 pub fn patch_set(first: &[u8], second: &[u8], algo_spec: AlgoSpec) -> Vec<Match> {
-    let mut match_iter = MatchIterator::new(first, second, algo_spec);
+    span!("patch_set", first.len() + second.len());
+    patches_of(MatchIterator::new(first, second, algo_spec))
+}
+
+/// Same as [`patch_set`](fn.patch_set.html) but without the patches shorter than `min_copy` bytes,
+/// whose content is left for literal data.
+///
+/// The minimal matching length of `algo_spec` decides which matches are found, while `min_copy`
+/// decides which of the selected patches are worth a copy operation: a copy of a few bytes costs
+/// more to encode than the bytes it saves. The patches are filtered after the selection, so they
+/// may have been truncated by an overlapping patch below `min_copy`. A `min_copy` at most equal to
+/// the minimal matching length gives the same result as `patch_set`, except for the truncated
+/// patches.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::{AlgoSpec, Match, patch_set_with_min_copy};
+///
+/// let a = b"0123456789abcdefghijklmnopqrstuvwxyz";
+/// let b = b"0123456789_xyz_abcdefghijklmnopqrstuvw";
+/// let patches = patch_set_with_min_copy(a, b, AlgoSpec::TreeMatch(3), 8);
+/// assert!(patches == [Match::new(0, 0, 10), Match::new(10, 15, 23)]);
+/// ```
+pub fn patch_set_with_min_copy(first: &[u8], second: &[u8], algo_spec: AlgoSpec, min_copy: usize) -> Vec<Match> {
+    let mut patches = patch_set(first, second, algo_spec);
+    patches.retain(|m| m.length >= min_copy);
+    patches
+}
+
+/// Identify a patch set building the second byte slice from the first, starting from `previous`,
+/// the patch set of a previous version of both byte slices.
+///
+/// Nightly builds and other successive versions of a pair of files are nearly identical to the
+/// previous pair, so most of the previous copies are still valid. Each previous patch is verified
+/// by comparing its bytes in `first` and `second`: the runs of at least the minimal matching
+/// length which still match are kept as patches, the first patch covering a byte of `second`
+/// winning. Only the regions of `second` which are left uncovered are then compared with the whole
+/// of `first`, whose index is only built if there is at least one such region.
+///
+/// Previous patches beyond the end of the byte slices are clipped, so any list of matches can
+/// serve as a hint. The result is sorted in ascending order of position in `second` but can differ
+/// from [`patch_set`](fn.patch_set.html): a still valid previous copy is kept even if a longer one
+/// appeared, and the patches found in the uncovered regions are clipped at their boundaries.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::{AlgoSpec, Match, patch_set, warm_patch_set};
+///
+/// let old_a = b"0123456789abcdefghijklmnopqrstuvwxyz";
+/// let old_b = b"abcdefghijklmnopqrstuvwxyz0123456789";
+/// let previous = patch_set(old_a, old_b, AlgoSpec::TreeMatch(4));
+/// let a = b"0123456789abcdefghijklmnopqrstuvwxyz";
+/// let b = b"abcdefghijklm#opqrstuvwxyz0123456789";
+/// let patches = warm_patch_set(a, b, &previous, AlgoSpec::TreeMatch(4));
+/// assert!(patches == [Match::new(10, 0, 13), Match::new(24, 14, 12), Match::new(0, 26, 10)]);
+/// ```
+pub fn warm_patch_set(first: &[u8], second: &[u8], previous: &[Match], algo_spec: AlgoSpec) -> Vec<Match> {
+    span!("warm_patch_set", first.len() + second.len());
+    assert!(algo_spec.is_supported(), "Unsupported AlgoSpec");
+    let minimal_length = algo_spec.minimal_length().max(1);
+    let mut hints = previous.to_vec();
+    hints.sort();
+    let mut patches = Vec::new();
+    // End of the region of second covered by the patches kept so far
+    let mut covered = 0;
+    for hint in hints {
+        if hint.first_pos >= first.len() || hint.second_pos >= second.len() {
+            continue;
+        }
+        let length = hint.length.min(first.len() - hint.first_pos).min(second.len() - hint.second_pos);
+        // Runs of equal bytes along the hint, after the covered region
+        let skip = covered.max(hint.second_pos) - hint.second_pos;
+        let mut run_start = skip;
+        for i in skip..length + 1 {
+            if i == length || first[hint.first_pos + i] != second[hint.second_pos + i] {
+                if i - run_start >= minimal_length {
+                    patches.push(Match::new(hint.first_pos + run_start, hint.second_pos + run_start, i - run_start));
+                    covered = hint.second_pos + i;
+                }
+                run_start = i + 1;
+            }
+        }
+    }
+    let uncovered = patches.iter().map(|m| (m.second_pos, m.second_end())).collect::<IntervalSet>().complement(second.len());
+    let mut index = None;
+    let mut found = Vec::new();
+    for region in uncovered.iter().filter(|r| r.len() >= minimal_length && first.len() >= minimal_length) {
+        let index = index.get_or_insert_with(|| PreparedIndex::new(first, algo_spec));
+        found.extend(index.patch_set(region.bytes(second)).into_iter()
+            .map(|m| Match::new(m.first_pos, m.second_pos + region.start, m.length)));
+    }
+    patches.extend(found);
+    patches.sort();
+    patches
+}
+
+/// Identify a patch set building the second byte slice from the first in two passes: a coarse one
+/// with `coarse` and a fine one with `fine` restricted to the regions the coarse one left
+/// uncovered.
+///
+/// On large inputs, the cost of a small minimal matching length comes from the scan enumerating
+/// countless short matches, not from the index. The coarse pass quickly establishes the long
+/// patches, then only the bytes of `second` they don't cover are scanned with the index of `first`
+/// for `fine`, the same way [`warm_patch_set`](fn.warm_patch_set.html) handles the regions its
+/// previous patches don't cover. This gives almost the quality of `fine` at almost the cost of
+/// `coarse` when most of the data is shared in long runs.
+///
+/// It will panic if one of the [`AlgoSpec`](enum.AlgoSpec.html) is not supported or if the
+/// minimal length of `fine` is larger than the one of `coarse`.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::{AlgoSpec, Match, refined_patch_set};
+///
+/// let a = b"0123456789abcdefghijklmnopqrstuvwxyz";
+/// let b = b"0123456789abcdefghij_mnop_klmnopqrstuvwxyz";
+/// let patches = refined_patch_set(a, b, AlgoSpec::TreeMatch(16), AlgoSpec::TreeMatch(4));
+/// assert!(patches == [Match::new(0, 0, 20), Match::new(22, 21, 4), Match::new(20, 26, 16)]);
+/// ```
+pub fn refined_patch_set(first: &[u8], second: &[u8], coarse: AlgoSpec, fine: AlgoSpec) -> Vec<Match> {
+    span!("refined_patch_set", first.len() + second.len());
+    assert!(coarse.is_supported() && fine.is_supported(), "Unsupported AlgoSpec");
+    assert!(fine.minimal_length() <= coarse.minimal_length(), "The fine minimal length must not exceed the coarse one");
+    let anchors = if first.len() < coarse.minimal_length() || second.len() < coarse.minimal_length() {
+        Vec::new()
+    }
+    else {
+        patch_set(first, second, coarse)
+    };
+    warm_patch_set(first, second, &anchors, fine)
+}
+
+pub(crate) fn patches_of(match_iter: MatchIterator) -> Vec<Match> {
+    span!("patch_set::matches", match_iter.second.len());
+    patches_from(match_iter)
+}
+
+// The patch set of matches given in ascending order of second_pos
+#[allow(clippy::needless_return)]
+pub(crate) fn patches_from<I: Iterator<Item=Match>>(mut match_iter: I) -> Vec<Match> {
     let mut patches = Vec::<Match>::new();
     // Always push first patch
     if let Some(m) = match_iter.next() {
@@ -239,29 +1751,349 @@ pub fn patch_set(first: &[u8], second: &[u8], algo_spec: AlgoSpec) -> Vec<Match>
     return patches;
 }
 
+/// Identify a patch set building the second byte slice from the first by aligning on their unique 
+/// substrings first, the way patience diff aligns on unique lines.
+///
+/// The maximal matches whose content occurs exactly once in both byte slices are used as anchors 
+/// and the longest collinear chain of them is kept. The regions between consecutive anchors are 
+/// then processed recursively, with uniqueness evaluated within the regions. A region without any 
+/// unique substring falls back to the longest collinear chain of its matches.
+///
+/// Unlike [`patch_set`](fn.patch_set.html), which greedily picks the matches covering the most of 
+/// the second byte slice, the patches are always in the same order in both byte slices and never 
+/// overlap. Moved content is thus reported as missing, but the patches follow the structure of the 
+/// data, which is far more meaningful for structured binaries.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::{AlgoSpec, Match, patience_patch_set};
+///
+/// let a = b"[rec]alpha[rec]beta[rec]gamma";
+/// let b = b"[rec]alpha[rec]NEW[rec]beta[rec]gamma";
+/// let patches = patience_patch_set(a, b, AlgoSpec::TreeMatch(4));
+/// assert!(patches == [Match::new(0, 0, 10), Match::new(10, 18, 19)]);
+/// ```
+pub fn patience_patch_set(first: &[u8], second: &[u8], algo_spec: AlgoSpec) -> Vec<Match> {
+    span!("patience_patch_set", first.len() + second.len());
+    let mut patches = Vec::new();
+    patience(first, second, 0, 0, algo_spec, &mut patches);
+    patches
+}
+
+fn patience(first: &[u8], second: &[u8], first_offset: usize, second_offset: usize, algo_spec: AlgoSpec, patches: &mut Vec<Match>) {
+    let minimal_length = algo_spec.minimal_length();
+    if first.len() < minimal_length.max(1) || second.len() < minimal_length.max(1) {
+        return;
+    }
+    let matches: Vec<Match> = MatchIterator::new(first, second, algo_spec).collect();
+    let anchors = collinear_chain(&unique_anchors(first, second, matches.clone())).matches;
+    let translate = |m: &Match| Match::new(m.first_pos + first_offset, m.second_pos + second_offset, m.length);
+    if anchors.is_empty() {
+        patches.extend(collinear_chain(&matches).matches.iter().map(translate));
+        return;
+    }
+    let mut first_pos = 0;
+    let mut second_pos = 0;
+    for anchor in &anchors {
+        patience(&first[first_pos..anchor.first_pos], &second[second_pos..anchor.second_pos], 
+                 first_offset + first_pos, second_offset + second_pos, algo_spec, patches);
+        patches.push(translate(anchor));
+        first_pos = anchor.first_end();
+        second_pos = anchor.second_end();
+    }
+    patience(&first[first_pos..], &second[second_pos..], first_offset + first_pos, second_offset + second_pos, algo_spec, patches);
+}
+
+/// Map each byte of the second byte slice to the offset in the first byte slice it can be copied 
+/// from, or `None` if it can't be found there.
+///
+/// The offsets are derived from the [`patch_set`](fn.patch_set.html), which covers every byte 
+/// belonging to a match, so a byte is `None` exactly when it is part of the 
+/// [`unique_strings`](fn.unique_strings.html). Inside a patch consecutive bytes have consecutive 
+/// offsets. This dense representation uses a word per byte of the second byte slice.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::{AlgoSpec, provenance};
+///
+/// let map = provenance(b"abcdefgh", b"efgXabc", AlgoSpec::TreeMatch(3));
+/// assert!(map == [Some(4), Some(5), Some(6), None, Some(0), Some(1), Some(2)]);
+/// ```
+pub fn provenance(first: &[u8], second: &[u8], algo_spec: AlgoSpec) -> Vec<Option<usize>> {
+    let mut map = vec![None; second.len()];
+    for patch in patch_set(first, second, algo_spec) {
+        for (offset, source) in map[patch.second_pos..patch.second_end()].iter_mut().enumerate() {
+            *source = Some(patch.first_pos + offset);
+        }
+    }
+    map
+}
+
+/// Return the set of bytes of the second byte slice which are covered by a match with the first.
+pub fn coverage(first: &[u8], second: &[u8], algo_spec: AlgoSpec) -> IntervalSet {
+    MatchIterator::new(first, second, algo_spec).map(|m| (m.second_pos, m.second_end())).collect()
+}
+
+/// Count the bytes of each byte slice which are covered by a match with the other one.
+///
+/// This is the single pass version of [`diff_regions`](fn.diff_regions.html) for when only the 
+/// amount of shared content matters: the backend reports every match as it finds it, only where 
+/// it is left maximal, and no [`Match`](struct.Match.html) nor diagonal tracking is involved. The 
+/// covered bytes are accumulated in a word per byte of the first byte slice. The counts are the 
+/// ones [`diff_regions`](fn.diff_regions.html) gives. With [`HashMatch`](hashmatch/index.html), 
+/// the second byte slice is indexed if it is more than 4 times smaller than the first one, which 
+/// doesn't change the counts.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::{AlgoSpec, coverage_stats};
+///
+/// let stats = coverage_stats(b"abcdefgh", b"efgh0123abcd", AlgoSpec::TreeMatch(4));
+/// assert!(stats.first_covered == 8 && stats.second_covered == 8);
+/// assert!(stats.similarity() == 0.8);
+/// ```
+pub fn coverage_stats(first: &[u8], second: &[u8], algo_spec: AlgoSpec) -> CoverageStats {
+    span!("coverage_stats", first.len() + second.len());
+    if let AlgoSpec::HashMatch(_) = algo_spec {
+        if second.len().saturating_mul(HASH_SWAP_RATIO) < first.len() {
+            let stats = coverage_stats_of(second, first, algo_spec);
+            return CoverageStats {
+                first_len: stats.second_len,
+                second_len: stats.first_len,
+                first_covered: stats.second_covered,
+                second_covered: stats.first_covered,
+            };
+        }
+    }
+    coverage_stats_of(first, second, algo_spec)
+}
+
+fn coverage_stats_of(first: &[u8], second: &[u8], algo_spec: AlgoSpec) -> CoverageStats {
+    let mut second_covered = 0;
+    let mut second_end = 0;
+    // The end of the longest match starting at each position of first
+    let mut first_ends = vec![0usize; first.len()];
+    scan(first, second, algo_spec, |first_pos, second_pos, length| {
+        // Matches come in second order, the covered part of second grows from the left
+        let end = second_pos + length;
+        if end > second_end {
+            second_covered += end - second_end.max(second_pos);
+            second_end = end;
+        }
+        first_ends[first_pos] = first_ends[first_pos].max(first_pos + length);
+    });
+    let mut first_covered = 0;
+    let mut first_end = 0;
+    for (first_pos, &end) in first_ends.iter().enumerate() {
+        if end > first_end {
+            first_covered += end - first_end.max(first_pos);
+            first_end = end;
+        }
+    }
+    CoverageStats {
+        first_len: first.len(),
+        second_len: second.len(),
+        first_covered,
+        second_covered,
+    }
+}
+
+/// Find the regions of each byte slice which can't be found in the other one.
+///
+/// This is the bidirectional version of [`unique_strings`](fn.unique_strings.html): 
+/// [`only_second`](struct.DiffRegions.html#structfield.only_second) is exactly what 
+/// `unique_strings(first, second, algo_spec)` returns while 
+/// [`only_first`](struct.DiffRegions.html#structfield.only_first) holds the parts of the first 
+/// byte slice not covered by any match. Both are computed from a single enumeration of the matches.
+pub fn diff_regions(first: &[u8], second: &[u8], algo_spec: AlgoSpec) -> DiffRegions {
+    let mut second_covered = IntervalSet::new();
+    let mut first_ranges = Vec::<(usize, usize)>::new();
+    for m in MatchIterator::new(first, second, algo_spec) {
+        second_covered.insert(m.second_pos, m.second_end());
+        first_ranges.push((m.first_pos, m.first_end()));
+    }
+    // Matches come in second order, sorting makes the insertion in the set linear
+    first_ranges.sort();
+    let first_covered: IntervalSet = first_ranges.into_iter().collect();
+    DiffRegions {
+        only_first: first_covered.complement(first.len()).regions().to_vec(),
+        only_second: second_covered.complement(second.len()).regions().to_vec(),
+    }
+}
+
+/// Return the similarity of two byte slices, between 0 and 1.
+///
+/// The similarity is the fraction of the bytes of both slices covered by a match with the other 
+/// one: identical slices have a similarity of 1 while slices without any common substring of the 
+/// minimal length have a similarity of 0. Two empty slices are considered identical. The counts 
+/// are computed in a single pass by [`coverage_stats`](fn.coverage_stats.html).
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::{AlgoSpec, similarity};
+///
+/// assert!(similarity(b"abcdefgh", b"abcdefgh", AlgoSpec::TreeMatch(4)) == 1.0);
+/// assert!(similarity(b"abcdefgh", b"abcd0123", AlgoSpec::TreeMatch(4)) == 0.5);
+/// ```
+pub fn similarity(first: &[u8], second: &[u8], algo_spec: AlgoSpec) -> f64 {
+    coverage_stats(first, second, algo_spec).similarity()
+}
+
+/// Return the Ratcliff-Obershelp similarity of two byte slices, between 0 and 1.
+///
+/// The longest common substring is matched first, then the same is done recursively on the 
+/// pieces to its left and to its right. The result is twice the number of matched bytes divided 
+/// by the total length. Ties are broken the same way, so this is exactly the ratio computed by 
+/// Python's `difflib.SequenceMatcher(None, first, second, autojunk=False).ratio()`. Two empty 
+/// slices are considered identical.
+///
+/// Unlike [`similarity`](fn.similarity.html), matched content must appear in the same order and 
+/// every match counts, whatever its length.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::similar;
+///
+/// assert!(similar(b"abcd", b"bcde") == 0.75);
+/// assert!(similar(b"GESTALT PATTERN MATCHING", b"GESTALT PRACTICE") == 0.6);
+/// ```
+pub fn similar(first: &[u8], second: &[u8]) -> f64 {
+    let total = first.len() + second.len();
+    if total == 0 {
+        return 1.0;
+    }
+    let mut matched = 0;
+    let mut pending = vec![(Region::new(0, first.len()), Region::new(0, second.len()))];
+    while let Some((first_region, second_region)) = pending.pop() {
+        if first_region.is_empty() || second_region.is_empty() {
+            continue;
+        }
+        // The longest match starting earliest in first, then earliest in second
+        let longest = MatchIterator::with_regions(first, first_region, second, second_region, AlgoSpec::TreeMatch(1))
+            .max_by_key(|m| (m.length, Reverse(m.first_pos), Reverse(m.second_pos)));
+        if let Some(m) = longest {
+            matched += m.length;
+            pending.push((Region::new(first_region.start, m.first_pos), Region::new(second_region.start, m.second_pos)));
+            pending.push((Region::new(m.first_end(), first_region.end), Region::new(m.second_end(), second_region.end)));
+        }
+    }
+    (2 * matched) as f64 / total as f64
+}
+
 /// Find the list of unique strings from the second byte slice which can't be found in the first.
 /// 
 /// The [`AlgoSpec`](enum.AlgoSpec.html) highly influence the result because it determines the 
 /// minimal length of a match. The longer is the minimal length of a match, the more 
 /// unique strings will be found.
-pub fn unique_strings(first: &[u8], second: &[u8], algo_spec: AlgoSpec) -> Vec<(usize,usize)> {
+pub fn unique_strings(first: &[u8], second: &[u8], algo_spec: AlgoSpec) -> Vec<Region> {
+    span!("unique_strings", first.len() + second.len());
+    coverage(first, second, algo_spec).complement(second.len()).regions().to_vec()
+}
+
+/// Compute the matching statistics of the second byte slice against the first: element `i` is 
+/// the length of the longest substring starting at position `i` of the second byte slice which 
+/// can be found anywhere in the first.
+///
+/// Where the match list only tells whether a region is covered by a match above the minimal 
+/// length, this profile grades every position, which makes it well suited to similarity scoring. 
+/// It is computed in linear time using the suffix links of a 
+/// [`SuffixTree`](suffixtree/struct.SuffixTree.html).
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::matching_statistics;
+///
+/// assert!(matching_statistics(b"banana", b"bandana") == [3, 2, 1, 0, 3, 2, 1]);
+/// ```
+pub fn matching_statistics(first: &[u8], second: &[u8]) -> Vec<usize> {
+    if first.len() <= suffixtree::MAX_LEN {
+        SuffixTree::<u32>::build(first).matching_statistics(first, second)
+    }
+    else {
+        SuffixTree::new(first).matching_statistics(first, second)
+    }
+}
+
+/// Find the shortest substrings of the second byte slice which can't be found anywhere in the 
+/// first and are at least `min_length` long.
+///
+/// For each position of the second byte slice, the shortest substring starting there and 
+/// absent from the first is computed. Only the minimal ones are returned: a substring containing 
+/// another returned substring is never reported. These are the smallest discriminators of the 
+/// second byte slice, the opposite of 
+/// [`longest_common_substrings`](fn.longest_common_substrings.html). The regions are returned 
+/// in ascending order and may overlap.
+pub fn shortest_unique_substrings(first: &[u8], second: &[u8], min_length: usize) -> Vec<Region> {
+    let stats = matching_statistics(first, second);
+    let mut uniques = Vec::<Region>::new();
+    let min_length = if min_length == 0 { 1 } else { min_length };
+    for (i, &matched) in stats.iter().enumerate() {
+        let end = i + if matched < min_length { min_length } else { matched + 1 };
+        // Not unique until the end of second, and neither will the following positions be
+        if end > second.len() {
+            break;
+        }
+        // The candidate ends are non decreasing, so the previous candidate contains this one if 
+        // they share the same end. It is then not minimal anymore.
+        if uniques.last().is_some_and(|last| last.end == end) {
+            uniques.pop();
+        }
+        uniques.push(Region::new(i, end));
+    }
+    uniques
+}
+
+/// Find the list of unique strings from the second byte slice which can't be found in the first, 
+/// together with the matches immediately before and after each of them.
+///
+/// The surrounding matches anchor the unique string in both pieces of data which makes it 
+/// possible to determine what it replaced in the first one (see 
+/// [`UniqueString::replaced`](struct.UniqueString.html#method.replaced)). The regions are the 
+/// same as the ones returned by [`unique_strings`](fn.unique_strings.html).
+pub fn unique_strings_with_context(first: &[u8], second: &[u8], algo_spec: AlgoSpec) -> Vec<UniqueString> {
     let match_iter = MatchIterator::new(first, second, algo_spec);
-    let mut uniques = Vec::<(usize,usize)>::new();
+    let mut uniques = Vec::<UniqueString>::new();
     let mut covered = 0;
+    // The longest match reaching the current coverage
+    let mut frontier: Option<Match> = None;
 
     for m in match_iter {
+        // The last unique string is still waiting for the matches starting at its end
+        if let Some(last) = uniques.last_mut() {
+            if last.region.end == m.second_pos && last.after.is_none_or(|a| m.length > a.length) {
+                last.after = Some(m);
+            }
+        }
         // There is a lapse in the second file coverage, add a unique string
         if m.second_pos > covered {
-            uniques.push((covered, m.second_pos));
+            uniques.push(UniqueString {
+                region: Region::new(covered, m.second_pos),
+                before: frontier,
+                after: Some(m),
+            });
         }
         // If more of the file is covered, extend the coverage
         if m.second_end() > covered {
             covered = m.second_end();
+            frontier = Some(m);
+        }
+        else if m.second_end() == covered && frontier.is_none_or(|f| m.length > f.length) {
+            frontier = Some(m);
         }
     }
     if covered < second.len() {
-        uniques.push((covered, second.len()));
+        uniques.push(UniqueString {
+            region: Region::new(covered, second.len()),
+            before: frontier,
+            after: None,
+        });
     }
 
-    return uniques;
+    uniques
 }
@@ -0,0 +1,88 @@
+// A small LZ77 compressor for the payloads of the binary formats.
+//
+// The compressed stream is a list of sequences, each made of the number of literal bytes, the
+// literal bytes, the length of the match following them and, if that length is not zero, the
+// distance to copy from. Integers are LEB128 varints. Matches are at least MIN_MATCH bytes long
+// and may overlap the bytes they produce. The last sequence has a match length of zero.
+
+use std::convert::TryFrom;
+
+use encoding::{read_varint, write_varint};
+
+const MIN_MATCH: usize = 4;
+const HASH_BITS: u32 = 16;
+
+fn hash(window: &[u8]) -> usize {
+    let v = u32::from_le_bytes([window[0], window[1], window[2], window[3]]);
+    (v.wrapping_mul(0x9e3779b1) >> (32 - HASH_BITS)) as usize
+}
+
+// Read a varint from the start of rest, or return None if it is truncated or overflows
+fn read_length(rest: &mut &[u8]) -> Option<usize> {
+    read_varint(rest).ok().and_then(|v| usize::try_from(v).ok())
+}
+
+pub(crate) fn compress(data: &[u8]) -> Vec<u8> {
+    span!("lz::compress", data.len());
+    let mut out = Vec::new();
+    // Last position of each hashed 4 bytes window
+    let mut table = vec![usize::MAX; 1 << HASH_BITS];
+    let mut literal_start = 0;
+    let mut pos = 0;
+    while pos + MIN_MATCH <= data.len() {
+        let h = hash(&data[pos..]);
+        let candidate = table[h];
+        table[h] = pos;
+        if candidate != usize::MAX && data[candidate..candidate + MIN_MATCH] == data[pos..pos + MIN_MATCH] {
+            let length = data[candidate..].iter().zip(&data[pos..]).take_while(|(a, b)| a == b).count();
+            write_varint(&mut out, (pos - literal_start) as u64);
+            out.extend_from_slice(&data[literal_start..pos]);
+            write_varint(&mut out, length as u64);
+            write_varint(&mut out, (pos - candidate) as u64);
+            // Index the windows inside the match sparsely to keep the compression fast
+            for i in (pos + 1..pos + length).step_by(4).filter(|&i| i + MIN_MATCH <= data.len()) {
+                table[hash(&data[i..])] = i;
+            }
+            pos += length;
+            literal_start = pos;
+        }
+        else {
+            pos += 1;
+        }
+    }
+    write_varint(&mut out, (data.len() - literal_start) as u64);
+    out.extend_from_slice(&data[literal_start..]);
+    write_varint(&mut out, 0);
+    out
+}
+
+// Return None if the stream is corrupted or doesn't decompress to exactly `len` bytes
+pub(crate) fn decompress(compressed: &[u8], len: usize) -> Option<Vec<u8>> {
+    span!("lz::decompress", len);
+    // The length comes from an untrusted header, long copies grow the output as they go
+    let mut out = Vec::with_capacity(len.min(compressed.len().saturating_mul(4)));
+    let mut rest = compressed;
+    loop {
+        let literal = read_length(&mut rest)?;
+        if literal > rest.len() || out.len().checked_add(literal).is_none_or(|end| end > len) {
+            return None;
+        }
+        out.extend_from_slice(&rest[..literal]);
+        rest = &rest[literal..];
+        let length = read_length(&mut rest)?;
+        if length == 0 {
+            break;
+        }
+        let distance = read_length(&mut rest)?;
+        if distance == 0 || distance > out.len() || out.len().checked_add(length).is_none_or(|end| end > len) {
+            return None;
+        }
+        // The copy may overlap the bytes it produces
+        let start = out.len() - distance;
+        for i in start..start + length {
+            let byte = out[i];
+            out.push(byte);
+        }
+    }
+    if rest.is_empty() && out.len() == len { Some(out) } else { None }
+}
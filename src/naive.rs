@@ -0,0 +1,75 @@
+//! Brute force reference implementations, available with the `naive` feature.
+//!
+//! These functions compare every position of the first piece of data with every position of the
+//! second one. They are quadratic or worse and only meant as a ground truth for differential
+//! testing and fuzzing: their results are the ones the optimized functions of the crate must
+//! return, computed in the most obvious way.
+//!
+//! # Examples
+//!
+//! ```
+//! use bcmp::{AlgoSpec, Match, MatchIterator};
+//! use bcmp::naive;
+//!
+//! let a = b"abcdefghij0123";
+//! let b = b"0123abcdXXghij";
+//! let mut found: Vec<Match> = MatchIterator::new(a, b, AlgoSpec::HashMatch(4)).collect();
+//! found.sort();
+//! assert!(found == naive::matches(a, b, 4));
+//! ```
+
+use {Match, Region};
+use interval::IntervalSet;
+
+/// Return every maximal exact match of at least `minimal_length` bytes between two pieces of data,
+/// sorted in their natural order.
+///
+/// This is the set of matches returned by [`MatchIterator`](../struct.MatchIterator.html) for an
+/// [`AlgoSpec`](../enum.AlgoSpec.html) with the same minimal length. A minimal length of zero is
+/// treated as one.
+pub fn matches(first: &[u8], second: &[u8], minimal_length: usize) -> Vec<Match> {
+    let minimal_length = minimal_length.max(1);
+    let mut matches = Vec::new();
+    for j in 0..second.len() {
+        for i in 0..first.len() {
+            // Only start at the left end of a match
+            if i > 0 && j > 0 && first[i - 1] == second[j - 1] {
+                continue;
+            }
+            let length = first[i..].iter().zip(&second[j..]).take_while(|&(a, b)| a == b).count();
+            if length >= minimal_length {
+                matches.push(Match::new(i, j, length));
+            }
+        }
+    }
+    matches
+}
+
+/// Return the longest common substring between two pieces of data, or an empty match at `(0, 0)`
+/// if they have no byte in common.
+///
+/// Among the longest common substrings, the first one in the natural order of
+/// [`Match`](../struct.Match.html) is returned. The backends of
+/// [`longest_common_substring`](../fn.longest_common_substring.html) may choose another one of the
+/// same length.
+pub fn longest_common_substring(first: &[u8], second: &[u8]) -> Match {
+    let mut longest = Match::new(0, 0, 0);
+    for m in matches(first, second, 1) {
+        if m.length > longest.length {
+            longest = m;
+        }
+    }
+    longest
+}
+
+/// Return the regions of the second piece of data which are not covered by any common substring
+/// of at least `minimal_length` bytes with the first one.
+///
+/// This is what [`unique_strings`](../fn.unique_strings.html) returns for an
+/// [`AlgoSpec`](../enum.AlgoSpec.html) with the same minimal length.
+pub fn unique_strings(first: &[u8], second: &[u8], minimal_length: usize) -> Vec<Region> {
+    let covered: IntervalSet = matches(first, second, minimal_length).iter()
+        .map(|m| (m.second_pos, m.second_end()))
+        .collect();
+    covered.complement(second.len()).regions().to_vec()
+}
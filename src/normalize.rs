@@ -0,0 +1,155 @@
+//! Pointer normalization for executable diffing.
+//!
+//! Two builds of the same code differ at almost every absolute address: a function moved by a
+//! few bytes changes every pointer and call target referencing it. Matching the raw bytes then
+//! only produces short fragments broken at each pointer. Following the approach of tools like
+//! Courgette, the pointer fields are masked before matching so that the surrounding code produces
+//! long matches. The pointer fields whose value differs are reported separately as fixups.
+
+use {AlgoSpec, Match, Region, patch_set};
+
+/// How the pointer fields of a piece of data are located.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub enum Relocations<'a> {
+    /// The offsets of the pointer fields, typically obtained from the relocation table.
+    List(&'a [usize]),
+    /// Every pointer sized value, aligned on its size, falling inside `[low..high)` is considered 
+    /// to be a pointer. This is a simple heuristic for images without relocation information 
+    /// where `low` and `high` are the bounds of the address space of the image.
+    Heuristic {
+        /// Lowest address considered as a pointer.
+        low: u64,
+        /// End of the address range, exclusive.
+        high: u64,
+    },
+}
+
+/// The description of the pointer fields of a piece of data.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct PointerLayout<'a> {
+    /// Size of a pointer in bytes, between 1 and 8.
+    pub width: usize,
+    /// Whether pointers are stored in big endian order.
+    pub big_endian: bool,
+    /// How the pointer fields are located.
+    pub relocations: Relocations<'a>,
+}
+
+/// The result of [`normalized_patch_set`](fn.normalized_patch_set.html).
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct NormalizedDiff {
+    /// The patch set computed over the normalized data.
+    pub patches: Vec<Match>,
+    /// The pointer fields of the second piece of data which are covered by a patch but whose 
+    /// value differs from the bytes copied from the first piece of data. Those need to be 
+    /// written explicitly after the patches are applied.
+    pub fixups: Vec<Region>,
+}
+
+fn read_pointer(bytes: &[u8], big_endian: bool) -> u64 {
+    let mut value = 0u64;
+    if big_endian {
+        for &b in bytes {
+            value = (value << 8) | b as u64;
+        }
+    }
+    else {
+        for &b in bytes.iter().rev() {
+            value = (value << 8) | b as u64;
+        }
+    }
+    value
+}
+
+/// Return the offsets of the pointer fields of `data` described by `layout`, in ascending order. 
+/// Fields extending beyond the end of `data` are ignored.
+///
+/// # Panics
+///
+/// It will panic if the layout width is not between 1 and 8.
+pub fn pointer_fields(data: &[u8], layout: &PointerLayout) -> Vec<usize> {
+    assert!(layout.width >= 1 && layout.width <= 8, "Unsupported pointer width");
+    match layout.relocations {
+        Relocations::List(offsets) => {
+            let mut fields: Vec<usize> = offsets.iter().cloned()
+                .filter(|&o| o + layout.width <= data.len())
+                .collect();
+            fields.sort();
+            fields.dedup();
+            fields
+        },
+        Relocations::Heuristic { low, high } => {
+            (0..data.len() / layout.width).map(|i| i * layout.width).filter(|&o| {
+                let value = read_pointer(&data[o..o + layout.width], layout.big_endian);
+                value >= low && value < high
+            }).collect()
+        }
+    }
+}
+
+/// Return a copy of `data` where the pointer fields described by `layout` are zeroed.
+pub fn normalize(data: &[u8], layout: &PointerLayout) -> Vec<u8> {
+    let mut normalized = data.to_vec();
+    for o in pointer_fields(data, layout) {
+        for b in &mut normalized[o..o + layout.width] {
+            *b = 0;
+        }
+    }
+    normalized
+}
+
+/// Compute the patch set building `second` from `first` after masking the pointer fields of both 
+/// pieces of data.
+///
+/// The patches are computed over the normalized copies, which have the same length as the 
+/// originals, so all the positions are valid in the original data. Applying them copies the 
+/// pointers of the first piece of data, the 
+/// [`fixups`](struct.NormalizedDiff.html#structfield.fixups) list the pointer fields which then 
+/// need to be overwritten with their value from `second`.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::AlgoSpec;
+/// use bcmp::normalize::{PointerLayout, Relocations, normalized_patch_set};
+///
+/// // Same code, but the pointer at offset 8 moved from 0x1000 to 0x1010
+/// let a = b"\x55\x48\x89\xe5\x48\x8b\x05\x00\x00\x10\x00\x00\xc3\x90\x90\x90";
+/// let b = b"\x55\x48\x89\xe5\x48\x8b\x05\x00\x10\x10\x00\x00\xc3\x90\x90\x90";
+/// let layout = PointerLayout { width: 4, big_endian: false, relocations: Relocations::List(&[8]) };
+/// let diff = normalized_patch_set(a, &layout, b, &layout, AlgoSpec::TreeMatch(4));
+/// assert!(diff.patches.len() == 1 && diff.patches[0].length == 16);
+/// assert!(diff.fixups.len() == 1 && diff.fixups[0].start == 8);
+/// ```
+pub fn normalized_patch_set(first: &[u8], first_layout: &PointerLayout, second: &[u8], second_layout: &PointerLayout, 
+                            algo_spec: AlgoSpec) -> NormalizedDiff {
+    let first_normalized = normalize(first, first_layout);
+    let second_normalized = normalize(second, second_layout);
+    let patches = patch_set(&first_normalized, &second_normalized, algo_spec);
+    let mut fixups = Vec::<Region>::new();
+    let fields = pointer_fields(second, second_layout);
+    let mut p = 0;
+    for o in fields {
+        let field = Region::new(o, o + second_layout.width);
+        // Patches are sorted by second_pos and do not overlap
+        while p < patches.len() && patches[p].second_end() <= field.start {
+            p += 1;
+        }
+        let mut i = p;
+        while i < patches.len() && patches[i].second_pos < field.end {
+            let m = patches[i];
+            let start = field.start.max(m.second_pos);
+            let end = field.end.min(m.second_end());
+            let source = m.first_pos + (start - m.second_pos);
+            if first[source..source + (end - start)] != second[start..end] {
+                fixups.push(field);
+                break;
+            }
+            i += 1;
+        }
+    }
+    NormalizedDiff {
+        patches,
+        fixups,
+    }
+}
@@ -0,0 +1,98 @@
+//! Approximate matching of numeric sequences.
+//!
+//! Sensor logs and sampled signals rarely repeat bit for bit. This module compares sequences of
+//! numbers where two elements are considered equal if they differ by at most a user provided
+//! epsilon. As tolerance-based equality can not be hashed nor indexed, the comparison is
+//! quadratic in the length of the sequences.
+
+use Match;
+
+/// Trait marking numeric types which can be compared with a tolerance.
+pub trait Tolerance: Copy {
+    /// Return true if `self` and `other` differ by at most `epsilon`.
+    fn within(self, other: Self, epsilon: Self) -> bool;
+}
+
+macro_rules! impl_tolerance_int {
+    ($($t:ty),*) => {
+        $(
+            impl Tolerance for $t {
+                #[inline]
+                fn within(self, other: $t, epsilon: $t) -> bool {
+                    self.abs_diff(other) <= epsilon.unsigned_abs()
+                }
+            }
+        )*
+    }
+}
+
+macro_rules! impl_tolerance_uint {
+    ($($t:ty),*) => {
+        $(
+            impl Tolerance for $t {
+                #[inline]
+                fn within(self, other: $t, epsilon: $t) -> bool {
+                    self.abs_diff(other) <= epsilon
+                }
+            }
+        )*
+    }
+}
+
+impl_tolerance_uint!(u8, u16, u32, u64);
+impl_tolerance_int!(i8, i16, i32, i64);
+
+impl Tolerance for f32 {
+    #[inline]
+    fn within(self, other: f32, epsilon: f32) -> bool {
+        (self - other).abs() <= epsilon
+    }
+}
+
+impl Tolerance for f64 {
+    #[inline]
+    fn within(self, other: f64, epsilon: f64) -> bool {
+        (self - other).abs() <= epsilon
+    }
+}
+
+/// Return the matches between two numeric sequences where elements are considered equal if they 
+/// are within `epsilon` of each other.
+///
+/// The [`Match`](../struct.Match.html) positions and lengths are expressed in elements, not bytes. 
+/// As with the byte matching algorithms, only matches of at least `minimal_length` elements which 
+/// cannot be extended on either side are returned, in ascending order of `second_pos`.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::Match;
+/// use bcmp::numeric::tolerance_matches;
+///
+/// let a = [1.0f32, 2.0, 3.0, 4.0, 5.0];
+/// let b = [9.0f32, 2.05, 2.98, 4.01, 7.0];
+/// let ms = tolerance_matches(&a, &b, 0.1, 2);
+/// assert!(ms == vec![Match::new(1, 1, 3)]);
+/// ```
+pub fn tolerance_matches<T: Tolerance>(first: &[T], second: &[T], epsilon: T, minimal_length: usize) -> Vec<Match> {
+    let mut matches = Vec::<Match>::new();
+    let minimal_length = if minimal_length == 0 { 1 } else { minimal_length };
+    for j in 0..second.len() {
+        for i in 0..first.len() {
+            // Only start matches which can't be extended to the left
+            if !first[i].within(second[j], epsilon) || 
+               (i > 0 && j > 0 && first[i - 1].within(second[j - 1], epsilon)) {
+                continue;
+            }
+            let mut length = 1;
+            while i + length < first.len() && j + length < second.len() && 
+                  first[i + length].within(second[j + length], epsilon) {
+                length += 1;
+            }
+            if length >= minimal_length {
+                matches.push(Match::new(i, j, length));
+            }
+        }
+    }
+    matches
+}
@@ -0,0 +1,899 @@
+//! A self-contained binary delta format built on top of [`patch_set`](../fn.patch_set.html).
+//!
+//! A patch set only describes the parts of the second piece of data which can be copied from the
+//! first one. A [`Delta`](struct.Delta.html) completes it with the literal bytes of the remaining
+//! parts, which is enough to rebuild the second piece of data from the first one alone.
+//!
+//! # Format
+//!
+//! A serialized delta starts with the 8 bytes magic `BCMPDL\0\x01` followed by the length of the
+//! source, the length of the target and the number of operations. Each operation starts with a
+//! tag byte: `0` for a copy, followed by the source offset and the length, or `1` for a literal,
+//! followed by its length and its bytes. All integers are encoded as little endian `u64`.
+//!
+//! Literals of novel data often compress well. A compressed delta starts with the magic
+//! `BCMPDL\0\x02` followed by the same three integers, the [`Compression`](enum.Compression.html)
+//! byte (`0` for none, `1` for the built-in LZ77 codec, `2` for zstd and `3` for LZMA), the
+//! length of the serialized operations and the length of their compressed form, then the
+//! compressed operations.
+//!
+//! A delta with [`Metadata`](struct.Metadata.html) starts with the magic `BCMPDL\0\x03` followed
+//! by the same three integers, the SHA-256 hashes of the source and the target, the algorithm (`0`
+//! for `HashMatch`, `1` for `TreeMatch`, `2` for `SuffixArrayMatch` and `3` for `Hybrid`) as a
+//! byte and its minimal matching length, followed by the length of the anchors for `Hybrid`, the
+//! length and the bytes of the version of the crate which created it, then
+//! the same fields as a compressed delta. [`PatchInfo::read`](struct.PatchInfo.html#method.read)
+//! reads everything but the operations, which allows to check a delta before applying it.
+//!
+//! [`version_chain`](fn.version_chain.html) computes the deltas between the consecutive versions
+//! of a piece of data together with the statistics of each update, while
+//! [`best_base`](fn.best_base.html) picks the source giving the smallest delta to a target.
+//!
+//! # zstd patch-from
+//!
+//! `zstd --patch-from=SOURCE` compresses a target using the whole source as a dictionary, which
+//! must fit in the compression window. [`dictionary`](fn.dictionary.html) keeps only the parts of
+//! the source the target copies from, giving a much smaller dictionary for large sources and thus
+//! smaller and faster deltas:
+//!
+//! ```sh
+//! bcmp patch dict old new old_to_new.dict
+//! zstd --patch-from=old_to_new.dict new -o new.zst
+//! zstd -d --patch-from=old_to_new.dict new.zst -o new
+//! ```
+//!
+//! The decompressing side only has the source: the dictionary is rebuilt from it with
+//! [`Dictionary::from_regions`](struct.Dictionary.html#method.from_regions), the few regions
+//! being shipped along with the compressed target.
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use bytepack::{LEPacker, LEUnpacker};
+
+use {AlgoSpec, Match, Region, patch_set, patch_set_with_min_copy, rolling_hashes, to_usize, warm_patch_set};
+use digest::sha256;
+use interval::IntervalSet;
+use lz;
+#[cfg(feature = "lzma")]
+use xz2::read::XzDecoder;
+#[cfg(feature = "lzma")]
+use xz2::write::XzEncoder;
+
+const MAGIC: &[u8; 8] = b"BCMPDL\x00\x01";
+const COMPRESSED_MAGIC: &[u8; 8] = b"BCMPDL\x00\x02";
+const METADATA_MAGIC: &[u8; 8] = b"BCMPDL\x00\x03";
+// Number of windows of the target looked up to estimate the delta from a base
+const ESTIMATE_SAMPLES: usize = 1024;
+// Number of bases with the best estimates for which the exact delta is computed
+const EXACT_BASES: usize = 3;
+// Copied regions of the source separated by less than this are merged in the dictionary
+const DICTIONARY_GAP: usize = 64;
+// Default zstd level and xz preset, favoring the ratio as deltas are written once
+#[cfg(feature = "zstd")]
+const ZSTD_LEVEL: i32 = 19;
+#[cfg(feature = "lzma")]
+const LZMA_PRESET: u32 = 6;
+
+/// An operation of a [`Delta`](struct.Delta.html).
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum Op {
+    /// Copy `length` bytes of the source starting at `offset`.
+    Copy {
+        /// Offset in the source.
+        offset: usize,
+        /// Number of bytes to copy.
+        length: usize,
+    },
+    /// Insert bytes which could not be found in the source.
+    Literal(Vec<u8>),
+}
+
+impl Op {
+    /// Return the number of bytes this operation produces.
+    pub fn len(&self) -> usize {
+        match *self {
+            Op::Copy { length, .. } => length,
+            Op::Literal(ref data) => data.len(),
+        }
+    }
+
+    /// Return true if this operation does not produce any byte.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The compression of the operations of a serialized [`Delta`](struct.Delta.html).
+///
+/// The zstd and LZMA codecs are only available with the `zstd` and `lzma` features. A delta 
+/// compressed with a codec which isn't enabled can't be read.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+#[non_exhaustive]
+pub enum Compression {
+    /// The operations are stored as is.
+    None = 0,
+    /// The operations are compressed with a fast LZ77 codec built in the crate.
+    Lz = 1,
+    /// The operations are compressed with zstd.
+    #[cfg(feature = "zstd")]
+    Zstd = 2,
+    /// The operations are compressed with LZMA, in an xz container.
+    #[cfg(feature = "lzma")]
+    Lzma = 3,
+}
+
+impl Compression {
+    fn from_byte(byte: u8) -> io::Result<Compression> {
+        match byte {
+            0 => Ok(Compression::None),
+            1 => Ok(Compression::Lz),
+            #[cfg(feature = "zstd")]
+            2 => Ok(Compression::Zstd),
+            #[cfg(feature = "lzma")]
+            3 => Ok(Compression::Lzma),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, "Unsupported delta compression"))
+        }
+    }
+
+    fn compress(self, ops: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(ops.to_vec()),
+            Compression::Lz => Ok(lz::compress(ops)),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => zstd::bulk::compress(ops, ZSTD_LEVEL),
+            #[cfg(feature = "lzma")]
+            Compression::Lzma => {
+                let mut encoder = XzEncoder::new(Vec::new(), LZMA_PRESET);
+                encoder.write_all(ops)?;
+                encoder.finish()
+            },
+        }
+    }
+
+    // Return None if the data is corrupted, the caller checks the length
+    fn decompress(self, data: Vec<u8>, len: usize) -> Option<Vec<u8>> {
+        match self {
+            Compression::None => Some(data),
+            Compression::Lz => lz::decompress(&data, len),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => bounded(zstd::stream::read::Decoder::with_buffer(&data[..]).ok()?, len),
+            #[cfg(feature = "lzma")]
+            Compression::Lzma => bounded(XzDecoder::new(&data[..]), len),
+        }
+    }
+}
+
+// Decompress at most one byte more than len: the length comes from an untrusted header
+#[cfg(any(feature = "zstd", feature = "lzma"))]
+fn bounded<R: Read>(decoder: R, len: usize) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    decoder.take((len as u64).saturating_add(1)).read_to_end(&mut out).ok()?;
+    Some(out)
+}
+
+/// The description of how a [`Delta`](struct.Delta.html) was created, stored in its header by
+/// [`Delta::write_with_metadata_to`](struct.Delta.html#method.write_with_metadata_to).
+#[derive(Clone,Debug)]
+pub struct Metadata {
+    /// The SHA-256 hash of the source.
+    pub source_hash: [u8; 32],
+    /// The SHA-256 hash of the target.
+    pub target_hash: [u8; 32],
+    /// The algorithm used to compute the delta.
+    pub algo_spec: AlgoSpec,
+    /// The version of the crate which created the delta.
+    pub version: String,
+}
+
+impl Metadata {
+    /// Describe the delta from `source` to `target` computed with `algo_spec` by this version of
+    /// the crate.
+    pub fn new(source: &[u8], target: &[u8], algo_spec: AlgoSpec) -> Metadata {
+        Metadata {
+            source_hash: sha256(source),
+            target_hash: sha256(target),
+            algo_spec,
+            version: String::from(env!("CARGO_PKG_VERSION")),
+        }
+    }
+
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(&self.source_hash)?;
+        w.write_all(&self.target_hash)?;
+        let (tag, length) = match self.algo_spec {
+            AlgoSpec::HashMatch(l) => (0u8, l),
+            AlgoSpec::TreeMatch(l) => (1u8, l),
+            AlgoSpec::SuffixArrayMatch(l) => (2u8, l),
+            AlgoSpec::Hybrid(_, l) => (3u8, l),
+        };
+        w.pack(tag)?;
+        w.pack(length as u64)?;
+        if let AlgoSpec::Hybrid(key, _) = self.algo_spec {
+            w.pack(key as u64)?;
+        }
+        w.pack(self.version.len() as u64)?;
+        w.write_all(self.version.as_bytes())
+    }
+
+    fn read_from<R: Read>(r: &mut R) -> io::Result<Metadata> {
+        let mut source_hash = [0u8; 32];
+        r.read_exact(&mut source_hash)?;
+        let mut target_hash = [0u8; 32];
+        r.read_exact(&mut target_hash)?;
+        let tag = r.unpack::<u8>()?;
+        let length = to_usize(r.unpack::<u64>()?)?;
+        let algo_spec = match tag {
+            0 => AlgoSpec::HashMatch(length),
+            1 => AlgoSpec::TreeMatch(length),
+            2 => AlgoSpec::SuffixArrayMatch(length),
+            3 => AlgoSpec::Hybrid(to_usize(r.unpack::<u64>()?)?, length),
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "Unknown delta algorithm"))
+        };
+        let version_len = r.unpack::<u64>()?;
+        let mut version = Vec::new();
+        r.take(version_len).read_to_end(&mut version)?;
+        if version.len() as u64 != version_len {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Truncated version"));
+        }
+        let version = String::from_utf8(version)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Invalid version"))?;
+        Ok(Metadata {
+            source_hash,
+            target_hash,
+            algo_spec,
+            version,
+        })
+    }
+}
+
+/// The header of a serialized [`Delta`](struct.Delta.html), read without its operations.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::AlgoSpec;
+/// use bcmp::patch::{Compression, Delta, Metadata, PatchInfo};
+///
+/// let old = b"The quick brown fox jumps over the lazy dog";
+/// let new = b"The quick red fox jumps over the lazy cat";
+/// let delta = Delta::new(old, new, AlgoSpec::TreeMatch(4));
+/// let mut encoded = Vec::new();
+/// delta.write_with_metadata_to(&mut encoded, &Metadata::new(old, new, AlgoSpec::TreeMatch(4)), Compression::Lz).unwrap();
+/// let info = PatchInfo::read(&mut &encoded[..]).unwrap();
+/// assert!(info.target_len == new.len() && info.compression == Compression::Lz);
+/// assert!(info.check_source(old).is_ok() && info.check_source(new).is_err());
+/// ```
+#[derive(Clone,Debug)]
+pub struct PatchInfo {
+    /// Length of the source the delta applies to.
+    pub source_len: usize,
+    /// Length of the target the delta produces.
+    pub target_len: usize,
+    /// The number of operations.
+    pub ops: usize,
+    /// How the operations are compressed.
+    pub compression: Compression,
+    /// The metadata of the delta, if it was written with them.
+    pub metadata: Option<Metadata>,
+}
+
+impl PatchInfo {
+    /// Read the header of a serialized delta from `r`, leaving the operations unread.
+    pub fn read<R: Read>(r: &mut R) -> io::Result<PatchInfo> {
+        PatchInfo::read_header(r).map(|(info, _)| info)
+    }
+
+    // Also tell if the operations are framed by a compression header
+    fn read_header<R: Read>(r: &mut R) -> io::Result<(PatchInfo, bool)> {
+        let mut magic = [0u8; 8];
+        r.read_exact(&mut magic)?;
+        let (framed, with_metadata) = match &magic {
+            m if m == MAGIC => (false, false),
+            m if m == COMPRESSED_MAGIC => (true, false),
+            m if m == METADATA_MAGIC => (true, true),
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "Not a bcmp delta"))
+        };
+        let source_len = to_usize(r.unpack::<u64>()?)?;
+        let target_len = to_usize(r.unpack::<u64>()?)?;
+        let ops = to_usize(r.unpack::<u64>()?)?;
+        let metadata = if with_metadata { Some(Metadata::read_from(r)?) } else { None };
+        let compression = if framed { Compression::from_byte(r.unpack::<u8>()?)? } else { Compression::None };
+        let info = PatchInfo {
+            source_len,
+            target_len,
+            ops,
+            compression,
+            metadata,
+        };
+        Ok((info, framed))
+    }
+
+    /// Check that the delta applies to `source`: it must have the recorded length and, if the
+    /// delta has metadata, the recorded hash.
+    pub fn check_source(&self, source: &[u8]) -> Result<(), PatchError> {
+        if source.len() != self.source_len {
+            return Err(PatchError::SourceLength { expected: self.source_len, found: source.len() });
+        }
+        match self.metadata {
+            Some(ref metadata) if metadata.source_hash != sha256(source) => Err(PatchError::SourceHash),
+            _ => Ok(())
+        }
+    }
+
+    /// Check that `target` is what the delta produces: it must have the recorded length and, if 
+    /// the delta has metadata, the recorded hash.
+    pub fn check_target(&self, target: &[u8]) -> Result<(), PatchError> {
+        if target.len() != self.target_len {
+            return Err(PatchError::TargetLength { expected: self.target_len, found: target.len() });
+        }
+        match self.metadata {
+            Some(ref metadata) if metadata.target_hash != sha256(target) => Err(PatchError::TargetHash),
+            _ => Ok(())
+        }
+    }
+}
+
+/// An error happening while applying a [`Delta`](struct.Delta.html).
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum PatchError {
+    /// The source does not have the length the delta was computed against.
+    SourceLength {
+        /// The length recorded in the delta.
+        expected: usize,
+        /// The length of the given source.
+        found: usize,
+    },
+    /// The operation, or the patch, at this index copies bytes outside of the source or the target.
+    OutOfBounds(usize),
+    /// The source does not have the hash recorded in the delta metadata.
+    SourceHash,
+    /// The target does not have the hash recorded in the delta metadata.
+    TargetHash,
+    /// The byte at this offset of the target is not the one produced by the patches.
+    Mismatch(usize),
+    /// The operations do not produce the number of bytes recorded in the delta.
+    TargetLength {
+        /// The length recorded in the delta.
+        expected: usize,
+        /// The number of bytes produced by the operations.
+        found: usize,
+    },
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PatchError::SourceLength { expected, found } => 
+                write!(f, "source length mismatch: expected {} bytes, found {}", expected, found),
+            PatchError::OutOfBounds(i) => write!(f, "operation {} copies outside of the source", i),
+            PatchError::SourceHash => write!(f, "source hash mismatch"),
+            PatchError::TargetHash => write!(f, "target hash mismatch"),
+            PatchError::Mismatch(offset) => write!(f, "target mismatch at offset {}", offset),
+            PatchError::TargetLength { expected, found } =>
+                write!(f, "target length mismatch: expected {} bytes, produced {}", expected, found),
+        }
+    }
+}
+
+impl Error for PatchError {}
+
+/// A list of operations rebuilding a target from a source.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::AlgoSpec;
+/// use bcmp::patch::Delta;
+///
+/// let old = b"The quick brown fox jumps over the lazy dog";
+/// let new = b"The quick red fox jumps over the lazy cat";
+/// let delta = Delta::new(old, new, AlgoSpec::TreeMatch(4));
+/// let mut encoded = Vec::new();
+/// delta.write_to(&mut encoded).unwrap();
+/// let decoded = Delta::read_from(&mut &encoded[..]).unwrap();
+/// assert!(decoded.apply(old).unwrap() == &new[..]);
+/// ```
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Delta {
+    /// Length of the source the delta applies to.
+    pub source_len: usize,
+    /// Length of the target the delta produces.
+    pub target_len: usize,
+    /// The operations, in target order.
+    pub ops: Vec<Op>,
+}
+
+impl Delta {
+    /// Compute the delta building `second` from `first` using the
+    /// [`patch_set`](../fn.patch_set.html) obtained with `algo_spec`.
+    pub fn new(first: &[u8], second: &[u8], algo_spec: AlgoSpec) -> Delta {
+        Delta::with_min_copy(first, second, algo_spec, 0)
+    }
+
+    /// Compute the delta building `second` from `first` using the
+    /// [`patch_set_with_min_copy`](../fn.patch_set_with_min_copy.html) obtained with `algo_spec`:
+    /// the copies shorter than `min_copy` bytes are stored as literal data instead.
+    ///
+    /// Every copy operation takes 17 bytes once encoded by [`write_to`](#method.write_to), so
+    /// copies shorter than that make the delta larger.
+    pub fn with_min_copy(first: &[u8], second: &[u8], algo_spec: AlgoSpec, min_copy: usize) -> Delta {
+        span!("patch::delta", first.len() + second.len());
+        let patches = if first.len() < algo_spec.minimal_length() || second.len() < algo_spec.minimal_length() {
+            Vec::new()
+        }
+        else {
+            patch_set_with_min_copy(first, second, algo_spec, min_copy)
+        };
+        Delta::from_patches(first.len(), second, &patches)
+    }
+
+    /// Compute the delta building `second` from `first` using the
+    /// [`warm_patch_set`](../fn.warm_patch_set.html) seeded with the [`copies`](#method.copies) of
+    /// `previous`, the delta between the previous versions of both pieces of data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bcmp::AlgoSpec;
+    /// use bcmp::patch::Delta;
+    ///
+    /// let old = b"The quick brown fox jumps over the lazy dog";
+    /// let new = b"The quick brown fox jumps over the lazy cat";
+    /// let previous = Delta::new(old, new, AlgoSpec::TreeMatch(4));
+    /// // The next build changes the source and the target the same way
+    /// let old = b"The quick brown fox leaps over the lazy dog";
+    /// let new = b"The quick brown fox leaps over the lazy cat";
+    /// let delta = Delta::warm(old, new, &previous, AlgoSpec::TreeMatch(4));
+    /// assert!(delta.apply(old).unwrap() == &new[..]);
+    /// assert!(delta.literal() == 3);
+    /// ```
+    pub fn warm(first: &[u8], second: &[u8], previous: &Delta, algo_spec: AlgoSpec) -> Delta {
+        span!("patch::warm", first.len() + second.len());
+        Delta::from_patches(first.len(), second, &warm_patch_set(first, second, &previous.copies(), algo_spec))
+    }
+
+    // The delta copying the non overlapping patches, sorted by position in second, and storing the
+    // rest of second as literal data
+    fn from_patches(first_len: usize, second: &[u8], patches: &[Match]) -> Delta {
+        let mut ops = Vec::new();
+        let mut cursor = 0;
+        for &m in patches {
+            if m.second_pos > cursor {
+                ops.push(Op::Literal(second[cursor..m.second_pos].to_vec()));
+            }
+            ops.push(Op::Copy { offset: m.first_pos, length: m.length });
+            cursor = m.second_end();
+        }
+        if cursor < second.len() {
+            ops.push(Op::Literal(second[cursor..].to_vec()));
+        }
+        Delta {
+            source_len: first_len,
+            target_len: second.len(),
+            ops,
+        }
+    }
+
+    /// Return the copy operations as matches between the source and the target.
+    pub fn copies(&self) -> Vec<Match> {
+        let mut copies = Vec::new();
+        let mut target_pos = 0;
+        for op in &self.ops {
+            if let Op::Copy { offset, length } = *op {
+                copies.push(Match::new(offset, target_pos, length));
+            }
+            target_pos += op.len();
+        }
+        copies
+    }
+
+    /// Return the number of bytes copied from the source.
+    pub fn copied(&self) -> usize {
+        self.ops.iter().filter(|op| matches!(op, Op::Copy { .. })).map(Op::len).sum()
+    }
+
+    /// Return the number of literal bytes stored in the delta.
+    pub fn literal(&self) -> usize {
+        self.ops.iter().filter(|op| matches!(op, Op::Literal(_))).map(Op::len).sum()
+    }
+
+    /// Return the number of bytes written by [`write_to`](#method.write_to).
+    pub fn encoded_len(&self) -> usize {
+        MAGIC.len() + 3 * 8 + self.ops.iter().map(|op| match *op {
+            Op::Copy { .. } => 1 + 2 * 8,
+            Op::Literal(ref data) => 1 + 8 + data.len(),
+        }).sum::<usize>()
+    }
+
+    /// Rebuild the target from `source`.
+    pub fn apply(&self, source: &[u8]) -> Result<Vec<u8>, PatchError> {
+        span!("patch::apply", self.target_len);
+        self.check(source)?;
+        let mut target = Vec::with_capacity(self.target_len);
+        for op in &self.ops {
+            match *op {
+                Op::Copy { offset, length } => target.extend_from_slice(&source[offset..offset + length]),
+                Op::Literal(ref data) => target.extend_from_slice(data),
+            }
+        }
+        Ok(target)
+    }
+
+    /// Check that the delta applies to `source` without building the target: this is a dry run 
+    /// of [`apply`](#method.apply) which fails in exactly the same cases.
+    pub fn check(&self, source: &[u8]) -> Result<(), PatchError> {
+        if source.len() != self.source_len {
+            return Err(PatchError::SourceLength { expected: self.source_len, found: source.len() });
+        }
+        let mut produced = 0usize;
+        for (i, op) in self.ops.iter().enumerate() {
+            if let Op::Copy { offset, length } = *op {
+                if offset.checked_add(length).is_none_or(|end| end > source.len()) {
+                    return Err(PatchError::OutOfBounds(i));
+                }
+            }
+            produced = produced.saturating_add(op.len());
+        }
+        if produced != self.target_len {
+            return Err(PatchError::TargetLength { expected: self.target_len, found: produced });
+        }
+        Ok(())
+    }
+
+    /// Serialize the delta to `w`.
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(MAGIC)?;
+        self.write_header(w)?;
+        self.write_ops(w)
+    }
+
+    /// Serialize the delta to `w`, compressing the operations with `compression`.
+    /// [`read_from`](#method.read_from) decompresses them transparently.
+    pub fn write_compressed_to<W: Write>(&self, w: &mut W, compression: Compression) -> io::Result<()> {
+        w.write_all(COMPRESSED_MAGIC)?;
+        self.write_header(w)?;
+        self.write_framed_ops(w, compression)
+    }
+
+    /// Serialize the delta to `w` with `metadata` in its header, compressing the operations with 
+    /// `compression`. The header can then be inspected with 
+    /// [`PatchInfo::read`](struct.PatchInfo.html#method.read).
+    pub fn write_with_metadata_to<W: Write>(&self, w: &mut W, metadata: &Metadata, compression: Compression) -> io::Result<()> {
+        w.write_all(METADATA_MAGIC)?;
+        self.write_header(w)?;
+        metadata.write_to(w)?;
+        self.write_framed_ops(w, compression)
+    }
+
+    fn write_framed_ops<W: Write>(&self, w: &mut W, compression: Compression) -> io::Result<()> {
+        let mut ops = Vec::new();
+        self.write_ops(&mut ops)?;
+        let compressed = compression.compress(&ops)?;
+        w.pack(compression as u8)?;
+        w.pack(ops.len() as u64)?;
+        w.pack(compressed.len() as u64)?;
+        w.write_all(&compressed)
+    }
+
+    fn write_header<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.pack(self.source_len as u64)?;
+        w.pack(self.target_len as u64)?;
+        w.pack(self.ops.len() as u64)
+    }
+
+    fn write_ops<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        for op in &self.ops {
+            match *op {
+                Op::Copy { offset, length } => {
+                    w.pack(0u8)?;
+                    w.pack(offset as u64)?;
+                    w.pack(length as u64)?;
+                },
+                Op::Literal(ref data) => {
+                    w.pack(1u8)?;
+                    w.pack(data.len() as u64)?;
+                    w.write_all(data)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Deserialize a delta, compressed or not, with metadata or not, from `r`.
+    pub fn read_from<R: Read>(r: &mut R) -> io::Result<Delta> {
+        let (info, framed) = PatchInfo::read_header(r)?;
+        let ops = if framed {
+            let len = r.unpack::<u64>()?;
+            let compressed_len = r.unpack::<u64>()?;
+            let mut data = Vec::new();
+            r.take(compressed_len).read_to_end(&mut data)?;
+            if data.len() as u64 != compressed_len {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Truncated operations"));
+            }
+            let ops = info.compression.decompress(data, to_usize(len)?).filter(|ops| ops.len() as u64 == len)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Corrupted compressed operations"))?;
+            let mut reader = &ops[..];
+            let decoded = Delta::read_ops(&mut reader, info.ops)?;
+            if !reader.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "Trailing bytes after the operations"));
+            }
+            decoded
+        }
+        else {
+            Delta::read_ops(r, info.ops)?
+        };
+        Ok(Delta {
+            source_len: info.source_len,
+            target_len: info.target_len,
+            ops,
+        })
+    }
+
+    fn read_ops<R: Read>(r: &mut R, count: usize) -> io::Result<Vec<Op>> {
+        let mut ops = Vec::new();
+        for _ in 0..count {
+            match r.unpack::<u8>()? {
+                0 => {
+                    let offset = to_usize(r.unpack::<u64>()?)?;
+                    let length = to_usize(r.unpack::<u64>()?)?;
+                    ops.push(Op::Copy { offset, length });
+                },
+                1 => {
+                    let length = r.unpack::<u64>()?;
+                    let mut data = Vec::new();
+                    r.take(length).read_to_end(&mut data)?;
+                    if data.len() as u64 != length {
+                        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Truncated literal"));
+                    }
+                    ops.push(Op::Literal(data));
+                },
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "Unknown delta operation"))
+            }
+        }
+        Ok(ops)
+    }
+}
+
+/// An update between two consecutive versions, as returned by
+/// [`version_chain`](fn.version_chain.html).
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct VersionStep {
+    /// The delta building this version from the previous one.
+    pub delta: Delta,
+    /// The serialized size of the delta.
+    pub delta_size: usize,
+    /// The bytes of this version which could not be copied from the previous one.
+    pub novel: usize,
+    /// The bytes of the previous version which are not copied to this one.
+    pub removed: usize,
+    /// The novel bytes of this version and all the previous updates.
+    pub cumulative_novel: usize,
+}
+
+impl VersionStep {
+    /// Return the churn of the update: the number of bytes added plus the number of bytes removed.
+    pub fn churn(&self) -> usize {
+        self.novel + self.removed
+    }
+}
+
+/// The updates of an ordered sequence of versions, as returned by
+/// [`version_chain`](fn.version_chain.html).
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct VersionChain {
+    /// The update to each version from the previous one, starting with the second version.
+    pub steps: Vec<VersionStep>,
+}
+
+impl VersionChain {
+    /// Return the total serialized size of the deltas.
+    pub fn delta_size(&self) -> usize {
+        self.steps.iter().map(|s| s.delta_size).sum()
+    }
+
+    /// Return the total number of novel bytes introduced by the updates.
+    pub fn novel(&self) -> usize {
+        self.steps.last().map_or(0, |s| s.cumulative_novel)
+    }
+
+    /// Return the total churn of the updates.
+    pub fn churn(&self) -> usize {
+        self.steps.iter().map(VersionStep::churn).sum()
+    }
+}
+
+/// Compute the [`Delta`](struct.Delta.html) from each version to the next one of an ordered
+/// sequence of versions, with the statistics of each update.
+///
+/// Each version is indexed only once, when it is the source of the next delta. A sequence of less
+/// than two versions has no update.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::AlgoSpec;
+/// use bcmp::patch::version_chain;
+///
+/// let versions: [&[u8]; 3] = [b"version 1 of the data", b"version 2 of the data", b"version 2 of the new data"];
+/// let chain = version_chain(&versions, AlgoSpec::TreeMatch(4));
+/// assert!(chain.steps.len() == 2);
+/// assert!(chain.steps[0].novel == 1 && chain.steps[0].removed == 1);
+/// assert!(chain.steps[1].novel == 3 && chain.steps[1].removed == 0);
+/// assert!(chain.novel() == 4 && chain.churn() == 5);
+/// ```
+pub fn version_chain(versions: &[&[u8]], algo_spec: AlgoSpec) -> VersionChain {
+    let mut steps = Vec::new();
+    let mut cumulative_novel = 0;
+    for pair in versions.windows(2) {
+        let delta = Delta::new(pair[0], pair[1], algo_spec);
+        let mut copies: Vec<(usize, usize)> = delta.ops.iter().filter_map(|op| match *op {
+            Op::Copy { offset, length } => Some((offset, offset + length)),
+            Op::Literal(_) => None,
+        }).collect();
+        // Sorting makes the insertion in the set linear
+        copies.sort();
+        let copied: IntervalSet = copies.into_iter().collect();
+        let novel = delta.literal();
+        cumulative_novel += novel;
+        steps.push(VersionStep {
+            delta_size: delta.encoded_len(),
+            novel,
+            removed: pair[0].len() - copied.total_len(),
+            cumulative_novel,
+            delta,
+        });
+    }
+    VersionChain {
+        steps,
+    }
+}
+
+// Number of the sampled windows of target which can be found in base
+fn estimate_copied(target: &[u8], base: &[u8], window: usize) -> usize {
+    if target.len() < window || base.len() < window {
+        return 0;
+    }
+    let windows: HashSet<u64> = rolling_hashes(base, window).map(|(_, h)| h).collect();
+    let stride = ((target.len() - window + 1) / ESTIMATE_SAMPLES).max(1);
+    rolling_hashes(target, window).step_by(stride).filter(|(_, h)| windows.contains(h)).count()
+}
+
+/// Return the index of the candidate giving the smallest [`Delta`](struct.Delta.html) to `target`,
+/// together with that delta, or `None` if there isn't any candidate.
+///
+/// Computing the exact delta from every candidate would be expensive. Instead, a sample of the
+/// windows of the target is looked up in a rolling hash index of each candidate to estimate how
+/// much of the target can be copied from it. The exact deltas are only computed for the few
+/// candidates with the best estimates and the smallest serialized delta wins, the first candidate
+/// on ties.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::AlgoSpec;
+/// use bcmp::patch::best_base;
+///
+/// let candidates: [&[u8]; 3] = [b"a completely unrelated text", b"The quick brown fox", b"The quick brown fox jumps"];
+/// let (index, delta) = best_base(b"The quick brown fox jumps over the dog", &candidates, AlgoSpec::TreeMatch(4)).unwrap();
+/// assert!(index == 2);
+/// assert!(delta.apply(candidates[2]).unwrap() == &b"The quick brown fox jumps over the dog"[..]);
+/// ```
+pub fn best_base(target: &[u8], candidates: &[&[u8]], algo_spec: AlgoSpec) -> Option<(usize, Delta)> {
+    let window = algo_spec.minimal_length().max(1);
+    let mut estimates: Vec<(usize, usize)> = candidates.iter().enumerate()
+        .map(|(i, candidate)| (estimate_copied(target, candidate, window), i))
+        .collect();
+    estimates.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    let mut best: Option<(usize, Delta)> = None;
+    for &(_, i) in estimates.iter().take(EXACT_BASES) {
+        let delta = Delta::new(candidates[i], target, algo_spec);
+        let better = match best {
+            Some((j, ref b)) => (delta.encoded_len(), i) < (b.encoded_len(), j),
+            None => true
+        };
+        if better {
+            best = Some((i, delta));
+        }
+    }
+    best
+}
+
+/// A dictionary made of the parts of a source a target copies from, as returned by
+/// [`dictionary`](fn.dictionary.html).
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Dictionary {
+    /// The regions of the source concatenated in the dictionary, in ascending order.
+    pub regions: Vec<Region>,
+    /// The content of the dictionary.
+    pub data: Vec<u8>,
+}
+
+impl Dictionary {
+    /// Rebuild the dictionary content from `source` and the regions.
+    ///
+    /// It will panic if a region is out of the source.
+    pub fn from_regions(source: &[u8], regions: Vec<Region>) -> Dictionary {
+        let data = regions.iter().flat_map(|r| source[r.start..r.end].iter().cloned()).collect();
+        Dictionary {
+            regions,
+            data,
+        }
+    }
+}
+
+/// Build a dictionary for `zstd --patch-from` out of the regions of `first` which the
+/// [`patch_set`](../fn.patch_set.html) of `second` copies from.
+///
+/// The regions are kept in source order so that consecutive copies stay contiguous, and copied
+/// regions separated by less than 64 bytes are merged since the bytes between them are usually
+/// similar to the ones replacing them.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::{AlgoSpec, Region};
+/// use bcmp::patch::dictionary;
+///
+/// let mut old = vec![0u8; 1000];
+/// old.extend_from_slice(b"The quick brown fox jumps over the lazy dog");
+/// let dict = dictionary(&old, b"The quick brown fox sleeps", AlgoSpec::TreeMatch(8));
+/// assert!(dict.regions == [Region::new(1000, 1020)]);
+/// assert!(dict.data == &b"The quick brown fox "[..]);
+/// ```
+pub fn dictionary(first: &[u8], second: &[u8], algo_spec: AlgoSpec) -> Dictionary {
+    let mut regions: Vec<Region> = Vec::new();
+    if first.len() >= algo_spec.minimal_length() && second.len() >= algo_spec.minimal_length() {
+        let mut copied: Vec<Region> = patch_set(first, second, algo_spec).iter()
+            .map(|m| Region::new(m.first_pos, m.first_end()))
+            .collect();
+        copied.sort_by_key(|r| r.start);
+        for region in copied {
+            match regions.last_mut() {
+                Some(last) if region.start < last.end + DICTIONARY_GAP => last.end = last.end.max(region.end),
+                _ => regions.push(region)
+            }
+        }
+    }
+    Dictionary::from_regions(first, regions)
+}
+
+/// Check that every patch of `patches` copies from `first` exactly the bytes of `second` it 
+/// covers, reporting the patch out of bounds with the lowest index or else the lowest mismatching 
+/// offset of `second`.
+///
+/// The bytes of `second` not covered by any patch are not checked since a delta stores them as 
+/// literals. Patches can be in any order and may overlap.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::Match;
+/// use bcmp::patch::{PatchError, verify_patch};
+///
+/// let first = b"abcdefgh";
+/// let second = b"efghXabcd";
+/// assert!(verify_patch(first, second, &[Match::new(4, 0, 4), Match::new(0, 5, 4)]).is_ok());
+/// assert!(verify_patch(first, second, &[Match::new(3, 0, 4)]) == Err(PatchError::Mismatch(0)));
+/// assert!(verify_patch(first, second, &[Match::new(0, 5, 4), Match::new(6, 0, 4)]) == Err(PatchError::OutOfBounds(1)));
+/// ```
+pub fn verify_patch(first: &[u8], second: &[u8], patches: &[Match]) -> Result<(), PatchError> {
+    for (i, m) in patches.iter().enumerate() {
+        let first_in = m.first_pos.checked_add(m.length).is_some_and(|end| end <= first.len());
+        let second_in = m.second_pos.checked_add(m.length).is_some_and(|end| end <= second.len());
+        if !first_in || !second_in {
+            return Err(PatchError::OutOfBounds(i));
+        }
+    }
+    let mismatch = patches.iter().filter_map(|m| {
+        let copied = &first[m.first_pos..m.first_end()];
+        let covered = &second[m.second_pos..m.second_end()];
+        copied.iter().zip(covered).position(|(a, b)| a != b).map(|p| m.second_pos + p)
+    }).min();
+    match mismatch {
+        Some(offset) => Err(PatchError::Mismatch(offset)),
+        None => Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+//! Empirical selection of the matching algorithm.
+//!
+//! Which backend is the fastest depends on the data more than on its size: a `HashMatch` map
+//! degenerates on highly repetitive data where a suffix tree stays linear, while on high entropy
+//! data the map is usually faster. Instead of guessing from the sizes,
+//! [`probe`](fn.probe.html) runs every backend on a window of each input, measures how fast they
+//! build their index and scan, and extrapolates these rates to the full inputs to pick the
+//! backend.
+//!
+//! # Examples
+//!
+//! ```
+//! use bcmp::MatchIterator;
+//! use bcmp::probe::{ProbeOptions, probe};
+//!
+//! let a = b"The quick brown fox jumps over the lazy dog";
+//! let b = b"The lazy dog jumps over the quick brown fox";
+//! let result = probe(a, b, 8, ProbeOptions::default());
+//! let matches: Vec<_> = MatchIterator::new(a, b, result.algo_spec).collect();
+//! assert!(matches.len() == 3);
+//! ```
+
+use std::time::{Duration, Instant};
+
+use {AlgoSpec, MatchIterator, estimate_memory};
+use suffixtree;
+
+/// Options of [`probe`](fn.probe.html).
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct ProbeOptions {
+    /// The size of the window of each input the backends are run on.
+    pub sample_size: usize,
+    /// The memory the full run may use, as estimated by
+    /// [`estimate_memory`](../fn.estimate_memory.html), if limited.
+    pub memory_limit: Option<usize>,
+}
+
+impl Default for ProbeOptions {
+    /// Windows of 256 KiB and no memory limit.
+    fn default() -> ProbeOptions {
+        ProbeOptions {
+            sample_size: 256 * 1024,
+            memory_limit: None,
+        }
+    }
+}
+
+/// The measurements of a backend on the windows of the inputs.
+#[derive(Clone,Copy,Debug)]
+pub struct BackendProbe {
+    /// The backend with the requested minimal matching length.
+    pub algo_spec: AlgoSpec,
+    /// The time spent building the index of the window of the first piece of data.
+    pub index_time: Duration,
+    /// The time spent enumerating the matches of the window of the second piece of data.
+    pub scan_time: Duration,
+    /// The time the full run is expected to take, extrapolated from the windows.
+    pub projected_time: Duration,
+    /// The memory the full run is expected to use, as estimated by
+    /// [`estimate_memory`](../fn.estimate_memory.html).
+    pub projected_memory: usize,
+}
+
+/// The result of [`probe`](fn.probe.html).
+#[derive(Clone,Debug)]
+pub struct Probe {
+    /// The selected backend.
+    pub algo_spec: AlgoSpec,
+    /// The measurements of every backend supporting the minimal matching length.
+    pub backends: Vec<BackendProbe>,
+}
+
+// The window of at most size bytes in the middle of data
+pub(crate) fn window(data: &[u8], size: usize) -> &[u8] {
+    let size = size.min(data.len());
+    let start = (data.len() - size) / 2;
+    &data[start..start + size]
+}
+
+// Scale a duration measured on sample bytes to total bytes
+fn extrapolate(time: Duration, sample: usize, total: usize) -> Duration {
+    if sample == 0 {
+        return time;
+    }
+    Duration::from_secs_f64(time.as_secs_f64() * total as f64 / sample as f64)
+}
+
+/// Select the fastest backend finding the matches of at least `minimal_length` bytes between
+/// `first` and `second`.
+///
+/// Every backend supporting `minimal_length` is run on the window of `options.sample_size` bytes
+/// in the middle of each input: its index build time is extrapolated to the length of `first` and
+/// its scan time to the length of `second`. The backend with the smallest projected time is
+/// selected among the ones whose estimated memory fits in `options.memory_limit`, or the one
+/// needing the least memory if none fits.
+///
+/// The windows should be large enough to be representative of the data: tiny windows mostly
+/// measure fixed costs. It will panic if `minimal_length` is zero.
+pub fn probe(first: &[u8], second: &[u8], minimal_length: usize, options: ProbeOptions) -> Probe {
+    assert!(minimal_length > 0, "The minimal length must be at least 1");
+    let first_window = window(first, options.sample_size);
+    let second_window = window(second, options.sample_size);
+    let mut candidates = vec![AlgoSpec::HashMatch(minimal_length), AlgoSpec::TreeMatch(minimal_length), AlgoSpec::SuffixArrayMatch(minimal_length)];
+    candidates.retain(|algo_spec| algo_spec.is_supported());
+    if first.len() > suffixtree::MAX_LEN {
+        candidates.retain(|algo_spec| !matches!(*algo_spec, AlgoSpec::TreeMatch(_)));
+    }
+    let backends: Vec<BackendProbe> = candidates.into_iter().map(|algo_spec| {
+        let start = Instant::now();
+        let match_iter = MatchIterator::new(first_window, second_window, algo_spec);
+        let index_time = start.elapsed();
+        let start = Instant::now();
+        match_iter.count();
+        let scan_time = start.elapsed();
+        BackendProbe {
+            algo_spec,
+            index_time,
+            scan_time,
+            projected_time: extrapolate(index_time, first_window.len(), first.len())
+                + extrapolate(scan_time, second_window.len(), second.len()),
+            projected_memory: estimate_memory(algo_spec, first.len(), second.len()).total(),
+        }
+    }).collect();
+    let fitting = backends.iter()
+        .filter(|b| options.memory_limit.is_none_or(|limit| b.projected_memory <= limit))
+        .min_by_key(|b| b.projected_time);
+    let selected = fitting.or_else(|| backends.iter().min_by_key(|b| b.projected_memory)).unwrap();
+    Probe {
+        algo_spec: selected.algo_spec,
+        backends,
+    }
+}
@@ -0,0 +1,82 @@
+//! Python bindings, available with the `python` feature.
+//!
+//! The bindings expose a `bcmp` Python module operating on `bytes` objects. The `algo` parameter
+//! selects `"tree"`, `"hash"` or `"array"` and `mml` is the minimal matching length. Building an
+//! importable extension module requires the `pyo3/extension-module` feature and a `cdylib` crate
+//! type, which is what `maturin build --features python,pyo3/extension-module` takes care of.
+//!
+//! ```python
+//! import bcmp
+//! bcmp.matches(b"abcdefg", b"012abc34cdef", "hash", 2)  # [(0, 3, 3), (2, 8, 4)]
+//! bcmp.patch_set(old, new)                             # [(first_pos, second_pos, length), ...]
+//! bcmp.similarity(old, new, "tree", 16)                # 0.97
+//! ```
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use {AlgoSpec, Match, MatchIterator};
+
+const HASH_LENGTHS: &[usize] = &[1, 2, 3, 4, 5, 6, 7, 8, 10, 12, 14, 16, 20, 24, 28, 32, 40, 48, 56, 64];
+
+fn algo_spec(algo: &str, mml: usize) -> PyResult<AlgoSpec> {
+    match algo {
+        _ if mml == 0 => Err(PyValueError::new_err("the minimal matching length must be at least 1")),
+        "tree" => Ok(AlgoSpec::TreeMatch(mml)),
+        "array" => Ok(AlgoSpec::SuffixArrayMatch(mml)),
+        "hash" if HASH_LENGTHS.contains(&mml) => Ok(AlgoSpec::HashMatch(mml)),
+        "hash" => Err(PyValueError::new_err(format!("unsupported minimal matching length {} for hash", mml))),
+        _ => Err(PyValueError::new_err(format!("unknown algorithm {}, use \"tree\", \"hash\" or \"array\"", algo))),
+    }
+}
+
+// The matching algorithms require both inputs to be at least as long as the minimal matching length
+fn comparable(a: &[u8], b: &[u8], mml: usize) -> bool {
+    a.len() >= mml && b.len() >= mml
+}
+
+fn to_tuples(matches: Vec<Match>) -> Vec<(usize, usize, usize)> {
+    matches.into_iter().map(|m| (m.first_pos, m.second_pos, m.length)).collect()
+}
+
+/// Return all the matches between `a` and `b` as `(first_pos, second_pos, length)` tuples.
+#[pyfunction]
+#[pyo3(signature = (a, b, algo="tree", mml=8))]
+fn matches(py: Python, a: &[u8], b: &[u8], algo: &str, mml: usize) -> PyResult<Vec<(usize, usize, usize)>> {
+    let spec = algo_spec(algo, mml)?;
+    if !comparable(a, b, mml) {
+        return Ok(Vec::new());
+    }
+    Ok(py.allow_threads(|| to_tuples(MatchIterator::new(a, b, spec).collect())))
+}
+
+/// Return the patch set building `b` from `a` as `(first_pos, second_pos, length)` tuples.
+#[pyfunction]
+#[pyo3(signature = (a, b, algo="tree", mml=8))]
+fn patch_set(py: Python, a: &[u8], b: &[u8], algo: &str, mml: usize) -> PyResult<Vec<(usize, usize, usize)>> {
+    let spec = algo_spec(algo, mml)?;
+    if !comparable(a, b, mml) {
+        return Ok(Vec::new());
+    }
+    Ok(py.allow_threads(|| to_tuples(::patch_set(a, b, spec))))
+}
+
+/// Return the similarity of `a` and `b`, between 0 and 1.
+#[pyfunction]
+#[pyo3(signature = (a, b, algo="tree", mml=8))]
+fn similarity(py: Python, a: &[u8], b: &[u8], algo: &str, mml: usize) -> PyResult<f64> {
+    let spec = algo_spec(algo, mml)?;
+    if !comparable(a, b, mml) {
+        return Ok(if a.is_empty() && b.is_empty() { 1.0 } else { 0.0 });
+    }
+    Ok(py.allow_threads(|| ::similarity(a, b, spec)))
+}
+
+/// The `bcmp` Python module.
+#[pymodule]
+fn bcmp(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(python::matches, m)?)?;
+    m.add_function(wrap_pyfunction!(python::patch_set, m)?)?;
+    m.add_function(wrap_pyfunction!(python::similarity, m)?)?;
+    Ok(())
+}
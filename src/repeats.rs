@@ -0,0 +1,189 @@
+//! Analyses of a single piece of data: unique and repeated substrings.
+//!
+//! These functions rely on the same [`SuffixTree`](../suffixtree/struct.SuffixTree.html) used by
+//! [`TreeMatch`](../treematch/index.html), built over the data itself.
+
+use {AlgoSpec, Match, MatchIterator, Region, sort_by_length_desc};
+use suffixtree::{MAX_LEN, NodeIndex, SuffixTree};
+
+/// Compute, for each position of `data`, the length of the shortest substring starting there 
+/// which occurs only once in `data`.
+///
+/// The entry is `None` if every substring starting at this position occurs more than once, which 
+/// happens for the positions near the end of the data whose suffix is repeated earlier.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::repeats::shortest_unique_lengths;
+///
+/// let lengths = shortest_unique_lengths("abab".as_bytes());
+/// assert!(lengths == vec![Some(3), Some(2), None, None]);
+/// ```
+pub fn shortest_unique_lengths(data: &[u8]) -> Vec<Option<usize>> {
+    let mut lengths = vec![None; data.len()];
+    if data.is_empty() {
+        return lengths;
+    }
+    if data.len() <= MAX_LEN {
+        unique_lengths(&SuffixTree::<u32>::build(data), data.len(), &mut lengths);
+    }
+    else {
+        unique_lengths(&SuffixTree::new(data), data.len(), &mut lengths);
+    }
+    lengths
+}
+
+// Fill lengths from the suffix tree of data, of length len
+fn unique_lengths<I: NodeIndex>(tree: &SuffixTree<I>, len: usize, lengths: &mut [Option<usize>]) {
+    tree.for_each_leaf(len, |start, parent_depth| {
+        // The path to the parent is shared with another suffix, one more byte makes it unique 
+        // unless this suffix ends there.
+        if start + parent_depth < len {
+            lengths[start] = Some(parent_depth + 1);
+        }
+    });
+}
+
+/// Return the shortest substring occurring only once in `data`. If several exist, the leftmost 
+/// one is returned. `None` is only returned for empty data.
+pub fn shortest_unique_substring(data: &[u8]) -> Option<Region> {
+    let mut best: Option<Region> = None;
+    for (start, length) in shortest_unique_lengths(data).into_iter().enumerate() {
+        if let Some(length) = length {
+            if best.is_none_or(|b| length < b.len()) {
+                best = Some(Region::new(start, start + length));
+            }
+        }
+    }
+    best
+}
+
+// Return one Match per left-diverse internal node of the suffix tree of data: the first two 
+// occurrences of the path leading to the node.
+fn repeated_nodes(data: &[u8]) -> Vec<Match> {
+    let mut repeats = Vec::<Match>::new();
+    if data.is_empty() {
+        return repeats;
+    }
+    if data.len() <= MAX_LEN {
+        repeats_of(&SuffixTree::<u32>::build(data), data, &mut repeats);
+    }
+    else {
+        repeats_of(&SuffixTree::new(data), data, &mut repeats);
+    }
+    repeats
+}
+
+// Push the repeats of repeated_nodes found in tree, the suffix tree of data
+fn repeats_of<I: NodeIndex>(tree: &SuffixTree<I>, data: &[u8], repeats: &mut Vec<Match>) {
+    let mut depth = vec![0usize; tree.nodes.len()];
+    // Two smallest suffix starts found under each node
+    let mut first = vec![(usize::MAX, usize::MAX); tree.nodes.len()];
+    // The byte preceding all the suffixes under each node, 256 if they differ, 257 for the start
+    let mut left = vec![0u16; tree.nodes.len()];
+    // Pre-order, processed in reverse it becomes a post-order
+    let mut order = Vec::<usize>::with_capacity(tree.nodes.len());
+    let mut stack = vec![0usize];
+    while let Some(cur) = stack.pop() {
+        order.push(cur);
+        for next in tree.nodes[cur].children().map(|(_, next)| next) {
+            depth[next] = depth[cur] + tree.nodes[next].edge_length();
+            stack.push(next);
+        }
+    }
+    for &cur in order.iter().rev() {
+        let mut smallest = usize::MAX;
+        let mut second = usize::MAX;
+        let mut leaf = true;
+        let mut left_char: Option<u16> = None;
+        for next in tree.nodes[cur].children().map(|(_, next)| next) {
+            leaf = false;
+            left_char = match left_char {
+                Some(c) if c != left[next] => Some(256),
+                _ => Some(left[next])
+            };
+            for &start in &[first[next].0, first[next].1] {
+                if start < smallest {
+                    second = smallest;
+                    smallest = start;
+                }
+                else if start < second {
+                    second = start;
+                }
+            }
+        }
+        if leaf {
+            let start = data.len() - depth[cur];
+            first[cur] = (start, usize::MAX);
+            left[cur] = match start {
+                0 => 257,
+                start => data[start - 1] as u16
+            };
+        }
+        else {
+            first[cur] = (smallest, second);
+            left[cur] = left_char.unwrap_or(256);
+            if cur != 0 && depth[cur] > 0 && left[cur] >= 256 {
+                repeats.push(Match::new(smallest, second, depth[cur]));
+            }
+        }
+    }
+}
+
+/// Return the longest substring occurring at least twice in `data`, as a 
+/// [`Match`](../struct.Match.html) between its first two occurrences. The occurrences may overlap. 
+/// `None` is returned if no byte is repeated.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::Match;
+/// use bcmp::repeats::longest_repeated_substring;
+///
+/// let m = longest_repeated_substring("xabcdyabcdz".as_bytes()).unwrap();
+/// assert!(m == Match::new(1, 6, 4));
+/// ```
+pub fn longest_repeated_substring(data: &[u8]) -> Option<Match> {
+    let mut repeats = repeated_nodes(data);
+    sort_by_length_desc(&mut repeats);
+    repeats.first().cloned()
+}
+
+/// Return the `number` longest repeated substrings of `data`, sorted in decreasing order of 
+/// length. Each one is reported as a [`Match`](../struct.Match.html) between its first two 
+/// occurrences.
+///
+/// Only maximal repeats are considered: a repeated substring is reported only if its occurrences 
+/// are neither all preceded nor all followed by the same byte. A substring which is only repeated 
+/// as a part of a longer repeat is therefore not reported.
+pub fn longest_repeated_substrings(data: &[u8], number: usize) -> Vec<Match> {
+    let mut repeats = repeated_nodes(data);
+    sort_by_length_desc(&mut repeats);
+    repeats.truncate(number);
+    repeats
+}
+
+/// Return the internal duplications of `data`: the matches between `data` and itself, excluding 
+/// the trivial identity match.
+///
+/// Comparing `data` against itself with a [`MatchIterator`](../struct.MatchIterator.html) reports 
+/// every duplication twice, once in each direction, and drowns them behind the full length match 
+/// of `data` with itself. This function only keeps the matches whose 
+/// [`first_pos`](../struct.Match.html#structfield.first_pos) is strictly smaller than their 
+/// [`second_pos`](../struct.Match.html#structfield.second_pos): the first occurrence is reported 
+/// in `first_pos` and the later one in `second_pos`. The matches are returned in ascending order 
+/// of `second_pos`.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::{AlgoSpec, Match};
+/// use bcmp::repeats::self_matches;
+///
+/// let ms = self_matches("0abcd1abcd2".as_bytes(), AlgoSpec::TreeMatch(3));
+/// assert!(ms == vec![Match::new(1, 6, 4)]);
+/// ```
+pub fn self_matches(data: &[u8], algo_spec: AlgoSpec) -> Vec<Match> {
+    MatchIterator::new(data, data, algo_spec).filter(|m| m.first_pos < m.second_pos).collect()
+}
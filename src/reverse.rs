@@ -0,0 +1,61 @@
+//! Detection of regions of the second piece of data equal to byte-reversed regions of the first.
+//!
+//! Endian-flipped tables and buffers written backward show up regularly in binary forensics. The
+//! functions of this module run the regular matching algorithms against a reversed copy of the
+//! first piece of data and translate the positions back.
+
+use {AlgoSpec, Match, MatchIterator};
+
+/// The direction in which a region of the first piece of data is found in the second.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,PartialOrd,Ord,Hash)]
+pub enum Orientation {
+    /// `second[second_pos..second_end()] == first[first_pos..first_end()]`
+    Forward,
+    /// `second[second_pos..second_end()]` is `first[first_pos..first_end()]` reversed.
+    Reverse,
+}
+
+/// A [`Match`](../struct.Match.html) together with its [`Orientation`](enum.Orientation.html).
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct OrientedMatch {
+    /// The positions and length of the match. For a reversed match, `first_pos` is the start of 
+    /// the region of the first piece of data as it is stored, not as it is read backward.
+    pub m: Match,
+    /// The direction of the match.
+    pub orientation: Orientation,
+}
+
+/// Return the matches between `second` and the byte-reversed `first`, in ascending order of 
+/// [`second_pos`](../struct.Match.html#structfield.second_pos).
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::{AlgoSpec, Match};
+/// use bcmp::reverse::reversed_matches;
+///
+/// let ms = reversed_matches("xx1234yy".as_bytes(), "4321".as_bytes(), AlgoSpec::TreeMatch(3));
+/// assert!(ms == vec![Match::new(2, 0, 4)]);
+/// ```
+pub fn reversed_matches(first: &[u8], second: &[u8], algo_spec: AlgoSpec) -> Vec<Match> {
+    let reversed: Vec<u8> = first.iter().rev().cloned().collect();
+    MatchIterator::new(&reversed, second, algo_spec).map(|mut m| {
+        m.first_pos = first.len() - m.first_end();
+        m
+    }).collect()
+}
+
+/// Return both the forward and the reversed matches between the two pieces of data, sorted by 
+/// [`second_pos`](../struct.Match.html#structfield.second_pos) with each match annotated with its 
+/// [`Orientation`](enum.Orientation.html). Palindromic regions are reported in both orientations.
+pub fn oriented_matches(first: &[u8], second: &[u8], algo_spec: AlgoSpec) -> Vec<OrientedMatch> {
+    let mut matches: Vec<OrientedMatch> = MatchIterator::new(first, second, algo_spec).map(|m| {
+        OrientedMatch { m, orientation: Orientation::Forward }
+    }).collect();
+    matches.extend(reversed_matches(first, second, algo_spec).into_iter().map(|m| {
+        OrientedMatch { m, orientation: Orientation::Reverse }
+    }));
+    // Stable, so the matches of each orientation keep their relative order
+    matches.sort_by(|a, b| a.m.second_pos.cmp(&b.m.second_pos).then(a.orientation.cmp(&b.orientation)));
+    matches
+}
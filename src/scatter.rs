@@ -0,0 +1,154 @@
+//! Comparison of scattered data, given as a list of non-contiguous segments.
+//!
+//! Network and database buffers are rarely contiguous and concatenating them in a single `Vec`
+//! doubles the memory usage. [`Scattered`](struct.Scattered.html) presents an iovec-style
+//! `&[&[u8]]` as one virtual linear piece of data without copying it. Matches are expressed in
+//! virtual linear offsets which [`Scattered::locate`](struct.Scattered.html#method.locate)
+//! translates back to `(segment, offset)` pairs.
+
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+use std::iter::Iterator;
+
+use Match;
+
+/// A virtual linear view over a list of byte segments.
+#[derive(Clone,Debug)]
+pub struct Scattered<'a> {
+    segments: &'a [&'a [u8]],
+    // Virtual offset of the start of each segment followed by the total length
+    starts: Vec<usize>,
+}
+
+impl<'a> Scattered<'a> {
+    /// Build a view over `segments`. Empty segments are allowed. It will panic if their total
+    /// length overflows a `usize`.
+    pub fn new(segments: &'a [&'a [u8]]) -> Scattered<'a> {
+        let mut starts = Vec::with_capacity(segments.len() + 1);
+        let mut offset = 0;
+        starts.push(0);
+        for s in segments {
+            offset = s.len().checked_add(offset).expect("The segments are longer than usize::MAX");
+            starts.push(offset);
+        }
+        Scattered {
+            segments,
+            starts,
+        }
+    }
+
+    /// Return the total length of the segments.
+    pub fn len(&self) -> usize {
+        self.starts[self.starts.len() - 1]
+    }
+
+    /// Return true if all the segments are empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the segments composing the view.
+    pub fn segments(&self) -> &'a [&'a [u8]] {
+        self.segments
+    }
+
+    /// Translate a virtual linear offset to a `(segment, offset)` pair. An offset equal to the
+    /// total length is located at the end of the last segment.
+    ///
+    /// It will panic if `pos` is larger than the total length.
+    pub fn locate(&self, pos: usize) -> (usize, usize) {
+        assert!(pos <= self.len(), "Offset out of bounds");
+        // Last segment starting at or before pos which is not empty, unless pos is at the end
+        let mut segment = match self.starts.binary_search(&pos) {
+            Ok(i) => i,
+            Err(i) => i - 1,
+        };
+        while segment > 0 && segment >= self.segments.len() {
+            segment -= 1;
+        }
+        while segment + 1 < self.segments.len() && pos >= self.starts[segment + 1] {
+            segment += 1;
+        }
+        (segment, pos - self.starts[segment])
+    }
+
+    /// Translate a `(segment, offset)` pair to a virtual linear offset.
+    pub fn linear(&self, segment: usize, offset: usize) -> usize {
+        self.starts[segment] + offset
+    }
+
+    /// Return the byte at virtual linear offset `pos`.
+    pub fn get(&self, pos: usize) -> u8 {
+        let (segment, offset) = self.locate(pos);
+        self.segments[segment][offset]
+    }
+
+    /// Iterate over the bytes starting at virtual linear offset `pos`.
+    pub fn iter_from(&self, pos: usize) -> impl Iterator<Item=u8> + 'a {
+        let (segment, offset) = self.locate(pos);
+        let segments = self.segments;
+        segments[segment.min(segments.len().saturating_sub(1))..].iter().enumerate()
+            .flat_map(move |(i, s)| s[if i == 0 { offset.min(s.len()) } else { 0 }..].iter().cloned())
+    }
+}
+
+fn key_hash(data: &Scattered, pos: usize, len: usize) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for b in data.iter_from(pos).take(len) {
+        hasher.write_u8(b);
+    }
+    hasher.finish()
+}
+
+/// Return all the [`Match`](../struct.Match.html) of at least `minimal_length` bytes between two
+/// scattered pieces of data, in virtual linear offsets.
+///
+/// Matches spanning segment boundaries are found like any other. The first piece of data is
+/// indexed by the hash of its substrings of `minimal_length` bytes, so no segment is ever copied.
+/// Matches are returned sorted in their natural order.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::Match;
+/// use bcmp::scatter::{Scattered, scattered_matches};
+///
+/// let first_segments: [&[u8]; 2] = [b"0123abc", b"defgh"];
+/// let second_segments: [&[u8]; 3] = [b"xab", b"cdef", b"gx"];
+/// let first = Scattered::new(&first_segments);
+/// let second = Scattered::new(&second_segments);
+/// let matches = scattered_matches(&first, &second, 4);
+/// assert!(matches == [Match::new(4, 1, 7)]);
+/// assert!(first.locate(4) == (0, 4));
+/// assert!(second.locate(matches[0].second_end()) == (2, 1));
+/// ```
+pub fn scattered_matches(first: &Scattered, second: &Scattered, minimal_length: usize) -> Vec<Match> {
+    assert!(minimal_length > 0, "The minimal length must be at least 1");
+    let mut matches = Vec::new();
+    if first.len() < minimal_length || second.len() < minimal_length {
+        return matches;
+    }
+    let mut map = HashMap::<u64, Vec<usize>>::new();
+    for i in 0..first.len() - minimal_length + 1 {
+        map.entry(key_hash(first, i, minimal_length)).or_default().push(i);
+    }
+    let mut previous = None;
+    for (j, b) in second.iter_from(0).enumerate().take(second.len() - minimal_length + 1) {
+        if let Some(positions) = map.get(&key_hash(second, j, minimal_length)) {
+            for &p in positions {
+                // Only report left maximal matches
+                if p > 0 && previous == Some(first.get(p - 1)) {
+                    continue;
+                }
+                let length = first.iter_from(p).zip(second.iter_from(j)).take_while(|&(a, b)| a == b).count();
+                // The hash might collide
+                if length >= minimal_length {
+                    matches.push(Match::new(p, j, length));
+                }
+            }
+        }
+        previous = Some(b);
+    }
+    matches
+}
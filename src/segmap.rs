@@ -0,0 +1,117 @@
+//! Structured comparison of containers described by segment maps.
+//!
+//! Executables, firmware images and archives are made of sections which only make sense to
+//! compare with their counterpart. Comparing the whole files produces nonsensical matches between
+//! unrelated sections, while comparing each pair of corresponding segments keeps the results
+//! meaningful. Segments are paired by name.
+
+use {AlgoSpec, Match, Region, coverage, patch_set};
+
+/// A named region of a piece of data, like a section of an executable.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct NamedSegment {
+    /// The name used to pair the segments of the two pieces of data.
+    pub name: String,
+    /// The region of the piece of data covered by the segment.
+    pub region: Region,
+}
+
+impl NamedSegment {
+    /// Allocate a new `NamedSegment` starting at `offset` and `len` bytes long.
+    pub fn new(name: &str, offset: usize, len: usize) -> NamedSegment {
+        NamedSegment {
+            name: name.to_string(),
+            region: Region::new(offset, offset + len),
+        }
+    }
+}
+
+/// The comparison result of a pair of segments.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct SegmentReport {
+    /// The segment name.
+    pub name: String,
+    /// The segment region in the first piece of data, `None` if it only exists in the second.
+    pub first: Option<Region>,
+    /// The segment region in the second piece of data, `None` if it only exists in the first.
+    pub second: Option<Region>,
+    /// The patch set building the second segment from the first one, expressed in whole piece of 
+    /// data coordinates.
+    pub patches: Vec<Match>,
+    /// The number of bytes of the second segment covered by a match with the first segment.
+    pub covered: usize,
+}
+
+impl SegmentReport {
+    /// Return true if the segment exists in both pieces of data and the second segment is 
+    /// entirely covered by matches.
+    pub fn is_unchanged(&self) -> bool {
+        match (self.first, self.second) {
+            (Some(f), Some(s)) => f.len() == s.len() && self.covered == s.len() && 
+                                  self.patches.len() == 1,
+            _ => false
+        }
+    }
+}
+
+/// Compare two pieces of data segment by segment.
+///
+/// Segments are paired by name and each pair is compared independently using 
+/// [`patch_set`](../fn.patch_set.html). The reports are returned in the order of `second_map`, 
+/// followed by the segments only present in `first_map`.
+///
+/// # Panics
+///
+/// It will panic if a segment extends beyond the end of its piece of data.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::AlgoSpec;
+/// use bcmp::segmap::{NamedSegment, compare_segments};
+///
+/// let a = "HEADER..code_v1..data_v1";
+/// let b = "HEADER..code_v2..data_v1";
+/// let map = vec![NamedSegment::new("hdr", 0, 8), NamedSegment::new("code", 8, 9), NamedSegment::new("data", 17, 7)];
+/// let reports = compare_segments(a.as_bytes(), &map, b.as_bytes(), &map, AlgoSpec::TreeMatch(2));
+/// assert!(reports[0].is_unchanged());
+/// assert!(!reports[1].is_unchanged());
+/// assert!(reports[2].is_unchanged());
+/// ```
+pub fn compare_segments(first: &[u8], first_map: &[NamedSegment], second: &[u8], second_map: &[NamedSegment], 
+                        algo_spec: AlgoSpec) -> Vec<SegmentReport> {
+    let mut reports = Vec::<SegmentReport>::with_capacity(second_map.len());
+    for s in second_map {
+        let second_data = s.region.bytes(second);
+        let mut report = SegmentReport {
+            name: s.name.clone(),
+            first: None,
+            second: Some(s.region),
+            patches: Vec::new(),
+            covered: 0,
+        };
+        if let Some(f) = first_map.iter().find(|f| f.name == s.name) {
+            let first_data = f.region.bytes(first);
+            report.first = Some(f.region);
+            report.patches = patch_set(first_data, second_data, algo_spec).into_iter().map(|mut m| {
+                m.first_pos += f.region.start;
+                m.second_pos += s.region.start;
+                m
+            }).collect();
+            report.covered = coverage(first_data, second_data, algo_spec).total_len();
+        }
+        reports.push(report);
+    }
+    for f in first_map {
+        if !second_map.iter().any(|s| s.name == f.name) {
+            reports.push(SegmentReport {
+                name: f.name.clone(),
+                first: Some(f.region),
+                second: None,
+                patches: Vec::new(),
+                covered: 0,
+            });
+        }
+    }
+    reports
+}
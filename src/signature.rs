@@ -0,0 +1,221 @@
+//! Remote delta computation from a signature of the reference, the way rsync does.
+//!
+//! A [`Delta`](../patch/struct.Delta.html) normally requires both pieces of data. When the
+//! reference is on another machine, its owner can instead send a compact
+//! [`Signature`](struct.Signature.html): the reference is cut into fixed size blocks and each of
+//! them is described by a weak rolling checksum and a strong SHA-256 hash. The party holding the new
+//! data slides a window over it, looks the rolling checksum up and confirms candidates with the
+//! strong hash, producing a delta without ever seeing the reference bytes.
+//!
+//! Only whole blocks can be copied, so the delta is coarser than the one computed with the
+//! reference at hand.
+//!
+//! # Format
+//!
+//! A serialized signature starts with the 8 bytes magic `BCMPSG\0\x01` followed by the block size,
+//! the length of the reference and the number of blocks, encoded as little endian `u64`. Each
+//! block is then described by its weak checksum, a little endian `u32`, and its 32 bytes strong
+//! hash.
+
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+
+use bytepack::{LEPacker, LEUnpacker};
+
+use to_usize;
+use digest::sha256;
+use patch::{Delta, Op};
+
+const MAGIC: &[u8; 8] = b"BCMPSG\x00\x01";
+const MIN_BLOCK_SIZE: usize = 64;
+const MAX_BLOCK_SIZE: usize = 1 << 16;
+// Blocks allocated before reading them when deserializing
+const MAX_PREALLOCATED_BLOCKS: usize = 1 << 16;
+
+// rsync's rolling checksum: the sum of the bytes and the sum of the partial sums, both modulo
+// 2^16
+#[derive(Clone,Copy)]
+struct Rolling {
+    a: u32,
+    b: u32,
+    length: u32,
+}
+
+impl Rolling {
+    fn new(window: &[u8]) -> Rolling {
+        let mut rolling = Rolling { a: 0, b: 0, length: window.len() as u32 };
+        for &byte in window {
+            rolling.a = rolling.a.wrapping_add(byte as u32);
+            rolling.b = rolling.b.wrapping_add(rolling.a);
+        }
+        rolling
+    }
+
+    fn roll(&mut self, leaving: u8, entering: u8) {
+        self.a = self.a.wrapping_sub(leaving as u32).wrapping_add(entering as u32);
+        self.b = self.b.wrapping_sub(self.length.wrapping_mul(leaving as u32)).wrapping_add(self.a);
+    }
+
+    fn digest(&self) -> u32 {
+        (self.a & 0xffff) | (self.b << 16)
+    }
+}
+
+/// The checksums of a block of the reference.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct BlockSignature {
+    /// The rolling checksum of the block.
+    pub weak: u32,
+    /// The SHA-256 hash of the block.
+    pub strong: [u8; 32],
+}
+
+/// The signature of a reference, as returned by [`signature`](fn.signature.html).
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Signature {
+    /// The size of the blocks.
+    pub block_size: usize,
+    /// The length of the reference.
+    pub source_len: usize,
+    /// The signatures of the whole blocks of the reference, in order. A trailing partial block is
+    /// not described.
+    pub blocks: Vec<BlockSignature>,
+}
+
+impl Signature {
+    /// Compute the signature of `reference` with blocks of `block_size` bytes.
+    ///
+    /// It will panic if `block_size` is zero.
+    pub fn with_block_size(reference: &[u8], block_size: usize) -> Signature {
+        assert!(block_size > 0, "The block size must be at least 1");
+        span!("signature::signature", reference.len());
+        Signature {
+            block_size,
+            source_len: reference.len(),
+            blocks: reference.chunks_exact(block_size).map(|block| BlockSignature {
+                weak: Rolling::new(block).digest(),
+                strong: sha256(block),
+            }).collect(),
+        }
+    }
+
+    /// Serialize the signature to `w`.
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(MAGIC)?;
+        w.pack(self.block_size as u64)?;
+        w.pack(self.source_len as u64)?;
+        w.pack(self.blocks.len() as u64)?;
+        for block in &self.blocks {
+            w.pack(block.weak)?;
+            w.write_all(&block.strong)?;
+        }
+        Ok(())
+    }
+
+    /// Deserialize a signature from `r`.
+    pub fn read_from<R: Read>(r: &mut R) -> io::Result<Signature> {
+        let mut magic = [0u8; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Not a bcmp signature"));
+        }
+        let block_size = to_usize(r.unpack::<u64>()?)?;
+        let source_len = to_usize(r.unpack::<u64>()?)?;
+        let count = to_usize(r.unpack::<u64>()?)?;
+        if block_size == 0 || count.checked_mul(block_size).is_none_or(|covered| covered > source_len) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Inconsistent signature blocks"));
+        }
+        let mut blocks = Vec::with_capacity(count.min(MAX_PREALLOCATED_BLOCKS));
+        for _ in 0..count {
+            let weak = r.unpack::<u32>()?;
+            let mut strong = [0u8; 32];
+            r.read_exact(&mut strong)?;
+            blocks.push(BlockSignature { weak, strong });
+        }
+        Ok(Signature {
+            block_size,
+            source_len,
+            blocks,
+        })
+    }
+}
+
+/// Compute the signature of `reference` with a block size proportional to the square root of its
+/// length, between 64 bytes and 64 KiB, which balances the size of the signature with the
+/// granularity of the delta.
+pub fn signature(reference: &[u8]) -> Signature {
+    let mut block_size = MIN_BLOCK_SIZE;
+    while block_size < MAX_BLOCK_SIZE && block_size * block_size < reference.len() {
+        block_size *= 2;
+    }
+    Signature::with_block_size(reference, block_size)
+}
+
+/// Compute the [`Delta`](../patch/struct.Delta.html) building `new_data` from the reference
+/// described by `signature`.
+///
+/// The delta is only made of copies of whole blocks and literals. Consecutive copies are merged.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::signature::{Signature, delta_from_signature};
+///
+/// let reference = b"0123456789abcdefghijklmnopqrstuv";
+/// let new_data = b"0123456789abcdefXXghijklmnopqrstuv";
+/// let signature = Signature::with_block_size(reference, 8);
+/// let delta = delta_from_signature(&signature, new_data);
+/// assert!(delta.copied() == 32 && delta.literal() == 2);
+/// assert!(delta.apply(reference).unwrap() == &new_data[..]);
+/// ```
+pub fn delta_from_signature(signature: &Signature, new_data: &[u8]) -> Delta {
+    span!("signature::delta", new_data.len());
+    let block_size = signature.block_size;
+    let mut blocks = HashMap::<u32, Vec<usize>>::new();
+    for (i, block) in signature.blocks.iter().enumerate() {
+        blocks.entry(block.weak).or_default().push(i);
+    }
+    let mut ops = Vec::new();
+    let mut literal_start = 0;
+    let mut pos = 0;
+    let mut rolling = None;
+    while !blocks.is_empty() && pos + block_size <= new_data.len() {
+        let window = &new_data[pos..pos + block_size];
+        let weak = rolling.get_or_insert_with(|| Rolling::new(window)).digest();
+        let found = blocks.get(&weak).and_then(|candidates| {
+            let strong = sha256(window);
+            candidates.iter().find(|&&i| signature.blocks[i].strong == strong)
+        });
+        if let Some(&i) = found {
+            if literal_start < pos {
+                ops.push(Op::Literal(new_data[literal_start..pos].to_vec()));
+            }
+            let offset = i * block_size;
+            match ops.last_mut() {
+                Some(&mut Op::Copy { offset: previous, ref mut length }) if literal_start == pos && previous + *length == offset => {
+                    *length += block_size;
+                },
+                _ => ops.push(Op::Copy { offset, length: block_size })
+            }
+            pos += block_size;
+            literal_start = pos;
+            rolling = None;
+        }
+        else {
+            if pos + block_size < new_data.len() {
+                if let Some(r) = rolling.as_mut() {
+                    r.roll(new_data[pos], new_data[pos + block_size]);
+                }
+            }
+            pos += 1;
+        }
+    }
+    if literal_start < new_data.len() {
+        ops.push(Op::Literal(new_data[literal_start..].to_vec()));
+    }
+    Delta {
+        source_len: signature.source_len,
+        target_len: new_data.len(),
+        ops,
+    }
+}
@@ -0,0 +1,414 @@
+//! Abstraction over the storage of the data to compare.
+//!
+//! The [`ByteSource`](trait.ByteSource.html) trait only requires random access to the bytes and
+//! the total length. The iterators of the matching algorithms,
+//! [`TreeMatchIterator`](../treematch/struct.TreeMatchIterator.html),
+//! [`HashMatchIterator`](../hashmatch/struct.HashMatchIterator.html) and
+//! [`SuffixArrayMatchIterator`](../suffixarray/struct.SuffixArrayMatchIterator.html), accept any
+//! `ByteSource` and can thus work directly on memory maps, segmented buffers or any custom storage
+//! without first copying the data in a contiguous slice. [`source_matches`](fn.source_matches.html)
+//! and [`source_patch_set`](fn.source_patch_set.html) select the algorithm from an
+//! [`AlgoSpec`](../enum.AlgoSpec.html), like [`MatchIterator`](../struct.MatchIterator.html) and
+//! [`patch_set`](../fn.patch_set.html) do for byte slices. The other functions of the crate work
+//! on byte slices.
+//!
+//! The [`SuffixTree`](../suffixtree/struct.SuffixTree.html) works on a larger alphabet described
+//! by [`SymbolSource`](trait.SymbolSource.html): every byte source is a symbol source, and a
+//! [`Separated`](struct.Separated.html) source adds a unique end marker after each of several
+//! pieces of data, which builds a generalized suffix tree over all of them.
+//!
+//! A [`Translated`](struct.Translated.html) source maps every byte of another one through a
+//! table, which makes the algorithms match classes of equivalent bytes without a translated copy
+//! of the data.
+
+#[cfg(feature = "mmap")]
+use memmap2::Mmap;
+
+use {AlgoSpec, Match, Region, patches_from, source_backend};
+use scatter::Scattered;
+
+/// A random access source of bytes.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::scatter::Scattered;
+/// use bcmp::source::ByteSource;
+/// use bcmp::treematch::TreeMatchIterator;
+///
+/// let segments: [&[u8]; 3] = [b"abc", b"def", b"ghi"];
+/// let scattered = Scattered::new(&segments);
+/// assert!(scattered.len() == 9 && scattered.byte_at(4) == b'e');
+/// let matches: Vec<_> = TreeMatchIterator::new(&scattered, &b"__cdefg__"[..], 4).collect();
+/// assert!(matches.len() == 1 && matches[0].length == 5);
+/// ```
+pub trait ByteSource {
+    /// Return the number of bytes of the source.
+    fn len(&self) -> usize;
+
+    /// Return true if the source does not contain any byte.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the byte at `pos`. It will panic if `pos` is out of bounds.
+    fn byte_at(&self, pos: usize) -> u8;
+
+    /// Return all the bytes as a slice if the source stores them contiguously, which lets the 
+    /// algorithms compare whole ranges at once. The default implementation returns `None`.
+    fn as_slice(&self) -> Option<&[u8]> {
+        None
+    }
+}
+
+/// The symbol of the end of data of a [`SuffixTree`](../suffixtree/struct.SuffixTree.html).
+pub const END_SYMBOL: usize = 256;
+
+/// The largest number of pieces of data of a [`Separated`](struct.Separated.html) source: their end
+/// markers are the symbols after [`END_SYMBOL`](constant.END_SYMBOL.html) up to `u16::MAX`.
+pub const MAX_PIECES: usize = u16::MAX as usize - END_SYMBOL;
+
+/// A random access source of symbols, the alphabet of a
+/// [`SuffixTree`](../suffixtree/struct.SuffixTree.html): the 256 byte values followed by
+/// [`END_SYMBOL`](constant.END_SYMBOL.html) and the end markers of the pieces of a
+/// [`Separated`](struct.Separated.html) source.
+///
+/// Every [`ByteSource`](trait.ByteSource.html) is a `SymbolSource` whose symbols are its bytes.
+pub trait SymbolSource {
+    /// Return the number of symbols of the source.
+    fn symbol_count(&self) -> usize;
+
+    /// Return the symbol at `pos`. It will panic if `pos` is out of bounds.
+    fn symbol_at(&self, pos: usize) -> usize;
+}
+
+impl<S: ByteSource + ?Sized> SymbolSource for S {
+    #[inline]
+    fn symbol_count(&self) -> usize {
+        self.len()
+    }
+
+    #[inline]
+    fn symbol_at(&self, pos: usize) -> usize {
+        self.byte_at(pos) as usize
+    }
+}
+
+/// Several pieces of data, each followed by its own end marker, as a single
+/// [`SymbolSource`](trait.SymbolSource.html).
+///
+/// A [`SuffixTree`](../suffixtree/struct.SuffixTree.html) built from it is a generalized suffix
+/// tree: the end markers are unique, so no substring found in the tree spans two pieces. Positions
+/// are the ones of the concatenation of the pieces and their markers, which
+/// [`locate`](#method.locate) maps back to a piece.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::source::Separated;
+/// use bcmp::suffixtree::SuffixTree;
+///
+/// let pieces: [&[u8]; 3] = [b"banana", b"bandana", b"cabana"];
+/// let separated = Separated::new(&pieces);
+/// let tree = SuffixTree::new(&separated);
+/// let mut found: Vec<(usize, usize)> = tree.find_all(&separated, b"ana").into_iter().map(|p| separated.locate(p)).collect();
+/// found.sort();
+/// assert!(found == [(0, 1), (0, 3), (1, 4), (2, 3)]);
+/// // "nab" only exists across the end of the first piece and the start of the second one
+/// assert!(!tree.contains(&separated, b"nab") && tree.contains(&separated, b"cab"));
+/// ```
+#[derive(Clone,Debug)]
+pub struct Separated<'a> {
+    pieces: Vec<&'a [u8]>,
+    // Position of each piece in the concatenation
+    starts: Vec<usize>,
+    len: usize,
+}
+
+impl<'a> Separated<'a> {
+    /// Build a source from `pieces`. It will panic if there are more than
+    /// [`MAX_PIECES`](constant.MAX_PIECES.html) pieces.
+    pub fn new(pieces: &[&'a [u8]]) -> Separated<'a> {
+        assert!(pieces.len() <= MAX_PIECES, "Too many pieces of data");
+        let mut starts = Vec::with_capacity(pieces.len());
+        let mut len = 0;
+        for piece in pieces {
+            starts.push(len);
+            len += piece.len() + 1;
+        }
+        Separated {
+            pieces: pieces.to_vec(),
+            starts,
+            len,
+        }
+    }
+
+    /// Return the pieces of data.
+    pub fn pieces(&self) -> &[&'a [u8]] {
+        &self.pieces
+    }
+
+    /// Return the position of piece `index` in the concatenation.
+    pub fn start(&self, index: usize) -> usize {
+        self.starts[index]
+    }
+
+    /// Return the index of the piece containing position `pos` of the concatenation and the offset
+    /// of `pos` in this piece. The offset of an end marker is the length of its piece.
+    ///
+    /// It will panic if `pos` is out of bounds.
+    pub fn locate(&self, pos: usize) -> (usize, usize) {
+        assert!(pos < self.len, "Position out of bounds");
+        let index = self.starts.partition_point(|&start| start <= pos) - 1;
+        (index, pos - self.starts[index])
+    }
+}
+
+impl SymbolSource for Separated<'_> {
+    fn symbol_count(&self) -> usize {
+        self.len
+    }
+
+    fn symbol_at(&self, pos: usize) -> usize {
+        let (index, offset) = self.locate(pos);
+        match self.pieces[index].get(offset) {
+            Some(&byte) => byte as usize,
+            None => END_SYMBOL + 1 + index
+        }
+    }
+}
+
+impl ByteSource for [u8] {
+    #[inline]
+    fn len(&self) -> usize {
+        <[u8]>::len(self)
+    }
+
+    #[inline]
+    fn byte_at(&self, pos: usize) -> u8 {
+        self[pos]
+    }
+
+    #[inline]
+    fn as_slice(&self) -> Option<&[u8]> {
+        Some(self)
+    }
+}
+
+impl ByteSource for Vec<u8> {
+    #[inline]
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    #[inline]
+    fn byte_at(&self, pos: usize) -> u8 {
+        self[pos]
+    }
+
+    #[inline]
+    fn as_slice(&self) -> Option<&[u8]> {
+        Some(self)
+    }
+}
+
+impl<T: ByteSource + ?Sized> ByteSource for &T {
+    #[inline]
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+
+    #[inline]
+    fn byte_at(&self, pos: usize) -> u8 {
+        (**self).byte_at(pos)
+    }
+
+    #[inline]
+    fn as_slice(&self) -> Option<&[u8]> {
+        (**self).as_slice()
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl ByteSource for Mmap {
+    #[inline]
+    fn len(&self) -> usize {
+        self[..].len()
+    }
+
+    #[inline]
+    fn byte_at(&self, pos: usize) -> u8 {
+        self[pos]
+    }
+
+    #[inline]
+    fn as_slice(&self) -> Option<&[u8]> {
+        Some(self)
+    }
+}
+
+impl ByteSource for Scattered<'_> {
+    fn len(&self) -> usize {
+        Scattered::len(self)
+    }
+
+    fn byte_at(&self, pos: usize) -> u8 {
+        self.get(pos)
+    }
+}
+
+// A region of another source, as a source of its own
+pub(crate) struct Part<'a, S: ByteSource + ?Sized + 'a> {
+    source: &'a S,
+    region: Region,
+}
+
+impl<'a, S: ByteSource + ?Sized> Part<'a, S> {
+    pub(crate) fn new(source: &'a S, region: Region) -> Part<'a, S> {
+        Part {
+            source,
+            region,
+        }
+    }
+}
+
+impl<S: ByteSource + ?Sized> ByteSource for Part<'_, S> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.region.len()
+    }
+
+    #[inline]
+    fn byte_at(&self, pos: usize) -> u8 {
+        assert!(pos < self.region.len(), "Position out of bounds");
+        self.source.byte_at(self.region.start + pos)
+    }
+
+    #[inline]
+    fn as_slice(&self) -> Option<&[u8]> {
+        self.source.as_slice().map(|bytes| self.region.bytes(bytes))
+    }
+}
+
+/// A [`ByteSource`](trait.ByteSource.html) whose bytes are the ones of another source mapped
+/// through a 256 entries table.
+///
+/// Bytes with the same image in the table compare as equal, for example all the digits or the
+/// upper and lower case of a letter. The translation is applied on each access, so the positions
+/// of the matches are the ones of the original data. An
+/// [`equivalence_table`](fn.equivalence_table.html) builds a table from classes of bytes.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::Match;
+/// use bcmp::source::{Translated, equivalence_table};
+/// use bcmp::treematch::TreeMatchIterator;
+///
+/// let table = equivalence_table(&[b"0123456789"]);
+/// let first = Translated::new(&b"id=1234;name=x"[..], &table);
+/// let second = Translated::new(&b"id=9876;name=y"[..], &table);
+/// let matches: Vec<Match> = TreeMatchIterator::new(&first, &second, 4).collect();
+/// assert!(matches == [Match::new(0, 0, 13)]);
+/// ```
+#[derive(Clone,Copy)]
+pub struct Translated<'a, S: ByteSource + ?Sized + 'a> {
+    source: &'a S,
+    table: &'a [u8; 256],
+}
+
+impl<'a, S: ByteSource + ?Sized> Translated<'a, S> {
+    /// Wrap `source` so that each of its bytes `b` reads as `table[b]`.
+    pub fn new(source: &'a S, table: &'a [u8; 256]) -> Translated<'a, S> {
+        Translated {
+            source,
+            table,
+        }
+    }
+}
+
+impl<S: ByteSource + ?Sized> ByteSource for Translated<'_, S> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.source.len()
+    }
+
+    #[inline]
+    fn byte_at(&self, pos: usize) -> u8 {
+        self.table[self.source.byte_at(pos) as usize]
+    }
+}
+
+/// Return a translation table for [`Translated`](struct.Translated.html) where the bytes of each
+/// class are equal to its first byte, while the bytes outside of any class are left unchanged.
+///
+/// A byte belonging to several classes is mapped by the last one.
+pub fn equivalence_table(classes: &[&[u8]]) -> [u8; 256] {
+    let mut table = [0u8; 256];
+    for (b, t) in table.iter_mut().enumerate() {
+        *t = b as u8;
+    }
+    for class in classes {
+        if let Some(&representative) = class.first() {
+            for &b in class.iter() {
+                table[b as usize] = representative;
+            }
+        }
+    }
+    table
+}
+
+/// An iterator over the [`Match`](../struct.Match.html) between two byte sources, see
+/// [`source_matches`](fn.source_matches.html).
+pub struct SourceMatchIterator<'a> {
+    iter: Box<dyn Iterator<Item=Match> + 'a>
+}
+
+impl<'a> Iterator for SourceMatchIterator<'a> {
+    type Item = Match;
+    fn next(&mut self) -> Option<Match> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// Return an iterator over the matches between two [`ByteSource`](trait.ByteSource.html), using
+/// the algorithm selected by `algo_spec`.
+///
+/// The matches are the ones [`MatchIterator::new`](../struct.MatchIterator.html#method.new)
+/// returns for the same bytes, in ascending order of
+/// [`second_pos`](../struct.Match.html#structfield.second_pos). The first source is indexed and
+/// the second one is scanned, without copying either of them.
+///
+/// It will panic if the [`AlgoSpec`](../enum.AlgoSpec.html) is not supported.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::{AlgoSpec, Match};
+/// use bcmp::scatter::Scattered;
+/// use bcmp::source::source_matches;
+///
+/// let segments: [&[u8]; 2] = [b"abcdef", b"ghijkl"];
+/// let scattered = Scattered::new(&segments);
+/// let matches: Vec<Match> = source_matches(&scattered, &b"__efghij__"[..], AlgoSpec::HashMatch(4)).collect();
+/// assert!(matches == [Match::new(4, 2, 6)]);
+/// ```
+pub fn source_matches<'a, F: ByteSource + ?Sized, S: ByteSource + ?Sized>(first: &'a F, second: &'a S, algo_spec: AlgoSpec) -> SourceMatchIterator<'a> {
+    assert!(algo_spec.is_supported(), "Unsupported AlgoSpec");
+    SourceMatchIterator {
+        iter: source_backend(first, second, algo_spec)
+    }
+}
+
+/// Return the [`patch_set`](../fn.patch_set.html) building the second
+/// [`ByteSource`](trait.ByteSource.html) from the first one.
+///
+/// It will panic if the [`AlgoSpec`](../enum.AlgoSpec.html) is not supported.
+pub fn source_patch_set<F: ByteSource + ?Sized, S: ByteSource + ?Sized>(first: &F, second: &S, algo_spec: AlgoSpec) -> Vec<Match> {
+    span!("source_patch_set", first.len() + second.len());
+    patches_from(source_matches(first, second, algo_spec))
+}
@@ -0,0 +1,261 @@
+//! Streaming comparison of an in-memory piece of data against an `io::Read` source.
+//!
+//! [`StreamMatchIterator`](struct.StreamMatchIterator.html) indexes the first piece of data like
+//! [`HashMatchIterator`](../hashmatch/struct.HashMatchIterator.html) does but reads the second
+//! one incrementally. Only the bytes which can still be part of a match are kept in memory, so the
+//! second input can be piped through without ever being fully resident.
+//!
+//! [`pipelined_matches`](fn.pipelined_matches.html) reads the second input on a separate thread
+//! while the index of the first one is built, so that slow reads and index construction overlap.
+
+use std::collections::HashMap;
+use std::io::{Cursor, Error, ErrorKind, Read, Result};
+use std::iter::{FusedIterator, Iterator};
+use std::mem::size_of;
+use std::sync::mpsc::{Receiver, sync_channel};
+use std::thread;
+
+use bytepack::LEUnpacker;
+
+use Match;
+use hashmatch::{HashMatchKey, build_map};
+
+const CHUNK_SIZE: usize = 8 * 1024;
+const PIPELINE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// An iterator over all the [`Match`](../struct.Match.html) between a byte slice and a reader.
+///
+/// Matches are returned in the same order as
+/// [`HashMatchIterator`](../hashmatch/struct.HashMatchIterator.html) would return them if the whole
+/// content of the reader was given as the second piece of data. Since a match only ends once a
+/// mismatching byte has been read, the amount of buffered data is bounded by the length of the
+/// first piece of data plus the read chunk size.
+///
+/// An I/O error is returned as an `Err` item after which the iterator is exhausted. Positions in
+/// the reader are `usize` like in any [`Match`](../struct.Match.html): on 32-bit platforms, a
+/// reader longer than 4 GiB ends the iteration with an `InvalidData` error instead of overflowing.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::stream::StreamMatchIterator;
+///
+/// let a = "abcdefg";
+/// let b = "012abc34cdef56efg78abcdefg";
+/// for m in StreamMatchIterator::<u16, _>::new(a.as_bytes(), b.as_bytes()) {
+///     let m = m.unwrap();
+///     println!("Match: {:}", &a[m.first_pos..m.first_end()]);
+/// }
+/// ```
+pub struct StreamMatchIterator<'a, T: HashMatchKey, R: Read> {
+    first: &'a [u8],
+    reader: R,
+    buffer: Vec<u8>,
+    buffer_offset: usize,
+    eof: bool,
+    i: usize,
+    j: usize,
+    map: HashMap<T,Vec<usize>>,
+    matched: HashMap<i64, usize>
+}
+
+fn index<T: HashMatchKey>(first: &[u8]) -> HashMap<T,Vec<usize>> {
+    if first.len() >= size_of::<T>() {
+        build_map(&mut Cursor::new(first))
+    }
+    else {
+        HashMap::new()
+    }
+}
+
+impl<'a, T: HashMatchKey, R: Read> StreamMatchIterator<'a, T, R> {
+    /// Allocate a new iterator over the matches between a byte slice and a reader.
+    pub fn new(first: &'a [u8], reader: R) -> StreamMatchIterator<'a, T, R> {
+        StreamMatchIterator::from_map(first, index(first), reader)
+    }
+
+    // Allocate a new iterator reusing the map returned by index for first
+    fn from_map(first: &'a [u8], map: HashMap<T,Vec<usize>>, reader: R) -> StreamMatchIterator<'a, T, R> {
+        StreamMatchIterator {
+            first,
+            reader,
+            buffer: Vec::new(),
+            buffer_offset: 0,
+            eof: false,
+            i: 0,
+            j: 0,
+            map,
+            matched: HashMap::new()
+        }
+    }
+
+    /// Return the number of bytes consumed from the reader so far.
+    pub fn bytes_read(&self) -> usize {
+        self.buffer_offset + self.buffer.len()
+    }
+
+    // Make sure the byte at absolute position pos is buffered. Return false if the reader ended 
+    // before.
+    fn fill(&mut self, pos: usize) -> Result<bool> {
+        while pos >= self.bytes_read() {
+            if self.eof {
+                return Ok(false);
+            }
+            let mut chunk = [0u8; CHUNK_SIZE];
+            match self.reader.read(&mut chunk) {
+                Ok(0) => self.eof = true,
+                Ok(n) if self.bytes_read().checked_add(n).is_none() => {
+                    self.eof = true;
+                    return Err(Error::new(ErrorKind::InvalidData, "The stream is longer than usize::MAX"));
+                },
+                Ok(n) => self.buffer.extend_from_slice(&chunk[..n]),
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => {},
+                Err(e) => {
+                    self.eof = true;
+                    return Err(e);
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    // Drop the buffered bytes before j once they represent a significant amount of memory
+    fn compact(&mut self) {
+        let consumed = self.j - self.buffer_offset;
+        if consumed >= CHUNK_SIZE {
+            self.buffer.drain(..consumed);
+            self.buffer_offset = self.j;
+            let j = self.j;
+            self.matched.retain(|_, end| *end > j);
+        }
+    }
+
+    fn next_match(&mut self) -> Result<Option<Match>> {
+        loop {
+            if !self.fill(self.j.saturating_add(size_of::<T>() - 1))? {
+                return Ok(None);
+            }
+            let v = Cursor::new(&self.buffer[self.j - self.buffer_offset..]).unpack::<T>().unwrap();
+            let count = self.map.get(&v).map_or(0, |positions| positions.len());
+            while self.i < count {
+                let first_pos = self.map[&v][self.i];
+                self.i += 1;
+                // Check if this is a not part of a match already returned
+                let delta = first_pos as i64 - self.j as i64;
+                if self.matched.get(&delta).is_none_or(|end| *end < self.j) {
+                    // Compute match length, reading more data if needed
+                    let mut idx = 0;
+                    while first_pos + idx < self.first.len() && self.fill(self.j + idx)? &&
+                          self.first[first_pos + idx] == self.buffer[self.j + idx - self.buffer_offset] {
+                        idx += 1;
+                    }
+                    self.matched.insert(delta, self.j + idx);
+                    return Ok(Some(Match::new(first_pos, self.j, idx)));
+                }
+            }
+            self.j += 1;
+            self.i = 0;
+            self.compact();
+        }
+    }
+}
+
+impl<'a, T: HashMatchKey, R: Read> Iterator for StreamMatchIterator<'a, T, R> {
+    type Item = Result<Match>;
+    fn next(&mut self) -> Option<Result<Match>> {
+        match self.next_match() {
+            Ok(Some(m)) => Some(Ok(m)),
+            Ok(None) => None,
+            Err(e) => {
+                // Exhaust the iterator after an error
+                self.map.clear();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl<'a, T: HashMatchKey, R: Read> FusedIterator for StreamMatchIterator<'a, T, R> {}
+
+// The reading end of the pipeline, returning the chunks read by the reader thread
+struct Pipe {
+    chunks: Receiver<Result<Vec<u8>>>,
+    chunk: Vec<u8>,
+    pos: usize,
+}
+
+impl Read for Pipe {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        while self.pos == self.chunk.len() {
+            match self.chunks.recv() {
+                Ok(chunk) => {
+                    self.chunk = chunk?;
+                    self.pos = 0;
+                },
+                // The reader thread is done
+                Err(_) => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.chunk.len() - self.pos);
+        buf[..n].copy_from_slice(&self.chunk[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Call `on_match` with every [`Match`](../struct.Match.html) between a byte slice and a reader,
+/// in the order [`StreamMatchIterator`](struct.StreamMatchIterator.html) returns them, reading on
+/// a separate thread.
+///
+/// The reader thread starts reading while the index of `first` is built and then stays ahead of
+/// the scan, buffering at most about `read_ahead` bytes. When reading is slow, like from the
+/// network or a cold disk, the wall clock time thus approaches the largest of the read time and the
+/// index construction plus scan time instead of their sum. The first I/O error stops the scan and
+/// is returned.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::Match;
+/// use bcmp::stream::pipelined_matches;
+///
+/// let a = b"abcdefg";
+/// let b = b"012abc34cdef56efg78abcdefg";
+/// let mut matches = Vec::new();
+/// pipelined_matches::<u16, _, _>(a, &b[..], 1 << 20, |m| matches.push(m)).unwrap();
+/// assert!(matches[0] == Match::new(0, 3, 3));
+/// ```
+pub fn pipelined_matches<T, R, F>(first: &[u8], mut reader: R, read_ahead: usize, mut on_match: F) -> Result<()>
+    where T: HashMatchKey, R: Read + Send, F: FnMut(Match) {
+    let (sender, chunks) = sync_channel(read_ahead / PIPELINE_CHUNK_SIZE + 1);
+    thread::scope(|scope| {
+        scope.spawn(move || loop {
+            let mut chunk = vec![0u8; PIPELINE_CHUNK_SIZE];
+            let sent = match reader.read(&mut chunk) {
+                Ok(0) => return,
+                Ok(n) => {
+                    chunk.truncate(n);
+                    sender.send(Ok(chunk))
+                },
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    let _ = sender.send(Err(e));
+                    return;
+                }
+            };
+            // The scan stopped
+            if sent.is_err() {
+                return;
+            }
+        });
+        let pipe = Pipe {
+            chunks,
+            chunk: Vec::new(),
+            pos: 0,
+        };
+        for m in StreamMatchIterator::<T, _>::from_map(first, index(first), pipe) {
+            on_match(m?);
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,492 @@
+//! Suffix arrays, built in memory or in external memory, stored in an index file.
+//!
+//! A [`SuffixTree`](../suffixtree/struct.SuffixTree.html) needs around a hundred bytes per input
+//! byte, which rules it out for references larger than a fraction of the available memory. A
+//! suffix array only needs 8 bytes per input byte and can be built by
+//! [`build_external`](fn.build_external.html) using a bounded amount of memory: sorted runs of
+//! suffixes are written to temporary files next to the index and then merged. The resulting index
+//! file can be loaded with [`SuffixArray::open`](struct.SuffixArray.html#method.open) or, with the
+//! `mmap` feature, memory-mapped with [`SuffixArray::map`](struct.SuffixArray.html#method.map).
+//!
+//! The same index backs [`AlgoSpec::SuffixArrayMatch`](../enum.AlgoSpec.html), the memory-reduced 
+//! alternative to [`TreeMatch`](../treematch/index.html) for large references. Its matches are 
+//! enumerated by [`SuffixArrayMatchIterator`](struct.SuffixArrayMatchIterator.html).
+//!
+//! # Index format
+//!
+//! The index file starts with the 8 bytes magic `BCMPSA\0\x01` followed by the length of the
+//! indexed data and by the suffix array itself, all encoded as little endian `u64`.
+
+use std::borrow::Cow;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::fs::{File, read, remove_file};
+use std::io::{BufReader, BufWriter, Error, ErrorKind, Read, Result, Write};
+use std::iter::{FusedIterator, Iterator};
+use std::mem::{size_of, swap};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "mmap")]
+use memmap2::Mmap;
+
+use {Match, to_usize};
+use source::ByteSource;
+
+const MAGIC: &[u8; 8] = b"BCMPSA\x00\x01";
+const HEADER_LEN: usize = 16;
+
+/// Compute the suffix array of `data` in memory by prefix doubling.
+pub(crate) fn build_suffix_array<D: ByteSource + ?Sized>(data: &D) -> Vec<usize> {
+    span!("suffixarray::build", data.len());
+    let n = data.len();
+    let mut sa: Vec<usize> = (0..n).collect();
+    let mut rank: Vec<usize> = (0..n).map(|i| data.byte_at(i) as usize).collect();
+    let mut tmp = vec![0; n];
+    let mut k = 1;
+    if n == 0 {
+        return sa;
+    }
+    loop {
+        // Sort by the rank of the first k bytes then by the rank of the next k bytes, the end of 
+        // the data sorting first
+        let key = |i: usize| (rank[i], if i + k < n { rank[i + k] + 1 } else { 0 });
+        sa.sort_unstable_by_key(|&i| key(i));
+        tmp[sa[0]] = 0;
+        for w in 1..n {
+            tmp[sa[w]] = tmp[sa[w - 1]] + (key(sa[w - 1]) < key(sa[w])) as usize;
+        }
+        swap(&mut rank, &mut tmp);
+        if rank[sa[n - 1]] == n - 1 {
+            break;
+        }
+        k *= 2;
+    }
+    sa
+}
+
+// Compare the suffix of data at p, truncated to length bytes, with the length bytes of pattern at j
+fn compare_prefix<D: ByteSource + ?Sized, P: ByteSource + ?Sized>(data: &D, p: usize, pattern: &P, j: usize, length: usize) -> Ordering {
+    if let (Some(data), Some(pattern)) = (data.as_slice(), pattern.as_slice()) {
+        return data[p..data.len().min(p + length)].cmp(&pattern[j..j + length]);
+    }
+    for k in 0..length {
+        // A suffix shorter than the pattern sorts first
+        if p + k == data.len() {
+            return Ordering::Less;
+        }
+        match data.byte_at(p + k).cmp(&pattern.byte_at(j + k)) {
+            Ordering::Equal => (),
+            other => return other
+        }
+    }
+    Ordering::Equal
+}
+
+// Length of the common prefix of the suffixes of first at p and of second at j
+fn common_length<F: ByteSource + ?Sized, S: ByteSource + ?Sized>(first: &F, p: usize, second: &S, j: usize) -> usize {
+    if let (Some(first), Some(second)) = (first.as_slice(), second.as_slice()) {
+        return first[p..].iter().zip(second[j..].iter()).take_while(|&(a, b)| a == b).count();
+    }
+    let mut length = 0;
+    while p + length < first.len() && j + length < second.len() && first.byte_at(p + length) == second.byte_at(j + length) {
+        length += 1;
+    }
+    length
+}
+
+fn write_header<W: Write>(w: &mut W, len: usize) -> Result<()> {
+    w.write_all(MAGIC)?;
+    w.write_all(&(len as u64).to_le_bytes())
+}
+
+/// Read the little endian `u64` at `offset` in `bytes`.
+pub(crate) fn u64_at(bytes: &[u8], offset: usize) -> u64 {
+    let mut v = [0u8; 8];
+    v.copy_from_slice(&bytes[offset..offset + 8]);
+    u64::from_le_bytes(v)
+}
+
+fn read_u64<R: Read>(r: &mut R) -> Result<Option<u64>> {
+    let mut buf = [0u8; 8];
+    match r.read_exact(&mut buf) {
+        Ok(()) => Ok(Some(u64::from_le_bytes(buf))),
+        Err(ref e) if e.kind() == ErrorKind::UnexpectedEof => Ok(None),
+        Err(e) => Err(e)
+    }
+}
+
+fn run_path(path: &Path, run: usize) -> PathBuf {
+    let mut name = path.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    name.push(format!(".run{}", run));
+    path.with_file_name(name)
+}
+
+// The temporary run files of build_external, removed when dropped whatever the outcome
+struct Runs(Vec<PathBuf>);
+
+impl Drop for Runs {
+    fn drop(&mut self) {
+        for run in &self.0 {
+            let _ = remove_file(run);
+        }
+    }
+}
+
+/// Build the suffix array index of `data` into the file at `path` using roughly at most `memory`
+/// bytes of memory for the suffix positions.
+///
+/// `data` is only accessed randomly and can thus be a memory map of a file larger than the
+/// available memory. The suffixes are sorted by blocks of `memory / 8` positions, each block being
+/// written to a temporary run file next to `path`, before all runs are merged into the index file.
+/// Suffixes are compared byte by byte which makes this construction slow on highly repetitive
+/// data. The run files are removed even if the construction fails.
+pub fn build_external(data: &[u8], path: &Path, memory: usize) -> Result<()> {
+    span!("suffixarray::build_external", data.len());
+    let block = (memory / size_of::<u64>()).max(1);
+    let mut runs = Runs(Vec::new());
+    let mut start = 0;
+    while start < data.len() {
+        let end = data.len().min(start + block);
+        let mut positions: Vec<usize> = (start..end).collect();
+        positions.sort_unstable_by(|&a, &b| data[a..].cmp(&data[b..]));
+        let run = run_path(path, runs.0.len());
+        // Registered before its creation so that a partially written run is removed too
+        runs.0.push(run.clone());
+        let mut w = BufWriter::new(File::create(&run)?);
+        for p in positions {
+            w.write_all(&(p as u64).to_le_bytes())?;
+        }
+        w.flush()?;
+        start = end;
+    }
+    merge_runs(data, path, &runs.0)
+}
+
+fn merge_runs(data: &[u8], path: &Path, runs: &[PathBuf]) -> Result<()> {
+    let mut readers = Vec::with_capacity(runs.len());
+    for run in runs {
+        readers.push(BufReader::new(File::open(run)?));
+    }
+    let mut heap = BinaryHeap::new();
+    for (i, r) in readers.iter_mut().enumerate() {
+        if let Some(p) = read_u64(r)? {
+            heap.push(Reverse((&data[p as usize..], p, i)));
+        }
+    }
+    let mut w = BufWriter::new(File::create(path)?);
+    write_header(&mut w, data.len())?;
+    while let Some(Reverse((_, p, i))) = heap.pop() {
+        w.write_all(&p.to_le_bytes())?;
+        if let Some(p) = read_u64(&mut readers[i])? {
+            heap.push(Reverse((&data[p as usize..], p, i)));
+        }
+    }
+    w.flush()
+}
+
+/// A suffix array index stored in its serialized form.
+///
+/// The storage can be any byte container: a `Vec<u8>` for an index built in memory or loaded
+/// from a file, or a memory map. Like the [`SuffixTree`](../suffixtree/struct.SuffixTree.html),
+/// the index does not hold the indexed data which has to be given back to every query.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::Match;
+/// use bcmp::suffixarray::SuffixArray;
+///
+/// let reference = b"abcdefghijabcdxyz";
+/// let sa = SuffixArray::new(reference);
+/// assert!(sa.find(reference, b"abcd") == (0..2));
+/// let matches = sa.matches(reference, b"__cdefgh__xyz", 3);
+/// assert!(matches == [Match::new(2, 2, 6), Match::new(14, 10, 3)]);
+/// ```
+#[derive(Clone)]
+pub struct SuffixArray<S: AsRef<[u8]>> {
+    storage: S,
+}
+
+impl SuffixArray<Vec<u8>> {
+    /// Build the suffix array of `data` in memory.
+    pub fn new(data: &[u8]) -> SuffixArray<Vec<u8>> {
+        SuffixArray::from_source(data)
+    }
+
+    /// Build the suffix array of any [`ByteSource`](../source/trait.ByteSource.html) in memory.
+    pub fn from_source<D: ByteSource + ?Sized>(data: &D) -> SuffixArray<Vec<u8>> {
+        let mut storage = Vec::with_capacity(HEADER_LEN + data.len() * size_of::<u64>());
+        write_header(&mut storage, data.len()).unwrap();
+        for p in build_suffix_array(data) {
+            storage.extend_from_slice(&(p as u64).to_le_bytes());
+        }
+        SuffixArray {
+            storage
+        }
+    }
+
+    /// Load an index file in memory.
+    pub fn open(path: &Path) -> Result<SuffixArray<Vec<u8>>> {
+        SuffixArray::from_bytes(read(path)?)
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl SuffixArray<Mmap> {
+    /// Memory-map an index file.
+    ///
+    /// The file must not be modified while it is mapped.
+    pub fn map(path: &Path) -> Result<SuffixArray<Mmap>> {
+        let file = File::open(path)?;
+        // Safety: the mapping is read only and the caller guarantees the file is not modified
+        let mmap = unsafe { Mmap::map(&file)? };
+        SuffixArray::from_bytes(mmap)
+    }
+}
+
+impl<S: AsRef<[u8]>> SuffixArray<S> {
+    /// Wrap a serialized index, checking its header, its size and that every suffix starts within 
+    /// the indexed data.
+    pub fn from_bytes(storage: S) -> Result<SuffixArray<S>> {
+        let bytes = storage.as_ref();
+        if bytes.len() < HEADER_LEN || &bytes[..8] != MAGIC {
+            return Err(Error::new(ErrorKind::InvalidData, "Not a bcmp suffix array index"));
+        }
+        let len = to_usize(u64_at(bytes, 8))?;
+        let expected = len.checked_mul(size_of::<u64>()).and_then(|size| size.checked_add(HEADER_LEN));
+        if expected != Some(bytes.len()) {
+            return Err(Error::new(ErrorKind::InvalidData, "Truncated bcmp suffix array index"));
+        }
+        if bytes[HEADER_LEN..].chunks_exact(size_of::<u64>()).any(|p| u64_at(p, 0) >= len as u64) {
+            return Err(Error::new(ErrorKind::InvalidData, "Corrupted bcmp suffix array index"));
+        }
+        Ok(SuffixArray {
+            storage
+        })
+    }
+
+    /// Return the serialized form of the index, as stored in an index file.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.storage.as_ref()
+    }
+
+    /// Write the index to a file.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        File::create(path)?.write_all(self.as_bytes())
+    }
+
+    /// Return the number of suffixes in the index, which is also the length of the indexed data.
+    pub fn len(&self) -> usize {
+        (self.storage.as_ref().len() - HEADER_LEN) / size_of::<u64>()
+    }
+
+    /// Return true if the indexed data is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the start of the `i`-th suffix in lexicographic order.
+    pub fn get(&self, i: usize) -> usize {
+        u64_at(self.storage.as_ref(), HEADER_LEN + i * size_of::<u64>()) as usize
+    }
+
+    // First row whose suffix truncated to the pattern length is not less (or greater if upper) 
+    // than the length bytes of pattern at j
+    fn bound<D: ByteSource + ?Sized, P: ByteSource + ?Sized>(&self, data: &D, pattern: &P, j: usize, length: usize, upper: bool) -> usize {
+        let mut lo = 0;
+        let mut hi = self.len();
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            let order = compare_prefix(data, self.get(mid), pattern, j, length);
+            if order == Ordering::Less || (upper && order == Ordering::Equal) {
+                lo = mid + 1;
+            }
+            else {
+                hi = mid;
+            }
+        }
+        lo
+    }
+
+    /// Return the range of rows whose suffix starts with `pattern`.
+    ///
+    /// It will panic if `data` is not the indexed data.
+    pub fn find(&self, data: &[u8], pattern: &[u8]) -> Range<usize> {
+        self.find_at(data, pattern, 0, pattern.len())
+    }
+
+    // The range of rows whose suffix starts with the length bytes of pattern at j
+    fn find_at<D: ByteSource + ?Sized, P: ByteSource + ?Sized>(&self, data: &D, pattern: &P, j: usize, length: usize) -> Range<usize> {
+        assert!(data.len() == self.len(), "The data does not match the index");
+        self.bound(data, pattern, j, length, false)..self.bound(data, pattern, j, length, true)
+    }
+
+    /// Return all the [`Match`](../struct.Match.html) of at least `minimal_length` bytes between
+    /// the indexed data and `second`.
+    ///
+    /// The result is the same set of matches a [`TreeMatchIterator`](../treematch/struct.TreeMatchIterator.html)
+    /// returns, sorted in their natural order.
+    pub fn matches(&self, data: &[u8], second: &[u8], minimal_length: usize) -> Vec<Match> {
+        assert!(minimal_length > 0, "The minimal length must be at least 1");
+        let mut matches = Vec::new();
+        if second.len() < minimal_length {
+            return matches;
+        }
+        for j in 0..second.len() - minimal_length + 1 {
+            let start = matches.len();
+            self.matches_at(data, second, j, minimal_length, &mut matches);
+            matches[start..].sort();
+        }
+        matches
+    }
+
+    // Push the left maximal matches starting at position j of second
+    fn matches_at<D: ByteSource + ?Sized, P: ByteSource + ?Sized>(&self, data: &D, second: &P, j: usize, minimal_length: usize, matches: &mut Vec<Match>) {
+        self.scan_at(data, second, j, minimal_length, |p, j, length| matches.push(Match::new(p, j, length)));
+    }
+
+    // Call f(first_pos, second_pos, length) for the left maximal matches starting at position j of 
+    // second
+    fn scan_at<D: ByteSource + ?Sized, P: ByteSource + ?Sized, F: FnMut(usize, usize, usize)>(&self, data: &D, second: &P, j: usize, minimal_length: usize, mut f: F) {
+        for row in self.find_at(data, second, j, minimal_length) {
+            let p = self.get(row);
+            // Only report left maximal matches
+            if p > 0 && j > 0 && data.byte_at(p - 1) == second.byte_at(j - 1) {
+                continue;
+            }
+            f(p, j, common_length(data, p, second, j));
+        }
+    }
+
+    // Call f(first_pos, second_pos, length) for every match of at least minimal_length bytes 
+    // between the indexed data and second, in ascending order of second_pos, without building them
+    pub(crate) fn scan<F: FnMut(usize, usize, usize)>(&self, data: &[u8], second: &[u8], minimal_length: usize, mut f: F) {
+        assert!(minimal_length > 0, "The minimal length must be at least 1");
+        for j in 0..(second.len() + 1).saturating_sub(minimal_length) {
+            self.scan_at(data, second, j, minimal_length, &mut f);
+        }
+    }
+}
+
+/// An iterator over all the [`Match`](../struct.Match.html) between two pieces of data, using the 
+/// suffix array of the first one.
+///
+/// It returns the same matches as a 
+/// [`TreeMatchIterator`](../treematch/struct.TreeMatchIterator.html), in ascending order of 
+/// their position in the second piece of data and then of their position in the first one. The 
+/// suffix array takes 8 bytes per byte of the first piece of data, an order of magnitude less 
+/// than a suffix tree, at the cost of a binary search per position of the second piece of data. 
+/// The pieces of data can be byte slices or any other 
+/// [`ByteSource`](../source/trait.ByteSource.html).
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::suffixarray::SuffixArrayMatchIterator;
+///
+/// let a = "abcdefg";
+/// let b = "012abc34cdef56efg78abcdefg";
+/// for m in SuffixArrayMatchIterator::new(a.as_bytes(), b.as_bytes(), 2) {
+///     println!("Match: {:}", &a[m.first_pos..m.first_end()]);
+/// }
+/// ```
+pub struct SuffixArrayMatchIterator<'a, F: ByteSource + ?Sized + 'a = [u8], S: ByteSource + ?Sized + 'a = [u8]> {
+    first: &'a F,
+    second: &'a S,
+    array: Cow<'a, SuffixArray<Vec<u8>>>,
+    minimal_length: usize,
+    j: usize,
+    // Matches at the previous position of second, in reverse order
+    pending: Vec<Match>,
+    skip_ahead: bool,
+}
+
+impl<'a, F: ByteSource + ?Sized, S: ByteSource + ?Sized> SuffixArrayMatchIterator<'a, F, S> {
+    /// Allocate a new iterator over the matches between two pieces of data with a minimal matching 
+    /// length.
+    ///
+    /// It will panic if the minimal matching length is zero.
+    pub fn new(first: &'a F, second: &'a S, minimal_length: usize) -> SuffixArrayMatchIterator<'a, F, S> {
+        SuffixArrayMatchIterator::with_array(first, Cow::Owned(SuffixArray::from_source(first)), second, minimal_length)
+    }
+
+    /// Allocate a new iterator reusing `array`, which must have been built from `first`.
+    ///
+    /// It will panic if the minimal matching length is zero.
+    pub fn with_array(first: &'a F, array: Cow<'a, SuffixArray<Vec<u8>>>, second: &'a S, minimal_length: usize) -> SuffixArrayMatchIterator<'a, F, S> {
+        assert!(minimal_length > 0, "The minimal length must be at least 1");
+        SuffixArrayMatchIterator {
+            first,
+            second,
+            array,
+            minimal_length,
+            j: 0,
+            pending: Vec::new(),
+            skip_ahead: false,
+        }
+    }
+
+    /// Make the iterator skip ahead: once the matches at a position of the second piece of data 
+    /// are returned, the scan resumes at the end of the longest of them instead of the next 
+    /// position, as if [`seek`](#method.seek) was called there.
+    ///
+    /// On mostly identical data this avoids a binary search at every position of a long match. 
+    /// The cost is that the matches starting inside a returned match, including the ones 
+    /// overlapping its end, are never returned.
+    pub fn skip_ahead(mut self) -> SuffixArrayMatchIterator<'a, F, S> {
+        self.skip_ahead = true;
+        self
+    }
+
+    /// Move the iterator to `second_pos`: it then returns the matches at or after that position in 
+    /// the second piece of data, exactly as a complete iteration would. The position can be before 
+    /// or after the current one.
+    pub fn seek(&mut self, second_pos: usize) {
+        self.j = second_pos;
+        self.pending.clear();
+    }
+}
+
+impl<'a, F: ByteSource + ?Sized, S: ByteSource + ?Sized> Clone for SuffixArrayMatchIterator<'a, F, S> {
+    fn clone(&self) -> SuffixArrayMatchIterator<'a, F, S> {
+        SuffixArrayMatchIterator {
+            first: self.first,
+            second: self.second,
+            array: self.array.clone(),
+            minimal_length: self.minimal_length,
+            j: self.j,
+            pending: self.pending.clone(),
+            skip_ahead: self.skip_ahead,
+        }
+    }
+}
+
+impl<'a, F: ByteSource + ?Sized, S: ByteSource + ?Sized> Iterator for SuffixArrayMatchIterator<'a, F, S> {
+    type Item = Match;
+    fn next(&mut self) -> Option<Match> {
+        loop {
+            if let Some(m) = self.pending.pop() {
+                return Some(m);
+            }
+            if self.j + self.minimal_length > self.second.len() {
+                return None;
+            }
+            self.array.matches_at(self.first, self.second, self.j, self.minimal_length, &mut self.pending);
+            self.pending.sort_by(|a, b| b.cmp(a));
+            self.j += 1;
+            if self.skip_ahead {
+                self.j = self.pending.iter().map(|m| m.second_end()).fold(self.j, usize::max);
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Positions of second where a match can still start
+        let remaining = (self.second.len() + 1).saturating_sub(self.j + self.minimal_length);
+        let upper = remaining.checked_mul(self.first.len()).and_then(|u| u.checked_add(self.pending.len()));
+        (self.pending.len(), upper)
+    }
+}
+
+impl<'a, F: ByteSource + ?Sized, S: ByteSource + ?Sized> FusedIterator for SuffixArrayMatchIterator<'a, F, S> {}
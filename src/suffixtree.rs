@@ -0,0 +1,1198 @@
+//! A suffix tree built in linear time using Ukkonen's algorithm.
+//!
+//! [`SuffixTree`](struct.SuffixTree.html) is the index behind [`TreeMatch`](../treematch/index.html) 
+//! but it is usable on its own: it can grow online, answer substring queries, be traversed in 
+//! lexicographic order, export its suffix and LCP arrays, compute matching statistics and be saved 
+//! to disk. The tree does not own the data it indexes, which is passed back to the methods needing 
+//! it and can be any [`ByteSource`](../source/trait.ByteSource.html).
+//!
+//! The alphabet of the tree is made of the 256 byte values, the end of data and the end markers of
+//! a [`Separated`](../source/struct.Separated.html) source, see
+//! [`SymbolSource`](../source/trait.SymbolSource.html). Building a tree from several pieces of data
+//! separated by unique markers gives a generalized suffix tree, where every substring belongs to a
+//! single piece.
+//!
+//! # Tree format
+//!
+//! [`SuffixTree::save`](struct.SuffixTree.html#method.save) writes the 8 bytes magic 
+//! `BCMPST\0\x02` followed by the width of the positions and node indices, the length of the 
+//! data, the number of nodes, the state of the construction needed by 
+//! [`SuffixTree::append`](struct.SuffixTree.html#method.append) and the nodes themselves. Each node 
+//! is its edge start and end, its suffix link, its number of children and its `(key, index)` 
+//! children pairs. The positions and node indices take 4 bytes if they all fit in a `u32` and 8 
+//! otherwise, whatever the [`NodeIndex`](trait.NodeIndex.html) of the tree, and a missing suffix 
+//! link has all its bits set. All integers are little endian.
+
+use std::convert::TryFrom;
+use std::fmt::Debug;
+use std::io::{self, Read, Write};
+use std::iter::{FusedIterator, Iterator};
+use std::slice::Iter;
+
+use bytepack::{LEPacker, LEUnpacker};
+
+use source::{ByteSource, END_SYMBOL, SymbolSource};
+
+/// The integer type a [`SuffixTree`](struct.SuffixTree.html) stores the positions in the data and 
+/// the indices of its nodes with.
+///
+/// `usize` handles data of any length. `u32` makes the nodes about a third smaller, which matters 
+/// as much for the speed of the construction as for the memory, but limits the data to 
+/// [`MAX_LEN`](constant.MAX_LEN.html) bytes.
+pub trait NodeIndex: Copy + Eq + Ord + Debug + Send + Sync + 'static {
+    /// The maximal length of the data a tree using this type can be built from.
+    const MAX_LEN: usize;
+
+    /// Convert a position or a node index, or return `None` if it doesn't fit.
+    fn from_usize(value: usize) -> Option<Self>;
+
+    /// Convert back to a `usize`.
+    fn to_usize(self) -> usize;
+}
+
+impl NodeIndex for u32 {
+    // A tree has at most 2 nodes per byte plus the root and the empty suffix
+    const MAX_LEN: usize = (u32::MAX / 2 - 1) as usize;
+
+    fn from_usize(value: usize) -> Option<u32> {
+        u32::try_from(value).ok()
+    }
+
+    fn to_usize(self) -> usize {
+        self as usize
+    }
+}
+
+impl NodeIndex for usize {
+    const MAX_LEN: usize = usize::MAX / 2 - 1;
+
+    fn from_usize(value: usize) -> Option<usize> {
+        Some(value)
+    }
+
+    fn to_usize(self) -> usize {
+        self
+    }
+}
+
+// Convert a position or a node index checked against the maximal length of the data
+fn index<I: NodeIndex>(value: usize) -> I {
+    I::from_usize(value).expect("The data is too large for the suffix tree index type")
+}
+
+/// A node in the [`SuffixTree`](struct.SuffixTree.html)
+#[derive(Clone)]
+pub struct Node<I: NodeIndex = usize> {
+    /// The index in the data where the edge leading to this node starts.
+    pub start: I,
+    /// The index in the data where the edge leading to this node ends.
+    pub end: I,
+    // The sub nodes under this one, sorted by the symbol leading to them: a byte value, `256` for 
+    // the end of data or a larger end marker. Nodes have few children, which makes a sorted vector much smaller than 
+    // a 257 entries array and as fast to search.
+    edges: Vec<(u16, I)>,
+    /// Suffix link (see Ukkonen's algorithm).
+    pub suffix_link: Option<I>,
+}
+
+// The state of Ukkonen's algorithm after the last byte of data, before the end sentinel step
+#[derive(Clone,Copy,Default)]
+struct ActivePoint {
+    node: usize,
+    length: usize,
+    edge: usize,
+    remaining_suffix: usize,
+}
+
+// A modification of a node existing before the end sentinel step
+#[derive(Clone,Copy)]
+enum Undo<I> {
+    Start(usize, I),
+    Edge(usize, usize, Option<usize>),
+    SuffixLink(usize, Option<I>),
+}
+
+const MAGIC: &[u8; 8] = b"BCMPST\x00\x02";
+
+/// The maximal length of the data a compact `SuffixTree<u32>` can be built from, see 
+/// [`NodeIndex`](trait.NodeIndex.html).
+pub const MAX_LEN: usize = <u32 as NodeIndex>::MAX_LEN;
+
+/// A suffix tree.
+///
+/// The nodes are stored in a single vector and reference each other, as well as the data, with 
+/// indices of type `I`. [`SuffixTree::new`](#method.new) uses `usize` indices and 
+/// [`build`](#method.build) any [`NodeIndex`](trait.NodeIndex.html): `SuffixTree<u32>` keeps a 
+/// node around 40 bytes but limits the data to [`MAX_LEN`](constant.MAX_LEN.html) bytes. 
+/// [`TreeMatch`](../treematch/index.html) uses a compact tree whenever the data allows it.
+#[derive(Clone)]
+pub struct SuffixTree<I: NodeIndex = usize> {
+    /// A vector of [`Node`](struct.Node.html) composing this tree. The first element is the root 
+    /// node.
+    pub nodes: Vec<Node<I>>,
+    len: usize,
+    active: ActivePoint,
+    // Number of nodes before the end sentinel step and how to revert its modifications
+    sentinel_nodes: usize,
+    sentinel_undo: Vec<Undo<I>>,
+}
+
+impl Node {
+    /// Allocate a new node with a leading edge `[start..end]` and `usize` indices.
+    pub fn new(start: usize, end: usize) -> Node {
+        Node::build(start, end)
+    }
+}
+
+impl<I: NodeIndex> Node<I> {
+    /// Allocate a new node with a leading edge `[start..end]` and indices of type `I`.
+    ///
+    /// It will panic if `start` or `end` doesn't fit in the index type.
+    pub fn build(start: usize, end: usize) -> Node<I> {
+        Node {
+            start: index(start),
+            end: index(end),
+            edges: Vec::new(),
+            suffix_link: None,
+        }
+    }
+
+    /// Return [`start`](#structfield.start) as a `usize`.
+    pub fn start(&self) -> usize {
+        self.start.to_usize()
+    }
+
+    /// Return [`end`](#structfield.end) as a `usize`.
+    pub fn end(&self) -> usize {
+        self.end.to_usize()
+    }
+
+    /// Return [`suffix_link`](#structfield.suffix_link) as a `usize`.
+    pub fn suffix_link(&self) -> Option<usize> {
+        self.suffix_link.map(NodeIndex::to_usize)
+    }
+
+    /// Returns this node leading edge length.
+    pub fn edge_length(&self) -> usize {
+        self.end() - self.start()
+    }
+
+    /// Return the index in [`SuffixTree::nodes`](struct.SuffixTree.html#structfield.nodes) of the 
+    /// sub node whose edge starts with `key`, a byte value, `256` for the end of data or a larger 
+    /// end marker of a [`Separated`](../source/struct.Separated.html) source.
+    pub fn child(&self, key: usize) -> Option<usize> {
+        self.edges.binary_search_by_key(&key, |&(k, _)| k as usize).ok().map(|i| self.edges[i].1.to_usize())
+    }
+
+    /// Set or remove the sub node whose edge starts with `key`, a byte value, `256` for the end of 
+    /// data or a larger end marker.
+    pub fn set_child(&mut self, key: usize, child: Option<usize>) {
+        match (self.edges.binary_search_by_key(&key, |&(k, _)| k as usize), child) {
+            (Ok(i), Some(child)) => self.edges[i].1 = index(child),
+            (Ok(i), None) => { self.edges.remove(i); },
+            (Err(i), Some(child)) => self.edges.insert(i, (key as u16, index(child))),
+            (Err(_), None) => {}
+        }
+    }
+
+    /// Iterate over the sub nodes under this one as `(key, index)` pairs sorted by key, where `key` 
+    /// is the symbol starting their edge: a byte value, `256` for the end of data or a larger end 
+    /// marker.
+    pub fn children(&self) -> Children<'_, I> {
+        Children {
+            iter: self.edges.iter()
+        }
+    }
+
+    /// Return the sub nodes in the layout of the former `edges` field: the index of the sub node 
+    /// whose edge starts with each byte value, then with the end of data. The sub nodes starting 
+    /// with the end markers of a [`Separated`](../source/struct.Separated.html) source are left out.
+    #[deprecated(since = "0.5.0", note = "use children or child instead")]
+    pub fn edges(&self) -> [Option<usize>; 257] {
+        let mut edges = [None; 257];
+        for (key, child) in self.children().take_while(|&(key, _)| key <= END_SYMBOL) {
+            edges[key] = Some(child);
+        }
+        edges
+    }
+
+    /// Return true if this node has no sub node.
+    pub fn is_leaf(&self) -> bool {
+        self.edges.is_empty()
+    }
+}
+
+/// An iterator over the sub nodes of a [`Node`](struct.Node.html), see 
+/// [`Node::children`](struct.Node.html#method.children).
+#[derive(Clone)]
+pub struct Children<'a, I: NodeIndex = usize> {
+    iter: Iter<'a, (u16, I)>
+}
+
+impl<'a, I: NodeIndex> Iterator for Children<'a, I> {
+    type Item = (usize, usize);
+    fn next(&mut self) -> Option<(usize, usize)> {
+        self.iter.next().map(|&(k, child)| (k as usize, child.to_usize()))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<(usize, usize)> {
+        self.iter.nth(n).map(|&(k, child)| (k as usize, child.to_usize()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, I: NodeIndex> DoubleEndedIterator for Children<'a, I> {
+    fn next_back(&mut self) -> Option<(usize, usize)> {
+        self.iter.next_back().map(|&(k, child)| (k as usize, child.to_usize()))
+    }
+}
+
+impl<'a, I: NodeIndex> ExactSizeIterator for Children<'a, I> {}
+
+impl<'a, I: NodeIndex> FusedIterator for Children<'a, I> {}
+
+impl SuffixTree {
+    /// Build a new suffix tree for `data` using Ukkonen's algorithm, with `usize` indices.
+    ///
+    /// `data` can be any [`ByteSource`](../source/trait.ByteSource.html), or a 
+    /// [`Separated`](../source/struct.Separated.html) source for a generalized suffix tree.
+    pub fn new<S: SymbolSource + ?Sized>(data: &S) -> SuffixTree {
+        SuffixTree::build(data)
+    }
+
+    /// Deserialize a tree saved by [`save`](#method.save) with `usize` indices, see 
+    /// [`load_indexed`](#method.load_indexed).
+    pub fn load<R: Read, S: SymbolSource + ?Sized>(r: &mut R, data: &S) -> io::Result<SuffixTree> {
+        SuffixTree::load_indexed(r, data)
+    }
+}
+
+impl<I: NodeIndex> SuffixTree<I> {
+    /// Build a new suffix tree for `data` using Ukkonen's algorithm, with indices of type `I`.
+    ///
+    /// It will panic if `data` is longer than the 
+    /// [`MAX_LEN`](trait.NodeIndex.html#associatedconstant.MAX_LEN) of `I`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bcmp::suffixtree::SuffixTree;
+    ///
+    /// let data = b"abracadabra";
+    /// let tree = SuffixTree::<u32>::build(&data[..]);
+    /// assert!(tree.to_suffix_array() == SuffixTree::new(&data[..]).to_suffix_array());
+    /// ```
+    #[allow(clippy::needless_return)]
+    pub fn build<S: SymbolSource + ?Sized>(data: &S) -> SuffixTree<I> {
+        span!("suffixtree::build", data.symbol_count());
+        let mut tree = SuffixTree {
+            nodes: vec![Node::build(0, 0)],
+            len: 0,
+            active: ActivePoint::default(),
+            sentinel_nodes: 1,
+            sentinel_undo: Vec::new(),
+        };
+        tree.extend_tree(data);
+        tree.terminate(data);
+        return tree;
+    }
+
+    /// Return the length of the data this tree was built from.
+    pub fn data_len(&self) -> usize {
+        self.len
+    }
+
+    /// Serialize the tree to `w`, see the [tree format](index.html#tree-format).
+    ///
+    /// The data itself is not saved. Writing to a `BufWriter` is recommended as the tree is 
+    /// written integer by integer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bcmp::suffixtree::SuffixTree;
+    ///
+    /// let data = b"abracadabra";
+    /// let mut saved = Vec::new();
+    /// SuffixTree::new(&data[..]).save(&mut saved).unwrap();
+    /// let tree = SuffixTree::load(&mut &saved[..], &data[..]).unwrap();
+    /// assert!(tree.count(&data[..], b"abra") == 2);
+    /// ```
+    pub fn save<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        // The node indices are below the node count and the positions at most the length
+        let width = if self.len < u32::MAX as usize && self.nodes.len() < u32::MAX as usize { 4 } else { 8 };
+        w.write_all(MAGIC)?;
+        w.pack(width)?;
+        w.pack(self.len as u64)?;
+        w.pack(self.nodes.len() as u64)?;
+        w.pack(self.active.node as u64)?;
+        w.pack(self.active.length as u64)?;
+        w.pack(self.active.edge as u64)?;
+        w.pack(self.active.remaining_suffix as u64)?;
+        w.pack(self.sentinel_nodes as u64)?;
+        w.pack(self.sentinel_undo.len() as u64)?;
+        for undo in &self.sentinel_undo {
+            match *undo {
+                Undo::Start(node, start) => {
+                    w.pack(0u8)?;
+                    put(w, width, Some(node))?;
+                    put(w, width, Some(start.to_usize()))?;
+                },
+                Undo::Edge(node, key, child) => {
+                    w.pack(1u8)?;
+                    put(w, width, Some(node))?;
+                    w.pack(key as u16)?;
+                    put(w, width, child)?;
+                },
+                Undo::SuffixLink(node, link) => {
+                    w.pack(2u8)?;
+                    put(w, width, Some(node))?;
+                    put(w, width, link.map(NodeIndex::to_usize))?;
+                }
+            }
+        }
+        for node in &self.nodes {
+            put(w, width, Some(node.start()))?;
+            put(w, width, Some(node.end()))?;
+            put(w, width, node.suffix_link())?;
+            w.pack(node.edges.len() as u16)?;
+            for &(key, child) in &node.edges {
+                w.pack(key)?;
+                put(w, width, Some(child.to_usize()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Deserialize a tree saved by [`save`](#method.save) with indices of type `I`. `data` must be 
+    /// the data the tree was built from: its length is checked, as well as the structure of the 
+    /// tree, but not its content. Every node must be reached exactly once from the root, so the 
+    /// nodes can't form a cycle, and every suffix link must lead to a node one symbol shallower. The tree can be loaded with another index type than the one it 
+    /// was saved with, as long as the data isn't longer than its 
+    /// [`MAX_LEN`](trait.NodeIndex.html#associatedconstant.MAX_LEN).
+    pub fn load_indexed<R: Read, S: SymbolSource + ?Sized>(r: &mut R, data: &S) -> io::Result<SuffixTree<I>> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+        let mut magic = [0u8; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("Not a bcmp suffix tree"));
+        }
+        let width = r.unpack::<u8>()?;
+        if width != 4 && width != 8 {
+            return Err(invalid("Invalid suffix tree index width"));
+        }
+        let len = to_usize(r.unpack::<u64>()?)?;
+        if len != data.symbol_count() {
+            return Err(invalid("The suffix tree was built from different data"));
+        }
+        if len > I::MAX_LEN {
+            return Err(invalid("The suffix tree is too large for its index type"));
+        }
+        let count = to_usize(r.unpack::<u64>()?)?;
+        // A suffix tree has at most 2 nodes per byte plus the root and the empty suffix
+        if count == 0 || count > 2 * len + 2 {
+            return Err(invalid("Invalid suffix tree node count"));
+        }
+        let active = ActivePoint {
+            node: to_usize(r.unpack::<u64>()?)?,
+            length: to_usize(r.unpack::<u64>()?)?,
+            edge: to_usize(r.unpack::<u64>()?)?,
+            remaining_suffix: to_usize(r.unpack::<u64>()?)?,
+        };
+        let sentinel_nodes = to_usize(r.unpack::<u64>()?)?;
+        if active.node >= count || active.edge > u16::MAX as usize || sentinel_nodes > count {
+            return Err(invalid("Invalid suffix tree construction state"));
+        }
+        let undo_count = to_usize(r.unpack::<u64>()?)?;
+        let mut sentinel_undo = Vec::<Undo<I>>::with_capacity(undo_count.min(count));
+        for _ in 0..undo_count {
+            let tag = r.unpack::<u8>()?;
+            let node = match get(r, width)? {
+                Some(node) if node < sentinel_nodes => node,
+                _ => return Err(invalid("Invalid suffix tree construction state"))
+            };
+            let undo = match tag {
+                0 => match get(r, width)? {
+                    Some(start) if start <= len => Undo::Start(node, index(start)),
+                    _ => return Err(invalid("Invalid suffix tree construction state"))
+                },
+                1 => {
+                    let key = r.unpack::<u16>()? as usize;
+                    let child = get(r, width)?;
+                    if child.is_some_and(|child| child >= sentinel_nodes) {
+                        return Err(invalid("Invalid suffix tree construction state"));
+                    }
+                    Undo::Edge(node, key, child)
+                },
+                2 => match get(r, width)? {
+                    Some(link) if link >= count => return Err(invalid("Invalid suffix tree construction state")),
+                    link => Undo::SuffixLink(node, link.map(index))
+                },
+                _ => return Err(invalid("Unknown suffix tree construction step"))
+            };
+            sentinel_undo.push(undo);
+        }
+        let mut nodes = Vec::with_capacity(count);
+        for _ in 0..count {
+            let (start, end) = match (get(r, width)?, get(r, width)?) {
+                (Some(start), Some(end)) if start <= end && end <= len => (start, end),
+                _ => return Err(invalid("Invalid suffix tree node"))
+            };
+            let link = get(r, width)?;
+            if link.is_some_and(|link| link >= count) {
+                return Err(invalid("Invalid suffix tree node"));
+            }
+            let children = r.unpack::<u16>()? as usize;
+            let mut edges = Vec::with_capacity(children.min(257));
+            for _ in 0..children {
+                let key = r.unpack::<u16>()?;
+                let child = match get(r, width)? {
+                    Some(child) if child < count && edges.last().is_none_or(|&(k, _)| k < key) => child,
+                    _ => return Err(invalid("Invalid suffix tree node"))
+                };
+                edges.push((key, index(child)));
+            }
+            nodes.push(Node {
+                start: index(start),
+                end: index(end),
+                edges,
+                suffix_link: link.map(index),
+            });
+        }
+        // Every node must be reached exactly once from the root, which rules out cycles, and its 
+        // suffix link must lead to the node of its path without the first symbol
+        let mut depth = vec![None; count];
+        depth[0] = Some(0);
+        let mut stack = vec![0];
+        while let Some(cur) = stack.pop() {
+            let cur_depth = depth[cur].unwrap_or(0);
+            for (_, next) in nodes[cur].children() {
+                if depth[next].is_some() {
+                    return Err(invalid("The suffix tree nodes don't form a tree"));
+                }
+                depth[next] = Some(cur_depth + nodes[next].edge_length());
+                stack.push(next);
+            }
+        }
+        let links = nodes.iter().enumerate().filter_map(|(node, n)| n.suffix_link().map(|link| (node, link)))
+            .chain(sentinel_undo.iter().filter_map(|undo| match *undo {
+                Undo::SuffixLink(node, Some(link)) => Some((node, link.to_usize())),
+                _ => None
+            }));
+        for (node, link) in links {
+            match (depth[node], depth[link]) {
+                (Some(node_depth), Some(link_depth)) if link_depth + 1 == node_depth => {},
+                _ => return Err(invalid("Invalid suffix tree suffix link"))
+            }
+        }
+        if depth.iter().any(Option::is_none) {
+            return Err(invalid("The suffix tree nodes don't form a tree"));
+        }
+        Ok(SuffixTree {
+            nodes,
+            len,
+            active,
+            sentinel_nodes,
+            sentinel_undo,
+        })
+    }
+
+    /// Extend the tree with the bytes appended to the data since it was built.
+    ///
+    /// As the tree does not own the data, `data` is the whole grown data: its first 
+    /// [`data_len`](#method.data_len) bytes must be the data the tree was built from. Ukkonen's 
+    /// algorithm being online, only the new bytes are processed: the end of data sentinel is 
+    /// removed, the construction resumes where it stopped and the sentinel is added back.
+    ///
+    /// It will panic if `data` is shorter than the data the tree was built from or longer than the 
+    /// [`MAX_LEN`](trait.NodeIndex.html#associatedconstant.MAX_LEN) of `I`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bcmp::suffixtree::SuffixTree;
+    ///
+    /// let mut log = b"GET /index.html\n".to_vec();
+    /// let mut tree = SuffixTree::new(&log[..]);
+    /// log.extend_from_slice(b"GET /about.html\n");
+    /// tree.append(&log[..]);
+    /// assert!(tree.data_len() == log.len());
+    /// ```
+    pub fn append<S: SymbolSource + ?Sized>(&mut self, data: &S) {
+        assert!(data.symbol_count() >= self.len, "The data shrank since the tree was built");
+        if data.symbol_count() == self.len {
+            return;
+        }
+        // Revert the end sentinel step
+        while let Some(undo) = self.sentinel_undo.pop() {
+            match undo {
+                Undo::Start(node, start) => self.nodes[node].start = start,
+                Undo::Edge(node, idx, edge) => self.nodes[node].set_child(idx, edge),
+                Undo::SuffixLink(node, link) => self.nodes[node].suffix_link = link,
+            }
+        }
+        self.nodes.truncate(self.sentinel_nodes);
+        // Open leaves grow with the data
+        let old_len = self.len;
+        for node in self.nodes.iter_mut().skip(1) {
+            if node.end() == old_len && node.is_leaf() {
+                node.end = index(data.symbol_count());
+            }
+        }
+        self.extend_tree(data);
+        self.terminate(data);
+    }
+
+    fn set_start(&mut self, node: usize, start: usize) {
+        if node < self.sentinel_nodes {
+            self.sentinel_undo.push(Undo::Start(node, self.nodes[node].start));
+        }
+        self.nodes[node].start = index(start);
+    }
+
+    fn set_edge(&mut self, node: usize, idx: usize, edge: usize) {
+        if node < self.sentinel_nodes {
+            self.sentinel_undo.push(Undo::Edge(node, idx, self.nodes[node].child(idx)));
+        }
+        self.nodes[node].set_child(idx, Some(edge));
+    }
+
+    fn set_suffix_link(&mut self, node: usize, link: usize) {
+        if node < self.sentinel_nodes {
+            self.sentinel_undo.push(Undo::SuffixLink(node, self.nodes[node].suffix_link));
+        }
+        self.nodes[node].suffix_link = Some(index(link));
+    }
+
+    // Process the bytes of data after self.len
+    #[allow(unused_assignments, clippy::unnecessary_unwrap, clippy::manual_unwrap_or, clippy::manual_unwrap_or_default)]
+    fn extend_tree<S: SymbolSource + ?Sized>(&mut self, data: &S) {
+        assert!(data.symbol_count() <= I::MAX_LEN, "The data is too large for the suffix tree index type");
+        let mut last_new_node: Option<usize>;
+        let mut active_node = self.active.node;
+        let mut active_length = self.active.length;
+        let mut active_edge = self.active.edge;
+        let mut remaining_suffix = self.active.remaining_suffix;
+        for i in self.len..data.symbol_count() {
+            last_new_node = None;
+            remaining_suffix += 1;
+            while remaining_suffix > 0 {
+                if active_length == 0 {
+                    active_edge = data.symbol_at(i);
+                }
+                if let Some(next_node) = self.nodes[active_node].child(active_edge) {
+                    // If the active length is longer than the current edge, we walk down the edge 
+                    // to the next node.
+                    if active_length >= self.nodes[next_node].edge_length() {
+                        active_node = next_node;
+                        active_length -= self.nodes[next_node].edge_length();
+                        active_edge = data.symbol_at(i - active_length);
+                        continue;
+                    }
+                    // Rule 3: the current character is on the edge
+                    else if data.symbol_at(self.nodes[next_node].start() + active_length) == data.symbol_at(i) {
+                        // Make a suffix link to the active node if there is a node waiting and if 
+                        // the active node is not the root node
+                        if last_new_node.is_some() && active_node > 0 {
+                            self.nodes[last_new_node.unwrap()].suffix_link = Some(index(active_node));
+                            last_new_node = None;
+                        }
+                        active_length += 1;
+                        break;
+                    }
+                    // We need to split the edge at the current character
+                    else {
+                        let start = self.nodes[next_node].start();
+                        let split_pos = self.nodes[next_node].start() + active_length;
+                        self.nodes.push(Node::build(start, split_pos));
+                        let split = self.nodes.len() - 1;
+                        self.nodes[next_node].start = index(split_pos);
+                        self.nodes[active_node].set_child(data.symbol_at(start), Some(split));
+                        self.nodes[split].set_child(data.symbol_at(split_pos), Some(next_node));
+                        self.nodes.push(Node::build(i, data.symbol_count()));
+                        let leaf = self.nodes.len() - 1;
+                        self.nodes[split].set_child(data.symbol_at(i), Some(leaf));
+                        // Make a suffix link to our next node
+                        if last_new_node.is_some() {
+                            self.nodes[last_new_node.unwrap()].suffix_link = Some(index(split));
+                        }
+                        last_new_node = Some(split);
+                    }
+                }
+                else {
+                    // Rule 2: we create a new leaf edge
+                    self.nodes.push(Node::build(i, data.symbol_count()));
+                    let leaf = self.nodes.len() - 1;
+                    self.nodes[active_node].set_child(active_edge, Some(leaf));
+                    // Make a suffix link if there is a node waiting
+                    if last_new_node.is_some() {
+                        self.nodes[last_new_node.unwrap()].suffix_link = Some(index(active_node));
+                    }
+                    last_new_node = Some(active_node);
+                }
+                
+                remaining_suffix -= 1;
+                if active_node == 0 && active_length > 0 {
+                    active_length -= 1;
+                    active_edge = data.symbol_at(i - remaining_suffix + 1);
+                }
+                else if active_node != 0 {
+                    active_node = match self.nodes[active_node].suffix_link() {
+                        Some(linked) => linked,
+                        None => 0
+                    };
+                }
+            }
+        }
+        self.len = data.symbol_count();
+        self.active = ActivePoint {
+            node: active_node,
+            length: active_length,
+            edge: active_edge,
+            remaining_suffix,
+        };
+    }
+
+    // Add the end of data sentinel, logging the modifications of the existing nodes so they can be 
+    // reverted by append
+    #[allow(unused_assignments, clippy::unnecessary_unwrap, clippy::manual_unwrap_or, clippy::manual_unwrap_or_default)]
+    fn terminate<S: SymbolSource + ?Sized>(&mut self, data: &S) {
+        self.sentinel_nodes = self.nodes.len();
+        self.sentinel_undo.clear();
+        let mut active_node = self.active.node;
+        let mut active_length = self.active.length;
+        let mut active_edge = self.active.edge;
+        let mut remaining_suffix = self.active.remaining_suffix;
+        // Simulate end character by doing another step with the end of data symbol
+        let mut last_new_node: Option<usize> = None;
+        remaining_suffix += 1;
+        while remaining_suffix > 0 {
+            // Active length is zero, so the current character is *i* and no walk down is needed.
+            if active_length == 0 {
+                // Special end character
+                active_edge = END_SYMBOL;
+            }
+            if let Some(next_node) = self.nodes[active_node].child(active_edge) {
+                // If the active length is longer than the current edge, we walk down the edge
+                if active_length >= self.nodes[next_node].edge_length() {
+                    active_length -= self.nodes[next_node].edge_length();
+                    active_node = next_node;
+                    active_edge = match active_length {
+                       0 => END_SYMBOL,
+                       _ => data.symbol_at(data.symbol_count() - active_length)
+                    };
+                    continue;
+                }
+                else if self.nodes[next_node].start() + active_length == data.symbol_count() {
+                    // Make a suffix link to the active node if there is a node waiting and if 
+                    // the active node is not the root node
+                    if last_new_node.is_some() && active_node > 0 {
+                        self.set_suffix_link(last_new_node.unwrap(), active_node);
+                        last_new_node = None;
+                    }
+                    active_length += 1;
+                    break;
+                }
+                // We need to split the edge at the current character
+                else {
+                    let start = self.nodes[next_node].start();
+                    let split_pos = self.nodes[next_node].start() + active_length;
+                    self.nodes.push(Node::build(start, split_pos));
+                    let split = self.nodes.len() - 1;
+                    self.set_start(next_node, split_pos);
+                    self.set_edge(active_node, data.symbol_at(start), split);
+                    self.set_edge(split, data.symbol_at(split_pos), next_node);
+                    self.nodes.push(Node::build(data.symbol_count(), data.symbol_count()));
+                    let leaf = self.nodes.len() - 1;
+                    self.set_edge(split, END_SYMBOL, leaf);
+                    // Make a suffix link to our next node
+                    if last_new_node.is_some() {
+                        self.set_suffix_link(last_new_node.unwrap(), split);
+                    }
+                    last_new_node = Some(split);
+                }
+            }
+            else {
+                // Rule 2: we create a new leaf edge
+                self.nodes.push(Node::build(data.symbol_count(), data.symbol_count()));
+                let leaf = self.nodes.len() - 1;
+                self.set_edge(active_node, active_edge, leaf);
+                // Make a suffix link if there is a node waiting
+                if last_new_node.is_some() {
+                    self.set_suffix_link(last_new_node.unwrap(), active_node);
+                }
+                last_new_node = Some(active_node);
+            }
+            
+            remaining_suffix -= 1;
+            if active_node == 0 && active_length > 0 {
+                active_length -= 1;
+                if remaining_suffix < 2 {
+                    active_edge = END_SYMBOL;
+                }
+                else {
+                    active_edge = data.symbol_at(data.symbol_count() - remaining_suffix + 1);
+                }
+            }
+            else if active_node != 0 {
+                active_node = match self.nodes[active_node].suffix_link() {
+                    Some(linked) => linked,
+                    None => 0
+                };
+            }
+        }
+    }
+
+    /// Compute the matching statistics of `second` against `data`, the data this tree was built 
+    /// from: element `i` is the length of the longest substring starting at position `i` of 
+    /// `second` which occurs somewhere in `data`.
+    ///
+    /// The computation follows the suffix links of the tree and runs in time linear in the length 
+    /// of `second`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bcmp::suffixtree::SuffixTree;
+    ///
+    /// let data = b"abcdefg";
+    /// let tree = SuffixTree::new(&data[..]);
+    /// assert!(tree.matching_statistics(&data[..], &b"xcdefab"[..]) == [0, 4, 3, 2, 1, 2, 1]);
+    /// ```
+    pub fn matching_statistics<F: SymbolSource + ?Sized, S: ByteSource + ?Sized>(&self, data: &F, second: &S) -> Vec<usize> {
+        let mut stats = Vec::with_capacity(second.len());
+        // The matched substring second[i..i + length] ends remaining bytes down the edge below node, 
+        // whose path has length node_depth. The edge is followed fully only if it leads to an 
+        // internal node.
+        let mut node = 0;
+        let mut node_depth = 0;
+        let mut remaining = 0;
+        let mut length = 0;
+        for i in 0..second.len() {
+            // Extend the match as far as possible
+            while i + length < second.len() {
+                let byte = second.byte_at(i + length);
+                if remaining == 0 {
+                    match self.nodes[node].child(byte as usize) {
+                        Some(_) => remaining = 1,
+                        None => break
+                    }
+                }
+                else {
+                    let next = self.nodes[node].child(second.byte_at(i + node_depth) as usize).unwrap();
+                    let edge = &self.nodes[next];
+                    if remaining == edge.edge_length() || data.symbol_at(edge.start() + remaining) != byte as usize {
+                        break;
+                    }
+                    remaining += 1;
+                }
+                length += 1;
+                self.canonize(second, i, &mut node, &mut node_depth, &mut remaining);
+            }
+            stats.push(length);
+            if length == 0 {
+                continue;
+            }
+            // Move to the suffix starting at i + 1, through the suffix link when there is one, or 
+            // by rescanning from the root
+            length -= 1;
+            match self.nodes[node].suffix_link() {
+                Some(link) if node != 0 && link != 0 => {
+                    node = link;
+                    node_depth -= 1;
+                },
+                _ => {
+                    node = 0;
+                    node_depth = 0;
+                    remaining = length;
+                }
+            }
+            self.canonize(second, i + 1, &mut node, &mut node_depth, &mut remaining);
+        }
+        stats
+    }
+
+    // Walk down the internal nodes fully covered by the remaining bytes of the match starting at 
+    // position i of second
+    fn canonize<S: ByteSource + ?Sized>(&self, second: &S, i: usize, node: &mut usize, node_depth: &mut usize, remaining: &mut usize) {
+        while *remaining > 0 {
+            let next = self.nodes[*node].child(second.byte_at(i + *node_depth) as usize).unwrap();
+            let length = self.nodes[next].edge_length();
+            let internal = !self.nodes[next].is_leaf();
+            if *remaining > length || (*remaining == length && internal) {
+                *node = next;
+                *node_depth += length;
+                *remaining -= length;
+            }
+            else {
+                break;
+            }
+        }
+    }
+
+    /// Call `f(suffix_start, parent_depth)` for every leaf of the tree, where `parent_depth` is the 
+    /// length of the path leading to the parent of the leaf. `len` is the length of the data this 
+    /// tree was built from.
+    pub(crate) fn for_each_leaf<F: FnMut(usize, usize)>(&self, len: usize, f: F) {
+        self.for_each_leaf_under(0, 0, len, f);
+    }
+
+    // Same as for_each_leaf but restricted to the subtree of node, whose path has length depth
+    fn for_each_leaf_under<F: FnMut(usize, usize)>(&self, node: usize, depth: usize, len: usize, mut f: F) {
+        let mut stack = vec![(node, depth)];
+        while let Some((cur, depth)) = stack.pop() {
+            let mut leaf = true;
+            for next in self.nodes[cur].children().map(|(_, next)| next) {
+                leaf = false;
+                stack.push((next, depth + self.nodes[next].edge_length()));
+            }
+            if leaf && cur != 0 {
+                f(len - depth, depth - self.nodes[cur].edge_length());
+            }
+        }
+    }
+
+    // Find the node whose leading edge contains the end of pattern and the length of the path 
+    // leading to this node
+    fn locate<S: SymbolSource + ?Sized>(&self, data: &S, pattern: &[u8]) -> Option<(usize, usize)> {
+        let mut cur = 0;
+        let mut depth = 0;
+        while depth < pattern.len() {
+            let next = self.nodes[cur].child(pattern[depth] as usize)?;
+            let node = &self.nodes[next];
+            for j in 0..node.edge_length().min(pattern.len() - depth) {
+                if data.symbol_at(node.start() + j) != pattern[depth + j] as usize {
+                    return None;
+                }
+            }
+            depth += node.edge_length();
+            cur = next;
+        }
+        Some((cur, depth))
+    }
+
+    /// Return true if `pattern` occurs in `data`, the data this tree was built from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bcmp::suffixtree::SuffixTree;
+    ///
+    /// let data = b"abracadabra";
+    /// let tree = SuffixTree::new(&data[..]);
+    /// assert!(tree.contains(&data[..], b"cad"));
+    /// assert!(!tree.contains(&data[..], b"bad"));
+    /// assert!(tree.count(&data[..], b"abra") == 2);
+    /// assert!(tree.find_all(&data[..], b"a") == vec![0, 3, 5, 7, 10]);
+    /// ```
+    pub fn contains<S: SymbolSource + ?Sized>(&self, data: &S, pattern: &[u8]) -> bool {
+        self.locate(data, pattern).is_some()
+    }
+
+    /// Return the sorted list of the positions in `data`, the data this tree was built from, where 
+    /// `pattern` occurs. An empty pattern occurs at every position.
+    pub fn find_all<S: SymbolSource + ?Sized>(&self, data: &S, pattern: &[u8]) -> Vec<usize> {
+        let mut positions = Vec::new();
+        if let Some((node, depth)) = self.locate(data, pattern) {
+            self.for_each_leaf_under(node, depth, data.symbol_count(), |start, _| {
+                // Skip the empty suffix
+                if start < data.symbol_count() {
+                    positions.push(start);
+                }
+            });
+        }
+        positions.sort_unstable();
+        positions
+    }
+
+    /// Return the number of occurrences of `pattern` in `data`, the data this tree was built from.
+    pub fn count<S: SymbolSource + ?Sized>(&self, data: &S, pattern: &[u8]) -> usize {
+        let mut count = 0;
+        if let Some((node, depth)) = self.locate(data, pattern) {
+            self.for_each_leaf_under(node, depth, data.symbol_count(), |start, _| {
+                if start < data.symbol_count() {
+                    count += 1;
+                }
+            });
+        }
+        count
+    }
+
+    /// Iterate over the nodes of the tree in depth-first pre-order, starting with the root. The 
+    /// children of a node are visited in lexicographic order, the end of data and the end markers 
+    /// coming first. Each 
+    /// item is a node index in [`nodes`](#structfield.nodes) and the length of the path leading to 
+    /// the end of this node.
+    pub fn depth_first(&self) -> DepthFirst<'_, I> {
+        DepthFirst {
+            tree: self,
+            stack: vec![(0, 0)],
+        }
+    }
+
+    /// Iterate over the leaves of the tree, yielding the start position of their suffix. The 
+    /// suffixes come in lexicographic order and the empty suffix is skipped.
+    pub fn leaves(&self) -> Leaves<'_, I> {
+        Leaves {
+            nodes: self.depth_first(),
+        }
+    }
+
+    /// Iterate over the suffixes of `data`, the data this tree was built from, in lexicographic 
+    /// order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bcmp::suffixtree::SuffixTree;
+    ///
+    /// let data = b"banana";
+    /// let tree = SuffixTree::new(&data[..]);
+    /// let suffixes: Vec<&[u8]> = tree.suffixes(&data[..]).collect();
+    /// assert!(suffixes == [&b"a"[..], b"ana", b"anana", b"banana", b"na", b"nana"]);
+    /// assert!(tree.leaves().collect::<Vec<usize>>() == [5, 3, 1, 0, 4, 2]);
+    /// ```
+    pub fn suffixes<'a>(&'a self, data: &'a [u8]) -> Suffixes<'a, I> {
+        Suffixes {
+            data,
+            leaves: self.leaves(),
+        }
+    }
+
+    /// Return the suffix array of the data this tree was built from: the start positions of its 
+    /// suffixes in lexicographic order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bcmp::suffixtree::SuffixTree;
+    ///
+    /// let tree = SuffixTree::new(&b"banana"[..]);
+    /// assert!(tree.to_suffix_array() == [5, 3, 1, 0, 4, 2]);
+    /// assert!(tree.lcp_array() == [0, 1, 3, 0, 0, 2]);
+    /// ```
+    pub fn to_suffix_array(&self) -> Vec<usize> {
+        self.leaves().collect()
+    }
+
+    /// Return the longest common prefix array matching 
+    /// [`to_suffix_array`](#method.to_suffix_array): element `i` is the length of the longest 
+    /// common prefix of the suffixes `i - 1` and `i` of the suffix array, the first element being 
+    /// zero.
+    pub fn lcp_array(&self) -> Vec<usize> {
+        let mut lcp = Vec::with_capacity(self.len);
+        // Depth of the lowest common ancestor of the previous leaf and the next one, which is the 
+        // parent of the first node visited after the previous leaf
+        let mut lca_depth: Option<usize> = Some(0);
+        for (node, depth) in self.depth_first().skip(1) {
+            if lca_depth.is_none() {
+                lca_depth = Some(depth - self.nodes[node].edge_length());
+            }
+            if depth > 0 && self.nodes[node].is_leaf() {
+                lcp.push(lca_depth.take().unwrap_or_default());
+            }
+        }
+        lcp
+    }
+
+    /// Render the tree in the Graphviz DOT language with the default 
+    /// [`GraphvizOptions`](struct.GraphvizOptions.html).
+    pub fn to_graphviz(&self, data: &[u8]) -> String {
+        self.to_graphviz_with(data, &GraphvizOptions::default())
+    }
+
+    /// Render the tree in the Graphviz DOT language. `data` is the data this tree was built from.
+    ///
+    /// Edge labels are escaped so arbitrary binary data always produces a valid graph: printable 
+    /// ASCII is kept and every other byte is written as `\xNN`. The end of data is written `$`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bcmp::suffixtree::{GraphvizOptions, SuffixTree};
+    ///
+    /// let data = b"banana\xff";
+    /// let tree = SuffixTree::new(&data[..]);
+    /// let options = GraphvizOptions {
+    ///     leaf_labels: true,
+    ///     suffix_links: false,
+    ///     max_label_length: Some(3),
+    /// };
+    /// let dot = tree.to_graphviz_with(&data[..], &options);
+    /// assert!(dot.contains("label = \"ban...\""));
+    /// assert!(!dot.contains("dashed"));
+    /// ```
+    #[allow(clippy::single_char_add_str, clippy::needless_return)]
+    pub fn to_graphviz_with(&self, data: &[u8], options: &GraphvizOptions) -> String {
+        let mut graphviz = String::new();
+        graphviz.push_str("digraph {\n");
+        for (i, depth) in self.depth_first() {
+            if options.leaf_labels && self.nodes[i].is_leaf() && i != 0 {
+                graphviz.push_str(&format!("    NODE_{} [ label = \"{}\" ];\n", i, data.len() - depth));
+            }
+            else {
+                graphviz.push_str(&format!("    NODE_{};\n", i));
+            }
+        }
+        for i in 0..self.nodes.len() {
+            for (key, edge) in self.nodes[i].children() {
+                let label = if key == END_SYMBOL {
+                    String::from("$")
+                }
+                else if key > END_SYMBOL {
+                    format!("${}", key - END_SYMBOL - 1)
+                }
+                else {
+                    let start = self.nodes[edge].start();
+                    let end = self.nodes[edge].end();
+                    let shown = options.max_label_length.map_or(end, |max| end.min(start + max));
+                    let mut label = escape_label(&data[start..shown]);
+                    if shown < end {
+                        label.push_str("...");
+                    }
+                    label
+                };
+                graphviz.push_str(&format!("    NODE_{} -> NODE_{} [ label = \"{}\" ];\n", i, edge, label));
+            }
+            if options.suffix_links {
+                if let Some(linked) = self.nodes[i].suffix_link() {
+                    graphviz.push_str(&format!("    NODE_{} -> NODE_{} [ style = \"dashed\" ];\n", i, linked));
+                }
+            }
+        }
+        graphviz.push_str("}");
+        return graphviz;
+    }
+}
+
+// Write a position or a node index on width bytes, all bits set for None
+fn put<W: Write>(w: &mut W, width: u8, value: Option<usize>) -> io::Result<()> {
+    if width == 4 {
+        w.pack(value.map_or(u32::MAX, |v| v as u32))
+    }
+    else {
+        w.pack(value.map_or(u64::MAX, |v| v as u64))
+    }
+}
+
+// Read a value written by put
+fn get<R: Read>(r: &mut R, width: u8) -> io::Result<Option<usize>> {
+    let value = if width == 4 {
+        Some(r.unpack::<u32>()?).filter(|&v| v != u32::MAX).map(u64::from)
+    }
+    else {
+        Some(r.unpack::<u64>()?).filter(|&v| v != u64::MAX)
+    };
+    value.map(to_usize).transpose()
+}
+
+// Convert an integer of the tree format, which can exceed usize on 32 bits platforms
+fn to_usize(value: u64) -> io::Result<usize> {
+    usize::try_from(value).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Invalid suffix tree integer"))
+}
+
+// Escape bytes for a DOT string, keeping printable ASCII
+fn escape_label(bytes: &[u8]) -> String {
+    let mut label = String::with_capacity(bytes.len());
+    for &b in bytes {
+        match b {
+            b'"' => label.push_str("\\\""),
+            b'\\' => label.push_str("\\\\"),
+            0x20..=0x7e => label.push(b as char),
+            _ => label.push_str(&format!("\\\\x{:02x}", b)),
+        }
+    }
+    label
+}
+
+/// Rendering options of [`SuffixTree::to_graphviz_with`](struct.SuffixTree.html#method.to_graphviz_with).
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct GraphvizOptions {
+    /// Label the leaves with the start position of their suffix.
+    pub leaf_labels: bool,
+    /// Draw the suffix links as dashed edges.
+    pub suffix_links: bool,
+    /// Truncate the edge labels longer than this number of bytes, marking them with `...`.
+    pub max_label_length: Option<usize>,
+}
+
+impl Default for GraphvizOptions {
+    /// Unlabeled leaves, suffix links and full edge labels.
+    fn default() -> GraphvizOptions {
+        GraphvizOptions {
+            leaf_labels: false,
+            suffix_links: true,
+            max_label_length: None,
+        }
+    }
+}
+
+/// A depth-first iterator over the nodes of a [`SuffixTree`](struct.SuffixTree.html), see 
+/// [`SuffixTree::depth_first`](struct.SuffixTree.html#method.depth_first).
+#[derive(Clone)]
+pub struct DepthFirst<'a, I: NodeIndex = usize> {
+    tree: &'a SuffixTree<I>,
+    stack: Vec<(usize, usize)>,
+}
+
+impl<'a, I: NodeIndex> Iterator for DepthFirst<'a, I> {
+    type Item = (usize, usize);
+    fn next(&mut self) -> Option<(usize, usize)> {
+        let (cur, depth) = self.stack.pop()?;
+        let node = &self.tree.nodes[cur];
+        // Pushed in reverse so that the end of data and the end markers are popped first, then the 
+        // bytes in order
+        let end = node.children().rev().take_while(|&(k, _)| k >= END_SYMBOL);
+        let bytes = node.children().rev().skip_while(|&(k, _)| k >= END_SYMBOL);
+        for (_, next) in bytes.chain(end) {
+            self.stack.push((next, depth + self.tree.nodes[next].edge_length()));
+        }
+        Some((cur, depth))
+    }
+}
+
+impl<'a, I: NodeIndex> FusedIterator for DepthFirst<'a, I> {}
+
+/// An iterator over the suffix start positions of the leaves of a 
+/// [`SuffixTree`](struct.SuffixTree.html), see 
+/// [`SuffixTree::leaves`](struct.SuffixTree.html#method.leaves).
+#[derive(Clone)]
+pub struct Leaves<'a, I: NodeIndex = usize> {
+    nodes: DepthFirst<'a, I>,
+}
+
+impl<'a, I: NodeIndex> Iterator for Leaves<'a, I> {
+    type Item = usize;
+    fn next(&mut self) -> Option<usize> {
+        let tree = self.nodes.tree;
+        for (node, depth) in self.nodes.by_ref() {
+            if node != 0 && depth > 0 && tree.nodes[node].is_leaf() {
+                return Some(tree.len - depth);
+            }
+        }
+        None
+    }
+}
+
+impl<'a, I: NodeIndex> FusedIterator for Leaves<'a, I> {}
+
+/// An iterator over the suffixes of the data a [`SuffixTree`](struct.SuffixTree.html) was built 
+/// from, in lexicographic order, see 
+/// [`SuffixTree::suffixes`](struct.SuffixTree.html#method.suffixes).
+#[derive(Clone)]
+pub struct Suffixes<'a, I: NodeIndex = usize> {
+    data: &'a [u8],
+    leaves: Leaves<'a, I>,
+}
+
+impl<'a, I: NodeIndex> Iterator for Suffixes<'a, I> {
+    type Item = &'a [u8];
+    fn next(&mut self) -> Option<&'a [u8]> {
+        self.leaves.next().map(|start| &self.data[start..])
+    }
+}
+
+impl<'a, I: NodeIndex> FusedIterator for Suffixes<'a, I> {}
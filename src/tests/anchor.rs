@@ -0,0 +1,56 @@
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use {AlgoSpec, Match, MatchIterator, Region, patch_set};
+use anchor::{Anchor, anchored_matches, anchored_patch_set, anchored_regions};
+
+#[test]
+fn anchored_sections() {
+    let mut rng = StdRng::from_seed(&[88usize][..]);
+    // Sections of random sizes sharing the same content in a shuffled order
+    let sections: Vec<Vec<u8>> = (0..6).map(|_| (0..rng.gen_range(100, 1000)).map(|_| rng.gen::<u8>()).collect()).collect();
+    let mut order: Vec<usize> = (0..sections.len()).collect();
+    rng.shuffle(&mut order);
+    let a: Vec<u8> = sections.concat();
+    let b: Vec<u8> = order.iter().flat_map(|&i| sections[i].clone()).collect();
+    let mut anchors = Vec::new();
+    let (mut first_pos, mut second_pos) = (0, 0);
+    for i in 0..sections.len() {
+        first_pos += sections[i].len();
+        second_pos += sections[order[i]].len();
+        anchors.push(Anchor::new(first_pos, second_pos));
+    }
+    anchors.pop();
+    let regions = anchored_regions(a.len(), b.len(), &anchors);
+    assert!(regions.len() == sections.len());
+    assert!(regions.first().unwrap().0.start == 0 && regions.last().unwrap().1.end == b.len());
+    for algo_spec in [AlgoSpec::HashMatch(8), AlgoSpec::TreeMatch(8), AlgoSpec::SuffixArrayMatch(8)] {
+        let matches: Vec<Match> = anchored_matches(&a, &b, &anchors, algo_spec).collect();
+        // Sorted and never crossing the regions
+        assert!(matches.windows(2).all(|w| w[0].second_pos <= w[1].second_pos));
+        for m in &matches {
+            let &(first_region, second_region) = regions.iter().find(|r| r.1.start <= m.second_pos && m.second_pos < r.1.end).unwrap();
+            assert!(first_region.start <= m.first_pos && m.first_end() <= first_region.end && m.second_end() <= second_region.end);
+        }
+        // Same as comparing the regions one by one
+        let expected: Vec<Match> = regions.iter().flat_map(|&(first_region, second_region)| {
+            MatchIterator::with_regions(&a, first_region, &b, second_region, algo_spec).collect::<Vec<_>>()
+        }).collect();
+        assert!(matches == expected);
+        let patches = anchored_patch_set(&a, &b, &anchors, algo_spec);
+        for p in &patches {
+            assert!(a[p.first_pos..p.first_end()] == b[p.second_pos..p.second_end()]);
+        }
+        // Without anchors the whole second piece of data is found
+        assert!(patch_set(&a, &b, algo_spec).iter().map(|m| m.length).sum::<usize>() == b.len());
+        assert!(patches.iter().map(|m| m.length).sum::<usize>() < b.len());
+    }
+    // No anchor compares everything
+    assert!(anchored_regions(10, 20, &[]) == [(Region::new(0, 10), Region::new(0, 20))]);
+    assert!(anchored_matches(&a, &b, &[], AlgoSpec::TreeMatch(8)).collect::<Vec<Match>>() == MatchIterator::new(&a, &b, AlgoSpec::TreeMatch(8)).collect::<Vec<Match>>());
+}
+
+#[test]
+#[should_panic]
+fn unordered_anchors() {
+    anchored_regions(100, 100, &[Anchor::new(10, 50), Anchor::new(20, 40)]);
+}
@@ -0,0 +1,31 @@
+use futures::executor::block_on;
+use futures::io::Cursor;
+use futures::stream::StreamExt;
+
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use {AlgoSpec, MatchIterator, patch_set};
+use asyncmatch::{MatchStream, patch_set_async, read_hash_index};
+use hashindex::HashIndex;
+
+#[test]
+fn match_stream() {
+    let mut rng = StdRng::from_seed(&[19usize][..]);
+    let a: Vec<u8> = (0..2000).map(|_| rng.gen_range(0, 4)).collect();
+    let b: Vec<u8> = (0..2000).map(|_| rng.gen_range(0, 4)).collect();
+    let expected: Vec<_> = MatchIterator::new(&a, &b, AlgoSpec::TreeMatch(8)).collect();
+    let streamed = block_on(MatchStream::new(&a, &b, AlgoSpec::TreeMatch(8)).budget(3).collect::<Vec<_>>());
+    assert!(expected.len() > 3);
+    assert!(streamed == expected);
+}
+
+#[test]
+fn async_readers() {
+    let a = b"0123456789abcdefghijklmnopqrstuvwxyz".to_vec();
+    let b = b"abcdefghij__0123456789".to_vec();
+    let patches = block_on(patch_set_async(Cursor::new(a.clone()), Cursor::new(b.clone()), AlgoSpec::TreeMatch(4))).unwrap();
+    assert!(patches == patch_set(&a, &b, AlgoSpec::TreeMatch(4)));
+    let index = HashIndex::new(&a, 4);
+    let loaded = block_on(read_hash_index(Cursor::new(index.as_bytes().to_vec()))).unwrap();
+    assert!(loaded.matches(&a, &b) == index.matches(&a, &b));
+}
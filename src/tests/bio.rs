@@ -0,0 +1,34 @@
+use AlgoSpec;
+use Match;
+use bio::{pairwise_lcs, pairwise_matches, read_fasta, read_fastq};
+
+#[test]
+fn fasta() {
+    let fasta = ">chr1 test\r\nACGTACGTAA\nCCGG\n\n>chr2\nTTTTACGTAC\n";
+    let records = read_fasta(fasta.as_bytes()).unwrap();
+    assert!(records.len() == 2);
+    assert!(records[0].name == "chr1 test");
+    assert!(records[0].seq == b"ACGTACGTAACCGG");
+    assert!(records[0].quality.is_none());
+    assert!(records[1].name == "chr2");
+    assert!(read_fasta("ACGT\n>x\n".as_bytes()).is_err());
+    let lcs = pairwise_lcs(&records, &records[1..], AlgoSpec::TreeMatch(4));
+    assert!(lcs.len() == 2);
+    assert!(lcs[0].first == 0 && lcs[0].second == 0);
+    assert!(lcs[0].matches == vec![Match::new(3, 3, 6)]);
+    assert!(lcs[1].matches == vec![Match::new(0, 0, 10)]);
+    let all = pairwise_matches(&records[..1], &records[1..], AlgoSpec::TreeMatch(4));
+    assert!(all[0].matches.len() == 2);
+}
+
+#[test]
+fn fastq() {
+    let fastq = "@read1\nACGT\n+\nIIII\n@read2\nGGCC\n+read2\n!!!!\n";
+    let records = read_fastq(fastq.as_bytes()).unwrap();
+    assert!(records.len() == 2);
+    assert!(records[1].name == "read2");
+    assert!(records[1].seq == b"GGCC");
+    assert!(records[1].quality == Some(b"!!!!".to_vec()));
+    assert!(read_fastq("@read1\nACGT\n+\nIII\n".as_bytes()).is_err());
+    assert!(read_fastq("@read1\nACGT\n".as_bytes()).is_err());
+}
@@ -0,0 +1,55 @@
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use {AlgoSpec, Region, coverage};
+use blame::{Blame, blame};
+
+#[test]
+fn blame_ancestors() {
+    let mut rng = StdRng::from_seed(&[63usize][..]);
+    let ancestors: Vec<Vec<u8>> = (0..3).map(|_| (0..2000).map(|_| rng.gen::<u8>()).collect()).collect();
+    let refs: Vec<&[u8]> = ancestors.iter().map(|a| &a[..]).collect();
+    // Pieces of known origin
+    let mut target = Vec::new();
+    let mut pieces = Vec::new();
+    while target.len() < 4000 {
+        let length = rng.gen_range(20, 100);
+        let ancestor = rng.gen_range(0, 4);
+        let start = target.len();
+        if ancestor == 3 {
+            target.extend((0..length).map(|_| rng.gen::<u8>()));
+        }
+        else {
+            let offset = rng.gen_range(0, 2000 - length);
+            target.extend_from_slice(&ancestors[ancestor][offset..offset + length]);
+        }
+        pieces.push((start, target.len(), ancestor));
+    }
+    for algo_spec in &[AlgoSpec::HashMatch(8), AlgoSpec::TreeMatch(8)] {
+        let blamed = blame(&refs, &target, *algo_spec);
+        assert!(blamed.first().unwrap().region.start == 0 && blamed.last().unwrap().region.end == target.len());
+        assert!(blamed.windows(2).all(|w| w[0].region.end == w[1].region.start && w[0].origin != w[1].origin));
+        let mut covered = vec![false; target.len()];
+        for b in &blamed {
+            if let Some(origin) = b.origin {
+                let copied = &ancestors[origin.ancestor][origin.offset..origin.offset + b.region.len()];
+                assert!(copied == &target[b.region.start..b.region.end]);
+                covered[b.region.start..b.region.end].iter_mut().for_each(|c| *c = true);
+            }
+        }
+        // Covered exactly where some ancestor matches
+        for (k, data) in refs.iter().enumerate() {
+            for r in coverage(data, &target, *algo_spec).regions() {
+                assert!(covered[r.start..r.end].iter().all(|&c| c), "ancestor {}", k);
+            }
+        }
+        // The middle of each copied piece is attributed to its ancestor
+        for &(start, end, ancestor) in &pieces {
+            let middle = (start + end) / 2;
+            let b = blamed.iter().find(|b| b.region.start <= middle && middle < b.region.end).unwrap();
+            if ancestor < 3 && end - start >= 40 {
+                assert!(b.origin.unwrap().ancestor == ancestor);
+            }
+        }
+    }
+    assert!(blame(&[], b"abc", AlgoSpec::TreeMatch(2)) == [Blame { region: Region::new(0, 3), origin: None }]);
+}
@@ -0,0 +1,43 @@
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use {AlgoSpec, Match, MatchIterator, Region};
+use bridge::bridge_gaps;
+
+#[test]
+fn substitutions() {
+    let mut rng = StdRng::from_seed(&[86usize][..]);
+    let a: Vec<u8> = (0..10000).map(|_| rng.gen::<u8>()).collect();
+    let mut b = a.clone();
+    // Substitutions of 1 to 3 bytes, at least 20 bytes apart
+    let mut pos = 100;
+    let mut edits = Vec::new();
+    while pos < b.len() - 100 {
+        let length = rng.gen_range(1, 4);
+        for byte in &mut b[pos..pos + length] {
+            *byte = !*byte;
+        }
+        edits.push(Region::new(pos, pos + length));
+        pos += rng.gen_range(20, 200);
+    }
+    let matches: Vec<Match> = MatchIterator::new(&a, &b, AlgoSpec::TreeMatch(8)).collect();
+    let bridged = bridge_gaps(matches.iter().cloned(), 3);
+    let whole = bridged.iter().find(|am| am.span == Match::new(0, 0, a.len())).unwrap();
+    assert!(whole.gaps == edits);
+    assert!(whole.matched_len() == a.len() - edits.iter().map(Region::len).sum::<usize>());
+    for m in whole.parts() {
+        assert!(a[m.first_pos..m.first_end()] == b[m.second_pos..m.second_end()]);
+    }
+    // Every match belongs to exactly one approximate match
+    let mut parts: Vec<Match> = bridged.iter().flat_map(|am| am.parts()).collect();
+    parts.sort();
+    assert!(parts == matches);
+    // Smaller gaps aren't bridged
+    let unbridged = bridge_gaps(matches.iter().cloned(), 0);
+    assert!(unbridged.iter().all(|am| am.gaps.is_empty()));
+    assert!(unbridged.iter().map(|am| am.span).collect::<Vec<Match>>() == matches);
+    assert!(bridge_gaps(matches.iter().cloned(), 2).len() > bridged.len());
+    // Overlapping matches on a diagonal are merged
+    let overlapping = bridge_gaps(vec![Match::new(10, 0, 8), Match::new(14, 4, 8), Match::new(30, 30, 4)], 0);
+    assert!(overlapping.len() == 2 && overlapping[0].span == Match::new(10, 0, 12) && overlapping[0].gaps.is_empty());
+    assert!(bridge_gaps(Vec::new(), 10).is_empty());
+}
@@ -0,0 +1,116 @@
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use {AlgoSpec, Match, MatchIterator};
+use chain::{ChainOptions, chain_align, chain_anchors, collinear_chain, synteny};
+
+#[test]
+fn chain_naive() {
+    let mut rng = StdRng::from_seed(&[57usize][..]);
+    for _ in 0..20 {
+        let a: Vec<u8> = (0..300).map(|_| rng.gen_range(0, 4)).collect();
+        let b: Vec<u8> = (0..300).map(|_| rng.gen_range(0, 4)).collect();
+        let mut matches: Vec<Match> = MatchIterator::new(&a, &b, AlgoSpec::TreeMatch(3)).collect();
+        matches.sort();
+        let chain = collinear_chain(&matches);
+        // The chain is made of the matches, collinear and not overlapping
+        assert!(chain.matches.iter().all(|m| matches.contains(m)));
+        assert!(chain.matches.windows(2).all(|w| w[0].first_end() <= w[1].first_pos && w[0].second_end() <= w[1].second_pos));
+        assert!(chain.covered == chain.matches.iter().map(|m| m.length).sum::<usize>());
+        // Quadratic dynamic programming over the matches sorted by position in second
+        let mut weights = vec![0; matches.len()];
+        for i in 0..matches.len() {
+            weights[i] = matches[i].length;
+            for j in 0..i {
+                if matches[j].first_end() <= matches[i].first_pos && matches[j].second_end() <= matches[i].second_pos {
+                    weights[i] = weights[i].max(weights[j] + matches[i].length);
+                }
+            }
+        }
+        assert!(chain.covered == weights.iter().cloned().max().unwrap_or(0));
+    }
+    assert!(collinear_chain(&[]).matches.is_empty());
+}
+
+#[test]
+fn chain_anchors_naive() {
+    let mut rng = StdRng::from_seed(&[96usize][..]);
+    let score = |chain: &[Match], penalty: usize| {
+        let shifts: usize = chain.windows(2).map(|w| (w[1].first_pos - w[0].first_end()).abs_diff(w[1].second_pos - w[0].second_end())).sum();
+        chain.iter().map(|m| m.length).sum::<usize>() as isize - (shifts * penalty) as isize
+    };
+    for round in 0..40 {
+        let penalty = round % 4;
+        let mut anchors: Vec<Match> = (0..rng.gen_range(0, 120))
+            .map(|_| Match::new(rng.gen_range(0, 1000), rng.gen_range(0, 1000), rng.gen_range(1, 60)))
+            .collect();
+        anchors.sort();
+        anchors.dedup();
+        let chain = chain_anchors(anchors.clone(), penalty);
+        assert!(chain.iter().all(|m| anchors.contains(m)));
+        assert!(chain.windows(2).all(|w| w[0].first_end() <= w[1].first_pos && w[0].second_end() <= w[1].second_pos));
+        // Quadratic dynamic programming over the anchors sorted by position in second
+        let mut scores = vec![0isize; anchors.len()];
+        for i in 0..anchors.len() {
+            let m = anchors[i];
+            scores[i] = m.length as isize;
+            for j in 0..i {
+                let p = anchors[j];
+                if p.first_end() <= m.first_pos && p.second_end() <= m.second_pos {
+                    let shift = (m.first_pos - p.first_end()).abs_diff(m.second_pos - p.second_end());
+                    scores[i] = scores[i].max(scores[j] + m.length as isize - (shift * penalty) as isize);
+                }
+            }
+        }
+        assert!(score(&chain, penalty) == scores.iter().cloned().max().unwrap_or(0));
+    }
+}
+
+#[test]
+fn synteny_bounds() {
+    let a = b"0123456789abcdefghijklmnopqrstuvwxyz";
+    assert!(synteny(a, a, AlgoSpec::HashMatch(4)) == 1.0);
+    assert!(synteny(b"", b"", AlgoSpec::HashMatch(4)) == 1.0);
+    assert!(synteny(a, b"zz", AlgoSpec::HashMatch(4)) == 0.0);
+    let shuffled = b"uvwxyzklmnopqrst0123456789abcdefghij";
+    let s = synteny(a, shuffled, AlgoSpec::TreeMatch(4));
+    assert!(s > 0.5 && s < 1.0);
+}
+
+#[test]
+fn chain_alignment() {
+    let mut rng = StdRng::from_seed(&[58usize][..]);
+    let a: Vec<u8> = (0..4000).map(|_| rng.gen::<u8>()).collect();
+    // Swap two blocks and mutate some bytes
+    let mut b = Vec::new();
+    b.extend_from_slice(&a[..1000]);
+    b.extend_from_slice(&a[3000..]);
+    b.extend_from_slice(&a[1000..3000]);
+    for _ in 0..20 {
+        let pos = rng.gen_range(0, b.len());
+        b[pos] = b[pos].wrapping_add(1);
+    }
+    for &unique_anchors in &[true, false] {
+        let options = ChainOptions { unique_anchors, gap_penalty: 0, ..Default::default() };
+        let alignment = chain_align(&a, &b, options);
+        assert!(alignment.anchors.windows(2).all(|w| w[0].first_end() <= w[1].first_pos && w[0].second_end() <= w[1].second_pos));
+        assert!(alignment.anchors.iter().all(|m| m.length >= 20 && a[m.first_pos..m.first_end()] == b[m.second_pos..m.second_end()]));
+        // The longest collinear content is the 3000 bytes kept in order
+        let covered: usize = alignment.anchors.iter().map(|m| m.length).sum();
+        assert!(covered > 2800 && covered <= 3000);
+        for gap in &alignment.gaps {
+            assert!(!gap.first.is_empty() && !gap.second.is_empty());
+            for m in &gap.matches {
+                assert!(m.first_pos >= gap.first.start && m.first_end() <= gap.first.end);
+                assert!(m.second_pos >= gap.second.start && m.second_end() <= gap.second.end);
+            }
+        }
+        // The gaps never overlap the anchors
+        let closed: usize = alignment.gaps.iter().flat_map(|g| g.matches.iter()).map(|m| m.length).sum();
+        assert!(covered + closed <= 4000);
+    }
+    let options = ChainOptions { anchors: AlgoSpec::HashMatch(8), gap_closing: None, ..Default::default() };
+    let alignment = chain_align(&a, &a, options);
+    assert!(alignment.anchors == [Match::new(0, 0, 4000)] && alignment.gaps.is_empty());
+    let alignment = chain_align(b"", b"abc", ChainOptions::default());
+    assert!(alignment.anchors.is_empty() && alignment.gaps.is_empty());
+}
@@ -0,0 +1,44 @@
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use {AlgoSpec, Match, MatchIterator, Region};
+use checkpoint::Checkpoint;
+
+#[test]
+fn checkpoint_resume() {
+    let mut rng = StdRng::from_seed(&[75usize][..]);
+    let a: Vec<u8> = (0..1500).map(|_| rng.gen_range(0, 4)).collect();
+    let b: Vec<u8> = (0..1000).map(|_| rng.gen_range(0, 4)).collect();
+    for &algo_spec in &[AlgoSpec::HashMatch(4), AlgoSpec::TreeMatch(5), AlgoSpec::SuffixArrayMatch(5)] {
+        let build = |kind: usize| match kind {
+            0 => MatchIterator::new(&a, &b, algo_spec),
+            1 => MatchIterator::with_regions(&a, Region::new(100, 1400), &b, Region::new(50, 900), algo_spec),
+            _ => MatchIterator::with_smaller_index(&b, &a, algo_spec),
+        };
+        for kind in 0..3 {
+            let full: Vec<Match> = build(kind).collect();
+            // Several matches start at the same position with such a small alphabet
+            assert!(full.windows(2).any(|w| w[0].second_pos == w[1].second_pos));
+            for k in (0..full.len() + 1).step_by(full.len() / 20 + 1).chain(Some(full.len())) {
+                let mut match_iter = build(kind);
+                for _ in 0..k {
+                    match_iter.next();
+                }
+                let mut saved = Vec::new();
+                match_iter.checkpoint().write_to(&mut saved).unwrap();
+                let mut resumed = build(kind);
+                resumed.resume(&Checkpoint::read_from(&mut &saved[..]).unwrap());
+                assert!(resumed.collect::<Vec<Match>>() == full[k..]);
+            }
+            // A checkpoint right after a seek resumes at the seek position
+            let mut match_iter = build(kind);
+            match_iter.seek(500);
+            let mut resumed = build(kind);
+            resumed.resume(&match_iter.checkpoint());
+            assert!(resumed.collect::<Vec<Match>>() == match_iter.collect::<Vec<Match>>());
+        }
+    }
+    let mut saved = Vec::new();
+    MatchIterator::new(&a, &b, AlgoSpec::TreeMatch(5)).checkpoint().write_to(&mut saved).unwrap();
+    saved[0] = b'X';
+    assert!(Checkpoint::read_from(&mut &saved[..]).is_err());
+}
@@ -0,0 +1,60 @@
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use {AlgoSpec, similar, similarity};
+use corpus::{closest, similarity_matrix};
+
+#[test]
+fn matrix_consistency() {
+    let mut rng = StdRng::from_seed(&[29usize][..]);
+    let base: Vec<u8> = (0..2000).map(|_| rng.gen::<u8>()).collect();
+    let mut samples = Vec::new();
+    for k in 0..6 {
+        let mut s = base.clone();
+        for _ in 0..k * 20 {
+            let pos = rng.gen_range(0, s.len());
+            s[pos] = rng.gen::<u8>();
+        }
+        samples.push(s);
+    }
+    samples.push(b"short".to_vec());
+    let refs: Vec<&[u8]> = samples.iter().map(|s| &s[..]).collect();
+    let matrix = similarity_matrix(&refs, AlgoSpec::HashMatch(8), 3);
+    let serial = similarity_matrix(&refs, AlgoSpec::HashMatch(8), 1);
+    assert!(matrix == serial);
+    for (i, row) in matrix.iter().enumerate() {
+        assert!(row[i] == 1.0);
+        assert!(row.iter().enumerate().all(|(j, &score)| score == matrix[j][i]));
+    }
+    assert!(matrix[0][1] == similarity(refs[0], refs[1], AlgoSpec::HashMatch(8)));
+    assert!(matrix[0][1] > matrix[0][5]);
+    assert!(matrix[0][6] == 0.0);
+}
+
+#[test]
+fn closest_samples() {
+    let mut rng = StdRng::from_seed(&[61usize][..]);
+    let query: Vec<u8> = (0..80).map(|_| rng.gen_range(b'a', b'i')).collect();
+    let mut samples = Vec::new();
+    for k in 0..40 {
+        let mut s = query.clone();
+        for _ in 0..k {
+            let pos = rng.gen_range(0, s.len());
+            s[pos] = rng.gen_range(b'a', b'z');
+        }
+        s.truncate(rng.gen_range(0, 81));
+        samples.push(s);
+    }
+    samples.push(Vec::new());
+    let refs: Vec<&[u8]> = samples.iter().map(|s| &s[..]).collect();
+    for &cutoff in &[0.0, 0.5, 0.8] {
+        for n in 0..5 {
+            // Ranking every candidate
+            let mut expected: Vec<(usize, f64)> = refs.iter().map(|s| similar(s, &query)).enumerate()
+                .filter(|&(_, score)| score >= cutoff).collect();
+            expected.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+            expected.truncate(n);
+            assert!(closest(&query, &refs, cutoff, n) == expected);
+        }
+    }
+    assert!(closest(b"", &[b""], 1.0, 1) == [(0, 1.0)]);
+}
@@ -0,0 +1,39 @@
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use dedup::{ChunkOptions, chunks, dedup_map};
+
+#[test]
+fn content_defined_chunks() {
+    let mut rng = StdRng::from_seed(&[66usize][..]);
+    let base: Vec<u8> = (0..200000).map(|_| rng.gen::<u8>()).collect();
+    let options = ChunkOptions { min_size: 256, average_size: 1024, max_size: 4096 };
+    let regions = chunks(&base, options);
+    assert!(regions.first().unwrap().start == 0 && regions.last().unwrap().end == base.len());
+    assert!(regions.windows(2).all(|w| w[0].end == w[1].start));
+    assert!(regions[..regions.len() - 1].iter().all(|r| r.len() >= 256 && r.len() <= 4096));
+    // An insertion at the start only changes the first chunks
+    let mut shifted = b"some inserted header".to_vec();
+    shifted.extend_from_slice(&base);
+    let map = dedup_map(&[&base, &shifted, &base], options);
+    let shared = map.buffers[1].iter().filter(|c| map.is_shared(c.id)).count();
+    assert!(shared + 3 >= map.buffers[1].len());
+    assert!(map.total_size() == 2 * base.len() + shifted.len());
+    assert!(map.deduplicated_size() < base.len() + 3 * 4096);
+    for (buffer, layout) in map.buffers.iter().enumerate() {
+        for chunk in layout {
+            assert!(map.contents[chunk.id].buffers.contains(&buffer));
+            assert!(map.contents[chunk.id].size == chunk.region.len());
+        }
+    }
+    // Same ids exactly for the same content
+    let data = [&base[..], &shifted[..]];
+    let all: Vec<(&[u8], usize)> = map.buffers[..2].iter().enumerate()
+        .flat_map(|(b, layout)| layout.iter().map(move |c| (&data[b][c.region.start..c.region.end], c.id)))
+        .collect();
+    for x in all.iter().step_by(7) {
+        for y in &all {
+            assert!((x.0 == y.0) == (x.1 == y.1));
+        }
+    }
+    assert!(chunks(b"", options).is_empty());
+}
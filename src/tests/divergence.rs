@@ -0,0 +1,51 @@
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use Region;
+use divergence::{divergence_map, first_difference, last_difference};
+
+// Naive divergence map: every differing offset, merged when separated by less than resync bytes
+fn naive_map(first: &[u8], second: &[u8], resync: usize) -> Vec<Region> {
+    let mut regions: Vec<Region> = Vec::new();
+    for i in 0..first.len().max(second.len()) {
+        if first.get(i) != second.get(i) {
+            match regions.last_mut() {
+                Some(last) if i < last.end + resync => last.end = i + 1,
+                _ => regions.push(Region::new(i, i + 1))
+            }
+        }
+    }
+    regions
+}
+
+#[test]
+fn divergence_naive() {
+    let mut rng = StdRng::from_seed(&[56usize][..]);
+    for _ in 0..50 {
+        let first: Vec<u8> = (0..rng.gen_range(0, 20000)).map(|_| rng.gen::<u8>()).collect();
+        let mut second = first.clone();
+        for _ in 0..rng.gen_range(0, 6) {
+            if second.is_empty() {
+                break;
+            }
+            // Bursts of modifications of various sizes
+            let start = rng.gen_range(0, second.len());
+            let end = (start + rng.gen_range(1, 3000)).min(second.len());
+            for x in &mut second[start..end] {
+                if rng.gen_range(0, 4) != 0 {
+                    *x = x.wrapping_add(1);
+                }
+            }
+        }
+        let new_len = second.len() + rng.gen_range(0, 10);
+        second.resize(new_len.saturating_sub(5), 0);
+        let differing: Vec<usize> = (0..first.len().max(second.len())).filter(|&i| first.get(i) != second.get(i)).collect();
+        assert!(first_difference(&first, &second) == differing.first().cloned());
+        assert!(last_difference(&first, &second) == differing.last().cloned());
+        for &resync in &[1, 2, 8, 100] {
+            assert!(divergence_map(&first, &second, resync) == naive_map(&first, &second, resync));
+            assert!(divergence_map(&second, &first, resync) == naive_map(&first, &second, resync));
+        }
+    }
+    assert!(divergence_map(b"", b"", 4).is_empty());
+    assert!(divergence_map(b"abc", b"", 4) == [Region::new(0, 3)]);
+}
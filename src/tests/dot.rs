@@ -0,0 +1,22 @@
+use {AlgoSpec, Match, patch_set};
+use dot::alignment_graph;
+
+#[test]
+fn alignment_blocks() {
+    let a = b"0123456789abcdefghijklmnopqrstuvwxyz";
+    let b = b"abcdefghij0123456789XXklmnopqrstuvwxyzabcdefghij";
+    let patches = patch_set(a, b, AlgoSpec::TreeMatch(4));
+    let dot = alignment_graph(&patches, a.len(), b.len());
+    // One labeled edge per patch
+    assert!(dot.lines().filter(|l| l.contains("->") && l.contains("label")).count() == patches.len());
+    // "abcdefghij" is copied twice from the same block, "XX" is new data
+    assert!(dot.contains("F_1 -> S_0 [ label = \"10\" ]"));
+    assert!(dot.contains("F_1 -> S_4 [ label = \"10\" ]"));
+    assert!(dot.contains("S_2 [ label = \"0x14..0x16\", style = filled ]"));
+    // Nothing copied: the whole first piece of data is unused
+    let dot = alignment_graph(&[], 16, 8);
+    assert!(dot.contains("F_0 [ label = \"0x0..0x10\", style = dashed ]"));
+    assert!(dot.contains("S_0 [ label = \"0x0..0x8\", style = filled ]"));
+    let dot = alignment_graph(&[Match::new(0, 0, 8)], 8, 8);
+    assert!(dot.contains("F_0 -> S_0 [ label = \"8\" ]"));
+}
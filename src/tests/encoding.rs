@@ -0,0 +1,37 @@
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use {AlgoSpec, Match, MatchIterator, patch_set};
+use encoding::{read_matches, write_matches};
+
+fn roundtrip(matches: &[Match]) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    write_matches(&mut encoded, matches).unwrap();
+    assert!(read_matches(&mut &encoded[..]).unwrap() == matches);
+    assert!(read_matches(&mut &encoded[..encoded.len() - 1]).is_err());
+    encoded
+}
+
+#[test]
+fn match_list_encoding() {
+    let mut rng = StdRng::from_seed(&[74usize][..]);
+    let a: Vec<u8> = (0..20000).map(|_| rng.gen_range(0, 4)).collect();
+    let b: Vec<u8> = (0..20000).map(|_| rng.gen_range(0, 4)).collect();
+    let matches: Vec<Match> = MatchIterator::new(&a, &b, AlgoSpec::TreeMatch(8)).collect();
+    let patches = patch_set(&a, &b, AlgoSpec::TreeMatch(8));
+    assert!(roundtrip(&matches).len() < 8 * matches.len());
+    assert!(roundtrip(&patches).len() < 8 * patches.len());
+    // Any order and extreme values
+    let mut shuffled = matches.clone();
+    rng.shuffle(&mut shuffled);
+    roundtrip(&shuffled);
+    roundtrip(&[]);
+    roundtrip(&[Match::new(usize::MAX, 0, 0), Match::new(0, usize::MAX - 5, 5), Match::new(3, 2, 1)]);
+    // Invalid data
+    let mut encoded = roundtrip(&patches);
+    encoded[7] = 2;
+    assert!(read_matches(&mut &encoded[..]).is_err());
+    let mut overflow = Vec::new();
+    write_matches(&mut overflow, &[Match::new(usize::MAX - 1, 0, 1)]).unwrap();
+    *overflow.last_mut().unwrap() = 2;
+    assert!(read_matches(&mut &overflow[..]).is_err());
+}
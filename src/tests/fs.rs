@@ -0,0 +1,101 @@
+use std::env::temp_dir;
+use std::fs::{create_dir_all, remove_dir_all, write};
+use std::path::Path;
+
+use AlgoSpec;
+use fs::{FileChange, diff_trees};
+
+fn make_tree(root: &Path, files: &[(&str, &[u8])]) {
+    for &(path, data) in files {
+        let path = root.join(path);
+        create_dir_all(path.parent().unwrap()).unwrap();
+        write(path, data).unwrap();
+    }
+}
+
+#[test]
+fn tree_diff() {
+    let root = temp_dir().join(format!("bcmp-tree-diff-{}", std::process::id()));
+    let old = root.join("old");
+    let new = root.join("new");
+    let _ = remove_dir_all(&root);
+    make_tree(&old, &[
+        ("same.txt", b"nothing changes here"),
+        ("lib/code.bin", b"abcdefghijklmnopqrstuvwxyz"),
+        ("moved.txt", b"this file will be moved somewhere else"),
+        ("gone.txt", b"deleted"),
+    ]);
+    make_tree(&new, &[
+        ("same.txt", b"nothing changes here"),
+        ("lib/code.bin", b"abcdefghijXXXXnopqrstuvwxyz"),
+        ("sub/dir/moved.txt", b"this file will be moved somewhere else!"),
+        ("new.txt", b"0123456789"),
+    ]);
+    let diff = diff_trees(&old, &new, AlgoSpec::TreeMatch(4), 0.5).unwrap();
+    remove_dir_all(&root).unwrap();
+
+    assert!(diff.changes.len() == 5);
+    assert!(diff.summary.added == 1);
+    assert!(diff.summary.removed == 1);
+    assert!(diff.summary.modified == 1);
+    assert!(diff.summary.renamed == 1);
+    assert!(diff.summary.unchanged == 1);
+    assert!(diff.summary.novel_bytes == 4 + 1 + 10);
+    let mut renamed = false;
+    for change in &diff.changes {
+        match *change {
+            FileChange::Modified { ref path, ref patches, novel } => {
+                assert!(path == Path::new("lib/code.bin"));
+                assert!(patches.len() == 2);
+                assert!(novel == 4);
+            },
+            FileChange::Renamed { ref from, ref to, similarity, .. } => {
+                assert!(from == Path::new("moved.txt"));
+                assert!(to == Path::new("sub/dir/moved.txt"));
+                assert!(similarity > 0.9);
+                renamed = true;
+            },
+            FileChange::Removed { ref path, size } => assert!(path == Path::new("gone.txt") && size == 7),
+            FileChange::Added { ref path, .. } => assert!(path == Path::new("new.txt")),
+            FileChange::Unchanged { ref path, .. } => assert!(path == Path::new("same.txt")),
+        }
+    }
+    assert!(renamed);
+}
+
+#[test]
+fn rename_candidates() {
+    let root = temp_dir().join(format!("bcmp-rename-candidates-{}", std::process::id()));
+    let old = root.join("old");
+    let new = root.join("new");
+    let _ = remove_dir_all(&root);
+    let repeated = b"abcd".repeat(100);
+    make_tree(&old, &[
+        ("a.txt", b"duplicated content"),
+        ("b.txt", b"duplicated content"),
+        ("big.bin", &repeated),
+    ]);
+    make_tree(&new, &[
+        ("c.txt", b"duplicated content"),
+        ("d.txt", b"duplicated content"),
+        ("small.bin", b"abcdabcd"),
+    ]);
+    let diff = diff_trees(&old, &new, AlgoSpec::TreeMatch(4), 0.5).unwrap();
+    remove_dir_all(&root).unwrap();
+
+    assert!(diff.summary.renamed == 2);
+    assert!(diff.summary.added == 1);
+    assert!(diff.summary.removed == 1);
+    for change in &diff.changes {
+        match *change {
+            FileChange::Renamed { ref from, ref to, similarity, .. } => {
+                assert!((from == Path::new("a.txt") && to == Path::new("c.txt")) || (from == Path::new("b.txt") && to == Path::new("d.txt")));
+                assert!(similarity == 1.0);
+            },
+            // Fully covered by the big file, but too small to be renamed from it
+            FileChange::Added { ref path, .. } => assert!(path == Path::new("small.bin")),
+            FileChange::Removed { ref path, .. } => assert!(path == Path::new("big.bin")),
+            _ => panic!("Unexpected change {:?}", change),
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use {AlgoSpec, Match, patch_set};
+use gen::{Generator, Mutations};
+
+// Return true if `small` can be obtained by deleting bytes of `large`
+fn is_subsequence(small: &[u8], large: &[u8]) -> bool {
+    let mut rest = large.iter();
+    small.iter().all(|b| rest.any(|c| c == b))
+}
+
+#[test]
+fn generated_data() {
+    let mut generator = Generator::new(1);
+    let base = generator.random(5000);
+    assert!(base.len() == 5000 && Generator::new(1).random(5000) == base);
+    assert!(Generator::new(2).random(5000) != base);
+    assert!(generator.random_alphabet(1000, 4).iter().all(|&b| b < 4));
+    let repetitive = generator.repetitive(1000, 7);
+    assert!(repetitive.len() == 1000 && repetitive[7..] == repetitive[..993]);
+    // Each kind of edit alone
+    assert!(generator.mutated(&base, Mutations { insert: 0.0, delete: 0.0, substitute: 0.0 }) == base);
+    let inserted = generator.mutated(&base, Mutations { insert: 0.02, delete: 0.0, substitute: 0.0 });
+    assert!(inserted.len() > 5050 && inserted.len() < 5150 && is_subsequence(&base, &inserted));
+    let deleted = generator.mutated(&base, Mutations { insert: 0.0, delete: 0.02, substitute: 0.0 });
+    assert!(deleted.len() > 4850 && deleted.len() < 4950 && is_subsequence(&deleted, &base));
+    let substituted = generator.mutated(&base, Mutations { insert: 0.0, delete: 0.0, substitute: 0.02 });
+    let changed = base.iter().zip(&substituted).filter(|&(a, b)| a != b).count();
+    assert!(substituted.len() == 5000 && changed > 50 && changed < 150);
+    // A shifted copy is a single match
+    let shifted = generator.shifted(&base, 13);
+    assert!(shifted.len() == 5013 && shifted[13..] == base[..]);
+    assert!(patch_set(&base, &shifted, AlgoSpec::TreeMatch(16)) == [Match::new(0, 13, 5000)]);
+}
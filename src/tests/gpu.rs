@@ -0,0 +1,66 @@
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use {AlgoSpec, coverage_stats};
+use corpus::{gpu_similarity_matrix, similarity_matrix};
+use gpu::Gpu;
+
+#[test]
+fn gpu_coverage_stats() {
+    let gpu = match Gpu::new() {
+        Some(gpu) => gpu,
+        None => return
+    };
+    let mut rng = StdRng::from_seed(&[94usize][..]);
+    for &alphabet in [4u8, 16, 255].iter() {
+        let a: Vec<u8> = (0..3000).map(|_| rng.gen_range(0, alphabet)).collect();
+        let mut b: Vec<u8> = a[500..2500].to_vec();
+        for _ in 0..40 {
+            let pos = rng.gen_range(0, b.len());
+            b[pos] = rng.gen_range(0, alphabet);
+        }
+        b.extend((0..500).map(|_| rng.gen_range(0, alphabet)));
+        for &algo_spec in [AlgoSpec::TreeMatch(3), AlgoSpec::TreeMatch(12), AlgoSpec::HashMatch(8),
+                           AlgoSpec::SuffixArrayMatch(5), AlgoSpec::Hybrid(8, 4)].iter() {
+            assert!(gpu.coverage_stats(&a, &b, algo_spec) == coverage_stats(&a, &b, algo_spec));
+        }
+    }
+    // Too many candidates, compared on the CPU
+    let zeros = vec![0u8; 2000];
+    assert!(gpu.coverage_stats(&zeros, &zeros, AlgoSpec::TreeMatch(4)) == coverage_stats(&zeros, &zeros, AlgoSpec::TreeMatch(4)));
+    assert!(gpu.coverage_stats(b"", b"", AlgoSpec::TreeMatch(4)).similarity() == 1.0);
+    assert!(gpu.coverage_stats(b"abc", b"abc", AlgoSpec::TreeMatch(4)).similarity() == 0.0);
+}
+
+#[test]
+fn gpu_extend() {
+    let gpu = match Gpu::new() {
+        Some(gpu) => gpu,
+        None => return
+    };
+    let a = b"0123456789abcdef";
+    let b = b"xx23456789abcdXX";
+    let lengths = gpu.extend(a, b, &[(2, 2), (0, 0), (10, 10), (16, 16), (15, 0), (13, 13)]);
+    assert!(lengths == [12, 0, 4, 0, 0, 1]);
+}
+
+#[test]
+fn gpu_matrix() {
+    let gpu = match Gpu::new() {
+        Some(gpu) => gpu,
+        None => return
+    };
+    let mut rng = StdRng::from_seed(&[95usize][..]);
+    let base: Vec<u8> = (0..2000).map(|_| rng.gen::<u8>()).collect();
+    let mut samples = Vec::new();
+    for k in 0..5 {
+        let mut s = base.clone();
+        for _ in 0..k * 20 {
+            let pos = rng.gen_range(0, s.len());
+            s[pos] = rng.gen::<u8>();
+        }
+        samples.push(s);
+    }
+    samples.push(b"short".to_vec());
+    let refs: Vec<&[u8]> = samples.iter().map(|s| &s[..]).collect();
+    assert!(gpu_similarity_matrix(&refs, AlgoSpec::HashMatch(8), &gpu, 3) == similarity_matrix(&refs, AlgoSpec::HashMatch(8), 1));
+}
@@ -0,0 +1,42 @@
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use {AlgoSpec, Match, MatchIterator};
+use grapheme::grapheme_matches;
+
+#[test]
+fn grapheme_boundaries() {
+    let mut rng = StdRng::from_seed(&[85usize][..]);
+    // Combining marks, regional indicators and zero width joiners glue characters together
+    let pieces = ["a", "e", "\u{301}", "\u{308}", "\u{1F1EB}", "\u{1F1F7}", "\u{200D}", "\u{1F469}", "é"];
+    for algo_spec in [AlgoSpec::HashMatch(2), AlgoSpec::TreeMatch(2), AlgoSpec::SuffixArrayMatch(2)] {
+        for _ in 0..20 {
+            let a: String = (0..200).map(|_| pieces[rng.gen_range(0, pieces.len())]).collect();
+            let b: String = (0..200).map(|_| pieces[rng.gen_range(0, pieces.len())]).collect();
+            let a_graphemes: Vec<&str> = a.graphemes(true).collect();
+            let b_graphemes: Vec<&str> = b.graphemes(true).collect();
+            for gm in grapheme_matches(&a, &b, algo_spec) {
+                let m = gm.bytes;
+                assert!(m.length >= 2);
+                assert!(a[m.first_pos..m.first_end()] == b[m.second_pos..m.second_end()]);
+                // The grapheme indices cover exactly the bytes of the match
+                assert!(a_graphemes[gm.first_graphemes.clone()].concat() == a[m.first_pos..m.first_end()]);
+                assert!(b_graphemes[gm.second_graphemes.clone()].concat() == b[m.second_pos..m.second_end()]);
+                assert!(a_graphemes[..gm.first_graphemes.start].concat().len() == m.first_pos);
+                assert!(b_graphemes[..gm.second_graphemes.start].concat().len() == m.second_pos);
+            }
+        }
+    }
+    // ASCII strings are matched like bytes, with a grapheme per byte
+    let a: String = (0..1000).map(|_| ['a', 'b', 'c'][rng.gen_range(0, 3)]).collect();
+    let b: String = (0..1000).map(|_| ['a', 'b', 'c'][rng.gen_range(0, 3)]).collect();
+    let matches: Vec<Match> = MatchIterator::new(a.as_bytes(), b.as_bytes(), AlgoSpec::TreeMatch(4)).collect();
+    let aligned: Vec<_> = grapheme_matches(&a, &b, AlgoSpec::TreeMatch(4)).collect();
+    assert!(aligned.iter().map(|gm| gm.bytes).collect::<Vec<Match>>() == matches);
+    assert!(aligned.iter().all(|gm| gm.first_graphemes == (gm.bytes.first_pos..gm.bytes.first_end())));
+    // A flag is never split, even if one of its regional indicators matches
+    let fr = "\u{1F1EB}\u{1F1F7}";
+    let fi = "\u{1F1EB}\u{1F1EE}";
+    assert!(grapheme_matches(fr, fi, AlgoSpec::TreeMatch(1)).count() == 0);
+}
@@ -0,0 +1,41 @@
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use {AlgoSpec, Match, MatchIterator};
+use guard::{GuardOptions, guarded};
+
+#[test]
+fn pathological_inputs() {
+    let mut rng = StdRng::from_seed(&[81usize][..]);
+    let options = GuardOptions::default();
+    // Random data is left alone
+    let a: Vec<u8> = (0..20000).map(|_| rng.gen::<u8>()).collect();
+    let b: Vec<u8> = a[5000..15000].to_vec();
+    let (match_iter, diagnostics) = guarded(&a, &b, AlgoSpec::HashMatch(4), options);
+    assert!(!diagnostics.is_degenerate() && diagnostics.candidates < 10);
+    assert!(match_iter.collect::<Vec<Match>>() == MatchIterator::new(&a, &b, AlgoSpec::HashMatch(4)).collect::<Vec<Match>>());
+    // A binary alphabet needs a longer key
+    let a: Vec<u8> = (0..20000).map(|_| rng.gen_range(0, 2)).collect();
+    let b: Vec<u8> = (0..10000).map(|_| rng.gen_range(0, 2)).collect();
+    let (match_iter, diagnostics) = guarded(&a, &b, AlgoSpec::HashMatch(4), options);
+    assert!(matches!(diagnostics.algo_spec, AlgoSpec::HashMatch(8)) && !diagnostics.skip_ahead);
+    assert!(diagnostics.candidates > options.max_candidates && diagnostics.final_candidates <= options.max_candidates);
+    assert!(match_iter.collect::<Vec<Match>>() == MatchIterator::new(&a, &b, AlgoSpec::HashMatch(8)).collect::<Vec<Match>>());
+    // Beyond the longest key of HashMatch
+    let unit: Vec<u8> = (0..100).map(|_| rng.gen::<u8>()).collect();
+    let mut a = Vec::new();
+    for _ in 0..3 {
+        a.extend((0..500).map(|_| rng.gen::<u8>()));
+        a.extend_from_slice(&unit);
+    }
+    let strict = GuardOptions { max_candidates: 2, ..options };
+    let (_, diagnostics) = guarded(&a, &a, AlgoSpec::HashMatch(48), strict);
+    assert!(matches!(diagnostics.algo_spec, AlgoSpec::SuffixArrayMatch(192)) && diagnostics.final_candidates == 1);
+    // Periodic data skips ahead
+    let zeros = vec![0u8; 50000];
+    let (match_iter, diagnostics) = guarded(&zeros, &zeros, AlgoSpec::SuffixArrayMatch(16), options);
+    assert!(matches!(diagnostics.algo_spec, AlgoSpec::TreeMatch(16)) && diagnostics.skip_ahead);
+    assert!(diagnostics.candidates == zeros.len() - 15);
+    assert!(match_iter.count() == zeros.len() - 15);
+    // Empty inputs
+    assert!(!guarded(b"", b"", AlgoSpec::TreeMatch(4), options).1.is_degenerate());
+}
@@ -0,0 +1,77 @@
+use std::env::temp_dir;
+use std::fs::remove_file;
+use std::io::ErrorKind;
+
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use hashindex::HashIndex;
+use hashmatch::HashMatchIterator;
+
+fn random_data(seed: usize, len: usize) -> Vec<u8> {
+    let mut rng = StdRng::from_seed(&[seed][..]);
+    (0..len).map(|_| rng.gen_range(0, 4)).collect()
+}
+
+#[test]
+fn hash_index_matches() {
+    let a = random_data(1, 3000);
+    let b = random_data(2, 3000);
+    let expected: Vec<_> = HashMatchIterator::<[u8;5]>::new(&a, &b).collect();
+    let index = HashIndex::new(&a, 5);
+    assert!(index.matches(&a, &b) == expected);
+    assert!(HashIndex::new(&a[..3], 5).matches(&a[..3], &b).is_empty());
+}
+
+#[test]
+fn hash_index_persistence() {
+    let a = random_data(3, 1000);
+    let b = random_data(4, 1000);
+    let path = temp_dir().join(format!("bcmp-index-{}.hi", std::process::id()));
+    HashIndex::new(&a, 6).save(&path).unwrap();
+    let loaded = HashIndex::open(&path).unwrap();
+    assert!(loaded.key_len() == 6 && loaded.data_len() == 1000);
+    assert!(loaded.matches(&a, &b) == HashMatchIterator::<[u8;6]>::new(&a, &b).collect::<Vec<_>>());
+    #[cfg(feature = "mmap")]
+    {
+        let mapped = HashIndex::map(&path).unwrap();
+        assert!(mapped.as_bytes() == loaded.as_bytes());
+    }
+    remove_file(&path).unwrap();
+    assert!(HashIndex::from_bytes(&loaded.as_bytes()[..30]).is_err());
+}
+
+#[test]
+fn oversized_index_header() {
+    let mut bytes = HashIndex::new(&random_data(4, 100), 4).as_bytes().to_vec();
+    bytes[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert!(HashIndex::from_bytes(&bytes[..]).is_err());
+    bytes[16..24].copy_from_slice(&(1u64 << 62).to_le_bytes());
+    assert!(HashIndex::from_bytes(&bytes[..]).is_err());
+}
+
+#[test]
+fn corrupted_index_positions() {
+    let a = random_data(5, 100);
+    let index = HashIndex::new(&a, 4);
+    assert!(index.check(&a).is_ok() && index.check(&a[1..]).is_err());
+    let bytes = index.as_bytes().to_vec();
+    let position = |i: usize| 24 + i * 8..32 + i * 8;
+    // A key extending beyond the data
+    let mut corrupted = bytes.clone();
+    corrupted[position(10)].copy_from_slice(&97u64.to_le_bytes());
+    let error = HashIndex::from_bytes(&corrupted[..]).err().unwrap();
+    assert!(error.kind() == ErrorKind::InvalidData);
+    corrupted[position(10)].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert!(HashIndex::from_bytes(&corrupted[..]).is_err());
+    // A position given twice
+    let mut corrupted = bytes.clone();
+    corrupted.copy_within(position(3), position(4).start);
+    assert!(HashIndex::from_bytes(&corrupted[..]).err().unwrap().kind() == ErrorKind::InvalidData);
+    // Two positions swapped, which only the data reveals
+    let mut corrupted = bytes.clone();
+    let first = corrupted[position(0)].to_vec();
+    corrupted.copy_within(position(96), position(0).start);
+    corrupted[position(96)].copy_from_slice(&first);
+    let swapped = HashIndex::from_bytes(&corrupted[..]).unwrap();
+    assert!(swapped.check(&a).err().unwrap().kind() == ErrorKind::InvalidData);
+}
@@ -0,0 +1,94 @@
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use {AlgoSpec, Match, MatchIterator, PreparedIndex, estimate_memory};
+use patch::{Compression, Delta, Metadata, PatchInfo};
+
+#[test]
+fn hybrid_matches() {
+    let mut rng = StdRng::from_seed(&[92usize][..]);
+    let a: Vec<u8> = (0..20000).map(|_| rng.gen_range(0, 16)).collect();
+    let mut b = a.clone();
+    for _ in 0..1000 {
+        let pos = rng.gen_range(0, b.len());
+        b[pos] = rng.gen_range(0, 16);
+    }
+    b.splice(8000..8000, (0..300).map(|_| rng.gen_range(0, 16)));
+    let algo_spec = AlgoSpec::Hybrid(16, 4);
+    let matches: Vec<Match> = MatchIterator::new(&a, &b, algo_spec).collect();
+    let mut tree: Vec<Match> = MatchIterator::new(&a, &b, AlgoSpec::TreeMatch(4)).collect();
+    tree.sort();
+    // A sorted subset of the matches of TreeMatch with all the long ones
+    assert!(matches.windows(2).all(|w| w[0] < w[1]));
+    assert!(matches.iter().all(|m| tree.binary_search(m).is_ok()));
+    let long: Vec<Match> = tree.iter().cloned().filter(|m| m.length >= 16).collect();
+    assert!(long == matches.iter().cloned().filter(|m| m.length >= 16).collect::<Vec<Match>>());
+    // The short matches between the substitutions are found
+    assert!(matches.iter().filter(|m| m.length < 16 && (m.second_pos == m.first_pos || m.second_pos == m.first_pos + 300)).count() > 100);
+    assert!(matches.len() < tree.len());
+    assert!(PreparedIndex::new(&a, algo_spec).matches(&b).collect::<Vec<Match>>() == matches);
+    assert!(estimate_memory(algo_spec, a.len(), b.len()).index == estimate_memory(AlgoSpec::HashMatch(16), a.len(), b.len()).index);
+    // The specification is kept in the metadata of a delta
+    let delta = Delta::new(&a, &b, algo_spec);
+    assert!(delta.apply(&a).unwrap() == b);
+    let mut encoded = Vec::new();
+    delta.write_with_metadata_to(&mut encoded, &Metadata::new(&a, &b, algo_spec), Compression::None).unwrap();
+    let info = PatchInfo::read(&mut &encoded[..]).unwrap();
+    assert!(matches!(info.metadata.unwrap().algo_spec, AlgoSpec::Hybrid(16, 4)));
+    assert!(AlgoSpec::Hybrid(16, 16).is_supported());
+    assert!(!AlgoSpec::Hybrid(16, 17).is_supported() && !AlgoSpec::Hybrid(9, 4).is_supported() && !AlgoSpec::Hybrid(8, 0).is_supported());
+}
+
+#[test]
+fn hybrid_skip_ahead() {
+    let mut rng = StdRng::from_seed(&[97usize][..]);
+    let mut a = Vec::new();
+    let mut b = Vec::new();
+    for _ in 0..50 {
+        let shared: Vec<u8> = (0..100).map(|_| rng.gen_range(0, 16)).collect();
+        a.extend_from_slice(&shared);
+        b.extend_from_slice(&shared);
+        // Between the anchors, the match of the end of each gap starts inside the match of its start
+        let gap: Vec<u8> = (0..8).map(|_| rng.gen_range(16, 255)).collect();
+        a.extend(gap[..4].iter().chain(&[255]).chain(&gap[2..]));
+        b.extend_from_slice(&gap);
+    }
+    let algo_spec = AlgoSpec::Hybrid(16, 4);
+    let all: Vec<Match> = MatchIterator::new(&a, &b, algo_spec).collect();
+    // The matches of a full iteration from second_pos which don't start inside a returned one
+    let greedy = |second_pos: usize| {
+        let mut expected = Vec::<Match>::new();
+        for m in all.iter().filter(|m| m.second_pos >= second_pos) {
+            let skip_to = expected.iter().map(|e| e.second_end()).max().unwrap_or(0);
+            if expected.last().is_none_or(|l| l.second_pos == m.second_pos || m.second_pos >= skip_to) {
+                expected.push(*m);
+            }
+        }
+        expected
+    };
+    let expected = greedy(0);
+    assert!(expected.len() < all.len());
+    let prepared = PreparedIndex::new(&a, algo_spec);
+    for match_iter in [MatchIterator::new(&a, &b, algo_spec), prepared.matches(&b)].iter() {
+        let mut match_iter = match_iter.clone().skip_ahead();
+        assert!(match_iter.clone().collect::<Vec<Match>>() == expected);
+        // Seeking back inside a skipped region restarts the skipping there
+        let middle = expected[expected.len() / 2];
+        let second_pos = middle.second_pos + 1;
+        match_iter.next();
+        match_iter.seek(second_pos);
+        assert!(match_iter.collect::<Vec<Match>>() == greedy(second_pos));
+    }
+}
+
+#[test]
+fn hybrid_smaller_index() {
+    // The anchors cross, the chain keeps one of them depending on which side is chained
+    let a = b"QRSTUVWXabccIJKLMNOP";
+    let b = b"IJKLMNOPQRSTUVWXbcc";
+    let algo_spec = AlgoSpec::Hybrid(8, 3);
+    let expected: Vec<Match> = MatchIterator::new(a, b, algo_spec).collect();
+    let mut swapped: Vec<Match> = MatchIterator::new(b, a, algo_spec).map(|m| Match::new(m.second_pos, m.first_pos, m.length)).collect();
+    swapped.sort();
+    assert!(swapped != expected);
+    assert!(MatchIterator::with_smaller_index(a, b, algo_spec).collect::<Vec<Match>>() == expected);
+}
@@ -0,0 +1,44 @@
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use {AlgoSpec, Match, MatchIterator};
+use incremental::{Edit, update_matches};
+
+#[test]
+fn incremental_equivalence() {
+    let mut rng = StdRng::from_seed(&[31usize][..]);
+    for round in 0..40 {
+        let algo_spec = if round % 2 == 0 { AlgoSpec::TreeMatch(4) } else { AlgoSpec::HashMatch(4) };
+        let first: Vec<u8> = (0..400).map(|_| rng.gen_range(0, 3)).collect();
+        let old: Vec<u8> = (0..400).map(|_| rng.gen_range(0, 3)).collect();
+        // Random non overlapping edits
+        let mut edits = Vec::new();
+        let mut new = Vec::new();
+        let mut cursor = 0;
+        while cursor < old.len() {
+            let position = cursor + rng.gen_range(0, 120);
+            if position >= old.len() {
+                break;
+            }
+            let removed = rng.gen_range(0, 6).min(old.len() - position);
+            let inserted = rng.gen_range(0, 6);
+            new.extend_from_slice(&old[cursor..position]);
+            new.extend((0..inserted).map(|_| rng.gen_range(0u8, 3)));
+            edits.push(Edit::new(position, removed, inserted));
+            cursor = position + removed;
+        }
+        new.extend_from_slice(&old[cursor..]);
+        let previous: Vec<Match> = MatchIterator::new(&first, &old, algo_spec).collect();
+        let mut expected: Vec<Match> = MatchIterator::new(&first, &new, algo_spec).collect();
+        expected.sort();
+        assert!(update_matches(&first, &new, &previous, &edits, algo_spec) == expected);
+    }
+}
+
+#[test]
+#[should_panic]
+fn incremental_hybrid() {
+    let first = b"abcdefghijklmnopqrstuvwxyz";
+    let old = b"__abcdefgh__mnopqrstu__";
+    let previous: Vec<Match> = MatchIterator::new(first, old, AlgoSpec::Hybrid(8, 3)).collect();
+    update_matches(first, b"__abcdefghijklmnopqrstu__", &previous, &[Edit::new(10, 2, 4)], AlgoSpec::Hybrid(8, 3));
+}
@@ -0,0 +1,56 @@
+use AlgoSpec;
+use coverage;
+use Region;
+use interval::IntervalSet;
+
+#[test]
+fn interval_insert() {
+    let mut set = IntervalSet::new();
+    set.insert(10, 20);
+    set.insert(30, 40);
+    set.insert(0, 5);
+    set.insert(5, 5);
+    assert!(set.regions() == [Region::new(0, 5), Region::new(10, 20), Region::new(30, 40)]);
+    set.insert(5, 10);
+    assert!(set.regions() == [Region::new(0, 20), Region::new(30, 40)]);
+    set.insert(15, 35);
+    assert!(set.regions() == [Region::new(0, 40)]);
+    assert!(set.contains(0));
+    assert!(set.contains(39));
+    assert!(!set.contains(40));
+}
+
+#[test]
+fn interval_algebra() {
+    let a: IntervalSet = vec![(0, 10), (20, 30), (40, 50)].into_iter().collect();
+    let b: IntervalSet = vec![(5, 25), (45, 60)].into_iter().collect();
+    assert!(a.union(&b).regions() == [Region::new(0, 30), Region::new(40, 60)]);
+    assert!(a.intersection(&b).regions() == [Region::new(5, 10), Region::new(20, 25), Region::new(45, 50)]);
+    assert!(a.difference(&b).regions() == [Region::new(0, 5), Region::new(25, 30), Region::new(40, 45)]);
+    assert!(b.difference(&a).regions() == [Region::new(10, 20), Region::new(50, 60)]);
+    assert!(a.complement(45).regions() == [Region::new(10, 20), Region::new(30, 40)]);
+    assert!(IntervalSet::new().complement(8).regions() == [Region::new(0, 8)]);
+    assert!(a.total_len() == 30);
+}
+
+#[test]
+fn coverage_minus_ignored() {
+    let a = "abcdefghijklmnopqrstuvwxyz";
+    let b = "abcdef01ghijklmnop3456qrstuvwxyz";
+    let ignored: IntervalSet = vec![(0, 4)].into_iter().collect();
+    let cov = coverage(a.as_bytes(), b.as_bytes(), AlgoSpec::TreeMatch(4));
+    assert!(cov.regions() == [Region::new(0, 6), Region::new(8, 18), Region::new(22, 32)]);
+    assert!(cov.difference(&ignored).total_len() == 22);
+}
+
+#[test]
+fn region() {
+    let data = "0123456789".as_bytes();
+    let r = Region::new(2, 6);
+    assert!(r.len() == 4);
+    assert!(!r.is_empty());
+    assert!(r.range() == (2..6));
+    assert!(r.bytes(data) == b"2345");
+    assert!(Region::from(3..3).is_empty());
+    assert!(format!("{}", Region::new(16, 32)) == "[0x10..0x20]");
+}
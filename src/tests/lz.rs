@@ -0,0 +1,31 @@
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use lz::{compress, decompress};
+
+#[test]
+fn lz_roundtrip() {
+    let mut rng = StdRng::from_seed(&[69usize][..]);
+    let words: [&[u8]; 6] = [b"delta ", b"patch ", b"source ", b"target ", b"match ", b"literal "];
+    let text: Vec<u8> = (0..3000).flat_map(|_| words[rng.gen_range(0, words.len())].iter().cloned()).collect();
+    let random: Vec<u8> = (0..5000).map(|_| rng.gen::<u8>()).collect();
+    let runs = vec![7u8; 10000];
+    for data in &[&text[..], &random[..], &runs[..], b"abc", b""] {
+        let compressed = compress(data);
+        assert!(decompress(&compressed, data.len()).unwrap() == *data);
+        assert!(decompress(&compressed, data.len() + 1).is_none());
+        if !data.is_empty() {
+            assert!(decompress(&compressed[..compressed.len() - 1], data.len()).is_none());
+        }
+    }
+    assert!(compress(&text).len() < text.len() / 2);
+    assert!(compress(&runs).len() < 16);
+    assert!(compress(&random).len() < random.len() + 16);
+    // Distance pointing before the start
+    assert!(decompress(&[1, b'a', 4, 2, 0, 0], 5).is_none());
+    // Lengths overflowing the output or 64 bits
+    let huge = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+    let overflowing: Vec<u8> = [1, b'a'].iter().chain(&huge).chain(&[1, 0]).cloned().collect();
+    assert!(decompress(&overflowing, 5).is_none());
+    let too_long: Vec<u8> = huge[..9].iter().chain(&[0x81, 0x01, 0]).cloned().collect();
+    assert!(decompress(&too_long, 5).is_none());
+}
@@ -1,20 +1,100 @@
 extern crate rand;
 
+mod anchor;
+#[cfg(feature = "async")]
+mod asyncmatch;
+#[cfg(feature = "bio")]
+mod bio;
+mod blame;
+mod bridge;
+mod chain;
+mod checkpoint;
+mod corpus;
+mod dedup;
+mod digest;
+mod divergence;
+mod dot;
+mod encoding;
+mod fs;
+#[cfg(feature = "gen")]
+mod gen;
+#[cfg(feature = "gpu")]
+mod gpu;
+#[cfg(feature = "grapheme")]
+mod grapheme;
+mod guard;
+mod hashindex;
+mod hybrid;
+mod incremental;
+mod interval;
+mod lz;
+#[cfg(feature = "naive")]
+mod naive;
+mod normalize;
+mod numeric;
+mod patch;
+mod probe;
+mod repeats;
+mod reverse;
+mod scatter;
+mod segmap;
+mod signature;
+mod source;
+mod stream;
+mod suffixarray;
+mod suffixtree;
+mod text;
+#[cfg(any(feature = "profiling", feature = "tracing"))]
+mod trace;
+mod treematch;
+mod walker;
+mod window;
+
 use AlgoSpec;
+use Circular;
+use circular_longest_common_substring;
+use Match;
+use MatchIterator;
+use sort_by_first;
+use sort_by_length_desc;
+use sort_by_second;
+use coverage;
+use coverage_stats;
+use diff_regions;
+use estimate_memory;
+use has_common_substring;
 use longest_common_substring;
+use longest_common_substring_at_least;
 use longest_common_substrings;
+use longest_common_substrings_by;
 use patch_set;
+use patience_patch_set;
+use provenance;
+use refined_patch_set;
+use similar;
+use PreparedIndex;
+use shortest_unique_substrings;
+use similarity;
 use unique_strings;
-use treematch::SuffixTree;
+use unique_strings_with_context;
+use warm_patch_set;
+use Region;
+use self::rand::{Rng, SeedableRng, StdRng};
+use std::cmp::Reverse;
+use suffixtree::SuffixTree;
 
+#[allow(clippy::redundant_static_lifetimes)]
 const ALGO_SPECS_4: &'static [AlgoSpec] = &[
     AlgoSpec::HashMatch(1), AlgoSpec::HashMatch(2), AlgoSpec::HashMatch(3), AlgoSpec::HashMatch(4),
     AlgoSpec::TreeMatch(1), AlgoSpec::TreeMatch(2), AlgoSpec::TreeMatch(3), AlgoSpec::TreeMatch(4),
+    AlgoSpec::SuffixArrayMatch(2), AlgoSpec::SuffixArrayMatch(4),
 ];
 
+#[allow(clippy::redundant_static_lifetimes)]
 const ALGO_SPECS_8: &'static [AlgoSpec] = &[
     AlgoSpec::HashMatch(1), AlgoSpec::HashMatch(2), AlgoSpec::HashMatch(4), AlgoSpec::HashMatch(8),
     AlgoSpec::TreeMatch(1), AlgoSpec::TreeMatch(2), AlgoSpec::TreeMatch(4), AlgoSpec::TreeMatch(8),
+    AlgoSpec::SuffixArrayMatch(2), AlgoSpec::SuffixArrayMatch(8),
 ];
 
 #[test]
@@ -29,6 +109,94 @@ fn lcs() {
     }
 }
 
+#[test]
+fn lcs_early_exit() {
+    let a = "abcdefghijklmnopqrstuvwxyz";
+    let b = "rstufghijklmnopqvwxyzabcde";
+    for algo_spec in ALGO_SPECS_4 {
+        let m = longest_common_substring_at_least(a.as_bytes(), b.as_bytes(), *algo_spec, 5);
+        assert!(m == Some(Match::new(5, 4, 12)));
+        let m = longest_common_substring_at_least(a.as_bytes(), b.as_bytes(), *algo_spec, 4);
+        assert!(m == Some(Match::new(17, 0, 4)));
+        assert!(longest_common_substring_at_least(a.as_bytes(), b.as_bytes(), *algo_spec, 13).is_none());
+        // A match covering all of second stops the search
+        let m = longest_common_substring(a.as_bytes(), b"klmnop", *algo_spec);
+        assert!(m == Match::new(10, 0, 6));
+    }
+}
+
+#[test]
+fn common_substring_check() {
+    let mut rng = StdRng::from_seed(&[55usize][..]);
+    for _ in 0..20 {
+        let a: Vec<u8> = (0..rng.gen_range(0, 200)).map(|_| rng.gen_range(0, 4)).collect();
+        let b: Vec<u8> = (0..rng.gen_range(0, 200)).map(|_| rng.gen_range(0, 4)).collect();
+        let longest = if a.is_empty() || b.is_empty() { 0 } else { longest_common_substring(&a, &b, AlgoSpec::TreeMatch(1)).length };
+        for min_length in 0..longest + 3 {
+            assert!(has_common_substring(&a, &b, min_length) == (min_length <= longest));
+            assert!(has_common_substring(&b, &a, min_length) == (min_length <= longest));
+        }
+    }
+}
+
+// Straightforward Ratcliff-Obershelp, as implemented by difflib
+fn naive_similar(a: &[u8], b: &[u8]) -> f64 {
+    fn matched(a: &[u8], b: &[u8]) -> usize {
+        let mut best = (0, 0, 0);
+        for i in 0..a.len() {
+            for j in 0..b.len() {
+                let length = a[i..].iter().zip(&b[j..]).take_while(|(x, y)| x == y).count();
+                if length > best.2 {
+                    best = (i, j, length);
+                }
+            }
+        }
+        let (i, j, length) = best;
+        if length == 0 {
+            return 0;
+        }
+        length + matched(&a[..i], &b[..j]) + matched(&a[i + length..], &b[j + length..])
+    }
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    (2 * matched(a, b)) as f64 / (a.len() + b.len()) as f64
+}
+
+#[test]
+fn ratcliff_obershelp() {
+    // Values computed by difflib
+    assert!(similar(b"private Thread currentThread;", b"private volatile Thread currentThread;") == 58.0 / 67.0);
+    assert!(similar(b"", b"") == 1.0);
+    assert!(similar(b"abc", b"") == 0.0);
+    let mut rng = StdRng::from_seed(&[60usize][..]);
+    for _ in 0..50 {
+        let a: Vec<u8> = (0..rng.gen_range(0, 60)).map(|_| rng.gen_range(0, 3)).collect();
+        let b: Vec<u8> = (0..rng.gen_range(0, 60)).map(|_| rng.gen_range(0, 3)).collect();
+        assert!(similar(&a, &b) == naive_similar(&a, &b));
+        assert!(similar(&a, &a) == 1.0);
+    }
+}
+
+#[test]
+fn circular_lcs() {
+    let a = "0123456789abcdef";
+    let b = "cdef0123XX";
+    let m = longest_common_substring(a.as_bytes(), b.as_bytes(), AlgoSpec::TreeMatch(2));
+    assert!(m.length == 4);
+    let m = circular_longest_common_substring(a.as_bytes(), b.as_bytes(), AlgoSpec::TreeMatch(2), Circular::First);
+    assert!(m == Match::new(12, 0, 8));
+    let m = circular_longest_common_substring(a.as_bytes(), b.as_bytes(), AlgoSpec::HashMatch(2), Circular::Second);
+    assert!(m.length == 4);
+    let b = "456789abcdef0123";
+    let m = circular_longest_common_substring(a.as_bytes(), b.as_bytes(), AlgoSpec::HashMatch(4), Circular::Both);
+    assert!(m.length == 16);
+    assert!((m.second_pos + 4) % 16 == m.first_pos);
+    // A circular piece of data cannot match more than its own length
+    let m = circular_longest_common_substring("ab".as_bytes(), "abababab".as_bytes(), AlgoSpec::TreeMatch(1), Circular::First);
+    assert!(m.length == 2);
+}
+
 #[test]
 fn lcss() {
     let a = "abcdefghijklmnopqrstuvwxyz";
@@ -51,6 +219,45 @@ fn lcss() {
     }
 }
 
+#[test]
+fn lcss_by() {
+    let a = "abcdefghijklmnopqrstuvwxyz";
+    let b = "rstufghijklmnopqvwxyzabcde";
+    for algo_spec in ALGO_SPECS_4 {
+        let by_length = longest_common_substrings_by(a.as_bytes(), b.as_bytes(), *algo_spec, 10, |m| m.length as u64);
+        assert!(by_length == longest_common_substrings(a.as_bytes(), b.as_bytes(), *algo_spec, 10));
+        // Favor the matches with the smallest offset between both positions
+        let by_offset = longest_common_substrings_by(a.as_bytes(), b.as_bytes(), *algo_spec, 2, |m| {
+            100 - (m.first_pos as isize - m.second_pos as isize).unsigned_abs() as u64
+        });
+        assert!(by_offset == [Match::new(5, 4, 12), Match::new(21, 16, 5)]);
+    }
+}
+
+#[test]
+fn lcss_top_k() {
+    let mut rng = StdRng::from_seed(&[54usize][..]);
+    let a: Vec<u8> = (0..600).map(|_| rng.gen_range(0, 4)).collect();
+    let b: Vec<u8> = (0..600).map(|_| rng.gen_range(0, 4)).collect();
+    let mut expected = Vec::new();
+    for algo_spec in ALGO_SPECS_8 {
+        let mut all: Vec<Match> = MatchIterator::new(&a, &b, *algo_spec).collect();
+        all.sort_by_key(|&m| Reverse((m.length, m)));
+        all.truncate(1000);
+        let top = longest_common_substrings(&a, &b, *algo_spec, 1000);
+        assert!(top == all);
+        // Ties are broken the same way whatever the algorithm
+        if algo_spec.minimal_length() == 1 {
+            if expected.is_empty() {
+                expected = top;
+            }
+            else {
+                assert!(top == expected);
+            }
+        }
+    }
+}
+
 #[test]
 fn ps1() {
     let a = "abcdefghijqrstuvwxyzfghijklmnopqr";
@@ -106,6 +313,107 @@ fn ps3() {
     }
 }
 
+#[test]
+fn patience_patches() {
+    let mut rng = StdRng::from_seed(&[59usize][..]);
+    // Records sharing a common header, some of them edited, removed or inserted
+    let records: Vec<Vec<u8>> = (0..50).map(|i| {
+        let mut record = b"<record>".to_vec();
+        record.extend((0..rng.gen_range(5, 40)).map(|_| rng.gen_range(b'a', b'z')));
+        record.push(i as u8);
+        record
+    }).collect();
+    let a: Vec<u8> = records.concat();
+    let mut b = Vec::new();
+    for record in &records {
+        match rng.gen_range(0, 6) {
+            0 => continue,
+            1 => b.extend_from_slice(b"<record>inserted"),
+            2 => b.extend_from_slice(&record[..record.len() / 2]),
+            _ => {}
+        }
+        b.extend_from_slice(record);
+    }
+    for algo_spec in ALGO_SPECS_8 {
+        let patches = patience_patch_set(&a, &b, *algo_spec);
+        assert!(patches.windows(2).all(|w| w[0].first_end() <= w[1].first_pos && w[0].second_end() <= w[1].second_pos));
+        assert!(patches.iter().all(|m| m.length >= algo_spec.minimal_length() && a[m.first_pos..m.first_end()] == b[m.second_pos..m.second_end()]));
+        // Most of the unchanged records are kept
+        let covered: usize = patches.iter().map(|m| m.length).sum();
+        assert!(covered * 10 > b.len() * 6);
+    }
+    assert!(patience_patch_set(b"abc", b"", AlgoSpec::TreeMatch(2)).is_empty());
+}
+
+#[test]
+fn provenance_map() {
+    let mut rng = StdRng::from_seed(&[62usize][..]);
+    let a: Vec<u8> = (0..3000).map(|_| rng.gen::<u8>()).collect();
+    let mut b = Vec::new();
+    while b.len() < 3000 {
+        if rng.gen_range(0, 3) == 0 {
+            b.extend((0..rng.gen_range(1, 30)).map(|_| rng.gen::<u8>()));
+        }
+        else {
+            let start = rng.gen_range(0, a.len() - 50);
+            b.extend_from_slice(&a[start..start + rng.gen_range(5, 50)]);
+        }
+    }
+    for algo_spec in ALGO_SPECS_4 {
+        let map = provenance(&a, &b, *algo_spec);
+        let covered = coverage(&a, &b, *algo_spec);
+        assert!(map.len() == b.len());
+        for (i, source) in map.iter().enumerate() {
+            assert!(source.is_some() == covered.contains(i));
+            assert!(source.is_none_or(|o| a[o] == b[i]));
+        }
+    }
+}
+
+#[test]
+fn shorter_than_key() {
+    let data = b"0123456789abcdef";
+    for algo_spec in &[AlgoSpec::HashMatch(4), AlgoSpec::HashMatch(8), AlgoSpec::HashMatch(16)] {
+        for short in 0..algo_spec.minimal_length() {
+            let short_data = &data[..short];
+            assert!(MatchIterator::new(short_data, data, *algo_spec).next().is_none());
+            assert!(MatchIterator::new(data, short_data, *algo_spec).next().is_none());
+            assert!(MatchIterator::new(data, short_data, *algo_spec).from_end().next().is_none());
+            assert!(PreparedIndex::new(short_data, *algo_spec).patch_set(data).is_empty());
+            assert!(patch_set(data, short_data, *algo_spec).is_empty());
+            assert!(longest_common_substring(short_data, data, *algo_spec).length == 0);
+            assert!(unique_strings(data, short_data, *algo_spec) == [Region::new(0, short)].iter().filter(|r| !r.is_empty()).cloned().collect::<Vec<_>>());
+            assert!(similarity(data, short_data, *algo_spec) == 0.0);
+        }
+        // The key length is enough
+        let m = longest_common_substring(data, &data[..algo_spec.minimal_length()], *algo_spec);
+        assert!(m == Match::new(0, 0, algo_spec.minimal_length()));
+    }
+}
+
+#[test]
+fn empty_inputs() {
+    let data = b"0123456789abcdef";
+    for algo_spec in ALGO_SPECS_4.iter().chain(ALGO_SPECS_8) {
+        for &(first, second) in &[(&b""[..], &data[..]), (&data[..], &b""[..]), (&b""[..], &b""[..])] {
+            assert!(MatchIterator::new(first, second, *algo_spec).next().is_none());
+            assert!(PreparedIndex::new(first, *algo_spec).matches(second).next().is_none());
+            assert!(patch_set(first, second, *algo_spec).is_empty());
+            assert!(longest_common_substring(first, second, *algo_spec).length == 0);
+            let diff = diff_regions(first, second, *algo_spec);
+            let whole = |d: &[u8]| if d.is_empty() { vec![] } else { vec![Region::new(0, d.len())] };
+            assert!(diff.only_first == whole(first) && diff.only_second == whole(second));
+            assert!(unique_strings(first, second, *algo_spec) == whole(second));
+            let expected = if first.is_empty() && second.is_empty() { 1.0 } else { 0.0 };
+            assert!(similarity(first, second, *algo_spec) == expected);
+            assert!(coverage_stats(first, second, *algo_spec).first_covered == 0);
+        }
+    }
+    let empty: &[u8] = b"";
+    let tree = SuffixTree::new(empty);
+    assert!(tree.data_len() == 0 && tree.to_suffix_array().is_empty());
+}
+
 #[test]
 fn us1() {
     let a = "abcdefghijklmnopqrstuvwxyz";
@@ -113,10 +421,10 @@ fn us1() {
     for algo_spec in ALGO_SPECS_4 {
         let us = unique_strings(a.as_bytes(), b.as_bytes(), *algo_spec);
         assert!(us.len() == 2);
-        assert!(us[0].0 == 6);
-        assert!(us[0].1 == 8);
-        assert!(us[1].0 == 18);
-        assert!(us[1].1 == 22);
+        assert!(us[0].start == 6);
+        assert!(us[0].end == 8);
+        assert!(us[1].start == 18);
+        assert!(us[1].end == 22);
     }
 }
 
@@ -127,8 +435,8 @@ fn us2() {
     for algo_spec in ALGO_SPECS_4 {
         let us = unique_strings(a.as_bytes(), b.as_bytes(), *algo_spec);
         assert!(us.len() == 1);
-        assert!(us[0].0 == 0);
-        assert!(us[0].1 == 5);
+        assert!(us[0].start == 0);
+        assert!(us[0].end == 5);
     }
 }
 
@@ -139,25 +447,80 @@ fn us3() {
     for algo_spec in ALGO_SPECS_4 {
         let us = unique_strings(a.as_bytes(), b.as_bytes(), *algo_spec);
         assert!(us.len() == 2);
-        assert!(us[0].0 == 0);
-        assert!(us[0].1 == 4);
-        assert!(us[1].0 == 8);
-        assert!(us[1].1 == 12);
+        assert!(us[0].start == 0);
+        assert!(us[0].end == 4);
+        assert!(us[1].start == 8);
+        assert!(us[1].end == 12);
     }
 }
 
 #[test]
+#[allow(clippy::len_zero)]
 fn us4() {
     let a = "abcdefghijklmnopqrstuvwxyz";
     let b = "abcdefghaxelionijklmnopqrstuvwxyz";
     let us = unique_strings(a.as_bytes(), b.as_bytes(), AlgoSpec::HashMatch(4));
     assert!(us.len() == 1);
-    assert!(us[0].0 == 8);
-    assert!(us[0].1 == 15);
+    assert!(us[0].start == 8);
+    assert!(us[0].end == 15);
     let us = unique_strings(a.as_bytes(), b.as_bytes(), AlgoSpec::HashMatch(1));
     assert!(us.len() == 0);
 }
 
+#[test]
+fn us_context() {
+    let a = "abcdefghijklmnopqrstuvwxyz";
+    let b = "abcdef01ghijklmnop3456qrstuvwxyz";
+    for algo_spec in ALGO_SPECS_4 {
+        let us = unique_strings_with_context(a.as_bytes(), b.as_bytes(), *algo_spec);
+        assert!(us.len() == 2);
+        assert!(us[0].region == Region::new(6, 8));
+        assert!(us[0].before == Some(Match::new(0, 0, 6)));
+        assert!(us[0].after == Some(Match::new(6, 8, 10)));
+        assert!(us[0].replaced() == Some(Region::new(6, 6)));
+        assert!(us[1].region == Region::new(18, 22));
+        assert!(us[1].before == Some(Match::new(6, 8, 10)));
+        assert!(us[1].after == Some(Match::new(16, 22, 10)));
+    }
+    let b = "12abcdefXYZ";
+    let us = unique_strings_with_context(a.as_bytes(), b.as_bytes(), AlgoSpec::TreeMatch(4));
+    assert!(us.len() == 2);
+    assert!(us[0].before.is_none());
+    assert!(us[0].after == Some(Match::new(0, 2, 6)));
+    assert!(us[1].region == Region::new(8, 11));
+    assert!(us[1].after.is_none());
+    assert!(us[1].replaced().is_none());
+}
+
+#[test]
+fn diff_both_ways() {
+    let a = "abcdefXXghijklmnopqrstuvYYYYwxyz";
+    let b = "abcdef01ghijklmnop3456qrstuvwxyz";
+    for algo_spec in ALGO_SPECS_4 {
+        let diff = diff_regions(a.as_bytes(), b.as_bytes(), *algo_spec);
+        assert!(diff.only_second == unique_strings(a.as_bytes(), b.as_bytes(), *algo_spec));
+        assert!(diff.only_second == vec![Region::new(6, 8), Region::new(18, 22)]);
+        assert!(diff.only_first == vec![Region::new(6, 8), Region::new(24, 28)]);
+    }
+}
+
+#[test]
+fn sus() {
+    let a = "abcdefghijklmnopqrstuvwxyz";
+    let b = "abcXdefgh";
+    let us = shortest_unique_substrings(a.as_bytes(), b.as_bytes(), 1);
+    assert!(us == vec![Region::new(3, 4)]);
+    let us = shortest_unique_substrings(a.as_bytes(), b.as_bytes(), 3);
+    assert!(us == vec![Region::new(1, 4), Region::new(2, 5), Region::new(3, 6)]);
+    let b = "acegi";
+    let us = shortest_unique_substrings(a.as_bytes(), b.as_bytes(), 1);
+    assert!(us == vec![Region::new(0, 2), Region::new(1, 3), Region::new(2, 4), Region::new(3, 5)]);
+    let us = shortest_unique_substrings(a.as_bytes(), "defg".as_bytes(), 1);
+    assert!(us.is_empty());
+    let us = shortest_unique_substrings(&[], "ab".as_bytes(), 1);
+    assert!(us == vec![Region::new(0, 1), Region::new(1, 2)]);
+}
+
 #[test]
 fn stree() {
     let a = "ABABABC";
@@ -181,6 +544,7 @@ fn random_compare() {
 }
 
 #[test]
+#[allow(clippy::same_item_push)]
 fn motif_compare() {
     let mut motif = Vec::<u8>::new();
     for i in 1..20 {
@@ -199,3 +563,471 @@ fn motif_compare() {
         }
     }
 }
+
+#[test]
+fn match_ordering() {
+    assert!(Match::new(5, 0, 3) < Match::new(0, 1, 3));
+    assert!(Match::new(0, 1, 3) < Match::new(2, 1, 1));
+    assert!(Match::new(2, 1, 1) < Match::new(2, 1, 4));
+    let a = "abcdefghijklmnopqrstuvwxyz";
+    let b = "rstufghijklmnopqvwxyzabcde";
+    for algo_spec in ALGO_SPECS_4 {
+        let mut ms: Vec<Match> = MatchIterator::new(a.as_bytes(), b.as_bytes(), *algo_spec).collect();
+        sort_by_length_desc(&mut ms);
+        assert!(ms[0] == Match::new(5, 4, 12));
+        assert!(ms[1] == Match::new(21, 16, 5));
+        assert!(ms[2] == Match::new(0, 21, 5));
+        sort_by_first(&mut ms);
+        assert!(ms[0] == Match::new(0, 21, 5));
+        assert!(ms.windows(2).all(|w| w[0].first_pos <= w[1].first_pos));
+        sort_by_second(&mut ms);
+        assert!(ms[0] == Match::new(17, 0, 4));
+        assert!(ms.windows(2).all(|w| w[0] <= w[1]));
+    }
+}
+
+fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+#[test]
+fn threaded_iteration() {
+    let a = "abcdefghijklmnopqrstuvwxyz";
+    let b = "rstufghijklmnopqvwxyzabcde";
+    for algo_spec in ALGO_SPECS_4 {
+        let iter = MatchIterator::new(a.as_bytes(), b.as_bytes(), *algo_spec);
+        assert_send_sync(&iter);
+        let expected: Vec<Match> = MatchIterator::new(a.as_bytes(), b.as_bytes(), *algo_spec).collect();
+        let collected = std::thread::scope(|s| s.spawn(move || iter.collect::<Vec<Match>>()).join().unwrap());
+        assert!(collected == expected);
+    }
+}
+
+#[test]
+fn forked_iteration() {
+    let a = "abcdefghijklmnopqrstuvwxyz";
+    let b = "rstufghijklmnopqvwxyzabcde";
+    for algo_spec in ALGO_SPECS_4 {
+        let mut iter = MatchIterator::new(a.as_bytes(), b.as_bytes(), *algo_spec);
+        let head = iter.next();
+        let fork = iter.clone();
+        let rest: Vec<Match> = iter.collect();
+        assert!(head.is_some() && !rest.is_empty());
+        assert!(fork.collect::<Vec<Match>>() == rest);
+    }
+}
+
+#[test]
+fn iterator_bounds() {
+    let a = "abcdefghijklmnopqrstuvwxyz";
+    let b = "rstufghijklmnopqvwxyzabcde";
+    for algo_spec in ALGO_SPECS_4 {
+        let mut iter = MatchIterator::new(a.as_bytes(), b.as_bytes(), *algo_spec).fuse();
+        let mut count = 0;
+        loop {
+            let (_, upper) = iter.size_hint();
+            let remaining = iter.clone().count();
+            assert!(upper.unwrap() >= remaining);
+            if iter.next().is_none() {
+                break;
+            }
+            count += 1;
+        }
+        assert!(count > 0);
+        assert!(iter.next().is_none());
+        assert!(iter.size_hint().1.unwrap() == 0);
+    }
+}
+
+#[test]
+fn matched_slices() {
+    let a = "abcdefghijklmnopqrstuvwxyz";
+    let b = "rstufghijklmnopqvwxyzabcde";
+    for algo_spec in ALGO_SPECS_4 {
+        let matches: Vec<Match> = MatchIterator::new(a.as_bytes(), b.as_bytes(), *algo_spec).collect();
+        let slices: Vec<_> = MatchIterator::new(a.as_bytes(), b.as_bytes(), *algo_spec).slices().collect();
+        assert!(slices.len() == matches.len());
+        for (s, m) in slices.iter().zip(matches.iter()) {
+            assert!(s.m == *m);
+            assert!(s.first == &a.as_bytes()[m.first_pos..m.first_end()]);
+            assert!(s.first == s.second);
+        }
+    }
+}
+
+#[test]
+fn maximal_matches() {
+    let mut rng = StdRng::from_seed(&[46usize][..]);
+    for algo_spec in ALGO_SPECS_4 {
+        let a: Vec<u8> = (0..300).map(|_| rng.gen_range(0, 3)).collect();
+        let b: Vec<u8> = (0..300).map(|_| rng.gen_range(0, 3)).collect();
+        let mut mems: Vec<Match> = MatchIterator::new(&a, &b, *algo_spec).maximal().collect();
+        mems.sort();
+        // Naive enumeration of the maximal exact matches
+        let mut expected = Vec::new();
+        for i in 0..a.len() {
+            for j in 0..b.len() {
+                if i > 0 && j > 0 && a[i - 1] == b[j - 1] {
+                    continue;
+                }
+                let length = a[i..].iter().zip(&b[j..]).take_while(|&(x, y)| x == y).count();
+                if length >= algo_spec.minimal_length() {
+                    expected.push(Match::new(i, j, length));
+                }
+            }
+        }
+        expected.sort();
+        assert!(mems == expected);
+        // Every backend only returns maximal matches
+        assert!(MatchIterator::new(&a, &b, *algo_spec).all(|m| m.is_maximal(&a, &b)));
+        assert!(MatchIterator::new(&a, &b, *algo_spec).count() == mems.len());
+    }
+    assert!(Match::new(1, 1, 4).is_maximal(b"xabcdy", b"zabcdw"));
+    assert!(!Match::new(2, 2, 3).is_maximal(b"xabcdy", b"zabcdw"));
+    assert!(!Match::new(1, 1, 3).is_maximal(b"xabcdy", b"zabcdw"));
+    assert!(Match::new(0, 3, 2).is_maximal(b"ab", b"xyzab"));
+}
+
+#[test]
+fn all_occurrences() {
+    let mut rng = StdRng::from_seed(&[50usize][..]);
+    for algo_spec in ALGO_SPECS_4 {
+        let a: Vec<u8> = (0..200).map(|_| rng.gen_range(0, 3)).collect();
+        let b: Vec<u8> = (0..200).map(|_| rng.gen_range(0, 3)).collect();
+        let found: Vec<Match> = MatchIterator::new(&a, &b, *algo_spec).all_occurrences().collect();
+        // Every pair of positions sharing at least the minimal length
+        let mut expected = Vec::new();
+        for j in 0..b.len() {
+            for i in 0..a.len() {
+                let length = a[i..].iter().zip(&b[j..]).take_while(|&(x, y)| x == y).count();
+                if length >= algo_spec.minimal_length() {
+                    expected.push(Match::new(i, j, length));
+                }
+            }
+        }
+        assert!(found == expected);
+    }
+}
+
+#[test]
+fn from_end() {
+    let mut rng = StdRng::from_seed(&[51usize][..]);
+    for algo_spec in ALGO_SPECS_4 {
+        let a: Vec<u8> = (0..300).map(|_| rng.gen_range(0, 16)).collect();
+        let mut b: Vec<u8> = (0..6000).map(|_| rng.gen_range(0, 16)).collect();
+        // A long stretch without any match
+        for x in &mut b[1000..5000] {
+            *x = 0xff;
+        }
+        for match_iter in [MatchIterator::new(&a, &b, *algo_spec), MatchIterator::with_smaller_index(&b, &a, *algo_spec)] {
+            let mut expected: Vec<Match> = match_iter.clone().collect();
+            expected.sort_by(|x, y| y.cmp(x));
+            let found: Vec<Match> = match_iter.from_end().collect();
+            assert!(found == expected);
+        }
+    }
+}
+
+#[test]
+fn region_restricted() {
+    let mut rng = StdRng::from_seed(&[52usize][..]);
+    for algo_spec in ALGO_SPECS_4 {
+        let a: Vec<u8> = (0..300).map(|_| rng.gen_range(0, 3)).collect();
+        let b: Vec<u8> = (0..300).map(|_| rng.gen_range(0, 3)).collect();
+        let (first_region, second_region) = (Region::new(40, 250), Region::new(100, 220));
+        let expected: Vec<Match> = MatchIterator::new(first_region.bytes(&a), second_region.bytes(&b), *algo_spec)
+            .map(|m| Match::new(m.first_pos + 40, m.second_pos + 100, m.length))
+            .collect();
+        let match_iter = MatchIterator::with_regions(&a, first_region, &b, second_region, *algo_spec);
+        assert!(match_iter.clone().collect::<Vec<Match>>() == expected);
+        assert!(match_iter.clone().maximal().count() == expected.len());
+        assert!(match_iter.clone().slices().all(|s| s.first == &a[s.m.first_pos..s.m.first_end()] && s.first == s.second));
+        let mut reversed: Vec<Match> = match_iter.clone().from_end().collect();
+        reversed.reverse();
+        let mut sorted = expected.clone();
+        sorted.sort();
+        assert!(reversed == sorted);
+        let mut match_iter = match_iter;
+        match_iter.seek(150);
+        assert!(match_iter.eq(expected.iter().cloned().filter(|m| m.second_pos >= 150)));
+        assert!(expected.iter().all(|m| m.first_pos >= 40 && m.first_end() <= 250 && m.second_pos >= 100 && m.second_end() <= 220));
+    }
+}
+
+#[test]
+fn capped_length() {
+    let mut rng = StdRng::from_seed(&[53usize][..]);
+    for algo_spec in ALGO_SPECS_4 {
+        let a: Vec<u8> = (0..300).map(|_| rng.gen_range(0, 3)).collect();
+        let mut b: Vec<u8> = (0..300).map(|_| rng.gen_range(0, 3)).collect();
+        b[100..250].copy_from_slice(&a[50..200]);
+        for &max_length in &[1, 5, 64] {
+            let pieces: Vec<Match> = MatchIterator::new(&a, &b, *algo_spec).capped(max_length).collect();
+            assert!(pieces.iter().all(|m| m.length <= max_length && m.length > 0));
+            assert!(pieces.windows(2).all(|w| w[0] < w[1]));
+            // Gluing consecutive pieces back gives the original matches
+            let mut glued: Vec<Match> = Vec::new();
+            let mut sorted = pieces.clone();
+            sorted.sort_by_key(|m| (m.first_pos as isize - m.second_pos as isize, m.second_pos));
+            for m in sorted {
+                match glued.last_mut() {
+                    Some(g) if g.length % max_length == 0 && g.first_end() == m.first_pos && g.second_end() == m.second_pos => g.length += m.length,
+                    _ => glued.push(m)
+                }
+            }
+            glued.sort();
+            let mut expected: Vec<Match> = MatchIterator::new(&a, &b, *algo_spec).collect();
+            expected.sort();
+            assert!(glued == expected);
+        }
+    }
+}
+
+#[test]
+fn smaller_index() {
+    let mut rng = StdRng::from_seed(&[48usize][..]);
+    for algo_spec in ALGO_SPECS_4 {
+        let a: Vec<u8> = (0..400).map(|_| rng.gen_range(0, 3)).collect();
+        let b: Vec<u8> = (0..100).map(|_| rng.gen_range(0, 3)).collect();
+        for &(first, second) in [(&a, &b), (&b, &a)].iter() {
+            let mut expected: Vec<Match> = MatchIterator::new(first, second, *algo_spec).collect();
+            expected.sort();
+            let found: Vec<Match> = MatchIterator::with_smaller_index(first, second, *algo_spec).collect();
+            assert!(found.windows(2).all(|w| w[0].second_pos <= w[1].second_pos));
+            let mut sorted = found.clone();
+            sorted.sort();
+            assert!(sorted == expected);
+        }
+    }
+}
+
+#[test]
+fn seek_position() {
+    let mut rng = StdRng::from_seed(&[49usize][..]);
+    for algo_spec in ALGO_SPECS_4 {
+        let a: Vec<u8> = (0..300).map(|_| rng.gen_range(0, 3)).collect();
+        let b: Vec<u8> = (0..200).map(|_| rng.gen_range(0, 3)).collect();
+        for mut match_iter in [MatchIterator::new(&a, &b, *algo_spec), MatchIterator::with_smaller_index(&b, &a, *algo_spec)] {
+            let all: Vec<Match> = match_iter.clone().collect();
+            for &pos in &[150, 0, 37, 299, 300, 500, 1] {
+                match_iter.seek(pos);
+                let found: Vec<Match> = match_iter.by_ref().collect();
+                let expected: Vec<Match> = all.iter().filter(|m| m.second_pos >= pos).cloned().collect();
+                assert!(found == expected);
+            }
+        }
+    }
+}
+
+#[test]
+fn similarity_bounds() {
+    let a = "abcdefghijklmnopqrstuvwxyz";
+    let b = "rstufghijklmnopqvwxyzabcde";
+    for algo_spec in ALGO_SPECS_4 {
+        assert!(similarity(a.as_bytes(), a.as_bytes(), *algo_spec) == 1.0);
+        assert!(similarity(a.as_bytes(), b.as_bytes(), *algo_spec) == 1.0);
+        assert!(similarity(a.as_bytes(), b"0123456789", *algo_spec) == 0.0);
+    }
+    let s = similarity(b"abcdefgh01234567", b"abcdefghXXXXXXXX", AlgoSpec::TreeMatch(8));
+    assert!(s == 0.5);
+}
+
+#[test]
+fn prepared_index() {
+    let a = "abcdefghijklmnopqrstuvwxyz";
+    let samples = ["rstufghijklmnopqvwxyzabcde", "012abcd345mnopq678", "nothing"];
+    for algo_spec in ALGO_SPECS_4 {
+        let index = PreparedIndex::new(a.as_bytes(), *algo_spec);
+        for sample in samples.iter() {
+            let expected: Vec<Match> = MatchIterator::new(a.as_bytes(), sample.as_bytes(), *algo_spec).collect();
+            assert!(index.matches(sample.as_bytes()).collect::<Vec<Match>>() == expected);
+            assert!(index.patch_set(sample.as_bytes()) == patch_set(a.as_bytes(), sample.as_bytes(), *algo_spec));
+            assert!(index.unique_strings(sample.as_bytes()) == unique_strings(a.as_bytes(), sample.as_bytes(), *algo_spec));
+            assert!(index.longest_common_substring(sample.as_bytes()).length == 
+                    longest_common_substring(a.as_bytes(), sample.as_bytes(), *algo_spec).length);
+        }
+    }
+}
+
+#[test]
+#[should_panic]
+fn match_end_overflow() {
+    Match::new(usize::MAX - 1, 0, 2).first_end();
+}
+
+#[test]
+fn memory_estimate() {
+    let data = vec![7u8; 1000];
+    let array = estimate_memory(AlgoSpec::SuffixArrayMatch(8), data.len(), 10);
+    assert!(array.index == ::suffixarray::SuffixArray::new(&data).as_bytes().len());
+    assert!(array.data == 1010 && array.total() == 1010 + array.index + array.working_set);
+    // Short keys have few distinct values
+    let short = estimate_memory(AlgoSpec::HashMatch(1), 1 << 20, 1 << 20);
+    let long = estimate_memory(AlgoSpec::HashMatch(8), 1 << 20, 1 << 20);
+    assert!(short.index < long.index && long.index < estimate_memory(AlgoSpec::HashMatch(32), 1 << 20, 1 << 20).index);
+    // The first piece of data is indexed, even if it is much larger
+    assert!(estimate_memory(AlgoSpec::HashMatch(8), 1 << 20, 1000).index > 100 * estimate_memory(AlgoSpec::HashMatch(8), 1000, 1 << 20).index);
+    assert!(estimate_memory(AlgoSpec::TreeMatch(8), 0, 0).total() == 0);
+    assert!(estimate_memory(AlgoSpec::HashMatch(8), usize::MAX, usize::MAX).total() == usize::MAX);
+}
+
+#[test]
+fn hash_direction() {
+    let mut rng = StdRng::from_seed(&[78usize][..]);
+    let a: Vec<u8> = (0..20000).map(|_| rng.gen_range(0, 4)).collect();
+    let b: Vec<u8> = (0..1000).map(|_| rng.gen_range(0, 4)).collect();
+    for &algo_spec in &[AlgoSpec::HashMatch(6), AlgoSpec::HashMatch(10)] {
+        // The small second piece of data is indexed but the matches and their order are the same
+        let lazy: Vec<Match> = MatchIterator::new(&a, &b, algo_spec).collect();
+        assert!(!lazy.is_empty());
+        assert!(MatchIterator::with_smaller_index(&a, &b, algo_spec).collect::<Vec<Match>>() == lazy);
+        let mut match_iter = MatchIterator::with_smaller_index(&a, &b, algo_spec);
+        match_iter.seek(500);
+        assert!(match_iter.collect::<Vec<Match>>() == lazy.iter().cloned().filter(|m| m.second_pos >= 500).collect::<Vec<Match>>());
+        assert!(patch_set(&a, &b, algo_spec) == ::patches_of(MatchIterator::with_smaller_index(&a, &b, algo_spec)));
+        assert!(coverage_stats(&a, &b, algo_spec).second_covered == coverage(&a, &b, algo_spec).total_len());
+    }
+}
+
+#[test]
+fn coverage_single_pass() {
+    let mut rng = StdRng::from_seed(&[79usize][..]);
+    let a: Vec<u8> = (0..3000).map(|_| rng.gen_range(0, 4)).collect();
+    let b: Vec<u8> = (0..2000).map(|_| rng.gen_range(0, 4)).collect();
+    let motif: Vec<u8> = (1..20).flat_map(|i| std::iter::repeat_n(0, i).chain(Some(i as u8))).collect();
+    let algo_specs = [AlgoSpec::TreeMatch(1), AlgoSpec::TreeMatch(5), AlgoSpec::SuffixArrayMatch(5), AlgoSpec::HashMatch(4), AlgoSpec::HashMatch(6)];
+    for &(first, second) in &[(&a[..], &b[..]), (&a[..], &b[..300]), (&b[..300], &a[..]), (&motif[..], &motif[..])] {
+        for algo_spec in algo_specs.iter() {
+            // The counts match the regions found by enumerating the matches
+            let diff = diff_regions(first, second, *algo_spec);
+            let stats = coverage_stats(first, second, *algo_spec);
+            assert!(stats.first_len == first.len() && stats.second_len == second.len());
+            assert!(stats.first_covered == first.len() - diff.only_first.iter().map(|r| r.len()).sum::<usize>());
+            assert!(stats.second_covered == second.len() - diff.only_second.iter().map(|r| r.len()).sum::<usize>());
+            assert!(stats.similarity() == similarity(first, second, *algo_spec));
+        }
+    }
+}
+
+#[test]
+fn skip_ahead() {
+    let mut rng = StdRng::from_seed(&[80usize][..]);
+    let a: Vec<u8> = (0..5000).map(|_| rng.gen::<u8>()).collect();
+    // A copy of a with a few edits
+    let mut b = a.clone();
+    for _ in 0..20 {
+        let pos = rng.gen_range(0, b.len());
+        b[pos] = rng.gen::<u8>();
+    }
+    // The end of a is found twice in duplicated, at overlapping positions of a
+    let duplicated: Vec<u8> = a[..3000].iter().chain(&a[2000..]).cloned().collect();
+    let algo_specs = [AlgoSpec::TreeMatch(6), AlgoSpec::SuffixArrayMatch(6), AlgoSpec::HashMatch(6)];
+    for &(first, second) in &[(&a[..], &b[..]), (&a[..], &b[1000..1500]), (&duplicated[..], &a[..])] {
+        for algo_spec in algo_specs.iter() {
+            // The matches of a full iteration which don't start inside a returned one
+            let mut expected = Vec::<Match>::new();
+            for m in MatchIterator::new(first, second, *algo_spec) {
+                let skip_to = expected.iter().map(|e| e.second_end()).max().unwrap_or(0);
+                if expected.last().is_none_or(|l| l.second_pos == m.second_pos || m.second_pos >= skip_to) {
+                    expected.push(m);
+                }
+            }
+            assert!(expected.len() < 100);
+            if first.len() > a.len() {
+                assert!(expected == [Match::new(0, 0, 3000)]);
+            }
+            let greedy: Vec<Match> = MatchIterator::new(first, second, *algo_spec).skip_ahead().collect();
+            assert!(greedy == expected);
+            assert!(MatchIterator::new(first, second, *algo_spec).skip_ahead().collect::<Vec<Match>>() == expected);
+            // Seeking restarts the skipping
+            let mut match_iter = MatchIterator::new(first, second, *algo_spec).skip_ahead();
+            match_iter.next();
+            match_iter.seek(0);
+            assert!(match_iter.collect::<Vec<Match>>() == expected);
+        }
+    }
+}
+
+#[test]
+fn skip_ahead_seek() {
+    let a: Vec<u8> = b"abcdXcdefgh".iter().cloned().chain((0..60).map(|_| b'_')).collect();
+    let b = b"abcdefgh";
+    for algo_spec in [AlgoSpec::TreeMatch(3), AlgoSpec::SuffixArrayMatch(3), AlgoSpec::HashMatch(3), AlgoSpec::Hybrid(8, 3)].iter() {
+        // "cdefgh" starts inside "abcd" and is skipped, until the skipping restarts at 2
+        for match_iter in [MatchIterator::new(&a, b, *algo_spec), MatchIterator::with_smaller_index(&a, b, *algo_spec)].iter() {
+            let mut match_iter = match_iter.clone().skip_ahead();
+            match_iter.seek(2);
+            assert!(match_iter.collect::<Vec<Match>>() == [Match::new(5, 2, 6)]);
+        }
+        assert!(MatchIterator::new(&a, b, *algo_spec).skip_ahead().collect::<Vec<Match>>() == [Match::new(0, 0, 4)]);
+    }
+}
+
+#[test]
+fn warm_start() {
+    let mut rng = StdRng::from_seed(&[89usize][..]);
+    let old_a: Vec<u8> = (0..20000).map(|_| rng.gen::<u8>()).collect();
+    let mut old_b = Vec::new();
+    while old_b.len() < 20000 {
+        let pos = rng.gen_range(0, old_a.len() - 1000);
+        old_b.extend_from_slice(&old_a[pos..pos + rng.gen_range(100, 1000)]);
+        old_b.extend((0..rng.gen_range(0, 10)).map(|_| rng.gen::<u8>()));
+    }
+    let algo_spec = AlgoSpec::TreeMatch(8);
+    let previous = patch_set(&old_a, &old_b, algo_spec);
+    // The next version changes a few bytes of both and inserts new content
+    let mut a = old_a.clone();
+    let mut b = old_b.clone();
+    for _ in 0..10 {
+        let pos = rng.gen_range(0, a.len());
+        a[pos] = !a[pos];
+        let pos = rng.gen_range(0, b.len());
+        b[pos] = !b[pos];
+    }
+    b.splice(5000..5000, a[100..600].iter().cloned());
+    let patches = warm_patch_set(&a, &b, &previous, algo_spec);
+    let mut second_end = 0;
+    for m in &patches {
+        assert!(m.length >= 8 && m.second_pos >= second_end);
+        assert!(a[m.first_pos..m.first_end()] == b[m.second_pos..m.second_end()]);
+        second_end = m.second_end();
+    }
+    let covered: usize = patches.iter().map(|m| m.length).sum();
+    let cold: usize = patch_set(&a, &b, algo_spec).iter().map(|m| m.length).sum();
+    assert!(covered * 100 >= cold * 99);
+    // The inserted content is found by the full matching
+    assert!(patches.iter().any(|m| m.second_pos <= 5000 && m.second_end() >= 5500));
+    // Without hints or with the exact patch set, the result is the patch set
+    assert!(warm_patch_set(&a, &b, &[], algo_spec) == patch_set(&a, &b, algo_spec));
+    assert!(warm_patch_set(&a, &b, &patch_set(&a, &b, algo_spec), algo_spec) == patch_set(&a, &b, algo_spec));
+    // Hints beyond the end are ignored
+    assert!(warm_patch_set(b"abcdefgh", b"abcdefgh", &[Match::new(4, 100, 8), Match::new(0, 0, 100)], AlgoSpec::TreeMatch(4)) == [Match::new(0, 0, 8)]);
+}
+
+#[test]
+fn coarse_to_fine() {
+    let mut rng = StdRng::from_seed(&[91usize][..]);
+    let a: Vec<u8> = (0..20000).map(|_| rng.gen::<u8>()).collect();
+    // Long copies separated by short ones
+    let mut b = Vec::new();
+    while b.len() < 20000 {
+        let length = if rng.gen_range(0, 3) == 0 { rng.gen_range(200, 2000) } else { rng.gen_range(6, 20) };
+        let pos = rng.gen_range(0, a.len() - length);
+        b.extend_from_slice(&a[pos..pos + length]);
+        b.push(rng.gen::<u8>());
+    }
+    let coarse = AlgoSpec::TreeMatch(32);
+    let fine = AlgoSpec::TreeMatch(6);
+    let patches = refined_patch_set(&a, &b, coarse, fine);
+    let mut second_end = 0;
+    for m in &patches {
+        assert!(m.length >= 6 && m.second_pos >= second_end);
+        assert!(a[m.first_pos..m.first_end()] == b[m.second_pos..m.second_end()]);
+        second_end = m.second_end();
+    }
+    // Every coarse patch is kept
+    let coarse_patches = patch_set(&a, &b, coarse);
+    assert!(coarse_patches.iter().all(|m| patches.contains(m)));
+    let covered = |patches: &[Match]| patches.iter().map(|m| m.length).sum::<usize>();
+    assert!(covered(&patches) > covered(&coarse_patches) + 500);
+    assert!(covered(&patches) * 100 >= covered(&patch_set(&a, &b, fine)) * 98);
+    assert!(refined_patch_set(b"abc", b"abcdef", coarse, fine).is_empty());
+}
@@ -0,0 +1,24 @@
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use {AlgoSpec, Match, MatchIterator, longest_common_substring, unique_strings};
+use naive;
+
+#[test]
+fn naive_differential() {
+    let mut rng = StdRng::from_seed(&[50usize][..]);
+    for round in 0..30 {
+        let mml = [1, 2, 4, 8][round % 4];
+        let a: Vec<u8> = (0..rng.gen_range(mml, 300)).map(|_| rng.gen_range(0, 3)).collect();
+        let b: Vec<u8> = (0..rng.gen_range(mml, 300)).map(|_| rng.gen_range(0, 3)).collect();
+        let expected = naive::matches(&a, &b, mml);
+        for algo_spec in [AlgoSpec::HashMatch(mml), AlgoSpec::TreeMatch(mml), AlgoSpec::SuffixArrayMatch(mml)].iter() {
+            let mut found: Vec<Match> = MatchIterator::new(&a, &b, *algo_spec).collect();
+            found.sort();
+            assert!(found == expected);
+            assert!(unique_strings(&a, &b, *algo_spec) == naive::unique_strings(&a, &b, mml));
+        }
+        let lcs = naive::longest_common_substring(&a, &b);
+        assert!(longest_common_substring(&a, &b, AlgoSpec::TreeMatch(1)).length == lcs.length);
+        assert!(a[lcs.first_pos..lcs.first_end()] == b[lcs.second_pos..lcs.second_end()]);
+    }
+}
@@ -0,0 +1,50 @@
+use AlgoSpec;
+use Region;
+use normalize::{PointerLayout, Relocations, normalize, normalized_patch_set, pointer_fields};
+use patch_set;
+
+// Build a fake code section where every 16 bytes hold 12 bytes of code and a pointer to base + i.
+fn image(base: u32, count: u32) -> Vec<u8> {
+    let mut data = Vec::new();
+    for i in 0..count {
+        data.extend_from_slice(&[0x48, 0x8b, 0x05, (i % 7) as u8, 0x89, 0xc7, 0xe8, 0x11, 0x22, 0x33, 0xc3, 0x90]);
+        data.extend_from_slice(&(base + i * 0x40).to_le_bytes());
+    }
+    data
+}
+
+#[test]
+fn heuristic() {
+    let a = image(0x400000, 32);
+    let b = image(0x400100, 32);
+    let layout = PointerLayout { width: 4, big_endian: false, relocations: Relocations::Heuristic { low: 0x400000, high: 0x500000 } };
+    let fields = pointer_fields(&a, &layout);
+    assert!(fields.len() == 32);
+    assert!(fields[1] == 28);
+    assert!(normalize(&a, &layout) == normalize(&b, &layout));
+    let raw = patch_set(&a, &b, AlgoSpec::TreeMatch(8));
+    let diff = normalized_patch_set(&a, &layout, &b, &layout, AlgoSpec::TreeMatch(8));
+    assert!(diff.patches.len() == 1);
+    assert!(raw.len() > diff.patches.len());
+    assert!(diff.fixups.len() == 32);
+    // Rebuilding second from the patches and the fixups
+    let mut rebuilt = vec![0u8; b.len()];
+    for m in &diff.patches {
+        rebuilt[m.second_pos..m.second_end()].copy_from_slice(&a[m.first_pos..m.first_end()]);
+    }
+    for f in &diff.fixups {
+        rebuilt[f.range()].copy_from_slice(f.bytes(&b));
+    }
+    assert!(rebuilt == b);
+}
+
+#[test]
+fn relocation_list() {
+    let a = b"\x00\x01\x02\x03AAAAAAAA\x10\x20\x30\x40BBBB";
+    let b = b"\x00\x01\x02\x03AAAAAAAA\x40\x30\x20\x10BBBB";
+    let layout = PointerLayout { width: 4, big_endian: true, relocations: Relocations::List(&[12, 18, 40]) };
+    assert!(pointer_fields(a, &layout) == vec![12]);
+    let diff = normalized_patch_set(a, &layout, b, &layout, AlgoSpec::HashMatch(4));
+    assert!(diff.patches.len() == 1);
+    assert!(diff.fixups == vec![Region::new(12, 16)]);
+}
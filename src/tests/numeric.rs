@@ -0,0 +1,25 @@
+use Match;
+use numeric::tolerance_matches;
+
+#[test]
+fn tolerance_int() {
+    let a: Vec<u16> = vec![100, 200, 300, 400, 500, 600, 700];
+    let b: Vec<u16> = vec![5, 302, 398, 503, 9, 101, 199];
+    assert!(tolerance_matches(&a, &b, 0, 1).is_empty());
+    let ms = tolerance_matches(&a, &b, 3, 2);
+    assert!(ms == vec![Match::new(2, 1, 3), Match::new(0, 5, 2)]);
+    let a: Vec<i32> = vec![-10, -20, -30];
+    let b: Vec<i32> = vec![-11, -19, -31];
+    assert!(tolerance_matches(&a, &b, -1, 3) == vec![Match::new(0, 0, 3)]);
+    let a: Vec<u32> = vec![7, 7, 7];
+    assert!(tolerance_matches(&a, &a, 0, 2) == vec![Match::new(0, 0, 3), Match::new(1, 0, 2), Match::new(0, 1, 2)]);
+}
+
+#[test]
+fn tolerance_float() {
+    let a: Vec<f32> = (0..100).map(|i| (i as f32 * 0.1).sin()).collect();
+    let b: Vec<f32> = a[40..60].iter().map(|x| x + 0.001).collect();
+    assert!(tolerance_matches(&a, &b, 0.0001, 4).is_empty());
+    let ms = tolerance_matches(&a, &b, 0.01, 10);
+    assert!(ms.contains(&Match::new(40, 0, 20)));
+}
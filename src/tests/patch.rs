@@ -0,0 +1,338 @@
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use std::io::Cursor;
+
+use {AlgoSpec, Match, patch_set, patch_set_with_min_copy};
+use hashmatch::build_map;
+use patch::{Compression, Delta, Dictionary, Metadata, Op, PatchError, PatchInfo, best_base, dictionary, verify_patch, version_chain};
+
+#[test]
+fn delta_roundtrip() {
+    let mut rng = StdRng::from_seed(&[23usize][..]);
+    let old: Vec<u8> = (0..5000).map(|_| rng.gen::<u8>()).collect();
+    let mut new = old.clone();
+    for _ in 0..20 {
+        let pos = rng.gen_range(0, new.len());
+        new[pos] = rng.gen::<u8>();
+    }
+    new.splice(1000..1000, b"inserted bytes".iter().cloned());
+    new.drain(3000..3500);
+    for algo_spec in &[AlgoSpec::TreeMatch(8), AlgoSpec::HashMatch(8)] {
+        let delta = Delta::new(&old, &new, *algo_spec);
+        assert!(delta.copied() + delta.literal() == new.len());
+        assert!(delta.literal() < 200);
+        let mut encoded = Vec::new();
+        delta.write_to(&mut encoded).unwrap();
+        let decoded = Delta::read_from(&mut &encoded[..]).unwrap();
+        assert!(decoded == delta);
+        assert!(decoded.apply(&old).unwrap() == new);
+        assert!(Delta::read_from(&mut &encoded[..encoded.len() - 1]).is_err());
+    }
+}
+
+#[test]
+fn delta_errors() {
+    let delta = Delta {
+        source_len: 4,
+        target_len: 6,
+        ops: vec![Op::Copy { offset: 2, length: 4 }, Op::Literal(b"ab".to_vec())],
+    };
+    assert!(delta.apply(b"abc") == Err(PatchError::SourceLength { expected: 4, found: 3 }));
+    assert!(delta.apply(b"abcd") == Err(PatchError::OutOfBounds(0)));
+    let empty = Delta::new(b"", b"", AlgoSpec::TreeMatch(4));
+    assert!(empty.ops.is_empty() && empty.apply(b"").unwrap().is_empty());
+    let short = Delta::new(b"ab", b"abc", AlgoSpec::HashMatch(4));
+    assert!(short.ops == [Op::Literal(b"abc".to_vec())]);
+}
+
+#[test]
+fn reproducible_bytes() {
+    // Keys must not depend on the platform endianness
+    let map = build_map::<u32>(&mut Cursor::new(&b"\x01\x02\x03\x04"[..]));
+    assert!(map.contains_key(&0x04030201));
+    // Little endian fields: lengths, op count then (tag, offset, length) or (tag, length, data)
+    let mut expected = b"BCMPDL\x00\x01".to_vec();
+    for &field in &[43u64, 44, 4] {
+        expected.extend_from_slice(&field.to_le_bytes());
+    }
+    for &(offset, length) in &[(31u64, 12u64), (19, 16), (4, 15)] {
+        expected.push(0);
+        expected.extend_from_slice(&offset.to_le_bytes());
+        expected.extend_from_slice(&length.to_le_bytes());
+    }
+    expected.push(1);
+    expected.extend_from_slice(&1u64.to_le_bytes());
+    expected.push(b'!');
+    let old = b"the quick brown fox jumps over the lazy dog";
+    let new = b"the lazy dog jumps over the quick brown fox!";
+    for algo_spec in &[AlgoSpec::TreeMatch(4), AlgoSpec::HashMatch(4), AlgoSpec::SuffixArrayMatch(4)] {
+        let mut encoded = Vec::new();
+        Delta::new(old, new, *algo_spec).write_to(&mut encoded).unwrap();
+        assert!(encoded == expected);
+    }
+}
+
+#[test]
+fn version_chain_statistics() {
+    let mut rng = StdRng::from_seed(&[64usize][..]);
+    let mut versions: Vec<Vec<u8>> = vec![(0..3000).map(|_| rng.gen::<u8>()).collect()];
+    for _ in 0..5 {
+        let mut next = versions.last().unwrap().clone();
+        let pos = rng.gen_range(0, next.len() - 200);
+        next.drain(pos..pos + rng.gen_range(0, 200));
+        let pos = rng.gen_range(0, next.len());
+        let inserted: Vec<u8> = (0..rng.gen_range(0, 200)).map(|_| rng.gen::<u8>()).collect();
+        next.splice(pos..pos, inserted);
+        versions.push(next);
+    }
+    let refs: Vec<&[u8]> = versions.iter().map(|v| &v[..]).collect();
+    let chain = version_chain(&refs, AlgoSpec::TreeMatch(8));
+    assert!(chain.steps.len() == 5);
+    let mut cumulative = 0;
+    for (step, pair) in chain.steps.iter().zip(refs.windows(2)) {
+        assert!(step.delta.apply(pair[0]).unwrap() == pair[1]);
+        let mut encoded = Vec::new();
+        step.delta.write_to(&mut encoded).unwrap();
+        assert!(step.delta_size == encoded.len());
+        assert!(step.novel == step.delta.literal());
+        // Nothing else than the edits is reported, up to a few bytes at their edges
+        assert!(step.novel <= 200 + 16 && step.removed <= 200 + 16);
+        assert!(step.churn() == step.novel + step.removed);
+        cumulative += step.novel;
+        assert!(step.cumulative_novel == cumulative);
+    }
+    assert!(chain.novel() == cumulative);
+    assert!(chain.delta_size() == chain.steps.iter().map(|s| s.delta_size).sum::<usize>());
+    assert!(version_chain(&refs[..1], AlgoSpec::TreeMatch(8)).steps.is_empty());
+}
+
+#[test]
+fn best_base_selection() {
+    let mut rng = StdRng::from_seed(&[65usize][..]);
+    let target: Vec<u8> = (0..4000).map(|_| rng.gen::<u8>()).collect();
+    // Candidates sharing more and more of the target
+    let mut candidates: Vec<Vec<u8>> = Vec::new();
+    for k in 0..8 {
+        let mut c = target.clone();
+        for _ in 0..(8 - k) * 30 {
+            let pos = rng.gen_range(0, c.len());
+            c[pos] = rng.gen::<u8>();
+        }
+        candidates.push(c);
+    }
+    candidates.swap(2, 7);
+    candidates.push(Vec::new());
+    let refs: Vec<&[u8]> = candidates.iter().map(|c| &c[..]).collect();
+    for algo_spec in &[AlgoSpec::TreeMatch(8), AlgoSpec::HashMatch(8)] {
+        let (index, delta) = best_base(&target, &refs, *algo_spec).unwrap();
+        let smallest = refs.iter().map(|c| Delta::new(c, &target, *algo_spec).encoded_len()).min().unwrap();
+        assert!(index == 2);
+        assert!(delta.encoded_len() == smallest);
+        assert!(delta.apply(refs[index]).unwrap() == target);
+    }
+    assert!(best_base(&target, &[], AlgoSpec::TreeMatch(8)).is_none());
+    assert!(best_base(b"", &[b"", b"abc"], AlgoSpec::TreeMatch(8)).unwrap().0 == 0);
+}
+
+#[test]
+fn patch_from_dictionary() {
+    let mut rng = StdRng::from_seed(&[68usize][..]);
+    let old: Vec<u8> = (0..50000).map(|_| rng.gen::<u8>()).collect();
+    // The new version only reuses a few parts of the old one
+    let mut new = Vec::new();
+    for _ in 0..10 {
+        let start = rng.gen_range(0, old.len() - 1000);
+        new.extend_from_slice(&old[start..start + rng.gen_range(100, 1000)]);
+        new.extend((0..rng.gen_range(0, 100)).map(|_| rng.gen::<u8>()));
+    }
+    let dict = dictionary(&old, &new, AlgoSpec::TreeMatch(16));
+    assert!(dict.data.len() < 10000);
+    assert!(dict.regions.windows(2).all(|w| w[0].end + 64 <= w[1].start));
+    assert!(dict.data.len() == dict.regions.iter().map(|r| r.len()).sum::<usize>());
+    // Every copied byte is in the dictionary
+    for m in patch_set(&old, &new, AlgoSpec::TreeMatch(16)) {
+        assert!(dict.regions.iter().any(|r| r.start <= m.first_pos && m.first_end() <= r.end));
+    }
+    assert!(Dictionary::from_regions(&old, dict.regions.clone()) == dict);
+    assert!(dictionary(b"abc", b"abc", AlgoSpec::TreeMatch(4)).data.is_empty());
+}
+
+#[test]
+fn compressed_delta() {
+    let mut rng = StdRng::from_seed(&[70usize][..]);
+    let old: Vec<u8> = (0..5000).map(|_| rng.gen::<u8>()).collect();
+    let mut new = old.clone();
+    // Novel but compressible content
+    let text: Vec<u8> = (0..400).flat_map(|i| format!("entry {} = enabled;\n", i % 20).into_bytes()).collect();
+    new.splice(2000..2000, text);
+    let delta = Delta::new(&old, &new, AlgoSpec::TreeMatch(8));
+    let mut plain = Vec::new();
+    delta.write_to(&mut plain).unwrap();
+    #[allow(unused_mut)]
+    let mut codecs = vec![Compression::None, Compression::Lz];
+    #[cfg(feature = "zstd")]
+    codecs.push(Compression::Zstd);
+    #[cfg(feature = "lzma")]
+    codecs.push(Compression::Lzma);
+    for &compression in &codecs {
+        let mut encoded = Vec::new();
+        delta.write_compressed_to(&mut encoded, compression).unwrap();
+        let decoded = Delta::read_from(&mut &encoded[..]).unwrap();
+        assert!(decoded == delta);
+        assert!(decoded.apply(&old).unwrap() == new);
+        assert!(Delta::read_from(&mut &encoded[..encoded.len() - 1]).is_err());
+        if compression != Compression::None {
+            assert!(encoded.len() * 4 < plain.len());
+            // The length of the operations is checked against the decompressed data
+            let mut shorter = encoded.clone();
+            shorter[33] = shorter[33].wrapping_sub(1);
+            assert!(Delta::read_from(&mut &shorter[..]).is_err());
+        }
+    }
+    // Unknown codec
+    let mut encoded = Vec::new();
+    delta.write_compressed_to(&mut encoded, Compression::None).unwrap();
+    encoded[32] = 9;
+    assert!(Delta::read_from(&mut &encoded[..]).is_err());
+}
+
+#[test]
+fn delta_metadata() {
+    let mut rng = StdRng::from_seed(&[71usize][..]);
+    let old: Vec<u8> = (0..3000).map(|_| rng.gen::<u8>()).collect();
+    let mut new = old.clone();
+    new.splice(1000..1010, b"changed".iter().cloned());
+    let algo_spec = AlgoSpec::HashMatch(8);
+    let delta = Delta::new(&old, &new, algo_spec);
+    let metadata = Metadata::new(&old, &new, algo_spec);
+    for &compression in &[Compression::None, Compression::Lz] {
+        let mut encoded = Vec::new();
+        delta.write_with_metadata_to(&mut encoded, &metadata, compression).unwrap();
+        let info = PatchInfo::read(&mut &encoded[..]).unwrap();
+        assert!(info.source_len == old.len() && info.target_len == new.len());
+        assert!(info.ops == delta.ops.len() && info.compression == compression);
+        let read = info.metadata.as_ref().unwrap();
+        assert!(read.source_hash == metadata.source_hash && read.target_hash == metadata.target_hash);
+        assert!(read.version == env!("CARGO_PKG_VERSION"));
+        match read.algo_spec {
+            AlgoSpec::HashMatch(8) => {},
+            _ => panic!("Wrong algorithm")
+        }
+        assert!(info.check_source(&old).is_ok());
+        let mut corrupted = old.clone();
+        corrupted[0] ^= 1;
+        assert!(info.check_source(&corrupted) == Err(PatchError::SourceHash));
+        assert!(info.check_source(&old[1..]) == Err(PatchError::SourceLength { expected: 3000, found: 2999 }));
+        let decoded = Delta::read_from(&mut &encoded[..]).unwrap();
+        assert!(decoded == delta && decoded.apply(&old).unwrap() == new);
+        assert!(PatchInfo::read(&mut &encoded[..80]).is_err());
+    }
+    // Deltas without metadata only know the length of the source
+    let mut encoded = Vec::new();
+    delta.write_to(&mut encoded).unwrap();
+    let info = PatchInfo::read(&mut &encoded[..]).unwrap();
+    assert!(info.metadata.is_none() && info.compression == Compression::None);
+    assert!(info.check_source(&[0u8; 3000]).is_ok());
+}
+
+#[test]
+fn patch_verification() {
+    let mut rng = StdRng::from_seed(&[72usize][..]);
+    let first: Vec<u8> = (0..4000).map(|_| rng.gen::<u8>()).collect();
+    let mut second = first.clone();
+    second.splice(500..500, (0..100).map(|_| rng.gen::<u8>()));
+    second.drain(2000..2300);
+    for algo_spec in &[AlgoSpec::TreeMatch(8), AlgoSpec::HashMatch(8), AlgoSpec::SuffixArrayMatch(8)] {
+        let mut patches = patch_set(&first, &second, *algo_spec);
+        assert!(verify_patch(&first, &second, &patches).is_ok());
+        // A hand assembled patch shifted by one byte
+        patches[1].first_pos += 1;
+        assert!(verify_patch(&first, &second, &patches) == Err(PatchError::Mismatch(patches[1].second_pos)));
+        patches.push(Match::new(first.len() - 10, 0, 11));
+        assert!(verify_patch(&first, &second, &patches) == Err(PatchError::OutOfBounds(patches.len() - 1)));
+    }
+    assert!(verify_patch(&first, &second, &[Match::new(usize::MAX, 0, 2)]) == Err(PatchError::OutOfBounds(0)));
+    // Dry run of a delta
+    let delta = Delta::new(&first, &second, AlgoSpec::TreeMatch(8));
+    assert!(delta.check(&first).is_ok());
+    assert!(delta.check(&first[1..]).is_err());
+    let mut broken = delta.clone();
+    broken.target_len += 1;
+    assert!(broken.check(&first) == Err(PatchError::TargetLength { expected: second.len() + 1, found: second.len() }));
+    assert!(broken.check(&first) == broken.apply(&first).map(|_| ()));
+    let mut encoded = Vec::new();
+    delta.write_with_metadata_to(&mut encoded, &Metadata::new(&first, &second, AlgoSpec::TreeMatch(8)), Compression::None).unwrap();
+    let info = PatchInfo::read(&mut &encoded[..]).unwrap();
+    assert!(info.check_target(&second).is_ok());
+    let mut altered = second.clone();
+    altered[0] ^= 1;
+    assert!(info.check_target(&altered) == Err(PatchError::TargetHash));
+}
+
+#[test]
+fn oversized_headers() {
+    // Lengths beyond the address space are rejected instead of overflowing or being allocated
+    let delta = Delta {
+        source_len: 4,
+        target_len: 2,
+        ops: vec![Op::Copy { offset: usize::MAX, length: 2 }],
+    };
+    assert!(delta.check(b"abcd") == Err(PatchError::OutOfBounds(0)));
+    let delta = Delta::new(b"abcdefgh", b"abcdefghXY", AlgoSpec::TreeMatch(4));
+    let mut encoded = Vec::new();
+    delta.write_compressed_to(&mut encoded, Compression::Lz).unwrap();
+    encoded[33..41].copy_from_slice(&(1u64 << 62).to_le_bytes());
+    assert!(Delta::read_from(&mut &encoded[..]).is_err());
+    let mut encoded = Vec::new();
+    delta.write_to(&mut encoded).unwrap();
+    encoded[24..32].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert!(Delta::read_from(&mut &encoded[..]).is_err());
+}
+
+#[test]
+fn minimal_copy_length() {
+    let mut rng = StdRng::from_seed(&[87usize][..]);
+    let old: Vec<u8> = (0..20000).map(|_| rng.gen::<u8>()).collect();
+    // Long copies separated by random bytes and tiny copies
+    let mut new = Vec::new();
+    while new.len() < 20000 {
+        let length = if rng.gen_range(0, 4) == 0 { rng.gen_range(100, 500) } else { rng.gen_range(4, 10) };
+        let pos = rng.gen_range(0, old.len() - length);
+        new.extend_from_slice(&old[pos..pos + length]);
+        new.extend((0..rng.gen_range(1, 20)).map(|_| rng.gen::<u8>()));
+    }
+    let algo_spec = AlgoSpec::TreeMatch(4);
+    let patches = patch_set(&old, &new, algo_spec);
+    let filtered = patch_set_with_min_copy(&old, &new, algo_spec, 17);
+    assert!(filtered == patches.iter().cloned().filter(|m| m.length >= 17).collect::<Vec<Match>>());
+    assert!(patch_set_with_min_copy(&old, &new, algo_spec, 0) == patches);
+    let delta = Delta::new(&old, &new, algo_spec);
+    let compact = Delta::with_min_copy(&old, &new, algo_spec, 17);
+    assert!(compact.apply(&old).unwrap() == new);
+    assert!(compact.encoded_len() < delta.encoded_len());
+    assert!(compact.copied() == filtered.iter().map(|m| m.length).sum::<usize>());
+    // The dropped copies are merged with the surrounding literal data
+    assert!(compact.ops.windows(2).all(|w| !matches!((&w[0], &w[1]), (Op::Literal(_), Op::Literal(_)))));
+    assert!(compact.ops.iter().all(|op| matches!(*op, Op::Literal(_)) || op.len() >= 17));
+}
+
+#[test]
+fn warm_delta() {
+    let mut rng = StdRng::from_seed(&[90usize][..]);
+    let old: Vec<u8> = (0..10000).map(|_| rng.gen::<u8>()).collect();
+    let mut new = old.clone();
+    new.drain(2000..2100);
+    new.splice(7000..7000, (0..50).map(|_| rng.gen::<u8>()));
+    let previous = Delta::new(&old, &new, AlgoSpec::HashMatch(8));
+    assert!(previous.copies().iter().map(|m| m.length).sum::<usize>() == previous.copied());
+    for m in previous.copies() {
+        assert!(old[m.first_pos..m.first_end()] == new[m.second_pos..m.second_end()]);
+    }
+    // The next build patches a constant in both versions
+    let (mut old, mut new) = (old, new);
+    old[500..504].copy_from_slice(b"\x01\x02\x03\x04");
+    new[500..504].copy_from_slice(b"\x01\x02\x03\x04");
+    let delta = Delta::warm(&old, &new, &previous, AlgoSpec::HashMatch(8));
+    assert!(delta.apply(&old).unwrap() == new);
+    assert!(delta.literal() == previous.literal());
+}
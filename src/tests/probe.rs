@@ -0,0 +1,30 @@
+use std::mem::discriminant;
+
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use {AlgoSpec, estimate_memory};
+use probe::{ProbeOptions, probe};
+
+#[test]
+fn backend_probe() {
+    let mut rng = StdRng::from_seed(&[77usize][..]);
+    let a: Vec<u8> = (0..20000).map(|_| rng.gen::<u8>()).collect();
+    let b: Vec<u8> = (0..5000).map(|_| rng.gen::<u8>()).collect();
+    let options = ProbeOptions { sample_size: 4096, memory_limit: None };
+    let result = probe(&a, &b, 8, options);
+    assert!(result.backends.len() == 3);
+    let fastest = result.backends.iter().min_by_key(|p| p.projected_time).unwrap();
+    assert!(discriminant(&result.algo_spec) == discriminant(&fastest.algo_spec) && result.algo_spec.minimal_length() == 8);
+    // HashMatch doesn't support every minimal length
+    let result = probe(&a, &b, 9, options);
+    assert!(result.backends.len() == 2);
+    assert!(result.backends.iter().all(|p| p.projected_memory == estimate_memory(p.algo_spec, a.len(), b.len()).total()));
+    // The suffix array is the smallest index
+    let result = probe(&a, &b, 8, ProbeOptions { memory_limit: Some(1), ..options });
+    assert!(matches!(result.algo_spec, AlgoSpec::SuffixArrayMatch(8)));
+    let limit = estimate_memory(AlgoSpec::SuffixArrayMatch(8), a.len(), b.len()).total();
+    let result = probe(&a, &b, 8, ProbeOptions { memory_limit: Some(limit), ..options });
+    assert!(matches!(result.algo_spec, AlgoSpec::SuffixArrayMatch(8)));
+    // Empty inputs
+    assert!(probe(b"", b"", 4, options).backends.len() == 3);
+}
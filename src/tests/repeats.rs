@@ -0,0 +1,58 @@
+use Match;
+use Region;
+use AlgoSpec;
+use repeats::{self_matches, longest_repeated_substring, longest_repeated_substrings, shortest_unique_lengths, shortest_unique_substring};
+
+fn occurrences(data: &[u8], pattern: &[u8]) -> usize {
+    data.windows(pattern.len()).filter(|w| *w == pattern).count()
+}
+
+#[test]
+fn sul() {
+    let data = "abracadabra".as_bytes();
+    let lengths = shortest_unique_lengths(data);
+    assert!(lengths.len() == data.len());
+    for (i, length) in lengths.iter().enumerate() {
+        match *length {
+            Some(l) => {
+                assert!(occurrences(data, &data[i..i + l]) == 1);
+                assert!(l == 1 || occurrences(data, &data[i..i + l - 1]) > 1);
+            },
+            None => assert!(occurrences(data, &data[i..]) > 1)
+        }
+    }
+    assert!(shortest_unique_substring(data) == Some(Region::new(4, 5)));
+    assert!(shortest_unique_substring(&[]).is_none());
+    assert!(shortest_unique_lengths("aaaa".as_bytes()) == vec![Some(4), None, None, None]);
+}
+
+#[test]
+fn lrs() {
+    let data = "abcdefgh0123abcdefgh4567abcd0123".as_bytes();
+    let m = longest_repeated_substring(data).unwrap();
+    assert!(m == Match::new(0, 12, 8));
+    let ms = longest_repeated_substrings(data, 3);
+    assert!(ms.len() == 3);
+    assert!(ms[0] == Match::new(0, 12, 8));
+    assert!(ms[1] == Match::new(0, 12, 4));
+    assert!(ms[2] == Match::new(8, 28, 4));
+    for m in ms {
+        assert!(data[m.first_pos..m.first_end()] == data[m.second_pos..m.second_end()]);
+    }
+    assert!(longest_repeated_substring("banana".as_bytes()) == Some(Match::new(1, 3, 3)));
+    assert!(longest_repeated_substring("abc".as_bytes()).is_none());
+    assert!(longest_repeated_substring(&[]).is_none());
+}
+
+#[test]
+fn self_match() {
+    let data = "abcdefgh0123abcdefgh4567abcd0123".as_bytes();
+    for algo_spec in &[AlgoSpec::HashMatch(4), AlgoSpec::TreeMatch(4)] {
+        let ms = self_matches(data, *algo_spec);
+        assert!(ms == vec![Match::new(0, 12, 8), Match::new(0, 24, 4), Match::new(12, 24, 4), Match::new(8, 28, 4)]);
+    }
+    assert!(self_matches("abcdef".as_bytes(), AlgoSpec::TreeMatch(1)).is_empty());
+    // Overlapping duplications
+    let ms = self_matches("aaaa".as_bytes(), AlgoSpec::TreeMatch(2));
+    assert!(ms == vec![Match::new(0, 1, 3), Match::new(0, 2, 2)]);
+}
@@ -0,0 +1,23 @@
+use AlgoSpec;
+use Match;
+use reverse::{Orientation, oriented_matches, reversed_matches};
+
+#[test]
+fn reversed() {
+    let a = "0123456789abcdef";
+    let b = "xx3210yy89abzzfedcba";
+    for algo_spec in &[AlgoSpec::HashMatch(4), AlgoSpec::TreeMatch(4)] {
+        let ms = reversed_matches(a.as_bytes(), b.as_bytes(), *algo_spec);
+        assert!(ms == vec![Match::new(0, 2, 4), Match::new(10, 14, 6)]);
+        for m in &ms {
+            let mut region = a.as_bytes()[m.first_pos..m.first_end()].to_vec();
+            region.reverse();
+            assert!(region[..] == b.as_bytes()[m.second_pos..m.second_end()]);
+        }
+        let oms = oriented_matches(a.as_bytes(), b.as_bytes(), *algo_spec);
+        assert!(oms.len() == 3);
+        assert!(oms[0].orientation == Orientation::Reverse && oms[0].m == Match::new(0, 2, 4));
+        assert!(oms[1].orientation == Orientation::Forward && oms[1].m == Match::new(8, 8, 4));
+        assert!(oms[2].orientation == Orientation::Reverse && oms[2].m == Match::new(10, 14, 6));
+    }
+}
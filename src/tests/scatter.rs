@@ -0,0 +1,44 @@
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use {AlgoSpec, Match, MatchIterator};
+use scatter::{Scattered, scattered_matches};
+
+fn split<'a>(rng: &mut StdRng, data: &'a [u8]) -> Vec<&'a [u8]> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    while start < data.len() {
+        let end = data.len().min(start + rng.gen_range(0, 50));
+        segments.push(&data[start..end]);
+        start = end;
+    }
+    segments
+}
+
+#[test]
+fn scattered_equivalence() {
+    let mut rng = StdRng::from_seed(&[13usize][..]);
+    let a: Vec<u8> = (0..2000).map(|_| rng.gen_range(0, 4)).collect();
+    let b: Vec<u8> = (0..2000).map(|_| rng.gen_range(0, 4)).collect();
+    let first_segments = split(&mut rng, &a);
+    let second_segments = split(&mut rng, &b);
+    let first = Scattered::new(&first_segments);
+    let second = Scattered::new(&second_segments);
+    assert!(first.len() == a.len());
+    for (pos, &byte) in a.iter().enumerate() {
+        let (s, o) = first.locate(pos);
+        assert!(first_segments[s][o] == byte && first.linear(s, o) == pos);
+    }
+    let mut expected: Vec<Match> = MatchIterator::new(&a, &b, AlgoSpec::TreeMatch(7)).collect();
+    expected.sort();
+    assert!(scattered_matches(&first, &second, 7) == expected);
+}
+
+#[test]
+fn scattered_locate() {
+    let segments: [&[u8]; 4] = [b"", b"ab", b"", b"c"];
+    let s = Scattered::new(&segments);
+    assert!(s.locate(0) == (1, 0));
+    assert!(s.locate(2) == (3, 0));
+    assert!(s.locate(3) == (3, 1));
+    assert!(s.iter_from(1).collect::<Vec<_>>() == b"bc");
+}
@@ -0,0 +1,28 @@
+use AlgoSpec;
+use Match;
+use Region;
+use segmap::{NamedSegment, compare_segments};
+
+#[test]
+fn segments() {
+    let a = "abcdefgh____abcdefgh0123";
+    let b = "abcdefgh0123____abcdXXgh";
+    let first_map = vec![NamedSegment::new("text", 0, 8), NamedSegment::new("data", 12, 12)];
+    let second_map = vec![NamedSegment::new("data", 0, 12), NamedSegment::new("text", 16, 8), NamedSegment::new("bss", 12, 4)];
+    let reports = compare_segments(a.as_bytes(), &first_map, b.as_bytes(), &second_map, AlgoSpec::TreeMatch(4));
+    assert!(reports.len() == 3);
+    assert!(reports[0].name == "data");
+    assert!(reports[0].first == Some(Region::new(12, 24)));
+    assert!(reports[0].patches == vec![Match::new(12, 0, 12)]);
+    assert!(reports[0].is_unchanged());
+    assert!(reports[1].name == "text");
+    assert!(reports[1].patches == vec![Match::new(0, 16, 4)]);
+    assert!(reports[1].covered == 4);
+    assert!(!reports[1].is_unchanged());
+    assert!(reports[2].name == "bss");
+    assert!(reports[2].first.is_none());
+    assert!(reports[2].covered == 0);
+    let reports = compare_segments(a.as_bytes(), &first_map, b.as_bytes(), &second_map[..1], AlgoSpec::TreeMatch(4));
+    assert!(reports.len() == 2);
+    assert!(reports[1].name == "text" && reports[1].second.is_none());
+}
@@ -0,0 +1,45 @@
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use signature::{Signature, delta_from_signature, signature};
+
+#[test]
+fn remote_delta() {
+    let mut rng = StdRng::from_seed(&[67usize][..]);
+    let reference: Vec<u8> = (0..20000).map(|_| rng.gen::<u8>()).collect();
+    let mut new_data = reference.clone();
+    new_data.splice(5000..5000, b"inserted bytes".iter().cloned());
+    new_data.drain(12000..12500);
+    for _ in 0..5 {
+        let pos = rng.gen_range(0, new_data.len());
+        new_data[pos] = rng.gen::<u8>();
+    }
+    let sig = signature(&reference);
+    assert!(sig.block_size == 256 && sig.blocks.len() == 20000 / 256);
+    let mut encoded = Vec::new();
+    sig.write_to(&mut encoded).unwrap();
+    assert!(encoded.len() == 32 + sig.blocks.len() * 36);
+    let decoded = Signature::read_from(&mut &encoded[..]).unwrap();
+    assert!(decoded == sig);
+    assert!(Signature::read_from(&mut &encoded[..encoded.len() - 1]).is_err());
+    let delta = delta_from_signature(&decoded, &new_data);
+    assert!(delta.apply(&reference).unwrap() == new_data);
+    // Each edit costs at most two blocks of literals, plus the unmatched trailing partial block
+    assert!(delta.literal() <= 8 * 2 * 256 + 256);
+    // Identical data is copied entirely, except the trailing partial block
+    let same = delta_from_signature(&sig, &reference);
+    assert!(same.ops.len() == 2 && same.literal() == 20000 % 256);
+    // An empty reference gives a single literal
+    let empty = delta_from_signature(&signature(b""), b"abc");
+    assert!(empty.apply(b"").unwrap() == b"abc");
+}
+
+#[test]
+fn oversized_signature() {
+    let mut encoded = Vec::new();
+    signature(&[7u8; 1000]).write_to(&mut encoded).unwrap();
+    // A single byte block size and a huge source claim an absurd number of blocks
+    encoded[8..16].copy_from_slice(&1u64.to_le_bytes());
+    encoded[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+    encoded[24..32].copy_from_slice(&(1u64 << 60).to_le_bytes());
+    assert!(Signature::read_from(&mut &encoded[..]).is_err());
+}
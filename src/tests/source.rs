@@ -0,0 +1,71 @@
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use {AlgoSpec, Match, MatchIterator, patch_set};
+use scatter::Scattered;
+use source::{ByteSource, Translated, equivalence_table, source_matches, source_patch_set};
+use treematch::TreeMatchIterator;
+
+#[test]
+fn tree_match_sources() {
+    let mut rng = StdRng::from_seed(&[17usize][..]);
+    let a: Vec<u8> = (0..1000).map(|_| rng.gen_range(0, 4)).collect();
+    let b: Vec<u8> = (0..1000).map(|_| rng.gen_range(0, 4)).collect();
+    let segments: Vec<&[u8]> = a.chunks(37).collect();
+    let scattered = Scattered::new(&segments);
+    assert!(ByteSource::len(&scattered) == a.len());
+    let expected: Vec<_> = TreeMatchIterator::new(&a[..], &b[..], 6).collect();
+    let from_vec: Vec<_> = TreeMatchIterator::new(&a, &b, 6).collect();
+    let from_scattered: Vec<_> = TreeMatchIterator::new(&scattered, &b[..], 6).collect();
+    assert!(!expected.is_empty());
+    assert!(from_vec == expected);
+    assert!(from_scattered == expected);
+}
+
+#[test]
+fn translated_source() {
+    let mut rng = StdRng::from_seed(&[82usize][..]);
+    let a: Vec<u8> = (0..2000).map(|_| rng.gen_range(b'0', b'z')).collect();
+    // Same text with different digits and letter cases
+    let b: Vec<u8> = a.iter().map(|&c| match c {
+        b'0'..=b'9' => b'0' + rng.gen_range(0, 10),
+        b'a'..=b'y' if rng.gen::<bool>() => c.to_ascii_uppercase(),
+        _ => c
+    }).collect();
+    let mut classes: Vec<Vec<u8>> = (b'a'..=b'z').map(|c| vec![c, c.to_ascii_uppercase()]).collect();
+    classes.push((b'0'..=b'9').collect());
+    let classes: Vec<&[u8]> = classes.iter().map(|c| &c[..]).collect();
+    let table = equivalence_table(&classes);
+    assert!(table[b'7' as usize] == b'0' && table[b'Q' as usize] == b'q' && table[b'q' as usize] == b'q' && table[b';' as usize] == b';');
+    let first = Translated::new(&a[..], &table);
+    let second = Translated::new(&b[..], &table);
+    assert!(ByteSource::len(&first) == a.len() && first.byte_at(0) == table[a[0] as usize]);
+    // The same matches as on translated copies
+    let a_copy: Vec<u8> = a.iter().map(|&c| table[c as usize]).collect();
+    let b_copy: Vec<u8> = b.iter().map(|&c| table[c as usize]).collect();
+    let expected: Vec<_> = TreeMatchIterator::new(&a_copy[..], &b_copy[..], 8).collect();
+    assert!(expected.len() == 1 && expected[0].length == a.len());
+    assert!(TreeMatchIterator::new(&first, &second, 8).collect::<Vec<_>>() == expected);
+    assert!(TreeMatchIterator::new(&a[..], &b[..], 8).count() > 1);
+}
+
+#[test]
+fn algo_spec_sources() {
+    let mut rng = StdRng::from_seed(&[93usize][..]);
+    let a: Vec<u8> = (0..3000).map(|_| rng.gen_range(0, 4)).collect();
+    let mut b: Vec<u8> = (0..2000).map(|_| rng.gen_range(0, 4)).collect();
+    b[500..1300].copy_from_slice(&a[1000..1800]);
+    let first_segments: Vec<&[u8]> = a.chunks(41).collect();
+    let second_segments: Vec<&[u8]> = b.chunks(29).collect();
+    let first = Scattered::new(&first_segments);
+    let second = Scattered::new(&second_segments);
+    for &algo_spec in &[AlgoSpec::TreeMatch(6), AlgoSpec::HashMatch(6), AlgoSpec::SuffixArrayMatch(6), AlgoSpec::Hybrid(16, 6)] {
+        let mut expected: Vec<Match> = MatchIterator::new(&a, &b, algo_spec).collect();
+        let matches: Vec<Match> = source_matches(&first, &second, algo_spec).collect();
+        assert!(matches.windows(2).all(|w| w[0].second_pos <= w[1].second_pos));
+        let mut sorted = matches.clone();
+        sorted.sort();
+        expected.sort();
+        assert!(sorted == expected);
+        assert!(source_patch_set(&first, &second, algo_spec) == patch_set(&a, &b, algo_spec));
+    }
+}
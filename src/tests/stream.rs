@@ -0,0 +1,90 @@
+use std::io::{Error, Read, Result, repeat};
+
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use Match;
+use hashmatch::HashMatchIterator;
+use stream::{StreamMatchIterator, pipelined_matches};
+
+// A reader returning its data in small pieces to exercise the refilling logic
+struct Trickle<'a> {
+    data: &'a [u8],
+    step: usize,
+    fail: bool,
+}
+
+impl<'a> Read for Trickle<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if self.data.is_empty() && self.fail {
+            return Err(Error::other("broken pipe"));
+        }
+        let n = self.step.min(buf.len()).min(self.data.len());
+        buf[..n].copy_from_slice(&self.data[..n]);
+        self.data = &self.data[n..];
+        Ok(n)
+    }
+}
+
+#[test]
+fn stream_equivalence() {
+    let mut rng = StdRng::from_seed(&[7usize][..]);
+    let mut a = vec![0u8; 2000];
+    let mut b = vec![0u8; 200000];
+    for x in a.iter_mut() {
+        *x = rng.gen_range(0, 4);
+    }
+    for x in b.iter_mut() {
+        *x = rng.gen_range(0, 4);
+    }
+    let expected: Vec<_> = HashMatchIterator::<[u8;6]>::new(&a, &b).collect();
+    let streamed: Vec<_> = StreamMatchIterator::<[u8;6], _>::new(&a, Trickle { data: &b, step: 7, fail: false })
+        .map(|m| m.unwrap()).collect();
+    assert!(!expected.is_empty());
+    assert!(expected == streamed);
+}
+
+#[test]
+fn stream_error() {
+    let a = b"abcdefgh";
+    let b = b"xxabcdxxefgh";
+    let mut iter = StreamMatchIterator::<u32, _>::new(a, Trickle { data: b, step: 3, fail: true });
+    assert!(iter.next().unwrap().unwrap() == Match::new(0, 2, 4));
+    assert!(iter.next().unwrap().unwrap() == Match::new(4, 8, 4));
+    assert!(iter.next().unwrap().is_err());
+    assert!(iter.next().is_none());
+}
+
+// Stream 5 GiB of zeros followed by a marker, which takes a few minutes
+#[cfg(target_pointer_width = "64")]
+#[test]
+#[ignore]
+fn stream_beyond_4gib() {
+    let mut rng = StdRng::from_seed(&[73usize][..]);
+    let mut marker: Vec<u8> = (0..64).map(|_| rng.gen_range(1, 255)).collect();
+    marker[0] = 1;
+    let offset = 5usize << 30;
+    let reader = repeat(0).take(offset as u64).chain(&marker[..]);
+    let mut iter = StreamMatchIterator::<[u8;8], _>::new(&marker, reader);
+    assert!(iter.next().unwrap().unwrap() == Match::new(0, offset, 64));
+    assert!(iter.next().is_none());
+    assert!(iter.bytes_read() == offset + 64);
+}
+
+#[test]
+fn pipelined_scan() {
+    let mut rng = StdRng::from_seed(&[76usize][..]);
+    let a: Vec<u8> = (0..5000).map(|_| rng.gen_range(0, 4)).collect();
+    let b: Vec<u8> = (0..300000).map(|_| rng.gen_range(0, 4)).collect();
+    let expected: Vec<_> = HashMatchIterator::<[u8;7]>::new(&a, &b).collect();
+    assert!(!expected.is_empty());
+    for &read_ahead in &[0, 100000, 1 << 30] {
+        let mut matches = Vec::new();
+        pipelined_matches::<[u8;7], _, _>(&a, Trickle { data: &b, step: 1000, fail: false }, read_ahead, |m| matches.push(m)).unwrap();
+        assert!(matches == expected);
+    }
+    // Errors are returned after the matches completed before them
+    let mut matches = Vec::new();
+    let result = pipelined_matches::<[u8;7], _, _>(&a, Trickle { data: &b[..10000], step: 100, fail: true }, 0, |m| matches.push(m));
+    assert!(result.is_err());
+    assert!(!matches.is_empty() && expected.starts_with(&matches));
+}
@@ -0,0 +1,81 @@
+use std::env::temp_dir;
+use std::fs::{create_dir, remove_dir, remove_file};
+
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use {Match, MatchIterator, AlgoSpec};
+use suffixarray::{SuffixArray, build_external, build_suffix_array};
+
+fn random_data(seed: usize, len: usize, alphabet: u8) -> Vec<u8> {
+    let mut rng = StdRng::from_seed(&[seed][..]);
+    (0..len).map(|_| rng.gen_range(0, alphabet)).collect()
+}
+
+#[test]
+fn suffix_array_order() {
+    for &(len, alphabet) in &[(0, 2), (1, 2), (500, 2), (500, 255)] {
+        let data = random_data(len, len, alphabet);
+        let mut expected: Vec<usize> = (0..data.len()).collect();
+        expected.sort_by(|&a, &b| data[a..].cmp(&data[b..]));
+        assert!(build_suffix_array(&data) == expected);
+    }
+}
+
+#[test]
+fn external_build() {
+    let data = random_data(3, 3000, 3);
+    let path = temp_dir().join(format!("bcmp-external-{}.sa", std::process::id()));
+    // 100 positions per run
+    build_external(&data, &path, 800).unwrap();
+    let external = SuffixArray::open(&path).unwrap();
+    remove_file(&path).unwrap();
+    let in_memory = SuffixArray::new(&data);
+    assert!(external.as_bytes() == in_memory.as_bytes());
+    assert!(SuffixArray::from_bytes(&in_memory.as_bytes()[..20]).is_err());
+    let mut corrupted = in_memory.as_bytes().to_vec();
+    corrupted[16..24].copy_from_slice(&3000u64.to_le_bytes());
+    assert!(SuffixArray::from_bytes(&corrupted[..]).is_err());
+}
+
+#[test]
+fn external_build_failure() {
+    let data = random_data(10, 1000, 3);
+    // The index can't be created over a directory, once the runs are written
+    let path = temp_dir().join(format!("bcmp-external-failure-{}.sa", std::process::id()));
+    create_dir(&path).unwrap();
+    assert!(build_external(&data, &path, 800).is_err());
+    remove_dir(&path).unwrap();
+    let mut run = path.file_name().unwrap().to_os_string();
+    run.push(".run0");
+    assert!(!path.with_file_name(run).exists());
+}
+
+#[test]
+fn suffix_array_matches() {
+    let a = random_data(5, 2000, 4);
+    let b = random_data(6, 2000, 4);
+    let sa = SuffixArray::new(&a);
+    let mut expected: Vec<Match> = MatchIterator::new(&a, &b, AlgoSpec::TreeMatch(7)).collect();
+    expected.sort();
+    assert!(sa.matches(&a, &b, 7) == expected);
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn mapped_index() {
+    let data = random_data(8, 1000, 4);
+    let path = temp_dir().join(format!("bcmp-mapped-{}.sa", std::process::id()));
+    SuffixArray::new(&data).save(&path).unwrap();
+    let mapped = SuffixArray::map(&path).unwrap();
+    assert!(mapped.as_bytes() == SuffixArray::new(&data).as_bytes());
+    assert!(mapped.find(&data, &data[10..20]).len() == 1);
+    drop(mapped);
+    remove_file(&path).unwrap();
+}
+
+#[test]
+fn oversized_array_header() {
+    let mut bytes = SuffixArray::new(&random_data(9, 100, 4)).as_bytes().to_vec();
+    bytes[8..16].copy_from_slice(&(1u64 << 62).to_le_bytes());
+    assert!(SuffixArray::from_bytes(&bytes[..]).is_err());
+}
@@ -0,0 +1,261 @@
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use std::borrow::Cow;
+
+use source::{END_SYMBOL, Separated, SymbolSource};
+use suffixarray::build_suffix_array;
+use suffixtree::{GraphvizOptions, Node, SuffixTree};
+use treematch::TreeMatchIterator;
+
+#[test]
+fn append_equivalence() {
+    let mut rng = StdRng::from_seed(&[41usize][..]);
+    for _ in 0..40 {
+        let data: Vec<u8> = (0..600).map(|_| rng.gen_range(0, 4)).collect();
+        let second: Vec<u8> = (0..300).map(|_| rng.gen_range(0, 4)).collect();
+        // Grow the tree by random steps, starting from an empty one
+        let mut tree = SuffixTree::new(&data[..0]);
+        let mut len = 0;
+        while len < data.len() {
+            len = (len + rng.gen_range(0, 80)).min(data.len());
+            tree.append(&data[..len]);
+            assert!(tree.data_len() == len);
+        }
+        let batch = SuffixTree::new(&data[..]);
+        assert!(tree.nodes.len() == batch.nodes.len());
+        let appended: Vec<_> = TreeMatchIterator::with_tree(&data[..], Cow::Owned(tree), &second[..], 4).collect();
+        let expected: Vec<_> = TreeMatchIterator::with_tree(&data[..], Cow::Owned(batch), &second[..], 4).collect();
+        assert!(appended == expected);
+    }
+}
+
+#[test]
+fn query_naive() {
+    let mut rng = StdRng::from_seed(&[42usize][..]);
+    for _ in 0..20 {
+        let data: Vec<u8> = (0..300).map(|_| rng.gen_range(0, 3)).collect();
+        let tree = SuffixTree::new(&data[..]);
+        for _ in 0..50 {
+            let pattern: Vec<u8> = (0..rng.gen_range(0, 8)).map(|_| rng.gen_range(0, 3)).collect();
+            let expected: Vec<usize> = (0..data.len())
+                .filter(|&i| data[i..].starts_with(&pattern))
+                .collect();
+            assert!(tree.find_all(&data[..], &pattern) == expected);
+            assert!(tree.count(&data[..], &pattern) == expected.len());
+            assert!(tree.contains(&data[..], &pattern) != expected.is_empty());
+        }
+    }
+}
+
+#[test]
+fn traversal_order() {
+    let mut rng = StdRng::from_seed(&[43usize][..]);
+    for _ in 0..20 {
+        let data: Vec<u8> = (0..rng.gen_range(0, 400)).map(|_| rng.gen_range(0, 4)).collect();
+        let tree = SuffixTree::new(&data[..]);
+        // Every node is visited exactly once
+        let mut visited = vec![false; tree.nodes.len()];
+        for (node, _) in tree.depth_first() {
+            assert!(!visited[node]);
+            visited[node] = true;
+        }
+        assert!(visited.iter().all(|&v| v));
+        let mut expected: Vec<usize> = (0..data.len()).collect();
+        expected.sort_by(|&a, &b| data[a..].cmp(&data[b..]));
+        assert!(tree.leaves().collect::<Vec<usize>>() == expected);
+        let suffixes: Vec<&[u8]> = tree.suffixes(&data[..]).collect();
+        assert!(suffixes.windows(2).all(|w| w[0] < w[1]));
+    }
+}
+
+#[test]
+fn suffix_and_lcp_arrays() {
+    let mut rng = StdRng::from_seed(&[44usize][..]);
+    for _ in 0..20 {
+        let data: Vec<u8> = (0..rng.gen_range(0, 400)).map(|_| rng.gen_range(0, 4)).collect();
+        let tree = SuffixTree::new(&data[..]);
+        let sa = tree.to_suffix_array();
+        assert!(sa == build_suffix_array(&data));
+        let lcp = tree.lcp_array();
+        assert!(lcp.len() == sa.len());
+        for i in 1..sa.len() {
+            let expected = data[sa[i - 1]..].iter().zip(&data[sa[i]..]).take_while(|&(a, b)| a == b).count();
+            assert!(lcp[i] == expected);
+        }
+    }
+}
+
+#[test]
+fn matching_statistics_naive() {
+    let mut rng = StdRng::from_seed(&[45usize][..]);
+    for _ in 0..30 {
+        let data: Vec<u8> = (0..rng.gen_range(0, 300)).map(|_| rng.gen_range(0, 3)).collect();
+        let second: Vec<u8> = (0..rng.gen_range(0, 300)).map(|_| rng.gen_range(0, 4)).collect();
+        let tree = SuffixTree::new(&data[..]);
+        let stats = tree.matching_statistics(&data[..], &second[..]);
+        assert!(stats.len() == second.len());
+        for (i, &length) in stats.iter().enumerate() {
+            let longest = (0..data.len())
+                .map(|j| data[j..].iter().zip(&second[i..]).take_while(|&(a, b)| a == b).count())
+                .max()
+                .unwrap_or(0);
+            assert!(length == longest);
+        }
+    }
+}
+
+#[test]
+fn node_children() {
+    let mut node = Node::new(0, 0);
+    assert!(node.is_leaf());
+    node.set_child(256, Some(3));
+    node.set_child(b'b' as usize, Some(1));
+    node.set_child(b'a' as usize, Some(2));
+    node.set_child(b'b' as usize, Some(4));
+    assert!(node.children().collect::<Vec<_>>() == [(b'a' as usize, 2), (b'b' as usize, 4), (256, 3)]);
+    assert!(node.child(b'a' as usize) == Some(2));
+    assert!(node.child(b'c' as usize).is_none());
+    #[allow(deprecated)]
+    let edges = node.edges();
+    assert!(edges[b'a' as usize] == Some(2) && edges[256] == Some(3) && edges.iter().flatten().count() == 3);
+    node.set_child(b'a' as usize, None);
+    assert!(node.children().count() == 2);
+    assert!(!node.is_leaf());
+}
+
+#[test]
+fn save_load() {
+    let mut rng = StdRng::from_seed(&[47usize][..]);
+    let data: Vec<u8> = (0..2000).map(|_| rng.gen_range(0, 4)).collect();
+    let second: Vec<u8> = (0..500).map(|_| rng.gen_range(0, 4)).collect();
+    let tree = SuffixTree::new(&data[..1500]);
+    let mut saved = Vec::new();
+    tree.save(&mut saved).unwrap();
+    let mut loaded = SuffixTree::load(&mut &saved[..], &data[..1500]).unwrap();
+    assert!(loaded.to_suffix_array() == tree.to_suffix_array());
+    // The construction state survives so the loaded tree can still grow
+    loaded.append(&data[..]);
+    let expected: Vec<_> = TreeMatchIterator::new(&data[..], &second[..], 4).collect();
+    let found: Vec<_> = TreeMatchIterator::with_tree(&data[..], Cow::Owned(loaded), &second[..], 4).collect();
+    assert!(found == expected);
+    // Wrong data, truncated or corrupted trees are rejected
+    assert!(SuffixTree::load(&mut &saved[..], &data[..]).is_err());
+    assert!(SuffixTree::load(&mut &saved[..saved.len() - 1], &data[..1500]).is_err());
+    saved[0] = b'X';
+    assert!(SuffixTree::load(&mut &saved[..], &data[..1500]).is_err());
+    // So are the nodes which don't form a tree and the wrong suffix links
+    let leaf = tree.nodes.iter().position(|n| n.is_leaf()).unwrap();
+    let internal = (1..tree.nodes.len()).find(|&i| !tree.nodes[i].is_leaf() && tree.nodes[i].suffix_link != Some(0)).unwrap();
+    let mut cyclic = tree.clone();
+    cyclic.nodes[leaf].set_child(b'a' as usize, Some(0));
+    let mut shared = tree.clone();
+    let child = tree.nodes[internal].children().next().unwrap().1;
+    shared.nodes[leaf].set_child(b'a' as usize, Some(child));
+    let mut linked = tree.clone();
+    linked.nodes[internal].suffix_link = Some(0);
+    for corrupted in &[cyclic, shared, linked] {
+        let mut saved = Vec::new();
+        corrupted.save(&mut saved).unwrap();
+        assert!(SuffixTree::load(&mut &saved[..], &data[..1500]).is_err());
+    }
+}
+
+#[test]
+fn graphviz_escaping() {
+    let data: Vec<u8> = (0..=255u8).chain(b"\"\\\"\\".iter().cloned()).collect();
+    let tree = SuffixTree::new(&data[..]);
+    let dot = tree.to_graphviz(&data);
+    assert!(dot.is_ascii());
+    // Every label is a properly terminated string
+    for line in dot.lines().filter(|l| l.contains("label")) {
+        let mut label = line[line.find("label = \"").unwrap() + 9..].bytes();
+        while let Some(b) = label.next() {
+            match b {
+                b'\\' => assert!(label.next().is_some()),
+                b'"' => break,
+                _ => {}
+            }
+        }
+        assert!(label.collect::<Vec<u8>>() == b" ];");
+    }
+    let options = GraphvizOptions {
+        leaf_labels: true,
+        suffix_links: false,
+        max_label_length: Some(4),
+    };
+    let dot = tree.to_graphviz_with(&data, &options);
+    assert!(!dot.contains("dashed"));
+    assert!(dot.contains("[ label = \"0\" ]"));
+    assert!(dot.contains("..."));
+}
+
+#[test]
+fn generalized_tree() {
+    let mut rng = StdRng::from_seed(&[83usize][..]);
+    let pieces: Vec<Vec<u8>> = (0..12).map(|_| (0..rng.gen_range(0, 60)).map(|_| rng.gen_range(0, 3)).collect()).collect();
+    let pieces: Vec<&[u8]> = pieces.iter().map(|p| &p[..]).collect();
+    let separated = Separated::new(&pieces);
+    assert!(separated.symbol_count() == pieces.iter().map(|p| p.len() + 1).sum::<usize>());
+    assert!(separated.symbol_at(separated.start(3) + pieces[3].len()) == END_SYMBOL + 4);
+    let tree = SuffixTree::new(&separated);
+    // Occurrences never span two pieces
+    for _ in 0..200 {
+        let pattern: Vec<u8> = (0..rng.gen_range(1, 8)).map(|_| rng.gen_range(0, 3)).collect();
+        let expected: Vec<(usize, usize)> = pieces.iter().enumerate()
+            .flat_map(|(i, p)| (0..p.len()).filter(|&j| p[j..].starts_with(&pattern)).map(|j| (i, j)).collect::<Vec<_>>())
+            .collect();
+        let found: Vec<(usize, usize)> = tree.find_all(&separated, &pattern).into_iter().map(|p| separated.locate(p)).collect();
+        assert!(found == expected);
+        assert!(tree.count(&separated, &pattern) == expected.len());
+    }
+    let second: Vec<u8> = (0..200).map(|_| rng.gen_range(0, 3)).collect();
+    let stats = tree.matching_statistics(&separated, &second[..]);
+    for (i, &length) in stats.iter().enumerate() {
+        let longest = pieces.iter()
+            .flat_map(|p| (0..p.len()).map(|j| p[j..].iter().zip(&second[i..]).take_while(|&(a, b)| a == b).count()).collect::<Vec<_>>())
+            .max()
+            .unwrap_or(0);
+        assert!(length == longest);
+    }
+    // Pieces can be added to the tree, which can be saved
+    let mut grown = SuffixTree::new(&Separated::new(&pieces[..5]));
+    grown.append(&separated);
+    assert!(grown.to_suffix_array() == tree.to_suffix_array() && grown.lcp_array() == tree.lcp_array());
+    let mut saved = Vec::new();
+    tree.save(&mut saved).unwrap();
+    let loaded = SuffixTree::load(&mut &saved[..], &separated).unwrap();
+    assert!(loaded.to_suffix_array() == tree.to_suffix_array());
+}
+
+#[test]
+fn compact_tree() {
+    let mut rng = StdRng::from_seed(&[96usize][..]);
+    let data: Vec<u8> = (0..3000).map(|_| rng.gen_range(0, 4)).collect();
+    let second: Vec<u8> = (0..1000).map(|_| rng.gen_range(0, 4)).collect();
+    let wide = SuffixTree::new(&data[..]);
+    let compact = SuffixTree::<u32>::build(&data[..]);
+    assert!(compact.nodes.len() == wide.nodes.len());
+    for (c, w) in compact.nodes.iter().zip(&wide.nodes) {
+        assert!(c.start() == w.start && c.end() == w.end && c.suffix_link() == w.suffix_link);
+        assert!(c.children().eq(w.children()));
+    }
+    assert!(compact.matching_statistics(&data[..], &second[..]) == wide.matching_statistics(&data[..], &second[..]));
+    let expected: Vec<_> = TreeMatchIterator::new(&data[..], &second[..], 5).collect();
+    let found: Vec<_> = TreeMatchIterator::with_tree(&data[..], Cow::Borrowed(&compact), &second[..], 5).collect();
+    assert!(found == expected);
+    // A tree can be loaded with the other index type
+    let mut saved = Vec::new();
+    compact.save(&mut saved).unwrap();
+    let loaded = SuffixTree::load(&mut &saved[..], &data[..]).unwrap();
+    assert!(loaded.to_suffix_array() == wide.to_suffix_array());
+    saved.clear();
+    wide.save(&mut saved).unwrap();
+    let loaded = SuffixTree::<u32>::load_indexed(&mut &saved[..], &data[..]).unwrap();
+    assert!(loaded.to_suffix_array() == wide.to_suffix_array());
+}
+
+#[test]
+#[should_panic]
+fn compact_node_overflow() {
+    Node::<u32>::build(0, u32::MAX as usize + 1);
+}
@@ -0,0 +1,43 @@
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use {AlgoSpec, Match, MatchIterator, longest_common_substring, patch_set};
+use text::{str_longest_common_substring, str_matches, str_patch_set};
+
+fn random_string(rng: &mut StdRng, chars: &[char], length: usize) -> String {
+    (0..length).map(|_| chars[rng.gen_range(0, chars.len())]).collect()
+}
+
+#[test]
+fn char_boundaries() {
+    let mut rng = StdRng::from_seed(&[84usize][..]);
+    // Characters of 1 to 4 bytes sharing their leading bytes
+    let chars = ['a', 'b', 'é', 'è', 'ï', '€', '₤', '𝄞', '𝄢'];
+    for algo_spec in [AlgoSpec::HashMatch(2), AlgoSpec::TreeMatch(2), AlgoSpec::SuffixArrayMatch(2)] {
+        for _ in 0..20 {
+            let a = random_string(&mut rng, &chars, 200);
+            let b = random_string(&mut rng, &chars, 200);
+            let matches: Vec<Match> = str_matches(&a, &b, algo_spec).collect();
+            let mut last = 0;
+            for m in &matches {
+                assert!(m.length >= 2 && m.second_pos >= last);
+                assert!(a[m.first_pos..m.first_end()] == b[m.second_pos..m.second_end()]);
+                last = m.second_pos;
+            }
+            let longest = str_longest_common_substring(&a, &b, algo_spec);
+            assert!(a[longest.first_pos..longest.first_end()] == b[longest.second_pos..longest.second_end()]);
+            assert!(matches.iter().all(|m| m.length <= longest.length));
+            for p in str_patch_set(&a, &b, algo_spec) {
+                assert!(a[p.first_pos..p.first_end()] == b[p.second_pos..p.second_end()]);
+            }
+        }
+    }
+    // ASCII strings are matched like bytes
+    let a = random_string(&mut rng, &['a', 'b', 'c'], 1000);
+    let b = random_string(&mut rng, &['a', 'b', 'c'], 1000);
+    let algo_spec = AlgoSpec::TreeMatch(4);
+    assert!(str_matches(&a, &b, algo_spec).collect::<Vec<Match>>() == MatchIterator::new(a.as_bytes(), b.as_bytes(), algo_spec).collect::<Vec<Match>>());
+    assert!(str_longest_common_substring(&a, &b, algo_spec) == longest_common_substring(a.as_bytes(), b.as_bytes(), algo_spec));
+    assert!(str_patch_set(&a, &b, algo_spec) == patch_set(a.as_bytes(), b.as_bytes(), algo_spec));
+    // A match made of a partial character only is dropped
+    assert!(str_matches("xé", "yè", AlgoSpec::TreeMatch(1)).count() == 0);
+}
@@ -0,0 +1,107 @@
+#[cfg(feature = "tracing")]
+use std::fmt;
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "profiling")]
+use std::thread;
+
+use super::rand::{Rng, SeedableRng, StdRng};
+#[cfg(feature = "tracing")]
+use tracing::{Event, Id, Metadata, Subscriber};
+#[cfg(feature = "tracing")]
+use tracing::field::{Field, Visit};
+#[cfg(feature = "tracing")]
+use tracing::span::{Attributes, Record};
+
+use {AlgoSpec, patch_set};
+#[cfg(feature = "profiling")]
+use patch::{Compression, Delta};
+#[cfg(feature = "profiling")]
+use trace::{Span, clear_subscriber, set_subscriber};
+
+#[test]
+#[cfg(feature = "profiling")]
+fn nested_spans() {
+    let mut rng = StdRng::from_seed(&[73usize][..]);
+    let a: Vec<u8> = (0..3000).map(|_| rng.gen_range(0, 4)).collect();
+    let b: Vec<u8> = (0..2000).map(|_| rng.gen_range(0, 4)).collect();
+    // Other tests run concurrently, only keep the spans of this thread
+    let spans = Arc::new(Mutex::new(Vec::<Span>::new()));
+    let recorded = spans.clone();
+    let id = thread::current().id();
+    set_subscriber(move |span| if thread::current().id() == id {
+        recorded.lock().unwrap().push(span.clone());
+    });
+    patch_set(&a, &b, AlgoSpec::HashMatch(8));
+    let mut encoded = Vec::new();
+    Delta::new(&a, &b, AlgoSpec::TreeMatch(8)).write_compressed_to(&mut encoded, Compression::Lz).unwrap();
+    clear_subscriber();
+    patch_set(&a, &b, AlgoSpec::HashMatch(8));
+    let spans = spans.lock().unwrap();
+    let names: Vec<(&str, usize, usize)> = spans.iter().map(|s| (s.name, s.bytes, s.depth)).collect();
+    assert!(names == [
+        ("hashmatch::build_map", 3000, 1),
+        ("patch_set::matches", 2000, 1),
+        ("patch_set", 5000, 0),
+        ("suffixtree::build", 3000, 2),
+        ("patch_set::matches", 2000, 2),
+        ("patch_set", 5000, 1),
+        ("patch::delta", 5000, 0),
+        ("lz::compress", spans[7].bytes, 0),
+    ]);
+    assert!(spans[0].elapsed + spans[1].elapsed <= spans[2].elapsed);
+}
+
+// Record the name and the bytes field of every new span
+#[cfg(feature = "tracing")]
+struct Collector(Arc<Mutex<Vec<(&'static str, u64)>>>);
+
+#[cfg(feature = "tracing")]
+struct Bytes(u64);
+
+#[cfg(feature = "tracing")]
+impl Visit for Bytes {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        if field.name() == "bytes" {
+            self.0 = value;
+        }
+    }
+
+    fn record_debug(&mut self, _: &Field, _: &dyn fmt::Debug) {}
+}
+
+#[cfg(feature = "tracing")]
+impl Subscriber for Collector {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes) -> Id {
+        let mut bytes = Bytes(0);
+        span.record(&mut bytes);
+        let mut spans = self.0.lock().unwrap();
+        spans.push((span.metadata().name(), bytes.0));
+        Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, _: &Id, _: &Record) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, _: &Event) {}
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+#[test]
+#[cfg(feature = "tracing")]
+fn tracing_spans() {
+    let mut rng = StdRng::from_seed(&[96usize][..]);
+    let a: Vec<u8> = (0..3000).map(|_| rng.gen_range(0, 4)).collect();
+    let b: Vec<u8> = (0..2000).map(|_| rng.gen_range(0, 4)).collect();
+    // The subscriber is only the default of this thread
+    let spans = Arc::new(Mutex::new(Vec::new()));
+    tracing::subscriber::with_default(Collector(spans.clone()), || patch_set(&a, &b, AlgoSpec::HashMatch(8)));
+    assert!(*spans.lock().unwrap() == [("patch_set", 5000), ("hashmatch::build_map", 3000), ("patch_set::matches", 2000)]);
+}
@@ -0,0 +1,29 @@
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use treematch::{TreeMatchIterator, generalized_matches};
+
+#[test]
+fn generalized_equivalence() {
+    let mut rng = StdRng::from_seed(&[49usize][..]);
+    for round in 0..30 {
+        let mml = 1 + round % 6;
+        let first: Vec<u8> = (0..rng.gen_range(0, 400)).map(|_| rng.gen_range(0, 3)).collect();
+        // Mostly similar inputs, with a few random edits, or unrelated inputs
+        let mut second = if round % 3 == 0 {
+            (0..rng.gen_range(0, 400)).map(|_| rng.gen_range(0, 3)).collect()
+        }
+        else {
+            first.clone()
+        };
+        for _ in 0..rng.gen_range(0, 20) {
+            let pos = rng.gen_range(0, second.len() + 1);
+            second.insert(pos, rng.gen_range(0, 4));
+        }
+        if first.len() < mml || second.len() < mml {
+            continue;
+        }
+        let mut expected: Vec<_> = TreeMatchIterator::new(&first[..], &second[..], mml).collect();
+        expected.sort();
+        assert!(generalized_matches(&first, &second, mml) == expected);
+    }
+}
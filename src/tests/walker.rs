@@ -0,0 +1,46 @@
+use AlgoSpec;
+use Match;
+use Region;
+use walker::{DiffWalker, Segment};
+
+fn rebuild(segments: &[Segment], a: &[u8], b: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let mut first = Vec::new();
+    let mut second = Vec::new();
+    for s in segments {
+        match *s {
+            Segment::Matched(m) => {
+                assert!(a[m.first_pos..m.first_end()] == b[m.second_pos..m.second_end()]);
+                first.extend_from_slice(&a[m.first_pos..m.first_end()]);
+                second.extend_from_slice(&b[m.second_pos..m.second_end()]);
+            },
+            Segment::OnlyFirst(r) => first.extend_from_slice(r.bytes(a)),
+            Segment::OnlySecond(r) => second.extend_from_slice(r.bytes(b)),
+        }
+    }
+    (first, second)
+}
+
+#[test]
+fn walker_segments() {
+    let a = "abcdefXXghijklmnop";
+    let b = "abcdef01ghijklmnop";
+    let segments: Vec<Segment> = DiffWalker::new(a.as_bytes(), b.as_bytes(), AlgoSpec::TreeMatch(4)).collect();
+    assert!(segments == vec![
+        Segment::Matched(Match::new(0, 0, 6)),
+        Segment::OnlyFirst(Region::new(6, 8)),
+        Segment::OnlySecond(Region::new(6, 8)),
+        Segment::Matched(Match::new(8, 8, 10)),
+    ]);
+}
+
+#[test]
+fn walker_rebuild() {
+    let a = "abcdefghijqrstuvwxyzfghijklmnopqrXXX";
+    let b = "YYabcdefghijklmnopqrstuvwxyz";
+    for algo_spec in &[AlgoSpec::HashMatch(2), AlgoSpec::HashMatch(4), AlgoSpec::TreeMatch(3)] {
+        let segments: Vec<Segment> = DiffWalker::new(a.as_bytes(), b.as_bytes(), *algo_spec).collect();
+        let (first, second) = rebuild(&segments, a.as_bytes(), b.as_bytes());
+        assert!(first == a.as_bytes());
+        assert!(second == b.as_bytes());
+    }
+}
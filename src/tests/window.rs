@@ -0,0 +1,37 @@
+use std::io::Cursor;
+
+use super::rand::{Rng, SeedableRng, StdRng};
+
+use {AlgoSpec, Match, MatchIterator};
+use window::windowed_matches;
+
+#[test]
+fn windowed_equivalence() {
+    let mut rng = StdRng::from_seed(&[11usize][..]);
+    let mut a = vec![0u8; 3000];
+    let mut b = vec![0u8; 2000];
+    for x in a.iter_mut() {
+        *x = rng.gen_range(0, 4);
+    }
+    for x in b.iter_mut() {
+        *x = rng.gen_range(0, 4);
+    }
+    // Plant a long match spanning many windows
+    let copy = a[500..1400].to_vec();
+    b[700..1600].copy_from_slice(&copy);
+    for &algo_spec in &[AlgoSpec::TreeMatch(6), AlgoSpec::HashMatch(6)] {
+        let mut expected: Vec<Match> = MatchIterator::new(&a, &b, algo_spec).collect();
+        expected.sort();
+        let windowed = windowed_matches(&mut Cursor::new(&a), &mut Cursor::new(&b), algo_spec, 256, 6).unwrap();
+        assert!(windowed == expected);
+        assert!(windowed.iter().any(|m| m.length >= 900));
+    }
+}
+
+#[test]
+#[should_panic]
+fn windowed_hybrid() {
+    let a = vec![b'a'; 18];
+    let b = vec![b'a'; 30];
+    windowed_matches(&mut Cursor::new(&a), &mut Cursor::new(&b), AlgoSpec::Hybrid(8, 2), 8, 4).unwrap();
+}
@@ -0,0 +1,137 @@
+//! Comparison of UTF-8 strings.
+//!
+//! The algorithms match bytes, so a match between two strings can start or end in the middle of a
+//! multi-byte character, and slicing a `&str` there panics. The functions of this module compare
+//! the bytes of the strings but snap the reported matches to character boundaries: a match starts
+//! at the first boundary inside it and ends at the last one, in both strings. Since the matched
+//! bytes are equal, the boundaries inside a match are the same in both strings, and only its ends
+//! move by at most 3 bytes.
+//!
+//! # Examples
+//!
+//! ```
+//! use bcmp::AlgoSpec;
+//! use bcmp::text::str_matches;
+//!
+//! // 'é' and 'è' are both encoded as 0xC3 followed by a different byte
+//! let a = "café noir";
+//! let b = "cafè noir";
+//! for m in str_matches(a, b, AlgoSpec::TreeMatch(3)) {
+//!     assert!(&a[m.first_pos..m.first_end()] == &b[m.second_pos..m.second_end()]);
+//! }
+//! ```
+
+use std::iter::FusedIterator;
+
+use {AlgoSpec, Match, MatchIterator, patch_set};
+
+// Shrink m to the character boundaries of both strings, or return None if no character is left
+fn snap(first: &str, second: &str, m: Match) -> Option<Match> {
+    let mut start = 0;
+    while start < m.length && !(first.is_char_boundary(m.first_pos + start) && second.is_char_boundary(m.second_pos + start)) {
+        start += 1;
+    }
+    let mut end = m.length;
+    while end > start && !(first.is_char_boundary(m.first_pos + end) && second.is_char_boundary(m.second_pos + end)) {
+        end -= 1;
+    }
+    if end == start {
+        return None;
+    }
+    Some(Match::new(m.first_pos + start, m.second_pos + start, end - start))
+}
+
+/// An iterator over the [`Match`](../struct.Match.html) between two strings, snapped to character
+/// boundaries, see [`str_matches`](fn.str_matches.html).
+#[derive(Clone)]
+pub struct StrMatches<'a> {
+    first: &'a str,
+    second: &'a str,
+    minimal_length: usize,
+    iter: MatchIterator<'a>,
+}
+
+impl<'a> Iterator for StrMatches<'a> {
+    type Item = Match;
+    fn next(&mut self) -> Option<Match> {
+        for m in self.iter.by_ref() {
+            if let Some(m) = snap(self.first, self.second, m) {
+                if m.length >= self.minimal_length {
+                    return Some(m);
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<'a> FusedIterator for StrMatches<'a> {}
+
+/// Return an iterator over the matches between two strings, whose positions are character
+/// boundaries of both of them.
+///
+/// The matches are the ones of [`MatchIterator::new`](../struct.MatchIterator.html#method.new)
+/// over the bytes of the strings, shrunk to the character boundaries. The matches which become
+/// shorter than the minimal length of `algo_spec` are dropped. Shrinking never moves a match past
+/// the next one, so they are still returned in ascending order of
+/// [`second_pos`](../struct.Match.html#structfield.second_pos).
+///
+/// It will panic if the [`AlgoSpec`](../enum.AlgoSpec.html) is not supported.
+pub fn str_matches<'a>(first: &'a str, second: &'a str, algo_spec: AlgoSpec) -> StrMatches<'a> {
+    StrMatches {
+        first,
+        second,
+        minimal_length: algo_spec.minimal_length(),
+        iter: MatchIterator::new(first.as_bytes(), second.as_bytes(), algo_spec),
+    }
+}
+
+/// Return the longest match between two strings snapped to character boundaries, or an empty
+/// match if there isn't any. Among the matches of the same length, the first one returned by
+/// [`str_matches`](fn.str_matches.html) is kept.
+///
+/// The snapped match can be up to 6 bytes shorter than the
+/// [`longest_common_substring`](../fn.longest_common_substring.html) of the bytes.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::AlgoSpec;
+/// use bcmp::text::str_longest_common_substring;
+///
+/// let a = "Grüße aus München";
+/// let b = "Viele Grüße!";
+/// let m = str_longest_common_substring(a, b, AlgoSpec::TreeMatch(2));
+/// assert!(&a[m.first_pos..m.first_end()] == "Grüße");
+/// ```
+pub fn str_longest_common_substring(first: &str, second: &str, algo_spec: AlgoSpec) -> Match {
+    let mut longest = Match::new(0, 0, 0);
+    for m in str_matches(first, second, algo_spec) {
+        if m.length > longest.length {
+            longest = m;
+        }
+    }
+    longest
+}
+
+/// Return the [`patch_set`](../fn.patch_set.html) of two strings with every patch snapped to
+/// character boundaries. The bytes a snapped patch no longer covers, at most 3 at each end, have
+/// to be inserted like the ones between the patches.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::AlgoSpec;
+/// use bcmp::text::str_patch_set;
+///
+/// let a = "naïve café";
+/// let b = "café naïve";
+/// for p in str_patch_set(a, b, AlgoSpec::HashMatch(4)) {
+///     println!("{:?} copied to {}", &a[p.first_pos..p.first_end()], p.second_pos);
+/// }
+/// ```
+pub fn str_patch_set(first: &str, second: &str, algo_spec: AlgoSpec) -> Vec<Match> {
+    patch_set(first.as_bytes(), second.as_bytes(), algo_spec).into_iter()
+        .filter_map(|m| snap(first, second, m))
+        .collect()
+}
@@ -0,0 +1,100 @@
+//! Instrumentation of the expensive phases, available with the `profiling` feature.
+//!
+//! Index construction, match enumeration and delta generation are wrapped in spans. When a span
+//! closes, a [`Span`](struct.Span.html) record with its name, the number of bytes it processed
+//! and its duration is passed to the subscriber installed with
+//! [`set_subscriber`](fn.set_subscriber.html). Spans nest: a `patch_set` span contains the index
+//! construction it triggered and the [`depth`](struct.Span.html#structfield.depth) of a record
+//! tells how deeply it was nested on its thread.
+//!
+//! The subscriber is a plain closure, so the records can be forwarded to a logging or metrics
+//! framework without bcmp depending on it. The `tracing` feature instead emits the same spans
+//! through the [`tracing`](https://docs.rs/tracing) crate, at the `INFO` level with a `bytes`
+//! field, to whatever `tracing` subscriber the application installed. Both features can be
+//! enabled together and without them the spans compile to nothing.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//! use bcmp::{AlgoSpec, patch_set};
+//! use bcmp::trace::{clear_subscriber, set_subscriber};
+//!
+//! static INDEXED: AtomicUsize = AtomicUsize::new(0);
+//! set_subscriber(|span| if span.name == "suffixtree::build" {
+//!     INDEXED.fetch_add(span.bytes, Ordering::Relaxed);
+//! });
+//! patch_set(b"abcdefgh", b"0abcd1efgh", AlgoSpec::TreeMatch(4));
+//! clear_subscriber();
+//! assert!(INDEXED.load(Ordering::Relaxed) >= 8);
+//! ```
+
+use std::cell::Cell;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+type Subscriber = Box<dyn Fn(&Span) + Send + Sync>;
+
+static SUBSCRIBER: RwLock<Option<Subscriber>> = RwLock::new(None);
+
+thread_local! {
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// The record of a closed span.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Span {
+    /// The name of the phase, like `hashmatch::build_map` or `patch::delta`.
+    pub name: &'static str,
+    /// The number of bytes processed by the phase, usually the length of its input.
+    pub bytes: usize,
+    /// The time spent in the phase, including its nested spans.
+    pub elapsed: Duration,
+    /// The number of spans enclosing this one on the same thread.
+    pub depth: usize,
+}
+
+/// Install `subscriber`, called from the thread of each span when it closes. It replaces the
+/// previous subscriber, if any, for the whole process.
+pub fn set_subscriber<F: Fn(&Span) + Send + Sync + 'static>(subscriber: F) {
+    *SUBSCRIBER.write().unwrap_or_else(|e| e.into_inner()) = Some(Box::new(subscriber));
+}
+
+/// Remove the subscriber: the spans are no longer reported.
+pub fn clear_subscriber() {
+    *SUBSCRIBER.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+// An open span, reported when dropped
+pub(crate) struct Guard {
+    name: &'static str,
+    bytes: usize,
+    start: Instant,
+    depth: usize,
+}
+
+impl Guard {
+    pub(crate) fn enter(name: &'static str, bytes: usize) -> Guard {
+        let depth = DEPTH.with(|d| d.replace(d.get() + 1));
+        Guard {
+            name,
+            bytes,
+            start: Instant::now(),
+            depth,
+        }
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        DEPTH.with(|d| d.set(self.depth));
+        if let Some(ref subscriber) = *SUBSCRIBER.read().unwrap_or_else(|e| e.into_inner()) {
+            subscriber(&Span {
+                name: self.name,
+                bytes: self.bytes,
+                elapsed: self.start.elapsed(),
+                depth: self.depth,
+            });
+        }
+    }
+}
@@ -1,250 +1,78 @@
 //! TreeMatch is a binary matching algorithm based on a suffix tree to retrieve matching strings. 
 //!
-//! The suffix tree is built in linear time using Ukkonen's algorithm.
+//! The suffix tree is built in linear time using Ukkonen's algorithm. It lives in the 
+//! [`suffixtree`](../suffixtree/index.html) module and is re-exported here.
 
+use std::borrow::Cow;
 use std::collections::HashMap;
-use std::iter::Iterator;
-use std::usize;
+use std::iter::{FusedIterator, Iterator};
 
 use Match;
+use scatter::Scattered;
+use source::ByteSource;
+pub use suffixtree::{Children, DepthFirst, Leaves, MAX_LEN, Node, NodeIndex, SuffixTree, Suffixes};
 
-/// A node in the [`SuffixTree`](struct.SuffixTree.html)
-pub struct Node {
-    /// The index in the data where the edge leading to this node starts.
-    pub start: usize,
-    /// The index in the data where the edge leading to this node ends.
-    pub end: usize,
-    /// The potential sub nodes under this one. Each index in the array represent on of the 
-    /// possible byte value. The index `256` is reserved for the end of data. Each element value is 
-    /// an index in the `SuffixTree::nodes`(struct.SuffixTree.html#nodes.v) vector.
-    pub edges: [Option<usize>; 257],
-    /// Suffix link (see Ukkonen's algorithm).
-    pub suffix_link: Option<usize>,
-}
-
-/// A suffix tree.
-pub struct SuffixTree {
-    /// A vector of [`Node`](struct.Node.html) composing this tree. The first element is the root 
-    /// node.
-    pub nodes: Vec<Node>,
-}
-
-impl Node {
-    /// Allocate a new node with a leading edge `[start..end]`.
-    pub fn new(start: usize, end: usize) -> Node {
-        Node {
-            start: start,
-            end: end,
-            edges: [None; 257],
-            suffix_link: None,
-        }
-    }
-    /// Returns this node leading edge length.
-    pub fn edge_length(&self) -> usize {
-        self.end - self.start
-    }
-}
-
-impl SuffixTree {
-    /// Build a new suffix tree for `data` using Ukkonen's algorithm.
-    pub fn new(data: &[u8]) -> SuffixTree {
-        let mut nodes = Vec::<Node>::new();
-        nodes.push(Node::new(0, 0));
-        let mut tree = SuffixTree {
-            nodes: nodes,
-        };
-        tree.extend_tree(data);
-        return tree;
-    }
-
-    #[allow(unused_assignments)]
-    fn extend_tree(&mut self, data: &[u8]) {
-        let mut last_new_node: Option<usize> = None;
-        let mut active_node: usize = 0;
-        let mut active_length: usize = 0;
-        let mut active_edge: usize = data[0] as usize;
-        let mut remaining_suffix: usize = 0;
-        for i in 0..data.len() {
-            last_new_node = None;
-            remaining_suffix += 1;
-            while remaining_suffix > 0 {
-                if active_length == 0 {
-                    active_edge = data[i] as usize;
-                }
-                if let Some(next_node) = self.nodes[active_node].edges[active_edge] {
-                    // If the active length is longer than the current edge, we walk down the edge 
-                    // to the next node.
-                    if active_length >= self.nodes[next_node].edge_length() {
-                        active_node = next_node;
-                        active_length -= self.nodes[next_node].edge_length();
-                        active_edge = data[i - active_length] as usize;
-                        continue;
-                    }
-                    // Rule 3: the current character is on the edge
-                    else if data[self.nodes[next_node].start + active_length] == data[i] {
-                        // Make a suffix link to the active node if there is a node waiting and if 
-                        // the active node is not the root node
-                        if last_new_node.is_some() && active_node > 0 {
-                            self.nodes[last_new_node.unwrap()].suffix_link = Some(active_node);
-                            last_new_node = None;
-                        }
-                        active_length += 1;
-                        break;
-                    }
-                    // We need to split the edge at the current character
-                    else {
-                        let start = self.nodes[next_node].start;
-                        let split_pos = self.nodes[next_node].start + active_length;
-                        self.nodes.push(Node::new(start, split_pos));
-                        let split = self.nodes.len() - 1;
-                        self.nodes[next_node].start = split_pos;
-                        self.nodes[active_node].edges[data[start] as usize] = Some(split);
-                        self.nodes[split].edges[data[split_pos] as usize] = Some(next_node);
-                        self.nodes.push(Node::new(i, data.len()));
-                        let leaf = self.nodes.len() - 1;
-                        self.nodes[split].edges[data[i] as usize] = Some(leaf);
-                        // Make a suffix link to our next node
-                        if last_new_node.is_some() {
-                            self.nodes[last_new_node.unwrap()].suffix_link = Some(split);
-                        }
-                        last_new_node = Some(split);
-                    }
-                }
-                else {
-                    // Rule 2: we create a new leaf edge
-                    self.nodes.push(Node::new(i, data.len()));
-                    let leaf = self.nodes.len() - 1;
-                    self.nodes[active_node].edges[active_edge] = Some(leaf);
-                    // Make a suffix link if there is a node waiting
-                    if last_new_node.is_some() {
-                        self.nodes[last_new_node.unwrap()].suffix_link = Some(active_node);
-                    }
-                    last_new_node = Some(active_node);
-                }
-                
-                remaining_suffix -= 1;
-                if active_node == 0 && active_length > 0 {
-                    active_length -= 1;
-                    active_edge = data[i - remaining_suffix + 1] as usize;
-                }
-                else if active_node != 0 {
-                    active_node = match self.nodes[active_node].suffix_link {
-                        Some(linked) => linked,
-                        None => 0
-                    };
-                }
+// Call f(first_pos, second_pos, length) for every match of at least minimal_length bytes between 
+// first, which tree was built from, and second, in ascending order of second_pos. A match is only 
+// reported where it is left maximal, which finds the same matches as TreeMatchIterator without 
+// tracking the diagonals.
+pub(crate) fn scan<I: NodeIndex, F: FnMut(usize, usize, usize)>(tree: &SuffixTree<I>, first: &[u8], second: &[u8], minimal_length: usize, mut f: F) {
+    // Empty matches cover nothing
+    let minimal_length = minimal_length.max(1);
+    // The nodes on the path matching second[i..], with the length of their path and the number of 
+    // bytes of second matched down to them
+    let mut path = Vec::<(usize, usize, usize)>::new();
+    let mut stack = Vec::<(usize, usize)>::new();
+    for i in 0..second.len() {
+        path.clear();
+        let mut cur = 0;
+        let mut depth = 0;
+        while i + depth < second.len() {
+            let next = match tree.nodes[cur].child(second[i + depth] as usize) {
+                Some(next) => next,
+                None => break
+            };
+            let node = &tree.nodes[next];
+            let matched = first[node.start()..node.end()].iter().zip(second[i + depth..].iter()).take_while(|&(a, b)| a == b).count();
+            path.push((next, depth + node.edge_length(), depth + matched));
+            if matched < node.edge_length() {
+                break;
             }
+            depth += node.edge_length();
+            cur = next;
         }
-        // Simulate end character by doing another step with false character 256
-        last_new_node = None;
-        remaining_suffix += 1;
-        while remaining_suffix > 0 {
-            // Active length is zero, so the current character is *i* and no walk down is needed.
-            if active_length == 0 {
-                // Special end character
-                active_edge = 256;
+        // The leaves below a node of the path but not below the next one have its matched length 
+        // in common with second[i..]
+        let mut below = None;
+        while let Some((node, node_depth, matched)) = path.pop() {
+            if matched < minimal_length {
+                break;
             }
-            if let Some(next_node) = self.nodes[active_node].edges[active_edge] {
-                // If the active length is longer than the current edge, we walk down the edge
-                if active_length >= self.nodes[next_node].edge_length() {
-                    active_length -= self.nodes[next_node].edge_length();
-                    active_node = next_node;
-                    active_edge = match active_length {
-                       0 => 256,
-                       _ => data[data.len() - active_length] as usize
-                    };
-                    continue;
-                }
-                else if self.nodes[next_node].start + active_length == data.len() {
-                    // Make a suffix link to the active node if there is a node waiting and if 
-                    // the active node is not the root node
-                    if last_new_node.is_some() && active_node > 0 {
-                        self.nodes[last_new_node.unwrap()].suffix_link = Some(active_node);
-                        last_new_node = None;
+            stack.push((node, node_depth));
+            while let Some((cur, depth)) = stack.pop() {
+                if tree.nodes[cur].is_leaf() {
+                    let first_pos = first.len() - depth;
+                    if i == 0 || first_pos == 0 || first[first_pos - 1] != second[i - 1] {
+                        f(first_pos, i, matched);
                     }
-                    active_length += 1;
-                    break;
                 }
-                // We need to split the edge at the current character
-                else {
-                    let start = self.nodes[next_node].start;
-                    let split_pos = self.nodes[next_node].start + active_length;
-                    self.nodes.push(Node::new(start, split_pos));
-                    let split = self.nodes.len() - 1;
-                    self.nodes[next_node].start = split_pos;
-                    self.nodes[active_node].edges[data[start] as usize] = Some(split);
-                    self.nodes[split].edges[data[split_pos] as usize] = Some(next_node);
-                    self.nodes.push(Node::new(data.len(), data.len()));
-                    let leaf = self.nodes.len() - 1;
-                    self.nodes[split].edges[256] = Some(leaf);
-                    // Make a suffix link to our next node
-                    if last_new_node.is_some() {
-                        self.nodes[last_new_node.unwrap()].suffix_link = Some(split);
+                for (_, next) in tree.nodes[cur].children() {
+                    if Some(next) != below {
+                        stack.push((next, depth + tree.nodes[next].edge_length()));
                     }
-                    last_new_node = Some(split);
-                }
-            }
-            else {
-                // Rule 2: we create a new leaf edge
-                self.nodes.push(Node::new(data.len(), data.len()));
-                let leaf = self.nodes.len() - 1;
-                self.nodes[active_node].edges[active_edge] = Some(leaf);
-                // Make a suffix link if there is a node waiting
-                if last_new_node.is_some() {
-                    self.nodes[last_new_node.unwrap()].suffix_link = Some(active_node);
                 }
-                last_new_node = Some(active_node);
-            }
-            
-            remaining_suffix -= 1;
-            if active_node == 0 && active_length > 0 {
-                active_length -= 1;
-                if remaining_suffix < 2 {
-                    active_edge = 256;
-                }
-                else {
-                    active_edge = data[data.len() - remaining_suffix + 1] as usize;
-                }
-            }
-            else if active_node != 0 {
-                active_node = match self.nodes[active_node].suffix_link {
-                    Some(linked) => linked,
-                    None => 0
-                };
-            }
-        }
-    }
-
-    pub fn to_graphviz(&self, data: &[u8]) -> String {
-        let mut graphviz = String::new();
-        graphviz.push_str("digraph {\n");
-        for i in 0..self.nodes.len() {
-            graphviz.push_str(&format!("    NODE_{};\n", i));
-        }
-        for i in 0..self.nodes.len() {
-            for j in 0..self.nodes[i].edges.len() {
-                if let Some(edge) = self.nodes[i].edges[j] {
-                    let start = self.nodes[edge].start;
-                    let end = self.nodes[edge].end;
-                    if let Ok(s) = String::from_utf8(data[start..end].to_owned()) {
-                        graphviz.push_str(&format!("    NODE_{} -> NODE_{} [ label = \"{}\" ];\n", i, edge, &s));
-                    }
-                    else {
-                        graphviz.push_str(&format!("    NODE_{} -> NODE_{} [ label = \"{:?}\" ];\n", i, edge, &data[start..end]));
-                    }
-                }
-            }
-            if let Some(linked) = self.nodes[i].suffix_link {
-                graphviz.push_str(&format!("    NODE_{} -> NODE_{} [ style = \"dashed\" ];\n", i, linked));
             }
+            below = Some(node);
         }
-        graphviz.push_str("}");
-        return graphviz;
     }
 }
 
 /// An iterator over all the [`Match`](../struct.Match.html) bewteen two pieces of data.
 ///
+/// Only maximal exact matches are returned: they can neither be extended to the left nor to the 
+/// right. `I` is the [`NodeIndex`](../suffixtree/trait.NodeIndex.html) of the suffix tree of the 
+/// first piece of data.
+///
 /// # Examples
 /// 
 /// ```
@@ -257,44 +85,125 @@ impl SuffixTree {
 ///     println!("Match: {:}", &a[m.first_pos..m.first_end()]);
 /// }
 /// ```
-pub struct TreeMatchIterator<'a> {
-    first: &'a [u8],
-    second: &'a [u8],
-    tree: SuffixTree,
+pub struct TreeMatchIterator<'a, F: ByteSource + ?Sized + 'a = [u8], S: ByteSource + ?Sized + 'a = [u8], I: NodeIndex = usize> {
+    first: &'a F,
+    second: &'a S,
+    tree: Cow<'a, SuffixTree<I>>,
     minimal_length: usize,
     i: usize,
     backtrace: Vec<(usize,usize)>,
     match_length: usize,
     depth: usize,
-    matched: HashMap<isize, usize>
+    seek_pos: usize,
+    matched: HashMap<i64, usize>,
+    skip_ahead: bool,
+    // End in second of the matches returned at the current position
+    skip_to: usize
+}
+
+impl<'a, F: ByteSource + ?Sized, S: ByteSource + ?Sized> TreeMatchIterator<'a, F, S> {
+    /// Allocate a new iterator over the matches between two pieces of data with a minimal matching 
+    /// length. The pieces of data can be byte slices or any other 
+    /// [`ByteSource`](../source/trait.ByteSource.html).
+    ///
+    /// The suffix tree uses `usize` indices, [`with_tree`](#method.with_tree) takes a compact 
+    /// `SuffixTree<u32>` as well.
+    pub fn new(first: &'a F, second: &'a S, minimal_length: usize) -> TreeMatchIterator<'a, F, S> {
+        TreeMatchIterator::with_tree(first, Cow::Owned(SuffixTree::new(first)), second, minimal_length)
+    }
 }
 
-impl<'a> TreeMatchIterator<'a> {
-    /// Allocate a new iterator over the matches between two byte slices with a minimal matching length.
-    pub fn new(first: &'a[u8], second: &'a [u8], minimal_length: usize) -> TreeMatchIterator<'a> {
-        let tree = SuffixTree::new(first);
+impl<'a, F: ByteSource + ?Sized, S: ByteSource + ?Sized, I: NodeIndex> TreeMatchIterator<'a, F, S, I> {
+    /// Allocate a new iterator reusing `tree`, which must have been built from `first`. Borrowing 
+    /// the tree allows to compare many pieces of data against `first` while building its suffix 
+    /// tree only once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::borrow::Cow;
+    /// use bcmp::treematch::{SuffixTree, TreeMatchIterator};
+    ///
+    /// let a = b"abcdefg";
+    /// let tree = SuffixTree::<u32>::build(&a[..]);
+    /// let matches = TreeMatchIterator::with_tree(&a[..], Cow::Borrowed(&tree), &b"xxcdefxx"[..], 3);
+    /// assert!(matches.count() == 1);
+    /// ```
+    pub fn with_tree(first: &'a F, tree: Cow<'a, SuffixTree<I>>, second: &'a S, minimal_length: usize) -> TreeMatchIterator<'a, F, S, I> {
         TreeMatchIterator {
-            first: first,
-            second: second,
-            tree: tree,
-            minimal_length: minimal_length,
+            first,
+            second,
+            tree,
+            minimal_length,
             i: 0,
             backtrace: Vec::new(),
             match_length: 0,
             depth: 0,
-            matched: HashMap::new()
+            seek_pos: 0,
+            matched: HashMap::new(),
+            skip_ahead: false,
+            skip_to: 0
         }
     }
+
+    /// Make the iterator skip ahead: once the matches at a position of the second piece of data 
+    /// are returned, the scan resumes at the end of the longest of them instead of the next 
+    /// position, as if [`seek`](#method.seek) was called there.
+    ///
+    /// On mostly identical data this avoids walking the tree at every position of a long match. 
+    /// The cost is that the matches starting inside a returned match, including the ones 
+    /// overlapping its end, are never returned.
+    pub fn skip_ahead(mut self) -> TreeMatchIterator<'a, F, S, I> {
+        self.skip_ahead = true;
+        self
+    }
     /// Reset the iterator to its start. This allows to iterate multiple times over the matches 
     /// without wasting time regenerating the `HashMap`.
     pub fn reset(&mut self) {
-        self.i = 0;
+        self.seek(0);
+    }
+
+    /// Move the iterator to `second_pos`: it then returns the matches at or after that position in 
+    /// the second piece of data, exactly as a complete iteration would. The position can be before 
+    /// or after the current one.
+    pub fn seek(&mut self, second_pos: usize) {
+        self.i = second_pos;
+        self.seek_pos = second_pos;
         self.backtrace.clear();
         self.matched.clear();
+        self.skip_to = 0;
+    }
+
+    // Move to the next position of second, or past the matches returned when skipping ahead
+    fn advance(&mut self) {
+        self.i += 1;
+        if self.skip_ahead && self.skip_to > self.i {
+            self.i = self.skip_to;
+            self.seek_pos = self.skip_to;
+        }
+    }
+}
+
+impl<'a, F: ByteSource + ?Sized, S: ByteSource + ?Sized, I: NodeIndex> Clone for TreeMatchIterator<'a, F, S, I> {
+    fn clone(&self) -> TreeMatchIterator<'a, F, S, I> {
+        TreeMatchIterator {
+            first: self.first,
+            second: self.second,
+            tree: self.tree.clone(),
+            minimal_length: self.minimal_length,
+            i: self.i,
+            backtrace: self.backtrace.clone(),
+            match_length: self.match_length,
+            depth: self.depth,
+            seek_pos: self.seek_pos,
+            matched: self.matched.clone(),
+            skip_ahead: self.skip_ahead,
+            skip_to: self.skip_to
+        }
     }
 }
 
-impl<'a> Iterator for TreeMatchIterator<'a> {
+impl<'a, F: ByteSource + ?Sized, S: ByteSource + ?Sized, I: NodeIndex> Iterator for TreeMatchIterator<'a, F, S, I> {
     type Item = Match;
     fn next(&mut self) -> Option<Match> {
         while self.i < self.second.len() {
@@ -309,11 +218,11 @@ impl<'a> Iterator for TreeMatchIterator<'a> {
                     if second_idx >= self.second.len() {
                         break;
                     }
-                    if let Some(next) = self.tree.nodes[cur].edges[self.second[second_idx] as usize] {
+                    if let Some(next) = self.tree.nodes[cur].child(self.second.byte_at(second_idx) as usize) {
                         for j in 0..self.tree.nodes[next].edge_length() {
-                            let first_idx = self.tree.nodes[next].start + j;
+                            let first_idx = self.tree.nodes[next].start() + j;
                             let second_idx = self.i + self.depth + j;
-                            if second_idx < self.second.len() && self.first[first_idx] == self.second[second_idx] {
+                            if second_idx < self.second.len() && self.first.byte_at(first_idx) == self.second.byte_at(second_idx) {
                                 self.match_length += 1;
                             }
                             else {
@@ -329,50 +238,52 @@ impl<'a> Iterator for TreeMatchIterator<'a> {
                 }
                 // Was the dive successful? If not, go to the next index in second
                 if self.match_length < self.minimal_length {
-                    self.i += 1;
+                    self.advance();
                     continue;
                 }
                 // Mark this node as the start
                 self.backtrace.push((cur,0));
             }
-            while self.backtrace.len() > 0 {
-                let (cur, mut idx) = self.backtrace.last().unwrap().clone();
-                while idx < 257 {
-                    if let Some(next) = self.tree.nodes[cur].edges[idx] {
-                        // Are we still matching? or just enumerating the terminating leaf?
-                        if self.match_length == self.depth {
-                            for j in 0..self.tree.nodes[next].edge_length() {
-                                let first_idx = self.tree.nodes[next].start + j;
-                                let second_idx = self.i + self.depth + j;
-                                if second_idx < self.second.len() && self.first[first_idx] == self.second[second_idx] {
-                                    self.match_length += 1;
-                                }
-                                else {
-                                    break;
-                                }
+            while !self.backtrace.is_empty() {
+                let (cur, idx) = *self.backtrace.last().unwrap();
+                if let Some((_, next)) = self.tree.nodes[cur].children().nth(idx) {
+                    // Are we still matching? or just enumerating the terminating leaf?
+                    if self.match_length == self.depth {
+                        for j in 0..self.tree.nodes[next].edge_length() {
+                            let first_idx = self.tree.nodes[next].start() + j;
+                            let second_idx = self.i + self.depth + j;
+                            if second_idx < self.second.len() && self.first.byte_at(first_idx) == self.second.byte_at(second_idx) {
+                                self.match_length += 1;
+                            }
+                            else {
+                                break;
                             }
                         }
-                        // Update the idx
-                        self.backtrace.last_mut().unwrap().1 = idx + 1;
-                        // Go down
-                        self.depth += self.tree.nodes[next].edge_length();
-                        self.backtrace.push((next,0));
-                        break;
                     }
-                    idx += 1;
+                    // Update the idx
+                    self.backtrace.last_mut().unwrap().1 = idx + 1;
+                    // Go down
+                    self.depth += self.tree.nodes[next].edge_length();
+                    self.backtrace.push((next,0));
                 }
                 // If we are still on the same node
                 if cur == self.backtrace.last().unwrap().0 {
-                    // If we went over all the possible edges without finding a node, we were on a leaf
+                    // If the node has no child, we were on a leaf
                     if self.backtrace.last().unwrap().1 == 0 {
                         // Update the idx
                         self.backtrace.last_mut().unwrap().1 = idx + 1;
                         // Handle the match
-                        let m = Match::new(self.tree.nodes[cur].end - self.depth, self.i, self.match_length);
-                        let delta = m.first_pos as isize - m.second_pos as isize;
+                        let m = Match::new(self.tree.nodes[cur].end() - self.depth, self.i, self.match_length);
+                        let delta = m.first_pos as i64 - m.second_pos as i64;
                         if !(self.matched.contains_key(&delta) && self.matched.get(&delta).unwrap() >= &m.second_pos) {
                             self.matched.insert(delta, m.second_pos + m.length);
-                            return Some(m);
+                            // The rest of a match starting before the seek position isn't a match
+                            let continued = self.i == self.seek_pos && self.i > 0 && m.first_pos > 0 
+                                && self.first.byte_at(m.first_pos - 1) == self.second.byte_at(self.i - 1);
+                            if !continued {
+                                self.skip_to = self.skip_to.max(m.second_pos + m.length);
+                                return Some(m);
+                            }
                         }
                     }
                     // Else we just backtrack
@@ -387,8 +298,142 @@ impl<'a> Iterator for TreeMatchIterator<'a> {
                     }
                 }
             }
-            self.i += 1;
+            self.advance();
+        }
+        None
+    }
+
+    // Each remaining position in second can start at most one match per suffix of first
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.second.len().saturating_sub(self.i);
+        (0, remaining.checked_mul(self.first.len()))
+    }
+}
+
+impl<'a, F: ByteSource + ?Sized, S: ByteSource + ?Sized, I: NodeIndex> FusedIterator for TreeMatchIterator<'a, F, S, I> {}
+
+// Leaves of a subtree as (left key, position) pairs sorted by key, where the left key is the byte 
+// preceding the position or 256 at the start of the data
+type LeafList = Vec<(u16, usize)>;
+
+fn merge_leaves(a: LeafList, b: LeafList) -> LeafList {
+    let mut merged = Vec::with_capacity(a.len() + b.len());
+    let mut b = b.into_iter().peekable();
+    for x in a {
+        while let Some(&y) = b.peek() {
+            if y.0 >= x.0 {
+                break;
+            }
+            merged.push(y);
+            b.next();
+        }
+        merged.push(x);
+    }
+    merged.extend(b);
+    merged
+}
+
+// Call f for every pair of leaves whose left keys differ or are the start of the data
+fn left_maximal_pairs<F: FnMut(usize, usize)>(firsts: &[(u16, usize)], seconds: &[(u16, usize)], mut f: F) {
+    // Groups of the second positions sharing the same left key
+    let mut groups = Vec::new();
+    let mut start = 0;
+    for i in 1..seconds.len() + 1 {
+        if i == seconds.len() || seconds[i].0 != seconds[start].0 {
+            groups.push((seconds[start].0, start, i));
+            start = i;
+        }
+    }
+    for &(key, p) in firsts {
+        for &(group_key, start, end) in &groups {
+            if key != group_key || key == 256 {
+                for &(_, q) in &seconds[start..end] {
+                    f(p, q);
+                }
+            }
+        }
+    }
+}
+
+/// Return all the [`Match`](../struct.Match.html) of at least `minimal_length` bytes between two 
+/// pieces of data using a generalized suffix tree built over both of them.
+///
+/// Instead of walking the second piece of data byte by byte against the suffix tree of the first 
+/// one like [`TreeMatchIterator`](struct.TreeMatchIterator.html), the matches are read directly 
+/// from the tree of `first` followed by `second`: every internal node at least `minimal_length` 
+/// deep pairs the suffixes of `first` and `second` below it whose preceding bytes differ. Only the 
+/// subtrees deep enough are visited, which makes it substantially faster on highly similar inputs.
+///
+/// It returns the same matches as `TreeMatchIterator`, sorted in their natural order. It will 
+/// panic if the minimal matching length is zero.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::Match;
+/// use bcmp::treematch::generalized_matches;
+///
+/// let matches = generalized_matches(b"abcdefg", b"012abc34cdef56efg78abcdefg", 3);
+/// assert!(matches[0] == Match::new(0, 3, 3));
+/// assert!(matches.last() == Some(&Match::new(0, 19, 7)));
+/// ```
+pub fn generalized_matches(first: &[u8], second: &[u8], minimal_length: usize) -> Vec<Match> {
+    assert!(minimal_length > 0, "The minimal length must be at least 1");
+    let mut matches = Vec::new();
+    if first.len() < minimal_length || second.len() < minimal_length {
+        return matches;
+    }
+    let segments = [first, second];
+    let joined = Scattered::new(&segments);
+    if joined.len() <= MAX_LEN {
+        pairs(&SuffixTree::<u32>::build(&joined), first, second, minimal_length, &mut matches);
+    }
+    else {
+        pairs(&SuffixTree::new(&joined), first, second, minimal_length, &mut matches);
+    }
+    matches.sort();
+    matches
+}
+
+// Push the matches read from the generalized suffix tree of first followed by second
+fn pairs<I: NodeIndex>(tree: &SuffixTree<I>, first: &[u8], second: &[u8], minimal_length: usize, matches: &mut Vec<Match>) {
+    let joined_len = first.len() + second.len();
+    let order: Vec<(usize, usize)> = tree.depth_first().collect();
+    let mut lists: Vec<Option<(LeafList, LeafList)>> = vec![None; tree.nodes.len()];
+    // Children are visited after their parent, so the reverse order processes them first
+    for &(node, depth) in order.iter().rev() {
+        // Nothing is reported at or above a node shallower than the minimal length, so only the 
+        // leaves of deep enough nodes are needed
+        if tree.nodes[node].is_leaf() {
+            if depth - tree.nodes[node].edge_length() < minimal_length {
+                continue;
+            }
+            let start = joined_len - depth;
+            if start < first.len() {
+                // Suffixes of first shorter than the minimal length can't start a match
+                if first.len() - start >= minimal_length {
+                    let key = if start == 0 { 256 } else { first[start - 1] as u16 };
+                    lists[node] = Some((vec![(key, start)], Vec::new()));
+                }
+            }
+            else if start < joined_len {
+                let q = start - first.len();
+                let key = if q == 0 { 256 } else { second[q - 1] as u16 };
+                lists[node] = Some((Vec::new(), vec![(key, q)]));
+            }
+            continue;
+        }
+        if depth < minimal_length {
+            continue;
+        }
+        let mut acc: (LeafList, LeafList) = (Vec::new(), Vec::new());
+        for (_, child) in tree.nodes[node].children() {
+            if let Some((firsts, seconds)) = lists[child].take() {
+                left_maximal_pairs(&acc.0, &seconds, |p, q| matches.push(Match::new(p, q, depth.min(first.len() - p))));
+                left_maximal_pairs(&firsts, &acc.1, |p, q| matches.push(Match::new(p, q, depth.min(first.len() - p))));
+                acc = (merge_leaves(acc.0, firsts), merge_leaves(acc.1, seconds));
+            }
         }
-        return None;
+        lists[node] = Some(acc);
     }
 }
@@ -0,0 +1,136 @@
+//! Walk two pieces of data simultaneously and describe them as a sequence of matching and unique
+//! segments.
+//!
+//! [`DiffWalker`](struct.DiffWalker.html) is the streaming counterpart of
+//! [`diff_regions`](../fn.diff_regions.html): segments are produced in order, as the matches are
+//! enumerated, without collecting anything. This is the natural way to feed a renderer or a
+//! patch encoder.
+
+use std::iter::{FusedIterator, Iterator};
+
+use {AlgoSpec, Match, MatchIterator, Region};
+
+/// A segment of the alignment of two pieces of data.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub enum Segment {
+    /// Data present in both pieces of data.
+    Matched(Match),
+    /// Data only present in the first piece of data.
+    OnlyFirst(Region),
+    /// Data only present in the second piece of data.
+    OnlySecond(Region),
+}
+
+/// An iterator over the [`Segment`](enum.Segment.html) of two pieces of data.
+///
+/// The walker keeps one cursor in each piece of data and greedily accepts every match which lies
+/// after both cursors, trimming it if it overlaps the previously accepted one. Matches going
+/// backward in the first piece of data are skipped, so the segments always describe a collinear
+/// alignment: concatenating the `Matched` and `OnlyFirst` segments rebuilds the first piece of
+/// data while concatenating the `Matched` and `OnlySecond` segments rebuilds the second one.
+///
+/// # Examples
+///
+/// ```
+/// use bcmp::AlgoSpec;
+/// use bcmp::walker::{DiffWalker, Segment};
+///
+/// let a = "abcdefXXghijklmnop";
+/// let b = "abcdef01ghijklmnop";
+/// for segment in DiffWalker::new(a.as_bytes(), b.as_bytes(), AlgoSpec::TreeMatch(4)) {
+///     match segment {
+///         Segment::Matched(m) => println!("= {}", &a[m.first_pos..m.first_end()]),
+///         Segment::OnlyFirst(r) => println!("- {}", &a[r.range()]),
+///         Segment::OnlySecond(r) => println!("+ {}", &b[r.range()]),
+///     }
+/// }
+/// ```
+pub struct DiffWalker<'a> {
+    first_len: usize,
+    second_len: usize,
+    iter: MatchIterator<'a>,
+    first_cursor: usize,
+    second_cursor: usize,
+    pending: Option<Match>,
+    done: bool,
+}
+
+impl<'a> DiffWalker<'a> {
+    /// Allocate a new walker over two byte slices using the given
+    /// [`AlgoSpec`](../enum.AlgoSpec.html).
+    pub fn new(first: &'a [u8], second: &'a [u8], algo_spec: AlgoSpec) -> DiffWalker<'a> {
+        DiffWalker {
+            first_len: first.len(),
+            second_len: second.len(),
+            iter: MatchIterator::new(first, second, algo_spec),
+            first_cursor: 0,
+            second_cursor: 0,
+            pending: None,
+            done: false,
+        }
+    }
+
+    // Emit the unique segments before the match m, or the match itself
+    fn emit(&mut self, m: Match) -> Segment {
+        if self.first_cursor < m.first_pos {
+            let r = Region::new(self.first_cursor, m.first_pos);
+            self.first_cursor = m.first_pos;
+            self.pending = Some(m);
+            Segment::OnlyFirst(r)
+        }
+        else if self.second_cursor < m.second_pos {
+            let r = Region::new(self.second_cursor, m.second_pos);
+            self.second_cursor = m.second_pos;
+            self.pending = Some(m);
+            Segment::OnlySecond(r)
+        }
+        else {
+            self.first_cursor = m.first_end();
+            self.second_cursor = m.second_end();
+            self.pending = None;
+            Segment::Matched(m)
+        }
+    }
+}
+
+impl<'a> Iterator for DiffWalker<'a> {
+    type Item = Segment;
+    fn next(&mut self) -> Option<Segment> {
+        if let Some(m) = self.pending {
+            return Some(self.emit(m));
+        }
+        if !self.done {
+            for mut m in self.iter.by_ref() {
+                // Skip matches going backward or entirely behind the cursors
+                if m.first_end() <= self.first_cursor || m.second_end() <= self.second_cursor {
+                    continue;
+                }
+                // Trim the part overlapping the previous match
+                let overlap = self.first_cursor.saturating_sub(m.first_pos)
+                    .max(self.second_cursor.saturating_sub(m.second_pos));
+                if overlap >= m.length {
+                    continue;
+                }
+                m.first_pos += overlap;
+                m.second_pos += overlap;
+                m.length -= overlap;
+                return Some(self.emit(m));
+            }
+            self.done = true;
+        }
+        // Flush the tails
+        if self.first_cursor < self.first_len {
+            let r = Region::new(self.first_cursor, self.first_len);
+            self.first_cursor = self.first_len;
+            return Some(Segment::OnlyFirst(r));
+        }
+        if self.second_cursor < self.second_len {
+            let r = Region::new(self.second_cursor, self.second_len);
+            self.second_cursor = self.second_len;
+            return Some(Segment::OnlySecond(r));
+        }
+        None
+    }
+}
+
+impl<'a> FusedIterator for DiffWalker<'a> {}
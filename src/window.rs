@@ -0,0 +1,111 @@
+//! Comparison of very large pieces of data, one window at a time.
+//!
+//! Building a [`SuffixTree`](../suffixtree/struct.SuffixTree.html) or a `HashMap` index over a
+//! multi-gigabyte input quickly exhausts the available memory, and so does loading the input in
+//! the first place. The functions of this module read both inputs one window at a time and compare
+//! every pair of overlapping windows, so the memory used is bounded by the window size. Matches
+//! found in different windows are then translated to global offsets and stitched back together.
+
+use std::cmp::max;
+use std::io::{Read, Result, Seek, SeekFrom};
+
+use {AlgoSpec, Match, MatchIterator, to_usize};
+
+// Start offsets of the windows covering [0..len)
+fn window_starts(len: usize, size: usize, overlap: usize) -> Vec<usize> {
+    let step = size - overlap;
+    let mut starts = vec![0];
+    while starts[starts.len() - 1] + size < len {
+        let next = starts[starts.len() - 1] + step;
+        starts.push(next);
+    }
+    starts
+}
+
+fn stream_len<R: Seek>(r: &mut R) -> Result<usize> {
+    to_usize(r.seek(SeekFrom::End(0))?)
+}
+
+// Replace the content of window with the bytes [start..end) of r
+fn load<R: Read + Seek>(r: &mut R, start: usize, end: usize, window: &mut Vec<u8>) -> Result<()> {
+    r.seek(SeekFrom::Start(start as u64))?;
+    window.resize(end - start, 0);
+    r.read_exact(window)
+}
+
+fn delta(m: &Match) -> i64 {
+    m.first_pos as i64 - m.second_pos as i64
+}
+
+/// Return all the [`Match`](../struct.Match.html) between two pieces of data, computed by
+/// comparing windows of `size` bytes overlapping by `overlap` bytes.
+///
+/// Both inputs are read through `Read + Seek`, like a `File` or a `Cursor` over a slice: only one
+/// window of each of them is in memory at a time, together with the index of the first one. The
+/// windows of `first` are read again for every window of `second`.
+///
+/// As long as `overlap` is at least the minimal matching length of `algo_spec`, every match
+/// crossing a window boundary is seen in some window pair and the pieces are merged back: the
+/// result is the same set of matches a [`MatchIterator`](../struct.MatchIterator.html) would
+/// return over the whole data, sorted in their natural order. Comparing every pair of windows
+/// costs a number of matching passes quadratic in the number of windows, trading time for memory.
+///
+/// # Panics
+///
+/// It will panic if `overlap` is smaller than the minimal matching length, if `size` is not
+/// larger than `overlap` or if `algo_spec` is [`Hybrid`](../enum.AlgoSpec.html#variant.Hybrid),
+/// whose anchors depend on the whole data.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use bcmp::{AlgoSpec, Match};
+/// use bcmp::window::windowed_matches;
+///
+/// let mut a = Cursor::new(b"0123456789abcdefghijklmnopqrstuvwxyz");
+/// let mut b = Cursor::new(b"__abcdefghijklmnopqrstuvwxyz__");
+/// let matches = windowed_matches(&mut a, &mut b, AlgoSpec::TreeMatch(4), 12, 4).unwrap();
+/// assert!(matches == [Match::new(10, 2, 26)]);
+/// ```
+pub fn windowed_matches<F: Read + Seek, S: Read + Seek>(first: &mut F, second: &mut S, algo_spec: AlgoSpec, size: usize, overlap: usize) -> Result<Vec<Match>> {
+    assert!(overlap >= algo_spec.minimal_length(), "The window overlap is smaller than the minimal matching length");
+    assert!(size > overlap, "The window size must be larger than the overlap");
+    assert!(!matches!(algo_spec, AlgoSpec::Hybrid(..)), "Hybrid matches can not be computed by windows");
+    let first_len = stream_len(first)?;
+    let second_len = stream_len(second)?;
+    let mut first_window = Vec::<u8>::with_capacity(size.min(first_len));
+    let mut second_window = Vec::<u8>::with_capacity(size.min(second_len));
+    let mut pieces = Vec::<Match>::new();
+    for &s in &window_starts(second_len, size, overlap) {
+        let second_end = second_len.min(s + size);
+        if second_end - s < algo_spec.minimal_length() {
+            continue;
+        }
+        load(second, s, second_end, &mut second_window)?;
+        for &f in &window_starts(first_len, size, overlap) {
+            let first_end = first_len.min(f + size);
+            if first_end - f < algo_spec.minimal_length() {
+                continue;
+            }
+            load(first, f, first_end, &mut first_window)?;
+            pieces.extend(MatchIterator::new(&first_window, &second_window, algo_spec)
+                .map(|m| Match::new(m.first_pos + f, m.second_pos + s, m.length)));
+        }
+    }
+    // Stitch together the pieces lying on the same diagonal which overlap or touch
+    pieces.sort_by_key(|m| (delta(m), m.second_pos));
+    let mut matches = Vec::<Match>::new();
+    for p in pieces {
+        if let Some(last) = matches.last_mut() {
+            if delta(last) == delta(&p) && p.second_pos <= last.second_end() {
+                last.length = max(last.second_end(), p.second_end()) - last.second_pos;
+                continue;
+            }
+        }
+        matches.push(p);
+    }
+    matches.sort();
+    Ok(matches)
+}